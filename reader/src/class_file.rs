@@ -32,6 +32,7 @@ pub struct ClassFile {
 }
 
 impl ClassFile {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         magic: U4,
         minor_version: U2,
@@ -23,6 +23,7 @@ fn log(message: &str) {
     }
 }
 
+#[allow(clippy::large_enum_variant)]
 enum ClassFileStream<'a> {
     File(BufReader<File>),
     Zip(ZipFile<'a>),
@@ -712,7 +713,7 @@ impl<'a> ClassFileParser<'a> {
             if frame_type <= 63 {
                 // SAME
                 stack_map_table.push(StackMapFrame::SameFrame { frame_type });
-            } else if frame_type >= 64 && frame_type <= 127 {
+            } else if (64..=127).contains(&frame_type) {
                 // SAME_LOCALS_1_STACK_ITEM
                 let tag = self.class_file_stream.read_u1();
                 let verify_type_info = self.parse_verification_type_info(tag);
@@ -730,7 +731,7 @@ impl<'a> ClassFileParser<'a> {
                     offset_delta,
                     stack: [verify_type_info],
                 })
-            } else if frame_type >= 248 && frame_type <= 250 {
+            } else if (248..=250).contains(&frame_type) {
                 // CHOP
                 let offset_delta = self.class_file_stream.read_u2();
                 stack_map_table.push(StackMapFrame::ChopFrame {
@@ -744,7 +745,7 @@ impl<'a> ClassFileParser<'a> {
                     frame_type,
                     offset_delta,
                 })
-            } else if frame_type >= 252 && frame_type <= 254 {
+            } else if (252..=254).contains(&frame_type) {
                 // APPEND
                 let offset_delta = self.class_file_stream.read_u2();
                 let mut locals = Vec::new();
@@ -925,7 +926,7 @@ impl<'a> ClassFileParser<'a> {
                     bound_index,
                 }
             }
-            0x13 | 0x14 | 0x15 => TargetInfo::EmptyTarget,
+            0x13..=0x15 => TargetInfo::EmptyTarget,
             0x16 => {
                 let formal_parameter_index = self.class_file_stream.read_u1();
                 TargetInfo::FormalParameterTarget {
@@ -956,11 +957,11 @@ impl<'a> ClassFileParser<'a> {
                     exception_table_index,
                 }
             }
-            0x43 | 0x44 | 0x45 | 0x46 => {
+            0x43..=0x46 => {
                 let offset = self.class_file_stream.read_u2();
                 TargetInfo::OffsetTarget { offset }
             }
-            0x47 | 0x48 | 0x49 | 0x4A | 0x4B => {
+            0x47..=0x4B => {
                 let offset = self.class_file_stream.read_u2();
                 let type_argument_index = self.class_file_stream.read_u1();
                 TargetInfo::TypeArgumentTarget {
@@ -25,8 +25,8 @@ fn log(message: &str) {
 }
 
 enum ClassPathEntry {
-    DIR { path: String },
-    JAR { path: String },
+    Dir { path: String },
+    Jar { path: String },
 }
 
 #[derive(Debug, Clone)]
@@ -42,6 +42,12 @@ pub struct ClassPathManager {
     run_time_class_path: Vec<ClassPathEntry>,
 }
 
+impl Default for ClassPathManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ClassPathManager {
     pub fn new() -> ClassPathManager {
         ClassPathManager {
@@ -54,11 +60,11 @@ impl ClassPathManager {
         log(&format!("[ClassPathManager] 添加类路径: {}", abs_path.display()));
         let md = fs::metadata(&abs_path).expect("Invalid class path");
         let source = if md.is_dir() {
-            ClassPathEntry::DIR {
+            ClassPathEntry::Dir {
                 path: abs_path.display().to_string(),
             }
         } else if md.is_file() && abs_path.to_string_lossy().ends_with(".jar") {
-            ClassPathEntry::JAR {
+            ClassPathEntry::Jar {
                 path: abs_path.display().to_string(),
             }
         } else {
@@ -74,9 +80,12 @@ impl ClassPathManager {
     }
 
     pub fn search_class(&self, class_name: &str) -> Result<ClassFile, ClassNotFoundError> {
+        // 描述符形式（Lpkg/Name;）只去掉首尾，不能误删以L开头的类名
+        let class_name = class_name
+            .strip_prefix('L')
+            .and_then(|name| name.strip_suffix(';'))
+            .unwrap_or(class_name);
         let file_name = class_name
-            .trim_start_matches('L')
-            .trim_end_matches(';')
             .replace("/", std::path::MAIN_SEPARATOR_STR)
             .replace(".", std::path::MAIN_SEPARATOR_STR)
             + ".class";
@@ -86,7 +95,7 @@ impl ClassPathManager {
         
         for (i, entry) in self.run_time_class_path.iter().enumerate() {
             match entry {
-                ClassPathEntry::DIR { path } => {
+                ClassPathEntry::Dir { path } => {
                     log(&format!("[ClassPathManager] 条目 {}: 目录 {}", i, path));
                     let fname = std::path::Path::new(&path).join(&file_name);
                     log(&format!("[ClassPathManager] 查找类文件: {}", fname.display()));
@@ -99,7 +108,7 @@ impl ClassPathManager {
                         Err(_) => continue,
                     }
                 }
-                ClassPathEntry::JAR { path } => {
+                ClassPathEntry::Jar { path } => {
                     log(&format!("[ClassPathManager] 条目 {}: JAR {}", i, path));
                     let fname = std::path::Path::new(&path);
                     log(&format!("[ClassPathManager] 查找JAR文件: {}，类: {}", fname.display(), file_name));
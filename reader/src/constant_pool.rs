@@ -131,9 +131,9 @@ impl ConstantPool for Vec<CpInfo> {
                     .expect("Unknow name and type")
                 {
                     (
-                        self.get_utf8_string(class_name_index.clone()),
-                        self.get_utf8_string(name_index.clone()),
-                        self.get_utf8_string(descriptor_index.clone()),
+                        self.get_utf8_string(*class_name_index),
+                        self.get_utf8_string(*name_index),
+                        self.get_utf8_string(*descriptor_index),
                     )
                 } else {
                     panic!("Wrong type")
@@ -235,7 +235,9 @@ impl ConstantPoolExt for Vec<CpInfo> {
         }
     }
     fn get_methodref_info(&self, methodref_index: crate::types::U2) -> (String, String, String) {
-        if let CpInfo::MethodRef { class_index, name_and_type_index, .. } = &self[(methodref_index - 1) as usize] {
+        // 接口方法（invokeinterface及接口的静态/私有方法）引用的是InterfaceMethodRef，结构与MethodRef相同
+        if let CpInfo::MethodRef { class_index, name_and_type_index, .. }
+        | CpInfo::InterfaceMethodRef { class_index, name_and_type_index, .. } = &self[(methodref_index - 1) as usize] {
             let class_name = self.get_class_name(*class_index);
             if let CpInfo::NameAndType { name_index, descriptor_index, .. } = &self[(*name_and_type_index - 1) as usize] {
                 let method_name = self.get_utf8_string(*name_index);
@@ -245,7 +247,7 @@ impl ConstantPoolExt for Vec<CpInfo> {
                 panic!("name_and_type_index不是NameAndType类型");
             }
         } else {
-            panic!("methodref_index不是MethodRef类型");
+            panic!("methodref_index不是MethodRef或InterfaceMethodRef类型");
         }
     }
     fn get_fieldref_info(&self, fieldref_index: crate::types::U2) -> (String, String, String) {
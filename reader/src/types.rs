@@ -3,4 +3,7 @@ pub type U2 = u16;
 pub type U4 = u32;
 pub type U8 = u64;
 
+pub const ACC_PUBLIC: u16 = 0x0001;
+pub const ACC_PRIVATE: u16 = 0x0002;
+pub const ACC_PROTECTED: u16 = 0x0004;
 pub const ACC_STATIC: u16 = 0x0008;
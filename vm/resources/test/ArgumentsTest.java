@@ -0,0 +1,36 @@
+/**
+ * 混合类型参数的调用约定测试：long/double占两个槽位，实例方法的this在槽位0
+ */
+public class ArgumentsTest {
+    long base = 1000;
+
+    static long mixed(long a, double b, Object o, int[] arr, float f) {
+        return a + (long) (b * 2) + (o == null ? 0 : 1) + arr[1] + (long) (f * 4);
+    }
+
+    long widened(int i, long l, float f, double d, boolean z, char c, short s, byte b) {
+        return base + i + l + (long) (f * 2) + (long) d + (z ? 100 : 0) + c + s + b;
+    }
+
+    static double doubles(double a, float b, double c) {
+        return a * 4 + b * 2 + c;
+    }
+
+    static long mixedStatic() {
+        return mixed(1L << 40, 2.5, new Object(), new int[]{3, 4}, 1.25f);
+    }
+
+    static long mixedVirtual() {
+        return new ArgumentsTest().widened(-7, -(1L << 33), 0.5f, 1e10, true, 'A', (short) -300, (byte) -2);
+    }
+
+    static double mixedDoubles() {
+        return doubles(-0.5, 0.25f, 1e-3);
+    }
+
+    public static void main(String[] args) {
+        System.out.println(mixedStatic());
+        System.out.println(mixedVirtual());
+        System.out.println(mixedDoubles());
+    }
+}
@@ -0,0 +1,83 @@
+/**
+ * System.exit / Runtime.halt 和关闭钩子：exit展开所有帧（不执行finally）后按注册顺序运行钩子，halt不运行钩子
+ */
+public class ExitTest {
+    static int hookRuns;
+    static int finallyRan;
+    static int order;
+
+    static class RecordingHook extends Thread {
+        public void run() {
+            order = order * 10 + 2;
+        }
+    }
+
+    static void exitWithHooks() {
+        Runtime runtime = Runtime.getRuntime();
+        runtime.addShutdownHook(new Thread(() -> {
+            hookRuns++;
+            order = order * 10 + 1;
+        }));
+        runtime.addShutdownHook(new RecordingHook());
+        Thread removed = new Thread(() -> hookRuns += 100);
+        runtime.addShutdownHook(removed);
+        if (!runtime.removeShutdownHook(removed) || runtime.removeShutdownHook(removed)) {
+            return;
+        }
+        try {
+            nested(3);
+        } finally {
+            finallyRan = 1;
+        }
+    }
+
+    static void nested(int status) {
+        try {
+            System.exit(status);
+        } catch (Throwable t) {
+            finallyRan = 2;
+        }
+    }
+
+    static void halt() {
+        Runtime.getRuntime().addShutdownHook(new Thread(() -> hookRuns++));
+        Runtime.getRuntime().halt(7);
+        finallyRan = 1;
+    }
+
+    static void haltInHook() {
+        Runtime runtime = Runtime.getRuntime();
+        runtime.addShutdownHook(new Thread(() -> {
+            hookRuns++;
+            Runtime.getRuntime().halt(9);
+        }));
+        runtime.addShutdownHook(new Thread(() -> hookRuns += 10));
+        runtime.exit(4);
+    }
+
+    static void uncaught() {
+        Runtime.getRuntime().addShutdownHook(new Thread(() -> hookRuns++));
+        throw new RuntimeException("boom");
+    }
+
+    static void failingHook() {
+        Runtime runtime = Runtime.getRuntime();
+        runtime.addShutdownHook(new Thread(() -> {
+            throw new RuntimeException("hook failed");
+        }));
+        runtime.addShutdownHook(new Thread(() -> hookRuns++));
+    }
+
+    // 参数个数选择退出方式，供命令行检查进程的退出状态码
+    public static void main(String[] args) {
+        Runtime.getRuntime().addShutdownHook(new Thread(() -> System.out.println("shutdown hook")));
+        if (args.length == 1) {
+            System.exit(3);
+        } else if (args.length == 2) {
+            throw new RuntimeException("boom");
+        } else if (args.length == 3) {
+            Runtime.getRuntime().halt(7);
+        }
+        System.out.println("main returned");
+    }
+}
@@ -0,0 +1,20 @@
+/**
+ * 字段布局测试：4字节的float字段夹在int字段和引用字段之间，写入时不能覆盖相邻字段
+ */
+public class FieldLayoutTest {
+    int a;
+    float f;
+    Object r;
+
+    public static int floatBetweenIntAndRef() {
+        FieldLayoutTest t = new FieldLayoutTest();
+        t.a = 7;
+        t.r = t;
+        t.f = 3.0f;
+        if (t.a != 7 || t.r != t || t.f != 3.0f) {
+            return -1;
+        }
+        t.f = t.f * 2.5f;
+        return (int) t.f + t.a;
+    }
+}
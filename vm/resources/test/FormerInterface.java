@@ -0,0 +1,8 @@
+/**
+ * StaleInterfaceCall 引用的类型的当前版本：编译 StaleInterfaceCall 时它还是接口
+ */
+public class FormerInterface {
+    public int value() {
+        return 1;
+    }
+}
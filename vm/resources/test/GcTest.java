@@ -0,0 +1,92 @@
+/**
+ * 垃圾收集测试：分配远超堆大小的临时对象，检查存活对象在收集后保持完整
+ */
+public class GcTest {
+    static class Node {
+        int value;
+        Node next;
+        int[] payload;
+
+        Node(int value, Node next) {
+            this.value = value;
+            this.next = next;
+            this.payload = new int[] { value, value * 2 };
+        }
+    }
+
+    static Node retained;
+
+    static int sum(Node node) {
+        int total = 0;
+        while (node != null) {
+            total += node.value + node.payload[1];
+            node = node.next;
+        }
+        return total;
+    }
+
+    // 临时数组在收集时被回收，局部变量中的链表存活
+    public static int garbageLoop() {
+        Node list = null;
+        for (int i = 0; i < 50000; i++) {
+            int[] garbage = new int[16];
+            garbage[0] = i;
+            if (i % 50 == 0) {
+                list = new Node(i, list);
+            }
+        }
+        return sum(list);
+    }
+
+    // 静态字段和引用数组中的对象存活
+    public static int staticRoots() {
+        Node[][] table = new Node[10][];
+        for (int i = 0; i < 10; i++) {
+            table[i] = new Node[10];
+        }
+        for (int round = 0; round < 2000; round++) {
+            Node node = new Node(round, null);
+            table[round % 10][round / 10 % 10] = node;
+            retained = new Node(round, retained == null ? null : retained.next);
+        }
+        int total = 0;
+        for (int i = 0; i < 10; i++) {
+            for (int j = 0; j < 10; j++) {
+                total += table[i][j].value;
+            }
+        }
+        return total + retained.value;
+    }
+
+    // 字符串对象和它们的字符数组在收集后仍然可以访问
+    public static int strings() {
+        String[] kept = new String[20];
+        String literal = "gc";
+        for (int i = 0; i < 20000; i++) {
+            String s = new String(new char[] { literal.charAt(0), literal.charAt(1), (char) ('a' + i % 26) });
+            if (i % 1000 == 0) {
+                kept[i / 1000] = s;
+            }
+        }
+        int total = 0;
+        for (int i = 0; i < 20; i++) {
+            total += kept[i].length() * 100 + kept[i].charAt(2);
+        }
+        return total + literal.length();
+    }
+
+    // 显式请求的收集不影响存活对象
+    public static int explicitGc() {
+        Node list = new Node(1, new Node(2, null));
+        System.gc();
+        Runtime.getRuntime().gc();
+        return sum(list);
+    }
+
+    public static void main(String[] args) {
+        System.out.println(garbageLoop());
+        System.out.println(staticRoots());
+        System.out.println(strings());
+        System.out.println(explicitGc());
+    }
+}
@@ -0,0 +1,48 @@
+/**
+ * 分代收集测试：长期存活的对象晋升到老年代后，仍然通过字段、数组元素和静态字段引用新分配的对象
+ */
+public class GenTest {
+    static class Node {
+        int value;
+        Node next;
+
+        Node(int value, Node next) {
+            this.value = value;
+            this.next = next;
+        }
+    }
+
+    static Node latest;
+
+    static void churn(int count) {
+        for (int i = 0; i < count; i++) {
+            int[] garbage = new int[8];
+            garbage[0] = i;
+        }
+    }
+
+    // 老年代的数组和对象在每轮中被写入新生代对象的引用
+    public static int oldToYoung() {
+        Node[] table = new Node[32];
+        Node holder = new Node(-1, null);
+        churn(20000);
+        for (int round = 0; round < 3000; round++) {
+            table[round % 32] = new Node(round, null);
+            holder.next = new Node(round, holder.next == null ? null : holder.next.next);
+            latest = new Node(round, latest);
+            if (round % 100 == 50) {
+                latest = null;
+            }
+            churn(10);
+        }
+        int total = 0;
+        for (int i = 0; i < 32; i++) {
+            total += table[i].value;
+        }
+        return total + holder.next.value + latest.value + latest.next.value;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(oldToYoung());
+    }
+}
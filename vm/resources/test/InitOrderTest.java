@@ -0,0 +1,71 @@
+/**
+ * 类初始化的时机（JVMS 5.5）：只引用类（instanceof、checkcast、实例方法调用、实例字段访问）时
+ * 解析符号引用但不执行<clinit>；new、getstatic、putstatic、invokestatic才初始化类
+ */
+public class InitOrderTest {
+    static int initialized;
+
+    static class Checked {
+        static {
+            initialized |= 1;
+        }
+    }
+
+    static class Called {
+        static {
+            initialized |= 2;
+        }
+
+        int value() {
+            return 1;
+        }
+    }
+
+    static class Holder {
+        static {
+            initialized |= 4;
+        }
+
+        int field;
+    }
+
+    static class Created {
+        static {
+            initialized |= 8;
+        }
+    }
+
+    static class Helper {
+        static {
+            initialized |= 16;
+        }
+
+        static int help() {
+            return 1;
+        }
+    }
+
+    static int referenced() {
+        Object object = new Object();
+        if (object instanceof Checked) {
+            return -1;
+        }
+        Called called = null;
+        try {
+            called.value();
+        } catch (NullPointerException e) {
+        }
+        Holder holder = null;
+        try {
+            holder.field = 1;
+        } catch (NullPointerException e) {
+        }
+        return initialized;
+    }
+
+    static int used() {
+        new Created();
+        Helper.help();
+        return initialized;
+    }
+}
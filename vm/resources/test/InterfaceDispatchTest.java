@@ -0,0 +1,99 @@
+/**
+ * invokeinterface 分派测试：继承链上的实现、最具体的default方法、VM创建的字符串，
+ * 以及 StaleImpls.java 中按旧版本接口编译的实现类
+ */
+public class InterfaceDispatchTest {
+    interface Shape {
+        int area();
+
+        default int sides() {
+            return 0;
+        }
+    }
+
+    interface Polygon extends Shape {
+        default int sides() {
+            return 3;
+        }
+    }
+
+    interface Labeled {
+        default int label() {
+            return 1;
+        }
+    }
+
+    interface Left {
+        default int pick() {
+            return 1;
+        }
+    }
+
+    interface Right {
+        default int pick() {
+            return 2;
+        }
+    }
+
+    interface Task {
+        void run();
+
+        int cost();
+    }
+
+    static class Base {
+        public int area() {
+            return 10;
+        }
+    }
+
+    static class Square extends Base implements Polygon, Labeled {
+        public int sides() {
+            return 4;
+        }
+    }
+
+    static class Triangle extends Base implements Polygon {
+    }
+
+    static class Circle implements Shape, Labeled {
+        public int area() {
+            return 3;
+        }
+
+        public int label() {
+            return 2;
+        }
+    }
+
+    static int describe(Shape shape) {
+        int label = shape instanceof Labeled ? ((Labeled) shape).label() : 0;
+        return shape.area() * 100 + shape.sides() * 10 + label;
+    }
+
+    static int square() {
+        return describe(new Square());
+    }
+
+    static int triangle() {
+        return describe(new Triangle());
+    }
+
+    static int circle() {
+        return describe(new Circle());
+    }
+
+    static int conflict() {
+        Left left = new StaleBoth();
+        return left.pick();
+    }
+
+    static int missing() {
+        Task task = new StaleTask();
+        return task.cost();
+    }
+
+    static int sequence(CharSequence text) {
+        return text.length() * 1000 + text.charAt(0);
+    }
+}
@@ -0,0 +1,120 @@
+/**
+ * invokedynamic 测试：LambdaMetafactory 链接的lambda表达式和方法引用
+ */
+public class LambdaTest {
+    interface IntOp {
+        int apply(int a, int b);
+    }
+
+    interface Counter {
+        int next();
+    }
+
+    interface Maker {
+        Point make(int x, int y);
+    }
+
+    interface Mapper<T, R> {
+        R map(T t);
+    }
+
+    interface PointMapper extends Mapper<Point, Point> {
+        Point map(Point p);
+    }
+
+    interface Marker {
+    }
+
+    static class Point {
+        final int x;
+        final int y;
+
+        Point(int x, int y) {
+            this.x = x;
+            this.y = y;
+        }
+
+        int sum() {
+            return x + y;
+        }
+    }
+
+    private int base = 100;
+
+    static int add(int a, int b) {
+        return a + b;
+    }
+
+    static int increment(int n) {
+        return n + 1;
+    }
+
+    int instanceLambda() {
+        IntOp op = (a, b) -> base + a + b;
+        return op.apply(6, 7);
+    }
+
+    public static int staticRef() {
+        IntOp op = LambdaTest::add;
+        return op.apply(6, 7);
+    }
+
+    public static int capturing() {
+        int k = 5;
+        IntOp op = (a, b) -> a * b + k;
+        return op.apply(5, 8);
+    }
+
+    public static int capturingThis() {
+        return new LambdaTest().instanceLambda();
+    }
+
+    public static int constructorRef() {
+        Maker maker = Point::new;
+        return maker.make(3, 4).sum();
+    }
+
+    public static int boundMethodRef() {
+        Point p = new Point(3, 5);
+        Counter counter = p::sum;
+        return counter.next();
+    }
+
+    public static int boxing() {
+        Mapper<Integer, Integer> inc = LambdaTest::increment;
+        return inc.map(2);
+    }
+
+    public static int markerInterface() {
+        IntOp op = (IntOp & Marker) (a, b) -> a * 1000 - b;
+        return op instanceof Marker ? op.apply(1, 1) : -1;
+    }
+
+    public static int bridge() {
+        PointMapper twice = p -> new Point(p.x * 2, p.y * 2);
+        Mapper<Point, Point> mapper = twice;
+        return mapper.map(new Point(5, 7)).sum();
+    }
+
+    public static int sameCallSite() {
+        int total = 0;
+        for (int i = 1; i <= 3; i++) {
+            int k = i;
+            Counter counter = () -> k * 10;
+            total += counter.next();
+        }
+        return total;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(staticRef());
+        System.out.println(capturing());
+        System.out.println(capturingThis());
+        System.out.println(constructorRef());
+        System.out.println(boundMethodRef());
+        System.out.println(boxing());
+        System.out.println(markerInterface());
+        System.out.println(bridge());
+        System.out.println(sameCallSite());
+    }
+}
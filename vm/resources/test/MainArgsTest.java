@@ -0,0 +1,29 @@
+/**
+ * main方法接收命令行参数：参数数组的运行时类型是String[]，元素是String对象
+ */
+public class MainArgsTest {
+    static int count;
+    static String first;
+    static String last;
+    static int storeRejected;
+    static int totalLength;
+
+    public static void main(String[] args) {
+        count = args.length;
+        if (args.length == 0) {
+            return;
+        }
+        for (int i = 0; i < args.length; i++) {
+            totalLength += args[i].length();
+        }
+        first = args[0];
+        last = args[args.length - 1];
+        // 通过Object[]存入非String对象时抛出ArrayStoreException
+        Object[] objects = args;
+        try {
+            objects[0] = new Object();
+        } catch (ArrayStoreException e) {
+            storeRejected = 1;
+        }
+    }
+}
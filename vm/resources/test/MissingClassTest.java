@@ -0,0 +1,24 @@
+/**
+ * 按包含 MissingHelper 的类路径编译，运行时类路径里没有 MissingHelper：
+ * 调用它的静态方法或实例方法时解析失败，错误向上传播而不是当作调用成功
+ */
+public class MissingClassTest {
+    static int callMissing() {
+        return 1 + MissingHelper.value(2);
+    }
+
+    static int callMissingVirtual() {
+        MissingHelper helper = null;
+        return helper.size();
+    }
+}
+
+class MissingHelper {
+    static int value(int x) {
+        return x;
+    }
+
+    int size() {
+        return 0;
+    }
+}
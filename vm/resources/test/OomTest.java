@@ -0,0 +1,59 @@
+/**
+ * 堆大小测试：存活对象多时堆扩大，堆耗尽时抛出可以捕获的OutOfMemoryError
+ */
+public class OomTest {
+    static class Node {
+        Node next;
+        int[] data;
+
+        Node(Node next, int[] data) {
+            this.next = next;
+            this.data = data;
+        }
+    }
+
+    // 一直保持存活的链表迫使堆扩大
+    public static int retainMany() {
+        Node list = null;
+        for (int i = 0; i < 20000; i++) {
+            list = new Node(list, new int[] { i });
+        }
+        int total = 0;
+        for (Node node = list; node != null; node = node.next) {
+            total += node.data[0] % 10;
+        }
+        return total;
+    }
+
+    // 一次分配比初始堆大得多的数组
+    public static int largeArray() {
+        int[] array = new int[300000];
+        array[array.length - 1] = 7;
+        return array.length + array[array.length - 1];
+    }
+
+    // 堆耗尽时OutOfMemoryError可以被捕获，释放引用后可以继续分配
+    public static int exhaust() {
+        Node list = null;
+        int count = 0;
+        String message = null;
+        try {
+            while (true) {
+                list = new Node(list, new int[10000]);
+                count++;
+            }
+        } catch (OutOfMemoryError e) {
+            list = null;
+            message = e.getMessage();
+        }
+        int[] after = new int[10000];
+        after[0] = count;
+        return count > 0 && after[0] == count && message.length() == 15 ? 1 : 0;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(retainMany());
+        System.out.println(largeArray());
+        System.out.println(exhaust());
+    }
+}
@@ -0,0 +1,48 @@
+/**
+ * 解释循环测试：深递归、长循环和线程栈溢出
+ */
+public class RecursionTest {
+    static int fib(int n) {
+        return n < 2 ? n : fib(n - 1) + fib(n - 2);
+    }
+
+    static int sumTo(int n) {
+        return n == 0 ? 0 : n + sumTo(n - 1);
+    }
+
+    static int down(int n) {
+        return down(n + 1) + 1;
+    }
+
+    public static int fib20() {
+        return fib(20);
+    }
+
+    public static int deepRecursion() {
+        return sumTo(3000);
+    }
+
+    public static int longLoop() {
+        int total = 0;
+        for (int i = 0; i < 200000; i++) {
+            total += i % 7;
+        }
+        return total;
+    }
+
+    public static int overflow() {
+        try {
+            down(0);
+            return -1;
+        } catch (StackOverflowError e) {
+            return 1;
+        }
+    }
+
+    public static void main(String[] args) {
+        System.out.println(fib20());
+        System.out.println(deepRecursion());
+        System.out.println(longLoop());
+        System.out.println(overflow());
+    }
+}
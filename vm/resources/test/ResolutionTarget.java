@@ -0,0 +1,11 @@
+/**
+ * ResolutionTest 引用的类的当前版本：删除了 gone 字段和 gone() 方法，
+ * secret 字段和 secret() 方法改为 private
+ */
+public class ResolutionTarget {
+    private int secret = 5;
+
+    private static int secret() {
+        return 6;
+    }
+}
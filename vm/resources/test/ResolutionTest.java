@@ -0,0 +1,80 @@
+/**
+ * 运行时常量池的解析测试。按旧版本的 ResolutionTarget 编译：当时它还有 gone 字段和 gone() 方法，
+ * secret 字段和 secret() 方法是 public 的。对照当前的 ResolutionTarget 无法重新编译，
+ * 运行时分别触发 NoSuchFieldError、NoSuchMethodError 和 IllegalAccessError
+ */
+public class ResolutionTest {
+    static class Base {
+        int value = 1;
+        static int counter = 10;
+    }
+
+    static class Derived extends Base {
+        int value = 2;
+    }
+
+    interface Limits {
+        int[] TABLE = {7};
+    }
+
+    static class Limited implements Limits {
+    }
+
+    /** 同名的字段按符号引用的类解析；通过子类和实现类引用的静态字段是父类和接口中的同一个字段 */
+    static int fields() {
+        Derived d = new Derived();
+        ((Base) d).value += 30;
+        Derived.counter += 5;
+        return Limited.TABLE[0] * 10000 + d.value * 1000 + ((Base) d).value * 10 + Base.counter;
+    }
+
+    static String literal() {
+        return "cached";
+    }
+
+    static int sameLiteral() {
+        return literal() == literal() ? 1 : 0;
+    }
+
+    static int missingField() {
+        return new ResolutionTarget().gone;
+    }
+
+    static int missingMethod() {
+        return new ResolutionTarget().gone();
+    }
+
+    static int privateField() {
+        return new ResolutionTarget().secret;
+    }
+
+    static int privateMethod() {
+        return ResolutionTarget.secret();
+    }
+
+    static int caught() {
+        int result = 0;
+        try {
+            missingField();
+        } catch (NoSuchFieldError e) {
+            result += 1;
+        }
+        try {
+            missingMethod();
+        } catch (NoSuchMethodError e) {
+            result += 10;
+        }
+        try {
+            privateMethod();
+        } catch (IllegalAccessError e) {
+            result += 100;
+        }
+        return result;
+    }
+
+    public static void main(String[] args) {
+        System.out.println(fields());
+        System.out.println(sameLiteral());
+        System.out.println(caught());
+    }
+}
@@ -0,0 +1,24 @@
+/**
+ * 异常栈轨迹测试：除零异常被包装后重新抛出
+ */
+public class StackTraceTest {
+    static int divide(int a, int b) {
+        return a / b;
+    }
+
+    static void compute() {
+        divide(1, 0);
+    }
+
+    static void wrap() {
+        try {
+            compute();
+        } catch (ArithmeticException e) {
+            throw new RuntimeException("wrapped", e);
+        }
+    }
+
+    static void entry() {
+        wrap();
+    }
+}
@@ -0,0 +1,12 @@
+/**
+ * 按旧版本接口编译的实现类：编译时 InterfaceDispatchTest.Right 还没有 pick()，
+ * InterfaceDispatchTest.Task 还没有 cost()。对照当前的接口无法重新编译，
+ * 运行时分别触发 IncompatibleClassChangeError 和 AbstractMethodError
+ */
+class StaleBoth implements InterfaceDispatchTest.Left, InterfaceDispatchTest.Right {
+}
+
+class StaleTask implements InterfaceDispatchTest.Task {
+    public void run() {
+    }
+}
@@ -0,0 +1,10 @@
+/**
+ * 按旧版本的 FormerInterface 编译：当时它是声明了 value() 的接口。对照当前的 FormerInterface 无法重新编译，
+ * 运行时解析接口方法引用触发 IncompatibleClassChangeError
+ */
+public class StaleInterfaceCall {
+    static int call() {
+        FormerInterface value = null;
+        return value.value();
+    }
+}
@@ -0,0 +1,11 @@
+/**
+ * 读取byte和short类型的静态字段：VM按字段类型保存的值以符号扩展后的int压栈
+ */
+public class StaticFieldTest {
+    static byte small;
+    static short medium;
+
+    static int read() {
+        return small * 100000 + medium;
+    }
+}
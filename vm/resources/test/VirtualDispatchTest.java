@@ -0,0 +1,75 @@
+/**
+ * invokevirtual 分派测试：按接收者的运行时类选择重写的方法
+ */
+public class VirtualDispatchTest {
+    interface Named {
+        default int id() {
+            return 9;
+        }
+    }
+
+    static abstract class Animal {
+        abstract int legs();
+
+        int weight() {
+            return 1;
+        }
+
+        int describe() {
+            return legs() * 10 + weight();
+        }
+    }
+
+    static class Bird extends Animal {
+        int legs() {
+            return 2;
+        }
+    }
+
+    static class Dog extends Animal {
+        int legs() {
+            return 4;
+        }
+
+        int weight() {
+            return 7;
+        }
+    }
+
+    static class Puppy extends Dog {
+        int weight() {
+            return super.weight() - 5;
+        }
+    }
+
+    static class Cat extends Animal implements Named {
+        int legs() {
+            return 4;
+        }
+    }
+
+    static int bird() {
+        Animal animal = new Bird();
+        return animal.describe();
+    }
+
+    static int dog() {
+        Animal animal = new Dog();
+        return animal.describe();
+    }
+
+    static int puppy() {
+        Dog dog = new Puppy();
+        return dog.describe();
+    }
+
+    static int cat() {
+        Cat cat = new Cat();
+        return cat.id();
+    }
+
+    static int nullReceiver() {
+        Animal animal = null;
+        return animal.legs();
+    }
+}
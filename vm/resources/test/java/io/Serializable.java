@@ -0,0 +1,7 @@
+package java.io;
+
+/**
+ * 测试用的最小 java.io.Serializable
+ */
+public interface Serializable {
+}
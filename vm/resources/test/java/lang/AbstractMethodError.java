@@ -0,0 +1,14 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.AbstractMethodError
+ */
+public class AbstractMethodError extends IncompatibleClassChangeError {
+    public AbstractMethodError() {
+        super();
+    }
+
+    public AbstractMethodError(String message) {
+        super(message);
+    }
+}
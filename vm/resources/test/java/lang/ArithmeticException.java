@@ -0,0 +1,14 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.ArithmeticException
+ */
+public class ArithmeticException extends RuntimeException {
+    public ArithmeticException() {
+        super();
+    }
+
+    public ArithmeticException(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,14 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.ArrayIndexOutOfBoundsException
+ */
+public class ArrayIndexOutOfBoundsException extends IndexOutOfBoundsException {
+    public ArrayIndexOutOfBoundsException() {
+        super();
+    }
+
+    public ArrayIndexOutOfBoundsException(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,14 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.ArrayStoreException
+ */
+public class ArrayStoreException extends RuntimeException {
+    public ArrayStoreException() {
+        super();
+    }
+
+    public ArrayStoreException(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,10 @@
+package java.lang;
+
+/**
+ * 测试用的 java.lang.CharSequence
+ */
+public interface CharSequence {
+    int length();
+
+    char charAt(int index);
+}
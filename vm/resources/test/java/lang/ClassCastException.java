@@ -0,0 +1,14 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.ClassCastException
+ */
+public class ClassCastException extends RuntimeException {
+    public ClassCastException() {
+        super();
+    }
+
+    public ClassCastException(String message) {
+        super(message);
+    }
+}
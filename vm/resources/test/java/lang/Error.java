@@ -0,0 +1,18 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.Error
+ */
+public class Error extends Throwable {
+    public Error() {
+        super();
+    }
+
+    public Error(String message) {
+        super(message);
+    }
+
+    public Error(String message, Throwable cause) {
+        super(message, cause);
+    }
+}
@@ -0,0 +1,18 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.Exception
+ */
+public class Exception extends Throwable {
+    public Exception() {
+        super();
+    }
+
+    public Exception(String message) {
+        super(message);
+    }
+
+    public Exception(String message, Throwable cause) {
+        super(message, cause);
+    }
+}
@@ -0,0 +1,20 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.Integer，只保留装箱和拆箱
+ */
+public final class Integer {
+    private final int value;
+
+    public Integer(int value) {
+        this.value = value;
+    }
+
+    public static Integer valueOf(int i) {
+        return new Integer(i);
+    }
+
+    public int intValue() {
+        return value;
+    }
+}
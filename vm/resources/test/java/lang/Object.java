@@ -0,0 +1,9 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.Object，只保留解释器需要的成员
+ */
+public class Object {
+    public Object() {
+    }
+}
@@ -0,0 +1,8 @@
+package java.lang;
+
+/**
+ * 测试用的 java.lang.Runnable
+ */
+public interface Runnable {
+    void run();
+}
@@ -0,0 +1,21 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.Runtime，方法都由VM的native实现提供
+ */
+public class Runtime {
+    private Runtime() {
+    }
+
+    public static native Runtime getRuntime();
+
+    public native void exit(int status);
+
+    public native void halt(int status);
+
+    public native void addShutdownHook(Thread hook);
+
+    public native boolean removeShutdownHook(Thread hook);
+
+    public native void gc();
+}
@@ -0,0 +1,18 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.RuntimeException
+ */
+public class RuntimeException extends Exception {
+    public RuntimeException() {
+        super();
+    }
+
+    public RuntimeException(String message) {
+        super(message);
+    }
+
+    public RuntimeException(String message, Throwable cause) {
+        super(message, cause);
+    }
+}
@@ -0,0 +1,27 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.String，字段布局与VM创建的字符串对象一致：value 是唯一的字段
+ */
+public final class String implements CharSequence {
+    private final char[] value;
+
+    public String() {
+        this.value = new char[0];
+    }
+
+    public String(char[] value) {
+        this.value = new char[value.length];
+        for (int i = 0; i < value.length; i++) {
+            this.value[i] = value[i];
+        }
+    }
+
+    public int length() {
+        return value.length;
+    }
+
+    public char charAt(int index) {
+        return value[index];
+    }
+}
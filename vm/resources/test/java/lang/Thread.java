@@ -0,0 +1,21 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.Thread，只用作关闭钩子：run 调用构造时传入的 Runnable
+ */
+public class Thread implements Runnable {
+    private Runnable target;
+
+    public Thread() {
+    }
+
+    public Thread(Runnable target) {
+        this.target = target;
+    }
+
+    public void run() {
+        if (target != null) {
+            target.run();
+        }
+    }
+}
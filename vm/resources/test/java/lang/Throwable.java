@@ -0,0 +1,44 @@
+package java.lang;
+
+/**
+ * 测试用的最小 java.lang.Throwable
+ * 解释器把内部错误转换为异常时会写入 detailMessage，栈轨迹由 native 方法维护
+ */
+public class Throwable implements java.io.Serializable {
+    private String detailMessage;
+    private Throwable cause;
+
+    public Throwable() {
+        fillInStackTrace();
+    }
+
+    public Throwable(String message) {
+        fillInStackTrace();
+        detailMessage = message;
+    }
+
+    public Throwable(String message, Throwable cause) {
+        fillInStackTrace();
+        detailMessage = message;
+        this.cause = cause;
+    }
+
+    public String getMessage() {
+        return detailMessage;
+    }
+
+    public Throwable getCause() {
+        return cause;
+    }
+
+    public Throwable initCause(Throwable cause) {
+        this.cause = cause;
+        return this;
+    }
+
+    public native Throwable fillInStackTrace();
+
+    public native StackTraceElement[] getStackTrace();
+
+    public native void printStackTrace();
+}
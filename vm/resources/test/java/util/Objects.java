@@ -0,0 +1,16 @@
+package java.util;
+
+/**
+ * 测试用的最小 java.util.Objects，javac为绑定接收者的方法引用生成 requireNonNull 调用
+ */
+public final class Objects {
+    private Objects() {
+    }
+
+    public static <T> T requireNonNull(T obj) {
+        if (obj == null) {
+            throw new NullPointerException();
+        }
+        return obj;
+    }
+}
@@ -1,11 +1,14 @@
 use crate::field::Field;
 use crate::heap::{Heap, RawPtr};
-use crate::method::Method;
+use crate::method::{ArrayType, Method};
+use crate::runtime_constant_pool::RunTimeConstantPool;
 use crate::JvmValue;
+use reader::attribute_info::AttributeInfo;
 use reader::class_file::ClassFile;
-use reader::constant_pool::{ConstantPool, CpInfo};
+use reader::constant_pool::{ConstantPool, ConstantPoolExt, CpInfo};
 use reader::types::U2;
 use std::collections::HashMap;
+use std::rc::Rc;
 use std::process::id;
 use crate::logger::Logger;
 use crate::jvm_log;
@@ -30,7 +33,43 @@ pub enum ComponentType {
     Array(Box<ArrayKlass>),
 }
 
+impl ComponentType {
+    /// 基本类型元素对应的ArrayType，引用类型返回None
+    pub fn array_type(&self) -> Option<ArrayType> {
+        match self {
+            ComponentType::Boolean => Some(ArrayType::Boolean),
+            ComponentType::Char => Some(ArrayType::Char),
+            ComponentType::Float => Some(ArrayType::Float),
+            ComponentType::Double => Some(ArrayType::Double),
+            ComponentType::Byte => Some(ArrayType::Byte),
+            ComponentType::Short => Some(ArrayType::Short),
+            ComponentType::Int => Some(ArrayType::Int),
+            ComponentType::Long => Some(ArrayType::Long),
+            _ => None,
+        }
+    }
+}
+
+/// 接口方法表中的一项：接口方法在实现类上按JVMS选出的最具体的超接口方法
+#[derive(Debug, Clone)]
+pub enum ItableEntry {
+    /// 唯一的最具体的非抽象（default）方法
+    Default(Method),
+    /// 最具体的方法都是抽象的
+    Abstract,
+    /// 多个最具体的default方法冲突，记录声明它们的接口
+    Conflict(Vec<String>),
+}
+
+/// BootstrapMethods属性中的一项：引导方法的MethodHandle常量及其静态参数的常量池下标
 #[derive(Debug, Clone)]
+pub struct BootstrapMethod {
+    pub method_ref: U2,
+    pub arguments: Vec<U2>,
+}
+
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum Klass {
     Instance(InstanceKlass),
     Array(ArrayKlass),
@@ -42,25 +81,62 @@ pub struct InstanceKlass {
     pub(crate) class_name: String,
     pub(crate) class_state: ClassState,
     pub(crate) super_class: String,
+    pub(crate) access_flags: u16,
+    /// 直接实现的接口名
+    pub(crate) interfaces: Vec<String>,
+    /// 所有超接口名，包括父类实现的接口和接口继承的接口
+    pub(crate) all_interfaces: Vec<String>,
+    /// 接口方法表，键为 `name.descriptor`，链接时构建
+    itable: HashMap<String, ItableEntry>,
+    /// 虚方法表：类中声明或继承的实例方法，重写的方法与被重写的方法位于同一表项
+    vtable: Vec<Method>,
+    /// `name.descriptor` -> 虚方法表下标
+    vtable_index: HashMap<String, usize>,
+    /// invokedynamic调用点使用的引导方法
+    bootstrap_methods: Vec<BootstrapMethod>,
     methods: Vec<Method>,
     i_fields: Vec<Field>,
     s_fields: Vec<Field>,
     s_field_val: Vec<JvmValue>,
     m_name_desc_lookup: HashMap<String, usize>,
     f_name_desc_lookup: HashMap<String, usize>,
-    cp: Vec<CpInfo>,
+    cp: Rc<RunTimeConstantPool>,
 }
 
 impl InstanceKlass {
-    pub fn of(class_file: &ClassFile, class_id: usize, heap: &mut Heap, super_klass: Option<&InstanceKlass>) -> InstanceKlass {
+    /// super_interfaces 为该类的所有超接口（含父类实现的接口及其父接口），由类加载器预先加载
+    pub fn of(
+        class_file: &ClassFile,
+        class_id: usize,
+        heap: &mut Heap,
+        super_klass: Option<&InstanceKlass>,
+        super_interfaces: &[InstanceKlass],
+    ) -> InstanceKlass {
         let cp = &class_file.constant_pool;
+        let class_name = class_file.get_class_name();
+        // 类的所有方法共享运行时常量池
+        let runtime_cp = Rc::new(RunTimeConstantPool::new(class_name.clone(), cp.clone()));
 
         // process methods
         let mut m_name_desc_lookup = HashMap::new();
         let mut methods = Vec::new();
-        let class_name = class_file.get_class_name();
-        for (idx, m_info) in (&class_file.methods).iter().enumerate() {
-            let mut method = Method::from_method_info(m_info, cp);
+        let source_file = class_file.attributes.iter().find_map(|attr| match attr {
+            AttributeInfo::SourceFile { sourcefile_index, .. } => Some(cp.get_utf8_string(*sourcefile_index)),
+            _ => None,
+        });
+        let bootstrap_methods = class_file.attributes.iter().find_map(|attr| match attr {
+            AttributeInfo::BootstrapMethods { bootstrap_methods, .. } => Some(
+                bootstrap_methods
+                    .iter()
+                    .map(|(method_ref, _, arguments)| BootstrapMethod { method_ref: *method_ref, arguments: arguments.clone() })
+                    .collect(),
+            ),
+            _ => None,
+        }).unwrap_or_default();
+        for (idx, m_info) in class_file.methods.iter().enumerate() {
+            let mut method = Method::from_method_info(m_info, &runtime_cp);
+            method.class_name = class_name.clone();
+            method.source_file = source_file.clone();
             // 临时修复：只有java/lang/Object.registerNatives才加ACC_NATIVE
             if class_name == "java/lang/Object" && method.name == "registerNatives" && method.descriptor == "()V" {
                 method.access_flags |= 0x0100; // ACC_NATIVE
@@ -95,7 +171,7 @@ impl InstanceKlass {
             jvm_log!("[FieldOffset] 继承后起始偏移: {}", cur_offset);
         }
         for field_info in &class_file.fields {
-            let mut field = Field::new(field_info, cp);
+            let mut field = Field::new(&class_name, field_info, cp);
             if field.is_static() {
                 let default_val = field.get_default();
                 f_name_desc_lookup.insert(field.get_fq_name_desc(), s_fields.len());
@@ -125,19 +201,86 @@ impl InstanceKlass {
             }
         }
 
+        let is_interface = class_file.access_flags & 0x0200 != 0;
+        let itable = Self::build_itable(&methods, is_interface, super_interfaces);
+        let (vtable, vtable_index) = Self::build_vtable(&methods, super_klass);
+
         InstanceKlass {
             class_id,
             class_name: class_file.get_class_name(),
             class_state: ClassState::LOADED,
             super_class: class_file.get_super_class_name(),
+            access_flags: class_file.access_flags,
+            interfaces: class_file.interfaces.iter().map(|&idx| cp.get_class_name(idx)).collect(),
+            all_interfaces: super_interfaces.iter().map(|interface| interface.class_name.clone()).collect(),
+            itable,
+            vtable,
+            vtable_index,
+            bootstrap_methods,
             methods,
             i_fields,
             s_fields,
             s_field_val,
             m_name_desc_lookup,
             f_name_desc_lookup,
-            cp: cp.clone(),
+            cp: runtime_cp,
+        }
+    }
+
+    /// 为超接口中声明的每个实例方法选出最具体的超接口方法（JVMS 5.4.3.3）：
+    /// 声明在另一个候选方法所在接口的父接口中的方法不是最具体的
+    fn build_itable(methods: &[Method], is_interface: bool, super_interfaces: &[InstanceKlass]) -> HashMap<String, ItableEntry> {
+        let is_candidate = |method: &Method| !method.is_static() && !method.is_private() && method.name != "<clinit>";
+        let mut candidates: HashMap<String, Vec<(&Method, &Vec<String>)>> = HashMap::new();
+        for interface in super_interfaces {
+            for method in interface.methods.iter().filter(|method| is_candidate(method)) {
+                candidates.entry(method.get_fq_name_desc()).or_default().push((method, &interface.all_interfaces));
+            }
+        }
+
+        let mut itable = HashMap::new();
+        for (key, methods) in &candidates {
+            let defaults: Vec<&Method> = methods
+                .iter()
+                .filter(|(method, _)| !methods.iter().any(|(_, supers)| supers.contains(&method.class_name)))
+                .map(|(method, _)| *method)
+                .filter(|method| !method.is_abstract())
+                .collect();
+            let entry = match defaults.as_slice() {
+                [] => ItableEntry::Abstract,
+                [method] => ItableEntry::Default((*method).clone()),
+                _ => ItableEntry::Conflict(defaults.iter().map(|method| method.class_name.clone()).collect()),
+            };
+            itable.insert(key.clone(), entry);
         }
+        // 接口自身声明的方法比所有超接口中的都具体
+        if is_interface {
+            for method in methods.iter().filter(|method| is_candidate(method)) {
+                let entry = if method.is_abstract() { ItableEntry::Abstract } else { ItableEntry::Default(method.clone()) };
+                itable.insert(method.get_fq_name_desc(), entry);
+            }
+        }
+        itable
+    }
+
+    /// 构建虚方法表：继承父类的表项，重写的方法替换被重写方法的表项，新声明的方法追加在末尾。
+    /// 超接口的default方法不在虚方法表中，由接口方法表提供
+    fn build_vtable(methods: &[Method], super_klass: Option<&InstanceKlass>) -> (Vec<Method>, HashMap<String, usize>) {
+        let (mut vtable, mut vtable_index) = match super_klass {
+            Some(super_klass) => (super_klass.vtable.clone(), super_klass.vtable_index.clone()),
+            None => (Vec::new(), HashMap::new()),
+        };
+        for method in methods.iter().filter(|method| !method.is_static() && !method.is_private() && method.name != "<init>") {
+            let key = method.get_fq_name_desc();
+            match vtable_index.get(&key) {
+                Some(&index) => vtable[index] = method.clone(),
+                None => {
+                    vtable_index.insert(key, vtable.len());
+                    vtable.push(method.clone());
+                }
+            }
+        }
+        (vtable, vtable_index)
     }
 
     pub fn get_method(&self, method_name: &str, method_desc: &str) -> Option<&Method> {
@@ -147,7 +290,7 @@ impl InstanceKlass {
         Logger::log_fmt(format_args!("[get_method] 所有key: {:?}", self.m_name_desc_lookup.keys().collect::<Vec<_>>()));
         let opt_idx = self.m_name_desc_lookup.get(&fq_name);
         let idx = match opt_idx {
-            Some(value) => value.clone(),
+            Some(value) => *value,
             None => return None,
         };
         self.methods.get(idx)
@@ -172,12 +315,10 @@ impl InstanceKlass {
         if !self.super_class.is_empty() {
             Logger::log_fmt(format_args!("[lookup_method] 递归父类: {} 传递name: {}, desc: {}", self.super_class, method_name, method_desc));
             // 尝试加载父类
-            if let Ok(super_klass) = vm.load(&self.super_class) {
-                if let crate::class::Klass::Instance(super_instance) = &super_klass {
-                    let result = super_instance.lookup_method(method_name, method_desc, vm);
-                    Logger::log_fmt(format_args!("[lookup_method] 父类返回: {:?}", result.as_ref().map(|m| m.name.as_str())));
-                    return result;
-                }
+            if let Ok(crate::class::Klass::Instance(super_instance)) = vm.load_uninitialized(&self.super_class) {
+                let result = super_instance.lookup_method(method_name, method_desc, vm);
+                Logger::log_fmt(format_args!("[lookup_method] 父类返回: {:?}", result.as_ref().map(|m| m.name.as_str())));
+                return result;
             }
         }
         
@@ -194,6 +335,42 @@ impl InstanceKlass {
         &self.super_class
     }
 
+    /// 获取直接实现的接口名
+    pub fn get_interfaces(&self) -> &Vec<String> {
+        &self.interfaces
+    }
+
+    /// 获取所有超接口名
+    pub fn get_all_interfaces(&self) -> &Vec<String> {
+        &self.all_interfaces
+    }
+
+    /// 查找虚方法表中的方法
+    pub fn get_vtable_method(&self, method_name: &str, method_desc: &str) -> Option<&Method> {
+        let index = self.vtable_index.get(&format!("{}.{}", method_name, method_desc))?;
+        self.vtable.get(*index)
+    }
+
+    /// 获取BootstrapMethods属性中下标为index的引导方法
+    pub fn get_bootstrap_method(&self, index: U2) -> Option<&BootstrapMethod> {
+        self.bootstrap_methods.get(index as usize)
+    }
+
+    /// 查找接口方法表中的项
+    pub fn get_itable_entry(&self, method_name: &str, method_desc: &str) -> Option<&ItableEntry> {
+        self.itable.get(&format!("{}.{}", method_name, method_desc))
+    }
+
+    /// 是否为接口（ACC_INTERFACE = 0x0200）
+    pub fn is_interface(&self) -> bool {
+        self.access_flags & 0x0200 != 0
+    }
+
+    /// 类的运行时常量池，类的所有方法共用
+    pub fn get_constant_pool(&self) -> &RunTimeConstantPool {
+        &self.cp
+    }
+
     pub fn get_field_info(&self, cp_index: U2) -> (String, String, String) {
         self.cp.get_field_info(cp_index)
     }
@@ -203,11 +380,19 @@ impl InstanceKlass {
         let opt_idx = self.f_name_desc_lookup.get(&fq_name);
         let idx = match opt_idx {
             None => panic!(),
-            Some(value) => value.clone(),
+            Some(value) => *value,
         };
         self.s_field_val[idx]
     }
 
+    /// 类自身声明的字段（不含继承的字段）
+    pub fn get_declared_field(&self, field_name: &str, field_desc: &str) -> Option<&Field> {
+        self.s_fields
+            .iter()
+            .chain(self.i_fields.iter().filter(|field| field.get_class_name() == self.class_name))
+            .find(|field| field.get_name() == field_name && field.get_descriptor() == field_desc)
+    }
+
     pub fn get_static_fields(&self) -> &Vec<Field> {
         &self.s_fields
     }
@@ -236,6 +421,21 @@ pub struct ArrayKlass {
     pub(crate) component_type: ComponentType,
 }
 
+impl ArrayKlass {
+    /// 元素是否为引用（对象或数组）
+    pub fn has_reference_elements(&self) -> bool {
+        matches!(self.component_type, ComponentType::Object(_) | ComponentType::Array(_))
+    }
+
+    /// 每个元素占用的字节数，引用类型按指针大小计算
+    pub fn get_element_size(&self) -> usize {
+        match self.component_type.array_type() {
+            Some(array_type) => array_type.get_element_size(),
+            None => std::mem::size_of::<RawPtr>(),
+        }
+    }
+}
+
 impl Klass {
     pub fn new_array(
         dimension: usize,
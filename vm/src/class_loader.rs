@@ -1,11 +1,12 @@
 use crate::class::{ArrayKlass, ComponentType, InstanceKlass, Klass};
 use reader::class_path_manager::ClassPathManager;
-use reader::constant_pool::ConstantPool;
+use reader::class_file::ClassFile;
+use reader::constant_pool::{ConstantPool, ConstantPoolExt};
 use std::cell::Cell;
 use std::{cell::RefCell, collections::HashMap};
 use crate::class::Klass::Instance;
-use crate::heap::Heap;
-use std::sync::Arc;
+use crate::heap::{Heap, RawPtr};
+use std::rc::Rc;
 use crate::JvmValue;
 use crate::error::JvmError;
 
@@ -46,9 +47,11 @@ pub struct BootstrapClassLoader {
     /// 类路径管理器，用于查找类文件
     class_path_manager: ClassPathManager,
     /// 已加载的类缓存
-    classes: RefCell<HashMap<String, Arc<RefCell<ClassLoadingInfo>>>>,
-    /// 类ID计数器
-    nxt_id: Cell<usize>,
+    classes: RefCell<HashMap<String, Rc<RefCell<ClassLoadingInfo>>>>,
+    /// 类名 -> 类ID，ID按首次出现顺序分配且对同一类名保持稳定
+    class_ids: RefCell<HashMap<String, usize>>,
+    /// 类ID -> 类名，用于从对象头反查类
+    class_names: RefCell<Vec<String>>,
 }
 
 impl BootstrapClassLoader {
@@ -62,7 +65,76 @@ impl BootstrapClassLoader {
         BootstrapClassLoader {
             class_path_manager,
             classes: RefCell::new(HashMap::new()),
-            nxt_id: Cell::new(0),
+            class_ids: RefCell::new(HashMap::new()),
+            class_names: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// 规范化类名：`LFoo;` 形式转换为 `Foo`，数组描述符保持不变
+    pub fn normalize_class_name(class_name: &str) -> &str {
+        if class_name.starts_with('L') && class_name.ends_with(';') {
+            &class_name[1..class_name.len() - 1]
+        } else {
+            class_name
+        }
+    }
+
+    /// 获取类名对应的类ID，首次出现时分配新ID
+    pub fn class_id_of(&self, class_name: &str) -> usize {
+        let class_name = Self::normalize_class_name(class_name);
+        if let Some(id) = self.class_ids.borrow().get(class_name) {
+            return *id;
+        }
+        let mut class_names = self.class_names.borrow_mut();
+        let id = class_names.len();
+        class_names.push(class_name.to_string());
+        self.class_ids.borrow_mut().insert(class_name.to_string(), id);
+        id
+    }
+
+    /// 根据类ID获取类名
+    pub fn class_name_of(&self, class_id: usize) -> Option<String> {
+        self.class_names.borrow().get(class_id).cloned()
+    }
+
+    /// 在类ID对应的已加载的类上执行f，对象头中只记录类ID。类通过借用访问而不复制，
+    /// f执行期间不能加载类；类未加载时返回None
+    pub fn with_klass<R>(&self, class_id: usize, f: impl FnOnce(&Klass) -> R) -> Option<R> {
+        let class_name = self.class_name_of(class_id)?;
+        self.with_class(&class_name, f)
+    }
+
+    /// 在已加载的类上执行f，类未加载时返回None
+    pub fn with_class<R>(&self, class_name: &str, f: impl FnOnce(&Klass) -> R) -> Option<R> {
+        let classes = self.classes.borrow();
+        let info = classes.get(Self::normalize_class_name(class_name))?.borrow();
+        info.klass.as_ref().map(f)
+    }
+
+    /// 遍历所有已加载完成的类
+    pub fn for_each_class(&self, mut f: impl FnMut(&Klass)) {
+        for info in self.classes.borrow().values() {
+            if let Some(klass) = &info.borrow().klass {
+                f(klass);
+            }
+        }
+    }
+
+    /// 访问类加载信息和类中保存的静态字段中的引用
+    pub fn visit_static_refs(&self, visitor: &mut dyn FnMut(&mut RawPtr)) {
+        for info in self.classes.borrow().values() {
+            let mut info = info.borrow_mut();
+            let info = &mut *info;
+            let prepared = info.static_fields.iter_mut().flatten();
+            let declared = match info.klass.as_mut() {
+                Some(Klass::Instance(instance)) => Some(instance.get_static_field_values_mut()),
+                _ => None,
+            };
+            for value in prepared.chain(declared.into_iter().flatten()) {
+                if let JvmValue::ObjRef(obj) = value {
+                    visitor(obj);
+                }
+            }
         }
     }
 
@@ -145,7 +217,7 @@ impl BootstrapClassLoader {
 
     /// 初始化类
     /// 执行静态初始化块
-    pub fn initialize_class(&self, class_name: &str, heap: &mut Heap, vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    pub fn initialize_class(&self, class_name: &str, vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
         let class_info = self.get_or_create_class_info(class_name);
 
         // 先判断状态，避免递归 borrow
@@ -174,8 +246,8 @@ impl BootstrapClassLoader {
         if let Klass::Instance(instance) = &klass {
             // 执行静态初始化块
             if let Some(clinit) = instance.get_method("<clinit>", "()V") {
-                let mut thread = crate::jvm_thread::JvmThread::new(65536, 128);
-                thread.execute(clinit, heap, vm)?;
+                let mut thread = crate::jvm_thread::JvmThread::with_frame(crate::jvm_thread::Frame::new(clinit));
+                thread.execute(clinit, vm)?;
             }
         }
 
@@ -183,13 +255,14 @@ impl BootstrapClassLoader {
     }
 
     /// 获取或创建类加载信息
-    fn get_or_create_class_info(&self, class_name: &str) -> Arc<RefCell<ClassLoadingInfo>> {
+    fn get_or_create_class_info(&self, class_name: &str) -> Rc<RefCell<ClassLoadingInfo>> {
+        let class_name = Self::normalize_class_name(class_name);
         let mut classes = self.classes.borrow_mut();
         if let Some(info) = classes.get(class_name) {
             return info.clone();
         }
         
-        let info = Arc::new(RefCell::new(ClassLoadingInfo {
+        let info = Rc::new(RefCell::new(ClassLoadingInfo {
             state: ClassLoadingState::NotLoaded,
             klass: None,
             error: None,
@@ -201,31 +274,30 @@ impl BootstrapClassLoader {
 
     fn do_load_class(&self, class_name: &str, heap: &mut Heap) -> Result<Klass, Box<dyn std::error::Error>> {
         let klass = if class_name.starts_with('[') {
-            Klass::Array(self.do_load_array(class_name, heap))
+            Klass::Array(self.do_load_array(class_name, heap)?)
         } else {
-            Klass::Instance(self.do_load_instance(class_name, heap))
+            Klass::Instance(self.do_load_instance(class_name, heap)?)
         };
         Ok(klass)
     }
 
-    fn do_load_array(&self, class_name: &str, heap: &mut Heap) -> ArrayKlass {
+    fn do_load_array(&self, class_name: &str, heap: &mut Heap) -> Result<ArrayKlass, JvmError> {
         let dimension_size = class_name
             .chars()
-            .into_iter()
             .take_while(|&ch| ch == '[')
             .count();
-        let element_type = self.load_element_type(&class_name[1..], heap);
-        Klass::new_array(dimension_size, element_type, self.nxt_id.get())
+        let element_type = self.load_element_type(&class_name[1..], heap)?;
+        Ok(Klass::new_array(dimension_size, element_type, self.class_id_of(class_name)))
     }
 
-    fn load_element_type(&self, element_type: &str, heap: &mut Heap) -> ComponentType {
-        match element_type.chars().next().unwrap() {
+    fn load_element_type(&self, element_type: &str, heap: &mut Heap) -> Result<ComponentType, JvmError> {
+        let component = match element_type.chars().next().unwrap() {
             '[' => {
-                let array_klass = self.do_load_array(element_type, heap);
+                let array_klass = self.do_load_array(element_type, heap)?;
                 ComponentType::Array(Box::new(array_klass))
             }
             'L' => {
-                let instance_klass = self.do_load_instance(element_type, heap);
+                let instance_klass = self.do_load_instance(element_type, heap)?;
                 ComponentType::Object(Box::new(instance_klass))
             }
             'B' => ComponentType::Byte,
@@ -237,31 +309,76 @@ impl BootstrapClassLoader {
             'F' => ComponentType::Float,
             'D' => ComponentType::Double,
             'V' => ComponentType::Void,
-            _ => panic!("Unknown element type {}", element_type),
-        }
+            _ => return Err(JvmError::ClassNotFoundError(format!("Unknown element type {}", element_type))),
+        };
+        Ok(component)
     }
 
-    fn do_load_instance(&self, class_name: &str, heap: &mut Heap) -> InstanceKlass {
-        let class_name = class_name.trim_start_matches('L').trim_end_matches(';');
+    fn do_load_instance(&self, class_name: &str, heap: &mut Heap) -> Result<InstanceKlass, JvmError> {
+        let class_name = Self::normalize_class_name(class_name);
         let class_file = self
             .class_path_manager
             .search_class(class_name)
-            .unwrap_or_else(|_| panic!("class {} not found", class_name));
+            .map_err(|_| JvmError::ClassNotFoundError(format!("class {} not found", class_name)))?;
+        self.link_instance(class_name, &class_file, heap)
+    }
+
+    /// 链接类文件：加载父类和超接口，构建InstanceKlass
+    fn link_instance(&self, class_name: &str, class_file: &ClassFile, heap: &mut Heap) -> Result<InstanceKlass, JvmError> {
         // 递归加载父类InstanceKlass
         let super_klass = if !class_file.get_super_class_name().is_empty() {
             let super_class_name = class_file.get_super_class_name();
-            let super_klass = self.do_load_instance(super_class_name.as_str(), heap);
+            let super_klass = self.do_load_instance(super_class_name.as_str(), heap)?;
             Some(Box::new(super_klass))
         } else {
             None
         };
         let super_klass_ref = super_klass.as_deref();
-        InstanceKlass::of(&class_file, self.nxt_id.get(), heap, super_klass_ref)
+        let super_interfaces = self.load_super_interfaces(class_file, super_klass_ref, heap)?;
+        Ok(InstanceKlass::of(class_file, self.class_id_of(class_name), heap, super_klass_ref, &super_interfaces))
+    }
+
+    /// 定义运行时生成的类（如lambda的实现类），之后与从类路径加载的类一样通过类名查找
+    pub fn define_class(&self, class_file: &ClassFile, heap: &mut Heap) -> Result<Klass, JvmError> {
+        let class_name = class_file.get_class_name();
+        let class_info = self.get_or_create_class_info(&class_name);
+        if class_info.borrow().state != ClassLoadingState::NotLoaded {
+            return Err(JvmError::IllegalStateError(format!("类 {} 已经定义", class_name)));
+        }
+        let klass = Klass::Instance(self.link_instance(&class_name, class_file, heap)?);
+        let mut info = class_info.borrow_mut();
+        info.klass = Some(klass.clone());
+        info.state = ClassLoadingState::Loaded;
+        self.prepare_class(&mut info, heap)?;
+        Ok(klass)
+    }
+
+    /// 加载类的所有超接口：父类的超接口，以及直接实现的接口和它们的超接口。
+    /// 链接时用它们构建接口方法表
+    fn load_super_interfaces(&self, class_file: &ClassFile, super_klass: Option<&InstanceKlass>, heap: &mut Heap) -> Result<Vec<InstanceKlass>, JvmError> {
+        let mut names: Vec<String> = super_klass.map(|klass| klass.get_all_interfaces().clone()).unwrap_or_default();
+        for &index in &class_file.interfaces {
+            let interface_name = class_file.constant_pool.get_class_name(index);
+            let interface = self.load_interface(&interface_name, heap)?;
+            for name in std::iter::once(&interface_name).chain(interface.get_all_interfaces()) {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+        }
+        names.iter().map(|name| self.load_interface(name, heap)).collect()
+    }
+
+    fn load_interface(&self, interface_name: &str, heap: &mut Heap) -> Result<InstanceKlass, JvmError> {
+        match self.load(interface_name, heap)? {
+            Klass::Instance(interface) if interface.is_interface() => Ok(interface),
+            _ => Err(JvmError::IncompatibleClassChangeError(format!("{} is not an interface", interface_name.replace('/', ".")))),
+        }
     }
 
     /// 设置静态字段值
     pub fn set_static_field(&self, class_name: &str, field_name: &str, value: crate::JvmValue, heap: &mut crate::heap::Heap) {
-        if let Some(class_info) = self.classes.borrow().get(class_name) {
+        if let Some(class_info) = self.classes.borrow().get(Self::normalize_class_name(class_name)) {
             let mut info = class_info.borrow_mut();
             if let Some(Klass::Instance(ref mut instance)) = info.klass {
                 let idx = instance.get_static_fields()
@@ -277,7 +394,7 @@ impl BootstrapClassLoader {
 
     /// 获取静态字段值
     pub fn get_static_field(&self, class_name: &str, field_name: &str) -> Option<crate::JvmValue> {
-        if let Some(class_info) = self.classes.borrow().get(class_name) {
+        if let Some(class_info) = self.classes.borrow().get(Self::normalize_class_name(class_name)) {
             let info = class_info.borrow();
             if let Some(Klass::Instance(ref instance)) = info.klass {
                 let fields = instance.get_static_fields();
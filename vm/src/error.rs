@@ -1,15 +1,85 @@
+use std::cell::Cell;
 use std::fmt;
+use std::rc::{Rc, Weak};
+
+use crate::heap::{AllocError, RawPtr};
 
 #[derive(Debug)]
 pub enum JvmError {
     ArithmeticError(String),
     NullPointerError(String),
+    ArrayIndexOutOfBoundsError(String),
+    ArrayStoreError(String),
+    NegativeArraySizeError(String),
+    ClassCastError(String),
+    IncompatibleClassChangeError(String),
+    AbstractMethodError(String),
     ClassNotFoundError(String),
+    NoSuchFieldError(String),
     NoSuchMethodError(String),
+    IllegalAccessError(String),
     IllegalStateError(String),
     StackOverflowError(String),
+    StackUnderflowError(String),
     OutOfMemoryError(String),
     Unimplemented(String),
+    /// 已抛出的Java异常对象，沿调用栈逐帧展开直到被异常表捕获
+    JavaException(ThrownException),
+    /// System.exit/Runtime.exit请求以给定状态码退出：不经过异常表展开所有帧，运行关闭钩子后终止
+    Exit(i32),
+    /// Runtime.halt请求立即以给定状态码终止，不运行关闭钩子
+    Halt(i32),
+}
+
+impl JvmError {
+    /// 解释器内部错误对应的Java异常类，没有对应类的错误不会被Java代码捕获
+    pub fn java_exception_class(&self) -> Option<&'static str> {
+        match self {
+            JvmError::ArithmeticError(_) => Some("java/lang/ArithmeticException"),
+            JvmError::NullPointerError(_) => Some("java/lang/NullPointerException"),
+            JvmError::ArrayIndexOutOfBoundsError(_) => Some("java/lang/ArrayIndexOutOfBoundsException"),
+            JvmError::ArrayStoreError(_) => Some("java/lang/ArrayStoreException"),
+            JvmError::NegativeArraySizeError(_) => Some("java/lang/NegativeArraySizeException"),
+            JvmError::ClassCastError(_) => Some("java/lang/ClassCastException"),
+            JvmError::IncompatibleClassChangeError(_) => Some("java/lang/IncompatibleClassChangeError"),
+            JvmError::AbstractMethodError(_) => Some("java/lang/AbstractMethodError"),
+            JvmError::NoSuchFieldError(_) => Some("java/lang/NoSuchFieldError"),
+            JvmError::NoSuchMethodError(_) => Some("java/lang/NoSuchMethodError"),
+            JvmError::IllegalAccessError(_) => Some("java/lang/IllegalAccessError"),
+            JvmError::StackOverflowError(_) => Some("java/lang/StackOverflowError"),
+            JvmError::OutOfMemoryError(_) => Some("java/lang/OutOfMemoryError"),
+            _ => None,
+        }
+    }
+
+    /// 是否是Java代码可以捕获的异常（已抛出的异常对象或可转换为Java异常的内部错误）
+    pub fn is_java_exception(&self) -> bool {
+        matches!(self, JvmError::JavaException(_)) || self.java_exception_class().is_some()
+    }
+
+    /// 内部错误携带的消息，作为Java异常的detailMessage
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            JvmError::ArithmeticError(msg)
+            | JvmError::NullPointerError(msg)
+            | JvmError::ArrayIndexOutOfBoundsError(msg)
+            | JvmError::ArrayStoreError(msg)
+            | JvmError::NegativeArraySizeError(msg)
+            | JvmError::ClassCastError(msg)
+            | JvmError::IncompatibleClassChangeError(msg)
+            | JvmError::AbstractMethodError(msg)
+            | JvmError::ClassNotFoundError(msg)
+            | JvmError::NoSuchFieldError(msg)
+            | JvmError::NoSuchMethodError(msg)
+            | JvmError::IllegalAccessError(msg)
+            | JvmError::IllegalStateError(msg)
+            | JvmError::StackOverflowError(msg)
+            | JvmError::StackUnderflowError(msg)
+            | JvmError::OutOfMemoryError(msg)
+            | JvmError::Unimplemented(msg) => Some(msg),
+            JvmError::JavaException(_) | JvmError::Exit(_) | JvmError::Halt(_) => None,
+        }
+    }
 }
 
 impl fmt::Display for JvmError {
@@ -17,14 +87,57 @@ impl fmt::Display for JvmError {
         match self {
             JvmError::ArithmeticError(msg) => write!(f, "ArithmeticError: {}", msg),
             JvmError::NullPointerError(msg) => write!(f, "NullPointerError: {}", msg),
+            JvmError::ArrayIndexOutOfBoundsError(msg) => write!(f, "ArrayIndexOutOfBoundsError: {}", msg),
+            JvmError::ArrayStoreError(msg) => write!(f, "ArrayStoreError: {}", msg),
+            JvmError::NegativeArraySizeError(msg) => write!(f, "NegativeArraySizeError: {}", msg),
+            JvmError::ClassCastError(msg) => write!(f, "ClassCastError: {}", msg),
+            JvmError::IncompatibleClassChangeError(msg) => write!(f, "IncompatibleClassChangeError: {}", msg),
+            JvmError::AbstractMethodError(msg) => write!(f, "AbstractMethodError: {}", msg),
             JvmError::ClassNotFoundError(msg) => write!(f, "ClassNotFoundError: {}", msg),
+            JvmError::NoSuchFieldError(msg) => write!(f, "NoSuchFieldError: {}", msg),
             JvmError::NoSuchMethodError(msg) => write!(f, "NoSuchMethodError: {}", msg),
+            JvmError::IllegalAccessError(msg) => write!(f, "IllegalAccessError: {}", msg),
             JvmError::IllegalStateError(msg) => write!(f, "IllegalStateError: {}", msg),
             JvmError::StackOverflowError(msg) => write!(f, "StackOverflowError: {}", msg),
+            JvmError::StackUnderflowError(msg) => write!(f, "StackUnderflowError: {}", msg),
             JvmError::OutOfMemoryError(msg) => write!(f, "OutOfMemoryError: {}", msg),
             JvmError::Unimplemented(msg) => write!(f, "Unimplemented: {}", msg),
+            JvmError::JavaException(obj) => write!(f, "JavaException: {:?}", obj.get()),
+            JvmError::Exit(status) => write!(f, "Exit: status {}", status),
+            JvmError::Halt(status) => write!(f, "Halt: status {}", status),
         }
     }
 }
 
-impl std::error::Error for JvmError {} 
\ No newline at end of file
+impl std::error::Error for JvmError {}
+
+/// 正在展开的异常对象的句柄，由 Vm::throw 创建并登记为垃圾收集的根：
+/// 错误值存活期间对象不会被回收，移动后句柄随之更新
+#[derive(Debug, Clone)]
+pub struct ThrownException(Rc<Cell<RawPtr>>);
+
+impl ThrownException {
+    pub(crate) fn new(exception: RawPtr) -> Self {
+        ThrownException(Rc::new(Cell::new(exception)))
+    }
+
+    /// 异常对象当前的地址
+    pub fn get(&self) -> RawPtr {
+        self.0.get()
+    }
+
+    /// 根枚举使用的弱引用，错误值丢弃后失效
+    pub(crate) fn downgrade(&self) -> Weak<Cell<RawPtr>> {
+        Rc::downgrade(&self.0)
+    }
+}
+
+/// 完整收集并扩大到最大堆之后仍然无法分配时抛出OutOfMemoryError
+impl From<AllocError> for JvmError {
+    fn from(err: AllocError) -> Self {
+        match err {
+            AllocError::OOM => JvmError::OutOfMemoryError("Java heap space".to_string()),
+            AllocError::BadRequest => JvmError::IllegalStateError("无效的分配请求".to_string()),
+        }
+    }
+} 
\ No newline at end of file
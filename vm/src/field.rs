@@ -21,14 +21,17 @@ pub enum ValueType {
 
 #[derive(Debug, Clone)]
 pub struct Field {
+    /// 声明该字段的类
+    class_name: String,
     name: String,
     descriptor: String,
     access_flags: U2,
     offset: usize,
 }
 impl Field {
-    pub fn new(field_info: &FieldInfo, cp_pool: &dyn ConstantPool) -> Field {
+    pub fn new(class_name: &str, field_info: &FieldInfo, cp_pool: &dyn ConstantPool) -> Field {
         Field {
+            class_name: class_name.to_string(),
             name: cp_pool.get_utf8_string(field_info.name_index),
             descriptor: cp_pool.get_utf8_string(field_info.descriptor_index),
             access_flags: field_info.access_flags,
@@ -40,6 +43,10 @@ impl Field {
         format!("{}.{}", self.name, self.descriptor)
     }
 
+    pub fn get_class_name(&self) -> &str {
+        &self.class_name
+    }
+
     pub fn get_name(&self) -> String {
         self.name.clone()
     }
@@ -48,9 +55,14 @@ impl Field {
         self.descriptor.clone()
     }
 
+    pub fn get_access_flags(&self) -> U2 {
+        self.access_flags
+    }
+
     pub fn is_static(&self) -> bool {
         self.access_flags & ACC_STATIC == ACC_STATIC
     }
+
     
     pub fn get_default(&self) -> JvmValue {
         match self.descriptor.as_str() {
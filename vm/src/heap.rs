@@ -10,21 +10,81 @@ use reader::types::{U1, U2, U4, U8};
 use crate::JvmValue;
 use std::hash::{Hash, Hasher};
 use crate::jvm_log;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
 
+/// 对象头的标记字：收集状态、年龄和identity hash
 #[bitfield(u64)]
 #[derive(PartialEq, Eq)]
-pub(crate) struct Header {
-    #[bits(10)]
-    pub(crate) class_id: usize,
-
+pub(crate) struct MarkWord {
     #[bits(1)]
     pub(crate) state: GcState,
 
-    #[bits(30)]
+    /// 对象在新生代收集中存活的次数
+    #[bits(4)]
+    pub(crate) age: u8,
+
+    #[bits(31)]
     identity_hash_code: i32,
 
-    #[bits(23)]
-    pub(crate) size: usize,
+    #[bits(28)]
+    __: u32,
+}
+
+/// 对象头：标记字之后是32位的类ID和以8字节为单位的对象大小，共16字节。
+/// 数组在对象头之后还有单独的8字节长度字，元素从ARRAY_BASE_OFFSET开始
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Header {
+    mark: MarkWord,
+    class_id: u32,
+    size_in_words: u32,
+}
+
+/// 对象头能表示的最大对象大小（字节）
+pub(crate) const MAX_OBJECT_SIZE: usize = u32::MAX as usize * 8;
+
+/// 数组元素相对于对象起始地址的偏移：对象头加长度字
+pub(crate) const ARRAY_BASE_OFFSET: usize = std::mem::size_of::<Header>() + 8;
+
+impl Header {
+    /// 新分配对象的对象头。调试构建中断言类ID和大小没有被截断
+    pub(crate) fn new(class_id: usize, size: usize) -> Self {
+        debug_assert!(class_id <= u32::MAX as usize, "类ID{}超出对象头的表示范围", class_id);
+        debug_assert!(size.is_multiple_of(8) && size <= MAX_OBJECT_SIZE, "对象大小{}超出对象头的表示范围", size);
+        let header = Header {
+            mark: MarkWord::new().with_state(GcState::Unmarked).with_age(0).with_identity_hash_code(0),
+            class_id: class_id as u32,
+            size_in_words: (size / 8) as u32,
+        };
+        debug_assert_eq!((header.class_id(), header.size()), (class_id, size));
+        header
+    }
+
+    pub(crate) fn class_id(&self) -> usize {
+        self.class_id as usize
+    }
+
+    /// 对象占用的字节数，包括对象头
+    pub(crate) fn size(&self) -> usize {
+        self.size_in_words as usize * 8
+    }
+
+    pub(crate) fn state(&self) -> GcState {
+        self.mark.state()
+    }
+
+    pub(crate) fn set_state(&mut self, state: GcState) {
+        self.mark.set_state(state);
+    }
+
+    pub(crate) fn age(&self) -> u8 {
+        self.mark.age()
+    }
+
+    pub(crate) fn set_age(&mut self, age: u8) {
+        self.mark.set_age(age);
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -170,33 +230,665 @@ impl MemoryChunk {
     }
 
     unsafe fn contains(&self, ptr: *const u8) -> bool {
-        ptr >= self.memory && ptr <= self.memory.add(self.used)
+        ptr >= self.memory && ptr < self.memory.add(self.used)
     }
 
     fn reset(&mut self) {
+        // Zero the memory, to attempt and catch bugs. Memory past `used` was never handed out
+        // and is still zeroed, so large chunks only pay for what they used
+        unsafe {
+            std::ptr::write_bytes(self.memory, 0, self.used);
+        }
         self.used = 0;
+    }
+}
 
-        // Zero the memory, to attempt and catch bugs
-        unsafe {
-            std::ptr::write_bytes(self.memory, 0, self.capacity);
+impl Drop for MemoryChunk {
+    fn drop(&mut self) {
+        let layout = Layout::from_size_align(self.capacity, 8).unwrap();
+        unsafe { std::alloc::dealloc(self.memory, layout) };
+    }
+}
+
+/// 根引用所在的位置，根枚举时随引用一起交给访问者
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RootKind {
+    /// 帧的局部变量
+    Local,
+    /// 帧的操作数栈
+    Operand,
+    /// 类的静态字段
+    StaticField,
+    /// 运行时常量池中已解析的字符串常量
+    StringConstant,
+    /// Runtime单例和已注册的关闭钩子
+    Runtime,
+    /// native代码登记的句柄
+    Handle,
+    /// 正在查找异常处理器或沿调用栈展开的异常对象
+    PendingException,
+    /// VM预先分配的对象，如堆耗尽时抛出的OutOfMemoryError
+    Reserved,
+}
+
+/// 对象中引用的位置，收集器按对象头中的类ID查询
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReferenceMap {
+    /// 实例对象的引用字段，偏移从对象头之后算起
+    Fields(Vec<usize>),
+    /// 引用类型的数组，length之后的每个元素都是引用
+    Elements,
+    /// 不包含引用：基本类型数组，或没有引用字段的对象
+    Empty,
+}
+
+/// 一次垃圾收集的统计信息
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    /// 收集后存活的对象数：新生代收集只统计新生代中的对象
+    pub live_objects: usize,
+    /// 存活对象占用的字节数
+    pub live_bytes: usize,
+    /// 回收的字节数
+    pub freed_bytes: usize,
+    /// 晋升到老年代的对象数
+    pub promoted_objects: usize,
+    /// 晋升到老年代的字节数
+    pub promoted_bytes: usize,
+}
+
+/// 卡表中一张卡对应的老年代字节数
+const CARD_SIZE: usize = 512;
+
+/// 对象在新生代收集中存活这么多次后晋升到老年代
+pub const TENURING_THRESHOLD: u8 = 3;
+
+/// 对象头中年龄位能表示的最大年龄
+const MAX_AGE: u8 = 15;
+
+/// 老年代的卡表：写屏障把保存了新生代引用的老年代对象所在的卡标记为脏，
+/// 新生代收集只扫描脏卡上的对象而不是整个老年代。
+/// starts记录覆盖每张卡起始地址的对象的偏移，从它开始可以找到卡上的每个对象
+struct CardTable {
+    dirty: Vec<bool>,
+    starts: Vec<usize>,
+}
+
+impl CardTable {
+    fn new(capacity: usize) -> Self {
+        let cards = capacity.div_ceil(CARD_SIZE);
+        CardTable {
+            dirty: vec![false; cards],
+            starts: vec![0; cards],
+        }
+    }
+
+    /// 登记老年代中从offset开始的size字节的对象
+    fn record_object(&mut self, offset: usize, size: usize) {
+        for card in offset.div_ceil(CARD_SIZE)..(offset + size).div_ceil(CARD_SIZE) {
+            self.starts[card] = offset;
         }
     }
+
+    /// 把从offset开始的对象所在的卡标记为脏
+    fn mark(&mut self, offset: usize) {
+        self.dirty[offset / CARD_SIZE] = true;
+    }
 }
 
+/// 按类ID缓存的对象引用位置
+#[derive(Default)]
+struct ReferenceMaps {
+    maps: HashMap<usize, Rc<ReferenceMap>>,
+}
+
+impl ReferenceMaps {
+    fn of(&mut self, obj: *mut u8, reference_map: &mut impl FnMut(usize) -> ReferenceMap) -> Rc<ReferenceMap> {
+        let class_id = unsafe { (*(obj as *const Header)).class_id() };
+        self.maps.entry(class_id).or_insert_with(|| Rc::new(reference_map(class_id))).clone()
+    }
+}
+
+/// 对对象中的每个引用槽调用f
+unsafe fn for_each_reference(obj: *mut u8, map: &ReferenceMap, mut f: impl FnMut(&mut RawPtr)) {
+    let header_size = std::mem::size_of::<Header>();
+    let size = (*(obj as *const Header)).size();
+    match map {
+        ReferenceMap::Fields(offsets) => {
+            // 简化创建的对象可能比类声明的字段布局小，只处理对象范围内的字段
+            for offset in offsets.iter().filter(|&offset| header_size + offset + 8 <= size) {
+                f(&mut *(obj.add(header_size + offset) as *mut RawPtr));
+            }
+        }
+        ReferenceMap::Elements => {
+            let length = *(obj.add(header_size) as *const usize);
+            for index in 0..length {
+                f(&mut *(Heap::array_element_addr(RawPtr(obj), index, 8) as *mut RawPtr));
+            }
+        }
+        ReferenceMap::Empty => {}
+    }
+}
+
+/// 大对象空间中的一个对象：单独分配的内存块，收集时不移动。
+/// dirty相当于这个对象的卡，写屏障在它保存新生代引用时设置
+struct LargeObject {
+    chunk: MemoryChunk,
+    dirty: bool,
+}
+
+/// 分代的堆。新生代是一对大小固定的半区，新对象在当前半区中分配；
+/// 老年代按顺序分配，保存晋升的对象，完整收集时整理并可以扩大到最大堆；
+/// 超过半区一半的大对象在大对象空间中单独分配，与老年代共用最大堆的额度
 pub struct Heap{
     cur: MemoryChunk,
     nxt: MemoryChunk,
+    old: MemoryChunk,
+    cards: CardTable,
+    // 大对象空间，按对象地址排序
+    large: BTreeMap<usize, LargeObject>,
+    large_used: usize,
+    // 老年代的初始容量，收缩时不小于它
+    min_old_capacity: usize,
+    // 老年代和大对象空间一共可以使用的最大容量
+    max_old_capacity: usize,
+}
+
+/// 一次Cheney式的新生代收集：根和脏卡上的老年代对象引用的新生代对象先被复制，再按复制的顺序扫描
+/// 复制出的对象，把它们引用的新生代对象依次复制过去，扫描追上分配位置时所有存活对象都已复制。
+/// 存活次数达到晋升阈值的对象复制到老年代，其余复制到另一个半区并增加年龄。
+/// 已复制对象的原对象头标记为Marked，对象头之后的第一个字存放新地址（转发指针）。
+/// 停顿时间只与新生代的大小和脏卡的数量有关，与老年代的大小无关
+pub struct Collection<'a> {
+    heap: &'a mut Heap,
+    used_before: usize,
+    // 收集开始时老年代的使用量，之后的对象是这次收集晋升的
+    old_used_before: usize,
+    maps: ReferenceMaps,
+    stats: GcStats,
+}
+
+impl Collection<'_> {
+    /// 更新一个引用：指向新生代当前半区的对象被复制（已复制过的直接使用转发地址），
+    /// null、老年代和堆外的引用（如System.out这类伪造引用）保持不变
+    pub fn evacuate(&mut self, slot: &mut RawPtr) {
+        if slot.is_null() || !unsafe { self.heap.cur.contains(slot.0) } {
+            return;
+        }
+        let header_size = std::mem::size_of::<Header>();
+        unsafe {
+            let header = &mut *(slot.0 as *mut Header);
+            let forwarding = slot.0.add(header_size) as *mut RawPtr;
+            if header.state() == GcState::Marked {
+                *slot = *forwarding;
+                return;
+            }
+            let size = header.size();
+            let age = (header.age() + 1).min(MAX_AGE);
+            // 老年代放不下时留在新生代，两个半区容量相同，存活对象总能放进另一个半区
+            let promoted = if age >= TENURING_THRESHOLD { self.heap.alloc_old(size) } else { None };
+            let copy = match promoted {
+                Some(copy) => {
+                    self.stats.promoted_objects += 1;
+                    self.stats.promoted_bytes += size;
+                    copy
+                }
+                None => self.heap.nxt.alloc(size).expect("to-space overflow during collection"),
+            };
+            std::ptr::copy_nonoverlapping(slot.0, copy.0, size);
+            (*(copy.0 as *mut Header)).set_age(age);
+            header.set_state(GcState::Marked);
+            *forwarding = copy;
+            self.stats.live_objects += 1;
+            self.stats.live_bytes += size;
+            *slot = copy;
+        }
+    }
+
+    /// 扫描脏卡上的老年代对象和已复制的对象并复制它们引用的对象，直到没有新的对象被复制
+    pub fn trace(&mut self, mut reference_map: impl FnMut(usize) -> ReferenceMap) {
+        self.scan_dirty_cards(&mut reference_map);
+        let mut young_scan = 0;
+        let mut old_scan = self.old_used_before;
+        loop {
+            if young_scan < self.heap.nxt.used {
+                let obj = unsafe { self.heap.nxt.memory.add(young_scan) };
+                young_scan += unsafe { (*(obj as *const Header)).size() };
+                self.scan_object(obj, &mut reference_map);
+            } else if old_scan < self.heap.old.used {
+                // 晋升的对象仍然引用新生代对象时登记到卡表，下次新生代收集从这里找到它们
+                let offset = old_scan;
+                let obj = unsafe { self.heap.old.memory.add(offset) };
+                old_scan += unsafe { (*(obj as *const Header)).size() };
+                if self.scan_object(obj, &mut reference_map) {
+                    self.heap.cards.mark(offset);
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// 脏卡上开始的老年代对象和标记为脏的大对象作为根，扫描后仍然引用新生代的对象保持为脏
+    fn scan_dirty_cards(&mut self, reference_map: &mut impl FnMut(usize) -> ReferenceMap) {
+        let dirty_large: Vec<usize> = self.heap.large.iter().filter(|(_, object)| object.dirty).map(|(&obj, _)| obj).collect();
+        for obj in dirty_large {
+            let has_young = self.scan_object(obj as *mut u8, reference_map);
+            self.heap.large.get_mut(&obj).expect("大对象在新生代收集中不会被释放").dirty = has_young;
+        }
+        for card in 0..self.heap.cards.dirty.len() {
+            if !self.heap.cards.dirty[card] {
+                continue;
+            }
+            self.heap.cards.dirty[card] = false;
+            let card_start = card * CARD_SIZE;
+            let card_end = (card_start + CARD_SIZE).min(self.old_used_before);
+            let mut offset = self.heap.cards.starts[card];
+            while offset < card_end {
+                let obj = unsafe { self.heap.old.memory.add(offset) };
+                let size = unsafe { (*(obj as *const Header)).size() };
+                if offset >= card_start && self.scan_object(obj, reference_map) {
+                    self.heap.cards.mark(offset);
+                }
+                offset += size;
+            }
+        }
+    }
+
+    /// 复制对象引用的新生代对象，返回对象是否仍然引用新生代
+    fn scan_object(&mut self, obj: *mut u8, reference_map: &mut impl FnMut(usize) -> ReferenceMap) -> bool {
+        let map = self.maps.of(obj, reference_map);
+        let mut has_young = false;
+        unsafe {
+            for_each_reference(obj, &map, |slot| {
+                self.evacuate(slot);
+                has_young |= self.heap.nxt.contains(slot.0);
+            });
+        }
+        has_young
+    }
+
+    /// 对象在收集后的地址：存活对象返回新地址，不可达的新生代对象返回None，老年代和堆外的引用原样返回。
+    /// 用于更新以对象为键的附属表
+    pub fn forwarded(&self, obj: RawPtr) -> Option<RawPtr> {
+        if obj.is_null() || !unsafe { self.heap.cur.contains(obj.0) } {
+            return Some(obj);
+        }
+        let header = unsafe { *(obj.0 as *const Header) };
+        if header.state() != GcState::Marked {
+            return None;
+        }
+        Some(unsafe { *(obj.0.add(std::mem::size_of::<Header>()) as *const RawPtr) })
+    }
+
+    /// 交换新生代的两个半区并清空原来的半区
+    pub fn finish(self) -> GcStats {
+        std::mem::swap(&mut self.heap.cur, &mut self.heap.nxt);
+        self.heap.nxt.reset();
+        GcStats {
+            freed_bytes: self.used_before - self.heap.cur.used - self.stats.promoted_bytes,
+            ..self.stats
+        }
+    }
+}
+
+/// 一次完整收集：标记新生代和老年代中所有可达的对象，为它们计算整理后的地址（老年代对象在前，
+/// 新生代对象随后晋升，老年代放不下的留在新生代的另一个半区），更新根和对象中的引用后再移动对象。
+/// 老年代放不下存活对象或存活率高时整理到扩大一倍的新老年代中
+pub struct FullCollection<'a> {
+    heap: &'a mut Heap,
+    used_before: usize,
+    gray: Vec<*mut u8>,
+    maps: ReferenceMaps,
+    forwarding: HashMap<usize, RawPtr>,
+    // 存活对象的原地址、新地址和大小，老年代的对象按地址顺序在前
+    moves: Vec<(*mut u8, *mut u8, usize)>,
+    // 扩大后的老年代
+    new_old: Option<MemoryChunk>,
+    old_used_after: usize,
+    stats: GcStats,
+}
+
+impl FullCollection<'_> {
+    fn in_heap(&self, obj: *mut u8) -> bool {
+        self.heap.contains(RawPtr(obj))
+    }
+
+    /// 已标记的大对象的地址和大小
+    fn marked_large_objects(&self) -> Vec<(*mut u8, usize)> {
+        self.heap
+            .large
+            .keys()
+            .map(|&obj| obj as *mut u8)
+            .filter(|&obj| unsafe { (*(obj as *const Header)).state() } == GcState::Marked)
+            .map(|obj| (obj, unsafe { (*(obj as *const Header)).size() }))
+            .collect()
+    }
+
+    /// 标记根引用的对象
+    pub fn mark(&mut self, slot: &mut RawPtr) {
+        if slot.is_null() || !self.in_heap(slot.0) {
+            return;
+        }
+        let header = unsafe { &mut *(slot.0 as *mut Header) };
+        if header.state() == GcState::Unmarked {
+            header.set_state(GcState::Marked);
+            self.gray.push(slot.0);
+        }
+    }
+
+    /// 标记所有从已标记对象可达的对象
+    pub fn trace(&mut self, mut reference_map: impl FnMut(usize) -> ReferenceMap) {
+        while let Some(obj) = self.gray.pop() {
+            let map = self.maps.of(obj, &mut reference_map);
+            unsafe { for_each_reference(obj, &map, |slot| self.mark(slot)) };
+        }
+    }
+
+    /// 为存活对象计算新地址，大对象不移动。grow为true时老年代至少扩大一倍（不超过最大堆），否则可能收缩
+    pub fn plan(&mut self, grow: bool) {
+        let live_old = Self::marked_objects(&self.heap.old);
+        let live_young = Self::marked_objects(&self.heap.cur);
+        let live_large_objects = self.marked_large_objects();
+        let live_large: usize = live_large_objects.iter().map(|&(_, size)| size).sum();
+        let live_bytes: usize = live_old.iter().chain(&live_young).map(|&(_, size)| size).sum();
+
+        // 整理后老年代至少要为下一次新生代收集的晋升留出一个半区的空间，存活率超过一半时也扩大。
+        // 存活的大对象占用的额度不能给老年代
+        let max_capacity = self.heap.max_old_capacity - live_large;
+        let young_capacity = self.heap.cur.capacity;
+        let too_small = |capacity: usize| capacity < live_bytes + young_capacity || live_bytes * 2 > capacity;
+        let mut capacity = self.heap.old.capacity;
+        if grow {
+            capacity = (capacity * 2).min(max_capacity);
+        } else {
+            // 老年代比存活对象需要的大得多时缩小，把额度留给大对象空间，但不小于初始大小
+            loop {
+                let half = Heap::align_to_8_bytes(capacity / 2).max(self.heap.min_old_capacity);
+                if half == capacity || too_small(half) {
+                    break;
+                }
+                capacity = half;
+            }
+        }
+        while capacity < max_capacity && too_small(capacity) {
+            capacity = (capacity * 2).min(max_capacity);
+        }
+        if capacity != self.heap.old.capacity {
+            self.new_old = Some(MemoryChunk::new(capacity));
+        }
+        let base = self.new_old.as_ref().map_or(self.heap.old.memory, |old| old.memory);
+
+        let mut used = 0;
+        for (obj, size) in live_old {
+            let dest = unsafe { base.add(used) };
+            used += size;
+            self.forwarding.insert(obj as usize, RawPtr(dest));
+            self.moves.push((obj, dest, size));
+        }
+        for (obj, size) in live_young {
+            let dest = if used + size <= capacity {
+                let dest = unsafe { base.add(used) };
+                used += size;
+                self.stats.promoted_objects += 1;
+                self.stats.promoted_bytes += size;
+                dest
+            } else {
+                self.heap.nxt.alloc(size).expect("to-space overflow during collection").0
+            };
+            self.forwarding.insert(obj as usize, RawPtr(dest));
+            self.moves.push((obj, dest, size));
+        }
+        self.old_used_after = used;
+        self.stats.live_objects = self.moves.len() + live_large_objects.len();
+        self.stats.live_bytes = live_bytes + live_large;
+    }
+
+    /// 空间中已标记的对象和它们的大小，按地址顺序
+    fn marked_objects(chunk: &MemoryChunk) -> Vec<(*mut u8, usize)> {
+        let mut objects = Vec::new();
+        let mut offset = 0;
+        while offset < chunk.used {
+            let obj = unsafe { chunk.memory.add(offset) };
+            let header = unsafe { *(obj as *const Header) };
+            if header.state() == GcState::Marked {
+                objects.push((obj, header.size()));
+            }
+            offset += header.size();
+        }
+        objects
+    }
+
+    /// 把根更新为对象的新地址
+    pub fn update(&self, slot: &mut RawPtr) {
+        if let Some(&dest) = self.forwarding.get(&(slot.0 as usize)) {
+            *slot = dest;
+        }
+    }
+
+    /// 对象在收集后的地址：存活对象返回新地址（大对象的地址不变），不可达的对象返回None，堆外的引用原样返回
+    pub fn forwarded(&self, obj: RawPtr) -> Option<RawPtr> {
+        if obj.is_null() || !self.in_heap(obj.0) {
+            return Some(obj);
+        }
+        if self.heap.large_object(obj.0).is_some() {
+            let header = unsafe { *(obj.0 as *const Header) };
+            return (header.state() == GcState::Marked).then_some(obj);
+        }
+        self.forwarding.get(&(obj.0 as usize)).copied()
+    }
+
+    /// 更新存活对象中的引用并移动对象，释放不可达的大对象，重建卡表
+    pub fn finish(mut self) -> GcStats {
+        let live_large = self.marked_large_objects();
+        let live: Vec<*mut u8> = self.moves.iter().map(|&(obj, _, _)| obj).chain(live_large.iter().map(|&(obj, _)| obj)).collect();
+        for obj in live {
+            let map = self.maps.of(obj, &mut |class_id| panic!("类{}的对象在标记时没有被扫描", class_id));
+            unsafe { for_each_reference(obj, &map, |slot| self.update(slot)) };
+        }
+        self.heap.large.retain(|&obj, _| unsafe { (*(obj as *const Header)).state() } == GcState::Marked);
+        for &(obj, _) in &live_large {
+            unsafe { (*(obj as *mut Header)).set_state(GcState::Unmarked) };
+        }
+        self.heap.large_used = live_large.iter().map(|&(_, size)| size).sum();
+        // 老年代的对象按地址顺序向低地址滑动，不会覆盖还没有移动的对象；新生代的对象随后复制
+        for &(obj, dest, size) in &self.moves {
+            unsafe {
+                std::ptr::copy(obj, dest, size);
+                (*(dest as *mut Header)).set_state(GcState::Unmarked);
+            }
+        }
+        match self.new_old.take() {
+            Some(old) => self.heap.old = old,
+            // 整理后空出来的部分清零，保持未分配的内存为零
+            None => unsafe {
+                std::ptr::write_bytes(self.heap.old.memory.add(self.old_used_after), 0, self.heap.old.used.saturating_sub(self.old_used_after));
+            },
+        }
+        self.heap.old.used = self.old_used_after;
+        self.heap.cur.reset();
+        std::mem::swap(&mut self.heap.cur, &mut self.heap.nxt);
+        self.rebuild_cards();
+        GcStats {
+            freed_bytes: self.used_before - self.stats.live_bytes,
+            ..self.stats
+        }
+    }
+
+    /// 按整理后的老年代重建卡表，仍然引用新生代的对象所在的卡和大对象标记为脏
+    fn rebuild_cards(&mut self) {
+        let large: Vec<usize> = self.heap.large.keys().copied().collect();
+        for obj in large {
+            let map = self.maps.of(obj as *mut u8, &mut |class_id| panic!("类{}的对象在标记时没有被扫描", class_id));
+            let mut has_young = false;
+            unsafe { for_each_reference(obj as *mut u8, &map, |slot| has_young |= self.heap.cur.contains(slot.0)) };
+            self.heap.large.get_mut(&obj).expect("存活的大对象").dirty = has_young;
+        }
+        self.heap.cards = CardTable::new(self.heap.old.capacity);
+        let mut offset = 0;
+        while offset < self.heap.old.used {
+            let obj = unsafe { self.heap.old.memory.add(offset) };
+            let size = unsafe { (*(obj as *const Header)).size() };
+            self.heap.cards.record_object(offset, size);
+            let map = self.maps.of(obj, &mut |class_id| panic!("类{}的对象在标记时没有被扫描", class_id));
+            let mut has_young = false;
+            unsafe { for_each_reference(obj, &map, |slot| has_young |= self.heap.cur.contains(slot.0)) };
+            if has_young {
+                self.heap.cards.mark(offset);
+            }
+            offset += size;
+        }
+    }
 }
 
 impl Heap {
+    /// 固定大小的堆
     pub fn with_maximum_memory(max_size: usize) -> Self {
-        let semi_space_capacity = max_size / 2;
+        Self::with_sizes(max_size, max_size)
+    }
+
+    /// 初始大小为initial、最多扩大到maximum的堆，initial大于maximum时按maximum。
+    /// 新生代的两个半区共占初始堆的一半，大小固定，新生代收集的停顿时间因此有上限；
+    /// 其余是老年代，完整收集时可以扩大
+    pub fn with_sizes(initial: usize, maximum: usize) -> Self {
+        let initial = initial.min(maximum);
+        let young_capacity = Self::align_to_8_bytes(initial / 4).max(8);
+        let max_old_capacity = Self::align_to_8_bytes(maximum.saturating_sub(2 * young_capacity)).max(8);
+        let old_capacity = Self::align_to_8_bytes(initial.saturating_sub(2 * young_capacity)).clamp(8, max_old_capacity);
         Self {
-            cur: MemoryChunk::new(semi_space_capacity),
-            nxt: MemoryChunk::new(semi_space_capacity),
+            cur: MemoryChunk::new(young_capacity),
+            nxt: MemoryChunk::new(young_capacity),
+            old: MemoryChunk::new(old_capacity),
+            cards: CardTable::new(old_capacity),
+            large: BTreeMap::new(),
+            large_used: 0,
+            min_old_capacity: old_capacity,
+            max_old_capacity,
+        }
+    }
+
+    /// 开始一次新生代收集
+    pub fn begin_young_collection(&mut self) -> Collection<'_> {
+        Collection {
+            used_before: self.cur.used,
+            old_used_before: self.old.used,
+            heap: self,
+            maps: ReferenceMaps::default(),
+            stats: GcStats::default(),
+        }
+    }
+
+    /// 开始一次完整收集
+    pub fn begin_full_collection(&mut self) -> FullCollection<'_> {
+        FullCollection {
+            used_before: self.used(),
+            heap: self,
+            gray: Vec::new(),
+            maps: ReferenceMaps::default(),
+            forwarding: HashMap::new(),
+            moves: Vec::new(),
+            new_old: None,
+            old_used_after: 0,
+            stats: GcStats::default(),
         }
     }
 
+    /// 老年代剩余的空间可能放不下下一次新生代收集晋升的对象，需要完整收集
+    pub fn needs_full_collection(&self) -> bool {
+        self.old.capacity - self.old.used < self.cur.capacity
+    }
+
+    /// 新生代、老年代和大对象空间已使用的字节数
+    pub fn used(&self) -> usize {
+        self.cur.used + self.old.used + self.large_used
+    }
+
+    /// 已占用的容量：新生代的一个半区、老年代和大对象
+    pub fn capacity(&self) -> usize {
+        self.cur.capacity + self.old.capacity + self.large_used
+    }
+
+    /// 老年代和大对象空间用满最大堆时的容量
+    pub fn max_capacity(&self) -> usize {
+        self.cur.capacity + self.max_old_capacity
+    }
+
+    /// 老年代（包括大对象空间）已使用的字节数
+    pub fn old_used(&self) -> usize {
+        self.old.used + self.large_used
+    }
+
+    /// 大对象空间已使用的字节数
+    pub fn large_used(&self) -> usize {
+        self.large_used
+    }
+
+    /// 判断引用是否指向堆中的对象
+    pub fn contains(&self, obj: RawPtr) -> bool {
+        unsafe { self.cur.contains(obj.0) || self.old.contains(obj.0) || self.large_object(obj.0).is_some() }
+    }
+
+    /// 判断引用是否指向老年代（包括大对象空间）中的对象
+    pub fn is_old(&self, obj: RawPtr) -> bool {
+        unsafe { self.old.contains(obj.0) || self.large_object(obj.0).is_some() }
+    }
+
+    /// 地址所在的大对象
+    fn large_object(&self, ptr: *const u8) -> Option<&LargeObject> {
+        let (_, object) = self.large.range(..=ptr as usize).next_back()?;
+        unsafe { object.chunk.contains(ptr) }.then_some(object)
+    }
+
+    fn large_object_mut(&mut self, ptr: *const u8) -> Option<&mut LargeObject> {
+        let (_, object) = self.large.range_mut(..=ptr as usize).next_back()?;
+        unsafe { object.chunk.contains(ptr) }.then_some(object)
+    }
+
+    /// 在大对象空间中单独分配，老年代和大对象一共不超过最大堆
+    fn alloc_large(&mut self, size: usize) -> Option<RawPtr> {
+        if self.old.capacity + self.large_used + size > self.max_old_capacity {
+            return None;
+        }
+        let mut chunk = MemoryChunk::new(size);
+        let ptr = chunk.alloc(size)?;
+        self.large.insert(ptr.0 as usize, LargeObject { chunk, dirty: false });
+        self.large_used += size;
+        Some(ptr)
+    }
+
+    /// 在老年代中分配并登记到卡表
+    fn alloc_old(&mut self, size: usize) -> Option<RawPtr> {
+        let ptr = self.old.alloc(size)?;
+        self.cards.record_object(ptr.0 as usize - self.old.memory as usize, size);
+        Some(ptr)
+    }
+
+    /// 写屏障：老年代对象中写入新生代对象的引用时，把对象所在的卡标记为脏，大对象标记它自己
+    fn write_barrier(&mut self, obj: RawPtr, value: RawPtr) {
+        if !unsafe { self.cur.contains(value.0) } {
+            return;
+        }
+        if unsafe { self.old.contains(obj.0) } {
+            self.cards.mark(obj.0 as usize - self.old.memory as usize);
+        } else if let Some(object) = self.large_object_mut(obj.0) {
+            object.dirty = true;
+        }
+    }
+
+    /// 分配size字节（已按8字节对齐）并初始化对象头，内存已清零。
+    /// 超过新生代半区一半的大对象在大对象空间中分配，避免在新生代中复制和在老年代中整理
+    pub(crate) fn alloc_with_header(&mut self, class_id: usize, size: usize) -> Result<RawPtr, AllocError> {
+        if size > MAX_OBJECT_SIZE {
+            return Err(AllocError::BadRequest);
+        }
+        let ptr = if size > self.cur.capacity / 2 { self.alloc_large(size) } else { self.cur.alloc(size) };
+        let ptr = ptr.ok_or(AllocError::OOM)?;
+        unsafe {
+            *(ptr.0 as *mut Header) = Header::new(class_id, size);
+        }
+        Ok(ptr)
+    }
+
     /// 分配一个对象，返回RawPtr
     pub fn alloc_object(&mut self, klass: &InstanceKlass) -> Result<RawPtr, AllocError> {
         let header_size = std::mem::size_of::<Header>();
@@ -231,37 +923,26 @@ impl Heap {
         jvm_log!("[AllocObject] 对象总大小: header_size({}) + max_end({}) = {}", 
             header_size, max_end, total_size);
         
-        let ptr = self.cur.alloc(total_size).ok_or(AllocError::OOM)?;
+        let ptr = self.alloc_with_header(klass.class_id, total_size)?;
         jvm_log!("[AllocObject] 分配成功: {:?}", ptr);
-        
-        // 初始化对象头部
-        unsafe {
-            let header_ptr = ptr.0 as *mut Header;
-            *header_ptr = Header::new()
-                .with_class_id(klass.class_id)
-                .with_state(GcState::Unmarked)
-                .with_identity_hash_code(0)
-                .with_size(total_size);
-        }
         Ok(ptr)
     }
 
     /// 分配一个数组对象，返回RawPtr
     pub fn alloc_array(&mut self, klass: &ArrayKlass, length: usize) -> Result<RawPtr, AllocError> {
-        let header_size = std::mem::size_of::<Header>();
-        let elem_size = 8; // 8字节对齐，支持int/引用
-        let total_size = Self::align_to_8_bytes(header_size + 8 + length * elem_size); // 8字节存储length
-        let ptr = self.cur.alloc(total_size).ok_or(AllocError::OOM)?;
-        // 初始化头部和length
+        self.alloc_array_of(klass.class_id, klass.get_element_size(), length)
+    }
+
+    /// 按类ID和元素宽度分配数组：对象头之后是长度字，然后是元素
+    pub(crate) fn alloc_array_of(&mut self, class_id: usize, elem_size: usize, length: usize) -> Result<RawPtr, AllocError> {
+        let total_size = length
+            .checked_mul(elem_size)
+            .and_then(|size| size.checked_add(ARRAY_BASE_OFFSET + 7))
+            .ok_or(AllocError::BadRequest)?
+            & !7;
+        let ptr = self.alloc_with_header(class_id, total_size)?;
         unsafe {
-            let header_ptr = ptr.0 as *mut Header;
-            *header_ptr = Header::new()
-                .with_class_id(klass.class_id)
-                .with_state(GcState::Unmarked)
-                .with_identity_hash_code(0)
-                .with_size(total_size);
-            let len_ptr = ptr.0.add(header_size) as *mut usize;
-            *len_ptr = length;
+            *(ptr.0.add(std::mem::size_of::<Header>()) as *mut usize) = length;
         }
         Ok(ptr)
     }
@@ -273,15 +954,18 @@ impl Heap {
         jvm_log!("[PutField] 设置字段: obj={:?}, header_size={}, field_offset={}, addr=0x{:016x}", 
             obj, header_size, field_offset, addr as u64);
         match value {
-            JvmValue::Boolean(v) => unsafe { *(addr as *mut u8) = v },
-            JvmValue::Byte(v) => unsafe { *(addr as *mut u8) = v },
+            JvmValue::Boolean(v) => unsafe { *addr = v },
+            JvmValue::Byte(v) => unsafe { *addr = v },
             JvmValue::Short(v) => unsafe { *(addr as *mut u16) = v },
             JvmValue::Char(v) => unsafe { *(addr as *mut u16) = v },
             JvmValue::Int(v) => unsafe { *(addr as *mut i32) = v as i32 },
             JvmValue::Long(v) => unsafe { *(addr as *mut i64) = v as i64 },
-            JvmValue::Float(v) => unsafe { *(addr as *mut u64) = v }, // 直接写入位表示
+            JvmValue::Float(v) => unsafe { *(addr as *mut u32) = v as u32 }, // float字段只占4字节
             JvmValue::Double(v) => unsafe { *(addr as *mut u64) = v },
-            JvmValue::ObjRef(ptr) => unsafe { *(addr as *mut RawPtr) = ptr },
+            JvmValue::ObjRef(ptr) => {
+                unsafe { *(addr as *mut RawPtr) = ptr };
+                self.write_barrier(obj, ptr);
+            }
             JvmValue::Null => unsafe { *(addr as *mut usize) = 0 },
         }
     }
@@ -319,26 +1003,232 @@ impl Heap {
         }
     }
 
-    /// 设置数组元素
-    pub fn put_array_element(&mut self, arr: RawPtr, index: usize, value: JvmValue) {
+    /// 数组元素起始地址：对象头和长度字之后
+    fn array_element_addr(arr: RawPtr, index: usize, elem_size: usize) -> *mut u8 {
+        unsafe { arr.0.add(ARRAY_BASE_OFFSET + index * elem_size) }
+    }
+
+    /// 获取数组长度
+    pub fn array_length(&self, arr: RawPtr) -> usize {
         let header_size = std::mem::size_of::<Header>();
-        let addr = unsafe { arr.0.add(header_size + 8 + index * 8) };
-        match value {
-            JvmValue::Int(v) => unsafe { *(addr as *mut i32) = v as i32 },
-            JvmValue::ObjRef(ptr) => unsafe { *(addr as *mut RawPtr) = ptr },
-            _ => unimplemented!("暂不支持该类型"),
+        unsafe { *(arr.0.add(header_size) as *const usize) }
+    }
+
+    /// 设置数组元素，元素宽度由值的类型决定
+    pub fn put_array_element(&mut self, arr: RawPtr, index: usize, value: JvmValue) {
+        unsafe {
+            match value {
+                JvmValue::Boolean(v) | JvmValue::Byte(v) => *Self::array_element_addr(arr, index, 1) = v,
+                JvmValue::Short(v) | JvmValue::Char(v) => *(Self::array_element_addr(arr, index, 2) as *mut u16) = v,
+                JvmValue::Int(v) => *(Self::array_element_addr(arr, index, 4) as *mut u32) = v,
+                JvmValue::Float(v) => *(Self::array_element_addr(arr, index, 4) as *mut u32) = v as u32,
+                JvmValue::Long(v) | JvmValue::Double(v) => *(Self::array_element_addr(arr, index, 8) as *mut u64) = v,
+                JvmValue::ObjRef(ptr) => {
+                    *(Self::array_element_addr(arr, index, 8) as *mut RawPtr) = ptr;
+                    self.write_barrier(arr, ptr);
+                }
+                JvmValue::Null => *(Self::array_element_addr(arr, index, 8) as *mut RawPtr) = RawPtr(std::ptr::null_mut()),
+            }
         }
     }
 
-    /// 获取数组元素
-    pub fn get_array_element(&self, arr: RawPtr, index: usize) -> JvmValue {
-        let header_size = std::mem::size_of::<Header>();
-        let addr = unsafe { arr.0.add(header_size + 8 + index * 8) };
-        let v = unsafe { *(addr as *const i32) };
-        JvmValue::Int(v as u32)
+    /// 获取数组元素，elem_desc为元素类型描述符
+    pub fn get_array_element(&self, arr: RawPtr, index: usize, elem_desc: &str) -> JvmValue {
+        unsafe {
+            match elem_desc {
+                "Z" => JvmValue::Boolean(*Self::array_element_addr(arr, index, 1)),
+                "B" => JvmValue::Byte(*Self::array_element_addr(arr, index, 1)),
+                "C" => JvmValue::Char(*(Self::array_element_addr(arr, index, 2) as *const u16)),
+                "S" => JvmValue::Short(*(Self::array_element_addr(arr, index, 2) as *const u16)),
+                "I" => JvmValue::Int(*(Self::array_element_addr(arr, index, 4) as *const u32)),
+                "F" => JvmValue::Float(*(Self::array_element_addr(arr, index, 4) as *const u32) as u64),
+                "J" => JvmValue::Long(*(Self::array_element_addr(arr, index, 8) as *const u64)),
+                "D" => JvmValue::Double(*(Self::array_element_addr(arr, index, 8) as *const u64)),
+                _ => JvmValue::ObjRef(*(Self::array_element_addr(arr, index, 8) as *const RawPtr)),
+            }
+        }
     }
 
     fn align_to_8_bytes(required_size: usize) -> usize {
         (required_size + 7) & !7
     }
 }
+
+/// 解析 -Xms/-Xmx 形式的内存大小：字节数，可以带 k/K、m/M、g/G 后缀
+pub fn parse_memory_size(size: &str) -> Option<usize> {
+    let (digits, unit) = match size.char_indices().last()? {
+        (index, 'k' | 'K') => (&size[..index], 1 << 10),
+        (index, 'm' | 'M') => (&size[..index], 1 << 20),
+        (index, 'g' | 'G') => (&size[..index], 1 << 30),
+        _ => (size, 1),
+    };
+    digits.parse::<usize>().ok()?.checked_mul(unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::Vm;
+
+    #[test]
+    fn test_parse_memory_size() {
+        assert_eq!(parse_memory_size("4096"), Some(4096));
+        assert_eq!(parse_memory_size("64k"), Some(64 * 1024));
+        assert_eq!(parse_memory_size("512M"), Some(512 * 1024 * 1024));
+        assert_eq!(parse_memory_size("2g"), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_memory_size(""), None);
+        assert_eq!(parse_memory_size("m"), None);
+        assert_eq!(parse_memory_size("12x"), None);
+    }
+
+    // 测试用的类ID：类1的对象在偏移0处有一个引用字段，类2的对象不含引用
+    fn test_reference_map(class_id: usize) -> ReferenceMap {
+        if class_id == 1 { ReferenceMap::Fields(vec![0]) } else { ReferenceMap::Empty }
+    }
+
+    fn young_collection(heap: &mut Heap, roots: &mut [RawPtr]) -> GcStats {
+        let mut collection = heap.begin_young_collection();
+        roots.iter_mut().for_each(|root| collection.evacuate(root));
+        collection.trace(test_reference_map);
+        collection.finish()
+    }
+
+    fn full_collection(heap: &mut Heap, roots: &mut [RawPtr], grow: bool) -> GcStats {
+        let mut collection = heap.begin_full_collection();
+        roots.iter_mut().for_each(|root| collection.mark(root));
+        collection.trace(test_reference_map);
+        collection.plan(grow);
+        roots.iter_mut().for_each(|root| collection.update(root));
+        collection.finish()
+    }
+
+    #[test]
+    fn test_header_holds_wide_class_ids_and_sizes() {
+        // 超过1024个类和8MB的对象不再截断
+        let header = Header::new(5000, 16 << 20);
+        assert_eq!((header.class_id(), header.size()), (5000, 16 << 20));
+        assert_eq!(std::mem::size_of::<Header>(), 16);
+
+        let mut heap = Heap::with_sizes(4096, 16384);
+        let array = heap.alloc_array_of(1 << 20, 8, 3).unwrap();
+        assert_eq!(Vm::object_class_id(array), Some(1 << 20));
+        assert_eq!(heap.array_length(array), 3);
+        assert_eq!(unsafe { (*(array.0 as *const Header)).size() }, ARRAY_BASE_OFFSET + 24);
+    }
+
+    #[test]
+    #[should_panic(expected = "超出对象头的表示范围")]
+    fn test_header_truncation_is_caught() {
+        Header::new(u32::MAX as usize + 1, 16);
+    }
+
+    #[test]
+    fn test_young_collection_promotes_after_tenuring_threshold() {
+        let mut heap = Heap::with_sizes(4096, 16384);
+        assert_eq!((heap.capacity(), heap.max_capacity()), (1024 + 2048, 1024 + 14336));
+        let mut roots = [heap.alloc_with_header(2, 64).unwrap()];
+        heap.alloc_with_header(2, 128).unwrap();
+
+        for _ in 1..TENURING_THRESHOLD {
+            let stats = young_collection(&mut heap, &mut roots);
+            assert_eq!((stats.live_objects, stats.promoted_objects), (1, 0));
+            assert!(!heap.is_old(roots[0]));
+        }
+        let stats = young_collection(&mut heap, &mut roots);
+        assert_eq!((stats.promoted_objects, stats.promoted_bytes, stats.freed_bytes), (1, 64, 0));
+        assert!(heap.is_old(roots[0]));
+        assert_eq!((heap.used(), heap.old_used()), (64, 64));
+    }
+
+    #[test]
+    fn test_card_marking_keeps_young_objects_referenced_from_old() {
+        let mut heap = Heap::with_sizes(4096, 16384);
+        let mut roots = [heap.alloc_with_header(1, 24).unwrap()];
+        for _ in 0..TENURING_THRESHOLD {
+            young_collection(&mut heap, &mut roots);
+        }
+        let holder = roots[0];
+        assert!(heap.is_old(holder));
+
+        // 新生代对象只被老年代对象引用，写屏障标记的脏卡让它在新生代收集中存活
+        let young = heap.alloc_with_header(2, 32).unwrap();
+        heap.put_field(holder, 0, JvmValue::ObjRef(young));
+        let stats = young_collection(&mut heap, &mut roots);
+        assert_eq!((stats.live_objects, stats.live_bytes), (1, 32));
+        let JvmValue::ObjRef(moved) = heap.get_field(holder, 0, "Ljava/lang/Object;") else { panic!("应为引用") };
+        assert_ne!(moved, young);
+        assert!(heap.contains(moved) && !heap.is_old(moved));
+
+        // 卡保持为脏，之后的收集继续更新引用直到对象晋升
+        for _ in 1..TENURING_THRESHOLD {
+            young_collection(&mut heap, &mut roots);
+        }
+        let JvmValue::ObjRef(promoted) = heap.get_field(holder, 0, "Ljava/lang/Object;") else { panic!("应为引用") };
+        assert!(heap.is_old(promoted));
+    }
+
+    #[test]
+    fn test_full_collection_compacts_and_grows_old_generation() {
+        let mut heap = Heap::with_sizes(4096, 16384);
+        let mut roots = [heap.alloc_with_header(2, 64).unwrap(), heap.alloc_with_header(1, 64).unwrap()];
+        for _ in 0..TENURING_THRESHOLD {
+            young_collection(&mut heap, &mut roots);
+        }
+        let [garbage, live] = roots;
+        assert!(heap.is_old(garbage) && heap.is_old(live));
+        let child = heap.alloc_with_header(2, 64).unwrap();
+        heap.put_field(live, 0, JvmValue::ObjRef(child));
+
+        // 老年代的存活对象滑动到开头，新生代的存活对象晋升到其后
+        let mut roots = [live];
+        let stats = full_collection(&mut heap, &mut roots, false);
+        assert_eq!((stats.live_objects, stats.live_bytes, stats.freed_bytes), (2, 128, 64));
+        assert_eq!(stats.promoted_objects, 1);
+        assert_eq!(roots[0], garbage);
+        assert_eq!(heap.old_used(), 128);
+        let JvmValue::ObjRef(child) = heap.get_field(roots[0], 0, "Ljava/lang/Object;") else { panic!("应为引用") };
+        assert!(heap.is_old(child));
+
+        let stats = full_collection(&mut heap, &mut roots, true);
+        assert_eq!(stats.live_bytes, 128);
+        assert_eq!(heap.capacity(), 1024 + 4096);
+        while heap.capacity() < heap.max_capacity() {
+            full_collection(&mut heap, &mut roots, true);
+        }
+        assert_eq!(heap.capacity(), 1024 + 14336);
+
+        // 不需要扩大时老年代收缩回初始大小
+        full_collection(&mut heap, &mut roots, false);
+        assert_eq!(heap.capacity(), 1024 + 2048);
+        assert_eq!(heap.old_used(), 128);
+    }
+
+    #[test]
+    fn test_large_objects_are_not_moved_and_freed_when_unreachable() {
+        let mut heap = Heap::with_sizes(4096, 16384);
+        let garbage = heap.alloc_with_header(2, 4096).unwrap();
+        let large = heap.alloc_with_header(1, 4096).unwrap();
+        assert!(heap.is_old(garbage) && heap.is_old(large));
+        assert_eq!((heap.large_used(), heap.capacity()), (8192, 1024 + 2048 + 8192));
+
+        // 大对象的卡在写入新生代引用后为脏，新生代收集更新它的字段
+        let young = heap.alloc_with_header(2, 32).unwrap();
+        heap.put_field(large, 0, JvmValue::ObjRef(young));
+        let mut roots = [large];
+        let stats = young_collection(&mut heap, &mut roots);
+        assert_eq!(stats.live_objects, 1);
+        assert_eq!(roots[0], large);
+        let JvmValue::ObjRef(moved) = heap.get_field(large, 0, "Ljava/lang/Object;") else { panic!("应为引用") };
+        assert!(heap.contains(moved) && moved != young);
+
+        // 完整收集释放不可达的大对象，存活的大对象留在原地
+        let stats = full_collection(&mut heap, &mut roots, false);
+        assert_eq!((stats.live_objects, stats.live_bytes, stats.freed_bytes), (2, 4096 + 32, 4096));
+        assert_eq!(roots[0], large);
+        assert_eq!(heap.large_used(), 4096);
+        assert!(!heap.contains(garbage));
+
+        // 大对象和老年代共用最大堆的额度
+        assert!(heap.alloc_with_header(2, 16384 - 2048).is_err());
+    }
+}
@@ -1,94 +1,194 @@
 use crate::jvm_thread::Frame;
 use crate::error::JvmError;
+use crate::instructions::Instruction;
 use crate::vm::Vm;
 
-pub fn exec_iadd(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let b = frame.stack.pop_int();
-    let a = frame.stack.pop_int();
-    frame.stack.push_int(a + b);
+pub fn exec_iadd(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_int()?;
+    let a = frame.stack.pop_int()?;
+    frame.stack.push_int(a.wrapping_add(b))?;
     Ok(())
 }
 
-pub fn exec_isub(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let b = frame.stack.pop_int();
-    let a = frame.stack.pop_int();
-    frame.stack.push_int(a - b);
+pub fn exec_isub(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_int()?;
+    let a = frame.stack.pop_int()?;
+    frame.stack.push_int(a.wrapping_sub(b))?;
     Ok(())
 }
 
-pub fn exec_imul(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let b = frame.stack.pop_int();
-    let a = frame.stack.pop_int();
-    frame.stack.push_int(a * b);
+pub fn exec_imul(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_int()?;
+    let a = frame.stack.pop_int()?;
+    frame.stack.push_int(a.wrapping_mul(b))?;
     Ok(())
 }
 
-pub fn exec_idiv(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let b = frame.stack.pop_int();
-    let a = frame.stack.pop_int();
+pub fn exec_idiv(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_int()?;
+    let a = frame.stack.pop_int()?;
     
     if b == 0 {
-        return Err(JvmError::ArithmeticError("Division by zero".to_string()));
+        return Err(JvmError::ArithmeticError("/ by zero".to_string()));
     }
     
-    frame.stack.push_int(a / b);
+    frame.stack.push_int(a.wrapping_div(b))?;
     Ok(())
 }
 
-pub fn exec_irem(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let v2 = frame.stack.pop_int();
-    let v1 = frame.stack.pop_int();
+pub fn exec_irem(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let v2 = frame.stack.pop_int()?;
+    let v1 = frame.stack.pop_int()?;
     if v2 == 0 {
-        return Err(JvmError::ArithmeticError("Modulo by zero".to_string()));
+        return Err(JvmError::ArithmeticError("/ by zero".to_string()));
     }
-    frame.stack.push_int(v1 % v2);
+    frame.stack.push_int(v1.wrapping_rem(v2))?;
     Ok(())
 }
 
-pub fn exec_ineg(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let v = frame.stack.pop_int();
-    frame.stack.push_int(-v);
+pub fn exec_ineg(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let v = frame.stack.pop_int()?;
+    frame.stack.push_int(v.wrapping_neg())?;
     Ok(())
 }
 
-pub fn exec_iand(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let v2 = frame.stack.pop_int();
-    let v1 = frame.stack.pop_int();
-    frame.stack.push_int(v1 & v2);
+pub fn exec_iand(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let v2 = frame.stack.pop_int()?;
+    let v1 = frame.stack.pop_int()?;
+    frame.stack.push_int(v1 & v2)?;
     Ok(())
 }
 
-pub fn exec_ior(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let v2 = frame.stack.pop_int();
-    let v1 = frame.stack.pop_int();
-    frame.stack.push_int(v1 | v2);
+pub fn exec_ior(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let v2 = frame.stack.pop_int()?;
+    let v1 = frame.stack.pop_int()?;
+    frame.stack.push_int(v1 | v2)?;
     Ok(())
 }
 
-pub fn exec_ixor(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let v2 = frame.stack.pop_int();
-    let v1 = frame.stack.pop_int();
-    frame.stack.push_int(v1 ^ v2);
+pub fn exec_ixor(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let v2 = frame.stack.pop_int()?;
+    let v1 = frame.stack.pop_int()?;
+    frame.stack.push_int(v1 ^ v2)?;
     Ok(())
 }
 
-pub fn exec_ishl(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let v2 = frame.stack.pop_int();
-    let v1 = frame.stack.pop_int();
-    frame.stack.push_int(v1 << (v2 & 0x1F));
+pub fn exec_ishl(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let v2 = frame.stack.pop_int()?;
+    let v1 = frame.stack.pop_int()?;
+    frame.stack.push_int(v1 << (v2 & 0x1F))?;
     Ok(())
 }
 
-pub fn exec_ishr(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let v2 = frame.stack.pop_int();
-    let v1 = frame.stack.pop_int();
-    frame.stack.push_int(v1 >> (v2 & 0x1F));
+pub fn exec_ishr(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let v2 = frame.stack.pop_int()?;
+    let v1 = frame.stack.pop_int()?;
+    frame.stack.push_int(v1 >> (v2 & 0x1F))?;
     Ok(())
 }
 
-pub fn exec_iushr(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let v2 = frame.stack.pop_int();
-    let v1 = frame.stack.pop_int();
-    frame.stack.push_int((v1 as u32 >> (v2 & 0x1F)) as i32);
+pub fn exec_iushr(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let v2 = frame.stack.pop_int()?;
+    let v1 = frame.stack.pop_int()?;
+    frame.stack.push_int((v1 as u32 >> (v2 & 0x1F)) as i32)?;
     Ok(())
-} 
\ No newline at end of file
+} 
+// long 指令族，按Java语义进行二进制补码回绕运算
+pub fn exec_ladd(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_long()?;
+    let a = frame.stack.pop_long()?;
+    frame.stack.push_long(a.wrapping_add(b))?;
+    Ok(())
+}
+
+pub fn exec_lsub(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_long()?;
+    let a = frame.stack.pop_long()?;
+    frame.stack.push_long(a.wrapping_sub(b))?;
+    Ok(())
+}
+
+pub fn exec_lmul(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_long()?;
+    let a = frame.stack.pop_long()?;
+    frame.stack.push_long(a.wrapping_mul(b))?;
+    Ok(())
+}
+
+pub fn exec_ldiv(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_long()?;
+    let a = frame.stack.pop_long()?;
+    if b == 0 {
+        return Err(JvmError::ArithmeticError("/ by zero".to_string()));
+    }
+    // Long.MIN_VALUE / -1 溢出后仍为 Long.MIN_VALUE
+    frame.stack.push_long(a.wrapping_div(b))?;
+    Ok(())
+}
+
+pub fn exec_lrem(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_long()?;
+    let a = frame.stack.pop_long()?;
+    if b == 0 {
+        return Err(JvmError::ArithmeticError("/ by zero".to_string()));
+    }
+    frame.stack.push_long(a.wrapping_rem(b))?;
+    Ok(())
+}
+
+pub fn exec_lneg(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let v = frame.stack.pop_long()?;
+    frame.stack.push_long(v.wrapping_neg())?;
+    Ok(())
+}
+
+// 移位量为int，只取低6位
+pub fn exec_lshl(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let shift = frame.stack.pop_int()?;
+    let v = frame.stack.pop_long()?;
+    frame.stack.push_long(v << (shift & 0x3F))?;
+    Ok(())
+}
+
+pub fn exec_lshr(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let shift = frame.stack.pop_int()?;
+    let v = frame.stack.pop_long()?;
+    frame.stack.push_long(v >> (shift & 0x3F))?;
+    Ok(())
+}
+
+pub fn exec_lushr(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let shift = frame.stack.pop_int()?;
+    let v = frame.stack.pop_long()?;
+    frame.stack.push_long((v as u64 >> (shift & 0x3F)) as i64)?;
+    Ok(())
+}
+
+pub fn exec_land(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_long()?;
+    let a = frame.stack.pop_long()?;
+    frame.stack.push_long(a & b)?;
+    Ok(())
+}
+
+pub fn exec_lor(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_long()?;
+    let a = frame.stack.pop_long()?;
+    frame.stack.push_long(a | b)?;
+    Ok(())
+}
+
+pub fn exec_lxor(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_long()?;
+    let a = frame.stack.pop_long()?;
+    frame.stack.push_long(a ^ b)?;
+    Ok(())
+}
+
+/// lcmp 指令 - 比较两个long值，压入 -1、0 或 1
+pub fn exec_lcmp(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_long()?;
+    let a = frame.stack.pop_long()?;
+    frame.stack.push_int(a.cmp(&b) as i32)?;
+    Ok(())
+}
@@ -1,139 +1,290 @@
-#![allow(static_mut_refs)]
-
 use crate::jvm_thread::Frame;
 use crate::error::JvmError;
+use crate::instructions::Instruction;
 use crate::vm::Vm;
 use crate::jvm_log;
 use crate::heap::RawPtr;
-use std::collections::HashMap;
+use crate::method::ArrayType;
+use crate::JvmValue;
+use reader::constant_pool::ConstantPoolExt;
 
-// 全局数组存储，用于模拟数组操作
-static mut ARRAY_STORAGE: Option<HashMap<RawPtr, Vec<i32>>> = None;
+/// 数组指令都需要访问堆
+fn require_vm(vm: Option<&mut Vm>) -> Result<&mut Vm, JvmError> {
+    vm.ok_or_else(|| JvmError::IllegalStateError("数组指令需要VM实例".to_string()))
+}
 
-fn get_array_storage() -> &'static mut HashMap<RawPtr, Vec<i32>> {
-    unsafe {
-        if ARRAY_STORAGE.is_none() {
-            ARRAY_STORAGE = Some(HashMap::new());
-        }
-        ARRAY_STORAGE.as_mut().unwrap()
+/// 将组件类型名转换为对应的数组类名，如 `java/lang/String` -> `[Ljava/lang/String;`
+fn array_class_name_of(component: &str) -> String {
+    if component.starts_with('[') {
+        format!("[{}", component)
+    } else {
+        format!("[L{};", component)
     }
 }
 
-pub fn exec_newarray(frame: &mut Frame, code: &[u8], mut vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let count = frame.stack.pop_int();
-    let array_type = code[frame.pc];
-    frame.pc += 1;
-    
+/// 分配指定数组类的数组，并按 NegativeArraySizeException 语义检查长度
+fn alloc_array(vm: &mut Vm, array_class: &str, count: i32) -> Result<RawPtr, JvmError> {
     if count < 0 {
-        return Err(JvmError::IllegalStateError(format!("Array size cannot be negative: {}", count)));
-    }
-    
-    if let Some(ref mut vm) = vm {
-        let array_ptr = match array_type {
-            4 => vm.create_simple_array(count as usize, 4).map_err(|e| JvmError::IllegalStateError(format!("Failed to create array: {:?}", e)))?,  // T_BOOLEAN
-            5 => vm.create_simple_array(count as usize, 5).map_err(|e| JvmError::IllegalStateError(format!("Failed to create array: {:?}", e)))?,  // T_CHAR
-            6 => vm.create_simple_array(count as usize, 6).map_err(|e| JvmError::IllegalStateError(format!("Failed to create array: {:?}", e)))?,  // T_FLOAT
-            7 => vm.create_simple_array(count as usize, 7).map_err(|e| JvmError::IllegalStateError(format!("Failed to create array: {:?}", e)))?,  // T_DOUBLE
-            8 => vm.create_simple_array(count as usize, 8).map_err(|e| JvmError::IllegalStateError(format!("Failed to create array: {:?}", e)))?,  // T_BYTE
-            9 => vm.create_simple_array(count as usize, 9).map_err(|e| JvmError::IllegalStateError(format!("Failed to create array: {:?}", e)))?,  // T_SHORT
-            10 => vm.create_simple_array(count as usize, 10).map_err(|e| JvmError::IllegalStateError(format!("Failed to create array: {:?}", e)))?, // T_INT
-            11 => vm.create_simple_array(count as usize, 11).map_err(|e| JvmError::IllegalStateError(format!("Failed to create array: {:?}", e)))?, // T_LONG
-            _ => return Err(JvmError::IllegalStateError(format!("Unsupported array type: {}", array_type))),
-        };
-        
-        // 为int数组初始化存储
-        if array_type == 10 { // T_INT
-            let storage = get_array_storage();
-            storage.insert(array_ptr, vec![0; count as usize]);
-        }
-        
-        frame.stack.push_obj_ref(array_ptr);
-        jvm_log!("[NewArray] 创建数组: 类型={}, 长度={}", array_type, count);
+        return Err(JvmError::NegativeArraySizeError(count.to_string()));
     }
-    
-    Ok(())
+    let klass = vm.load(array_class)?;
+    Ok(vm.alloc_array(&klass, count as usize)?)
 }
 
-pub fn exec_arraylength(frame: &mut Frame, _code: &[u8], _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let array_ref = frame.stack.pop_obj_ref();
-    
+/// 校验数组引用和下标，返回可用的下标
+fn check_index(vm: &Vm, array_ref: RawPtr, index: i32) -> Result<usize, JvmError> {
     if array_ref.is_null() {
-        return Err(JvmError::IllegalStateError("NullPointerException".to_string()));
+        return Err(JvmError::NullPointerError("数组引用为null".to_string()));
     }
-    
-    // 从存储中获取数组长度
-    let storage = get_array_storage();
-    let length = if let Some(array_data) = storage.get(&array_ref) {
-        array_data.len() as i32
-    } else {
-        10 // 默认长度
+    let length = vm.heap.borrow().array_length(array_ref);
+    if index < 0 || index as usize >= length {
+        return Err(JvmError::ArrayIndexOutOfBoundsError(format!(
+            "Index {} out of bounds for length {}",
+            index, length
+        )));
+    }
+    Ok(index as usize)
+}
+
+/// 弹出下标和数组引用并读取元素
+fn array_load(frame: &mut Frame, vm: Option<&mut Vm>, elem_desc: &str) -> Result<JvmValue, JvmError> {
+    let index = frame.stack.pop_int()?;
+    let array_ref = frame.stack.pop_obj_ref()?;
+    let vm = require_vm(vm)?;
+    let index = check_index(vm, array_ref, index)?;
+    let value = vm.heap.borrow().get_array_element(array_ref, index, elem_desc);
+    jvm_log!("[ArrayLoad] {:?}[{}] = {:?}", array_ref, index, value);
+    Ok(value)
+}
+
+/// 写入元素，值、下标和数组引用都已从操作数栈弹出
+fn array_store(vm: &mut Vm, array_ref: RawPtr, index: i32, value: JvmValue) -> Result<(), JvmError> {
+    let index = check_index(vm, array_ref, index)?;
+    put_element(vm, array_ref, index, value);
+    Ok(())
+}
+
+/// 写入已经检查过下标的元素
+fn put_element(vm: &mut Vm, array_ref: RawPtr, index: usize, value: JvmValue) {
+    jvm_log!("[ArrayStore] {:?}[{}] = {:?}", array_ref, index, value);
+    vm.heap.borrow_mut().put_array_element(array_ref, index, value);
+}
+
+pub fn exec_newarray(frame: &mut Frame, instruction: &Instruction, vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let count = frame.stack.pop_int()?;
+    let array_type = match instruction {
+        Instruction::Newarray(array_type) => array_type,
+        _ => return Err(JvmError::IllegalStateError(format!("newarray: 错误的指令 {:?}", instruction))),
     };
-    
-    frame.stack.push_int(length);
-    jvm_log!("[ArrayLength] 获取数组长度: {}", length);
-    
+    let vm = require_vm(vm)?;
+    let array_ptr = alloc_array(vm, &format!("[{}", array_type.get_descriptor()), count)?;
+    frame.stack.push_obj_ref(array_ptr)?;
+    jvm_log!("[NewArray] 创建数组: 类型={:?}, 长度={}", array_type, count);
     Ok(())
 }
 
-pub fn exec_iastore(frame: &mut Frame, _code: &[u8], _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let value = frame.stack.pop_int();
-    let index = frame.stack.pop_int();
-    let array_ref = frame.stack.pop_obj_ref();
-    
-    if array_ref.is_null() {
-        return Err(JvmError::IllegalStateError("NullPointerException".to_string()));
+/// anewarray 指令 - 创建引用类型数组
+pub fn exec_anewarray(frame: &mut Frame, instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let index = instruction.cp_index()?;
+    let count = frame.stack.pop_int()?;
+    let component = frame.method.constant_pool.get_class_name(index);
+
+    let vm = require_vm(vm)?;
+    let array_class = array_class_name_of(&component);
+    let array_ptr = alloc_array(vm, &array_class, count)?;
+    frame.stack.push_obj_ref(array_ptr)?;
+    jvm_log!("[ANewArray] 创建数组: {}, 长度={}", array_class, count);
+    Ok(())
+}
+
+/// 按各维长度递归创建多维数组，未指定长度的内层维度保持null
+fn alloc_multi_array(vm: &mut Vm, array_class: &str, counts: &[i32]) -> Result<RawPtr, JvmError> {
+    let array_ptr = alloc_array(vm, array_class, counts[0])?;
+    if counts.len() == 1 {
+        return Ok(array_ptr);
     }
-    
-    if index < 0 {
-        return Err(JvmError::IllegalStateError(format!("Array index cannot be negative: {}", index)));
+    // 创建内层数组时可能发生垃圾收集，外层数组通过句柄跟随移动
+    let handle = vm.push_handle(array_ptr);
+    let sub_class = &array_class[1..];
+    let result = (0..counts[0] as usize).try_for_each(|i| {
+        let sub_array = alloc_multi_array(vm, sub_class, &counts[1..])?;
+        vm.heap.borrow_mut().put_array_element(vm.handle(handle), i, JvmValue::ObjRef(sub_array));
+        Ok(())
+    });
+    let array_ptr = vm.handle(handle);
+    vm.pop_handles(handle);
+    result.map(|_| array_ptr)
+}
+
+/// multianewarray 指令 - 创建多维数组
+pub fn exec_multianewarray(frame: &mut Frame, instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let (index, dimensions) = match *instruction {
+        Instruction::Multianewarray(index, dimensions) => (index, dimensions as usize),
+        _ => return Err(JvmError::IllegalStateError(format!("multianewarray: 错误的指令 {:?}", instruction))),
+    };
+    let array_class = frame.method.constant_pool.get_class_name(index);
+
+    let mut counts = vec![0; dimensions];
+    for count in counts.iter_mut().rev() {
+        *count = frame.stack.pop_int()?;
     }
-    
-    // 存储数组元素
-    let storage = get_array_storage();
-    if let Some(array_data) = storage.get_mut(&array_ref) {
-        if (index as usize) < array_data.len() {
-            array_data[index as usize] = value;
-            jvm_log!("[IAStore] 存储数组元素: 索引={}, 值={}", index, value);
-        } else {
-            return Err(JvmError::IllegalStateError(format!("Array index out of bounds: {}", index)));
-        }
-    } else {
-        // 如果数组不在存储中，创建一个新的
-        let mut new_array = vec![0; (index + 1) as usize];
-        new_array[index as usize] = value;
-        storage.insert(array_ref, new_array);
-        jvm_log!("[IAStore] 创建新数组并存储元素: 索引={}, 值={}", index, value);
+    // 所有维度都要先检查，负数长度不应分配任何数组
+    if let Some(negative) = counts.iter().find(|&&c| c < 0) {
+        return Err(JvmError::NegativeArraySizeError(negative.to_string()));
     }
-    
+
+    let vm = require_vm(vm)?;
+    let array_ptr = alloc_multi_array(vm, &array_class, &counts)?;
+    frame.stack.push_obj_ref(array_ptr)?;
+    jvm_log!("[MultiANewArray] 创建数组: {}, 维度={:?}", array_class, counts);
     Ok(())
 }
 
-pub fn exec_iaload(frame: &mut Frame, _code: &[u8], _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let index = frame.stack.pop_int();
-    let array_ref = frame.stack.pop_obj_ref();
-    
+pub fn exec_arraylength(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let array_ref = frame.stack.pop_obj_ref()?;
+
     if array_ref.is_null() {
-        return Err(JvmError::IllegalStateError("NullPointerException".to_string()));
+        return Err(JvmError::NullPointerError("arraylength: 数组引用为null".to_string()));
     }
-    
-    if index < 0 {
-        return Err(JvmError::IllegalStateError(format!("Array index cannot be negative: {}", index)));
-    }
-    
-    // 从存储中获取数组元素
-    let storage = get_array_storage();
-    let value = if let Some(array_data) = storage.get(&array_ref) {
-        if (index as usize) < array_data.len() {
-            array_data[index as usize]
-        } else {
-            return Err(JvmError::IllegalStateError(format!("Array index out of bounds: {}", index)));
-        }
+
+    let vm = require_vm(vm)?;
+    let length = vm.heap.borrow().array_length(array_ref);
+    frame.stack.push_int(length as i32)?;
+    jvm_log!("[ArrayLength] 获取数组长度: {}", length);
+
+    Ok(())
+}
+
+// xaload 指令族
+pub fn exec_iaload(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let value = array_load(frame, vm, "I")?.as_int().unwrap_or(0);
+    frame.stack.push_int(value as i32)?;
+    Ok(())
+}
+
+pub fn exec_laload(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = array_load(frame, vm, "J")?.as_long().unwrap_or(0);
+    frame.stack.push_long(value as i64)?;
+    Ok(())
+}
+
+pub fn exec_faload(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = array_load(frame, vm, "F")?.as_float().unwrap_or(0);
+    frame.stack.push_float(f32::from_bits(value as u32))?;
+    Ok(())
+}
+
+pub fn exec_daload(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = array_load(frame, vm, "D")?.as_double().unwrap_or(0);
+    frame.stack.push_double(f64::from_bits(value))?;
+    Ok(())
+}
+
+pub fn exec_aaload(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = array_load(frame, vm, "L")?.as_obj_ref().unwrap_or(RawPtr(std::ptr::null_mut()));
+    frame.stack.push_obj_ref(value)?;
+    Ok(())
+}
+
+/// baload 指令 - byte[] 和 boolean[] 共用，结果符号扩展为int
+pub fn exec_baload(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = array_load(frame, vm, "B")?.as_byte().unwrap_or(0);
+    frame.stack.push_int(value as i8 as i32)?;
+    Ok(())
+}
+
+/// caload 指令 - 结果零扩展为int
+pub fn exec_caload(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = array_load(frame, vm, "C")?.as_char().unwrap_or(0);
+    frame.stack.push_int(value as i32)?;
+    Ok(())
+}
+
+/// saload 指令 - 结果符号扩展为int
+pub fn exec_saload(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = array_load(frame, vm, "S")?.as_short().unwrap_or(0);
+    frame.stack.push_int(value as i16 as i32)?;
+    Ok(())
+}
+
+// xastore 指令族
+pub fn exec_iastore(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    let index = frame.stack.pop_int()?;
+    let array_ref = frame.stack.pop_obj_ref()?;
+    array_store(require_vm(vm)?, array_ref, index, JvmValue::Int(value as u32))
+}
+
+pub fn exec_lastore(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_long()?;
+    let index = frame.stack.pop_int()?;
+    let array_ref = frame.stack.pop_obj_ref()?;
+    array_store(require_vm(vm)?, array_ref, index, JvmValue::Long(value as u64))
+}
+
+pub fn exec_fastore(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_float()?;
+    let index = frame.stack.pop_int()?;
+    let array_ref = frame.stack.pop_obj_ref()?;
+    array_store(require_vm(vm)?, array_ref, index, JvmValue::Float(value.to_bits() as u64))
+}
+
+pub fn exec_dastore(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_double()?;
+    let index = frame.stack.pop_int()?;
+    let array_ref = frame.stack.pop_obj_ref()?;
+    array_store(require_vm(vm)?, array_ref, index, JvmValue::Double(value.to_bits()))
+}
+
+/// bastore 指令 - 写入boolean[]时只保留最低位
+pub fn exec_bastore(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    let index = frame.stack.pop_int()?;
+    let array_ref = frame.stack.pop_obj_ref()?;
+    let vm = require_vm(vm)?;
+    let value = if vm.get_object_class_name(array_ref).as_deref() == Some("[Z") {
+        JvmValue::Boolean((value & 1) as u8)
     } else {
-        0 // 默认值
+        JvmValue::Byte(value as u8)
     };
-    
-    frame.stack.push_int(value);
-    jvm_log!("[IALoad] 加载数组元素: 索引={}, 值={}", index, value);
-    
+    array_store(vm, array_ref, index, value)
+}
+
+pub fn exec_castore(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    let index = frame.stack.pop_int()?;
+    let array_ref = frame.stack.pop_obj_ref()?;
+    array_store(require_vm(vm)?, array_ref, index, JvmValue::Char(value as u16))
+}
+
+pub fn exec_sastore(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    let index = frame.stack.pop_int()?;
+    let array_ref = frame.stack.pop_obj_ref()?;
+    array_store(require_vm(vm)?, array_ref, index, JvmValue::Short(value as u16))
+}
+
+/// aastore 指令 - 写入前检查值的运行时类型能否赋值给数组的元素类型
+pub fn exec_aastore(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_obj_ref()?;
+    let index = frame.stack.pop_int()?;
+    let array_ref = frame.stack.pop_obj_ref()?;
+    reference_array_store(require_vm(vm)?, array_ref, index, value)
+}
+
+/// 按aastore的语义写入引用数组元素：先检查数组引用和下标，再检查非null的值能否赋值给数组的元素类型
+pub fn reference_array_store(vm: &mut Vm, array_ref: RawPtr, index: i32, value: RawPtr) -> Result<(), JvmError> {
+    let index = check_index(vm, array_ref, index)?;
+    if !value.is_null() {
+        let array_class = vm.get_object_class_name(array_ref);
+        let value_class = vm.get_object_class_name(value);
+        if let (Some(array_class), Some(value_class)) = (array_class, value_class) {
+            let component = &array_class[1..];
+            if !vm.is_subtype_of(&value_class, component) {
+                return Err(JvmError::ArrayStoreError(value_class.replace('/', ".")));
+            }
+        }
+    }
+    put_element(vm, array_ref, index, JvmValue::ObjRef(value));
     Ok(())
-} 
\ No newline at end of file
+}
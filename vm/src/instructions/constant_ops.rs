@@ -10,11 +10,11 @@ pub fn exec_ldc(frame: &mut Frame, code: &[u8], pc: &mut usize, vm: Option<&mut
     match &cp[index - 1] {
         reader::constant_pool::CpInfo::Integer { bytes, .. } => {
             let value = *bytes as i32;
-            frame.stack.push_int(value);
+            frame.stack.push_int(value)?;
         }
         reader::constant_pool::CpInfo::Float { bytes, .. } => {
             let value = f32::from_bits(*bytes);
-            frame.stack.push_int(value.to_bits() as i32);
+            frame.stack.push_int(value.to_bits() as i32)?;
         }
         reader::constant_pool::CpInfo::String { string_index, .. } => {
             let s = cp.get_utf8_string(*string_index);
@@ -24,7 +24,7 @@ pub fn exec_ldc(frame: &mut Frame, code: &[u8], pc: &mut usize, vm: Option<&mut
                 match vm.create_string_object(&s) {
                     Ok(string_ptr) => {
                         // 将对象引用推入栈
-                        frame.stack.push_obj_ref(string_ptr);
+                        frame.stack.push_obj_ref(string_ptr)?;
                     }
                     Err(e) => {
                         return Err(JvmError::IllegalStateError(format!("Failed to create string object: {:?}", e)));
@@ -48,11 +48,11 @@ pub fn exec_ldc_w(frame: &mut Frame, code: &[u8], pc: &mut usize, vm: Option<&mu
     match &cp[index - 1] {
         reader::constant_pool::CpInfo::Integer { bytes, .. } => {
             let value = *bytes as i32;
-            frame.stack.push_int(value);
+            frame.stack.push_int(value)?;
         }
         reader::constant_pool::CpInfo::Float { bytes, .. } => {
             let value = f32::from_bits(*bytes);
-            frame.stack.push_int(value.to_bits() as i32);
+            frame.stack.push_int(value.to_bits() as i32)?;
         }
         reader::constant_pool::CpInfo::String { string_index, .. } => {
             let s = cp.get_utf8_string(*string_index);
@@ -62,7 +62,7 @@ pub fn exec_ldc_w(frame: &mut Frame, code: &[u8], pc: &mut usize, vm: Option<&mu
                 match vm.create_string_object(&s) {
                     Ok(string_ptr) => {
                         // 将对象引用推入栈
-                        frame.stack.push_obj_ref(string_ptr);
+                        frame.stack.push_obj_ref(string_ptr)?;
                     }
                     Err(e) => {
                         return Err(JvmError::IllegalStateError(format!("Failed to create string object: {:?}", e)));
@@ -88,16 +88,16 @@ pub fn exec_ldc2_w(frame: &mut Frame, code: &[u8], pc: &mut usize, _vm: Option<&
             let value = ((*high_bytes as u64) << 32) | (*low_bytes as u64);
             jvm_log!("ldc2_w long: {} (推入两个32位值)", value);
             // 先推高32位，再推低32位
-            frame.stack.push_int((*high_bytes) as i32);
-            frame.stack.push_int((*low_bytes) as i32);
+            frame.stack.push_int((*high_bytes) as i32)?;
+            frame.stack.push_int((*low_bytes) as i32)?;
         }
         reader::constant_pool::CpInfo::Double { high_bytes, low_bytes, .. } => {
             let bits = ((*high_bytes as u64) << 32) | (*low_bytes as u64);
             let value = f64::from_bits(bits);
             jvm_log!("ldc2_w double: {} (推入两个32位值)", value);
             // 先推高32位，再推低32位
-            frame.stack.push_int((*high_bytes) as i32);
-            frame.stack.push_int((*low_bytes) as i32);
+            frame.stack.push_int((*high_bytes) as i32)?;
+            frame.stack.push_int((*low_bytes) as i32)?;
         }
         _ => {
             return Err(JvmError::IllegalStateError(format!("ldc2_w: 常量池索引{}不是long/double", index)));
@@ -1,63 +1,78 @@
 use crate::jvm_thread::Frame;
 use crate::error::JvmError;
+use crate::instructions::Instruction;
 use crate::vm::Vm;
+use crate::heap::RawPtr;
 
 // iconst 指令族
-pub fn exec_iconst_m1(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    frame.stack.push_int(-1);
+pub fn exec_iconst_m1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_int(-1)?;
     Ok(())
 }
 
-pub fn exec_iconst_0(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    frame.stack.push_int(0);
+pub fn exec_iconst_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_int(0)?;
     Ok(())
 }
 
-pub fn exec_iconst_1(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    frame.stack.push_int(1);
+pub fn exec_iconst_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_int(1)?;
     Ok(())
 }
 
-pub fn exec_iconst_2(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    frame.stack.push_int(2);
+pub fn exec_iconst_2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_int(2)?;
     Ok(())
 }
 
-pub fn exec_iconst_3(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    frame.stack.push_int(3);
+pub fn exec_iconst_3(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_int(3)?;
     Ok(())
 }
 
-pub fn exec_iconst_4(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    frame.stack.push_int(4);
+pub fn exec_iconst_4(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_int(4)?;
     Ok(())
 }
 
-pub fn exec_iconst_5(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    frame.stack.push_int(5);
+pub fn exec_iconst_5(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_int(5)?;
+    Ok(())
+}
+
+// lconst 指令族
+pub fn exec_lconst_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_long(0)?;
+    Ok(())
+}
+
+pub fn exec_lconst_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_long(1)?;
     Ok(())
 }
 
 // bipush 指令
-pub fn exec_bipush(frame: &mut Frame, code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let byte = code[frame.pc] as i8;
-    frame.pc += 1;
-    frame.stack.push_int(byte as i32);
+pub fn exec_bipush(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let byte = match *instruction {
+        Instruction::Bipush(byte) => byte as i8,
+        _ => return Err(JvmError::IllegalStateError(format!("bipush: 错误的指令 {:?}", instruction))),
+    };
+    frame.stack.push_int(byte as i32)?;
     Ok(())
 }
 
 // sipush 指令
-pub fn exec_sipush(frame: &mut Frame, code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let high = code[frame.pc] as i16;
-    let low = code[frame.pc + 1] as i16;
-    frame.pc += 2;
-    let value = ((high << 8) | (low & 0xFF)) as i16;
-    frame.stack.push_int(value as i32);
+pub fn exec_sipush(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = match *instruction {
+        Instruction::Sipush(value) => value as i16,
+        _ => return Err(JvmError::IllegalStateError(format!("sipush: 错误的指令 {:?}", instruction))),
+    };
+    frame.stack.push_int(value as i32)?;
     Ok(())
 }
 
-// aconst_null 指令
-pub fn exec_aconst_null(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    frame.stack.push_null();
+// aconst_null 指令，null是引用值，压入引用栈以便ifnull/if_acmpeq等指令检查
+pub fn exec_aconst_null(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_null()?;
     Ok(())
 } 
\ No newline at end of file
@@ -1,68 +1,57 @@
 use crate::jvm_thread::Frame;
 use crate::error::JvmError;
+use crate::instructions::Instruction;
 use crate::vm::Vm;
 use crate::JvmValue;
 use crate::jvm_log;
 
 // goto 指令
-pub fn exec_goto(frame: &mut Frame, code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let offset = ((code[frame.pc] as i16) << 8 | (code[frame.pc + 1] as i16)) as i32;
-    frame.pc += 2;
-    frame.pc = (frame.pc as i32 + offset - 3) as usize; // -3 是因为我们已经读取了opcode和offset
+pub fn exec_goto(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.pc = instruction.branch_target()?;
     Ok(())
 }
 
 // ifeq 指令
-pub fn exec_ifeq(frame: &mut Frame, code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let offset = ((code[frame.pc] as i16) << 8 | (code[frame.pc + 1] as i16)) as i32;
-    frame.pc += 2;
-    let value = frame.stack.pop_int();
+pub fn exec_ifeq(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
+    let value = frame.stack.pop_int()?;
     if value == 0 {
-        frame.pc = (frame.pc as i32 + offset - 3) as usize;
+        frame.pc = target;
     }
     Ok(())
 }
 
 // ifne 指令
-pub fn exec_ifne(frame: &mut Frame, code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let offset = ((code[frame.pc] as i16) << 8 | (code[frame.pc + 1] as i16)) as i32;
-    frame.pc += 2;
-    let value = frame.stack.pop_int();
+pub fn exec_ifne(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
+    let value = frame.stack.pop_int()?;
     if value != 0 {
-        frame.pc = (frame.pc as i32 + offset - 3) as usize;
+        frame.pc = target;
     }
     Ok(())
 }
 
 // ifge 指令
-pub fn exec_ifge(frame: &mut Frame, code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let offset = ((code[frame.pc] as i16) << 8 | (code[frame.pc + 1] as i16)) as i32;
-    frame.pc += 2;
-    let value = frame.stack.pop_int();
+pub fn exec_ifge(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
+    let value = frame.stack.pop_int()?;
     if value >= 0 {
-        frame.pc = (frame.pc as i32 + offset - 3) as usize;
+        frame.pc = target;
     }
     Ok(())
 }
 
-// return 指令
-pub fn exec_return(_frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    // 这里可以直接返回Ok(())，实际弹栈在jvm_thread里处理
-    Ok(())
-}
-
 /// if_icmpeq 指令 - 如果两个int值相等则跳转
-pub fn exec_if_icmpeq(frame: &mut Frame, code: &[u8], _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let offset = ((code[frame.pc] as i16) << 8 | code[frame.pc + 1] as i16) as i32;
-    frame.pc += 2;
+pub fn exec_if_icmpeq(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
     
-    let value2 = frame.stack.pop_int();
-    let value1 = frame.stack.pop_int();
+    let value2 = frame.stack.pop_int()?;
+    let value1 = frame.stack.pop_int()?;
     
     jvm_log!("if_icmpeq: {} == {} ?", value1, value2);
     
     if value1 == value2 {
-        frame.pc = (frame.pc as i32 + offset) as usize;
+        frame.pc = target;
         jvm_log!("if_icmpeq: 跳转到 {}", frame.pc);
     }
     
@@ -70,17 +59,16 @@ pub fn exec_if_icmpeq(frame: &mut Frame, code: &[u8], _vm: Option<&mut crate::vm
 }
 
 /// if_icmpne 指令 - 如果两个int值不相等则跳转
-pub fn exec_if_icmpne(frame: &mut Frame, code: &[u8], _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let offset = ((code[frame.pc] as i16) << 8 | code[frame.pc + 1] as i16) as i32;
-    frame.pc += 2;
+pub fn exec_if_icmpne(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
     
-    let value2 = frame.stack.pop_int();
-    let value1 = frame.stack.pop_int();
+    let value2 = frame.stack.pop_int()?;
+    let value1 = frame.stack.pop_int()?;
     
     jvm_log!("if_icmpne: {} != {} ?", value1, value2);
     
     if value1 != value2 {
-        frame.pc = (frame.pc as i32 + offset) as usize;
+        frame.pc = target;
         jvm_log!("if_icmpne: 跳转到 {}", frame.pc);
     }
     
@@ -88,57 +76,150 @@ pub fn exec_if_icmpne(frame: &mut Frame, code: &[u8], _vm: Option<&mut crate::vm
 }
 
 /// tableswitch 指令 - 表跳转
-pub fn exec_tableswitch(frame: &mut Frame, code: &[u8], _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    // 对齐到4字节边界
-    let padding = (4 - (frame.pc % 4)) % 4;
-    frame.pc += padding;
-    
-    // 读取default offset
-    let default_offset = ((code[frame.pc] as i32) << 24 | 
-                         (code[frame.pc + 1] as i32) << 16 | 
-                         (code[frame.pc + 2] as i32) << 8 | 
-                         (code[frame.pc + 3] as i32)) as i32;
-    frame.pc += 4;
-    
-    // 读取low和high值
-    let low = ((code[frame.pc] as i32) << 24 | 
-               (code[frame.pc + 1] as i32) << 16 | 
-               (code[frame.pc + 2] as i32) << 8 | 
-               (code[frame.pc + 3] as i32)) as i32;
-    frame.pc += 4;
-    
-    let high = ((code[frame.pc] as i32) << 24 | 
-                (code[frame.pc + 1] as i32) << 16 | 
-                (code[frame.pc + 2] as i32) << 8 | 
-                (code[frame.pc + 3] as i32)) as i32;
-    frame.pc += 4;
-    
-    // 读取跳转表
-    let num_cases = (high - low + 1) as usize;
-    let mut jump_table = Vec::with_capacity(num_cases);
-    
-    for _ in 0..num_cases {
-        let offset = ((code[frame.pc] as i32) << 24 | 
-                     (code[frame.pc + 1] as i32) << 16 | 
-                     (code[frame.pc + 2] as i32) << 8 | 
-                     (code[frame.pc + 3] as i32)) as i32;
-        jump_table.push(offset);
-        frame.pc += 4;
-    }
-    
-    let key = frame.stack.pop_int();
+pub fn exec_tableswitch(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let (default, low, high, targets) = match instruction {
+        Instruction::Tableswitch(default, low, high, targets) => (*default, *low, *high, targets),
+        _ => return Err(JvmError::IllegalStateError(format!("tableswitch: 错误的指令 {:?}", instruction))),
+    };
+    let key = frame.stack.pop_int()?;
     jvm_log!("tableswitch: key={}, low={}, high={}", key, low, high);
-    
+
     // 查找匹配的case
     if key >= low && key <= high {
-        let index = (key - low) as usize;
-        let offset = jump_table[index];
-        frame.pc = (frame.pc as i32 + offset) as usize;
-        jvm_log!("tableswitch: 跳转到case {}, offset={}", key, offset);
+        frame.pc = targets[(key as i64 - low as i64) as usize];
+        jvm_log!("tableswitch: 跳转到case {}, target={}", key, frame.pc);
     } else {
-        frame.pc = (frame.pc as i32 + default_offset) as usize;
-        jvm_log!("tableswitch: 跳转到default, offset={}", default_offset);
+        frame.pc = default;
+        jvm_log!("tableswitch: 跳转到default, target={}", default);
+    }
+    Ok(())
+}
+
+/// lookupswitch 指令 - 按键值查找跳转，匹配对已按键值升序排列
+pub fn exec_lookupswitch(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let (default, pairs) = match instruction {
+        Instruction::Lookupswitch(default, pairs) => (*default, pairs),
+        _ => return Err(JvmError::IllegalStateError(format!("lookupswitch: 错误的指令 {:?}", instruction))),
+    };
+    let key = frame.stack.pop_int()?;
+    let target = pairs
+        .binary_search_by_key(&key, |&(match_key, _)| match_key)
+        .map_or(default, |i| pairs[i].1);
+    jvm_log!("lookupswitch: key={}, target={}", key, target);
+    frame.pc = target;
+    Ok(())
+}
+
+/// goto_w 指令 - 使用32位偏移量无条件跳转
+pub fn exec_goto_w(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.pc = instruction.branch_target()?;
+    Ok(())
+}
+
+/// iflt 指令 - 如果int值小于0则跳转
+pub fn exec_iflt(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
+    let value = frame.stack.pop_int()?;
+    if value < 0 {
+        frame.pc = target;
+    }
+    Ok(())
+}
+
+/// ifgt 指令 - 如果int值大于0则跳转
+pub fn exec_ifgt(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
+    let value = frame.stack.pop_int()?;
+    if value > 0 {
+        frame.pc = target;
+    }
+    Ok(())
+}
+
+/// ifle 指令 - 如果int值小于等于0则跳转
+pub fn exec_ifle(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
+    let value = frame.stack.pop_int()?;
+    if value <= 0 {
+        frame.pc = target;
     }
-    
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// if_icmplt 指令 - 如果第一个int值小于第二个int值则跳转
+pub fn exec_if_icmplt(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
+    let value2 = frame.stack.pop_int()?;
+    let value1 = frame.stack.pop_int()?;
+    jvm_log!("if_icmplt: {} < {} ?", value1, value2);
+    if value1 < value2 {
+        frame.pc = target;
+    }
+    Ok(())
+}
+
+/// if_icmpgt 指令 - 如果第一个int值大于第二个int值则跳转
+pub fn exec_if_icmpgt(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
+    let value2 = frame.stack.pop_int()?;
+    let value1 = frame.stack.pop_int()?;
+    jvm_log!("if_icmpgt: {} > {} ?", value1, value2);
+    if value1 > value2 {
+        frame.pc = target;
+    }
+    Ok(())
+}
+
+/// if_icmple 指令 - 如果第一个int值小于等于第二个int值则跳转
+pub fn exec_if_icmple(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
+    let value2 = frame.stack.pop_int()?;
+    let value1 = frame.stack.pop_int()?;
+    jvm_log!("if_icmple: {} <= {} ?", value1, value2);
+    if value1 <= value2 {
+        frame.pc = target;
+    }
+    Ok(())
+}
+
+/// if_acmpeq 指令 - 如果两个引用是同一个对象则跳转
+pub fn exec_if_acmpeq(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
+    let value2 = frame.stack.pop_obj_ref()?;
+    let value1 = frame.stack.pop_obj_ref()?;
+    if value1 == value2 {
+        frame.pc = target;
+    }
+    Ok(())
+}
+
+/// if_acmpne 指令 - 如果两个引用不是同一个对象则跳转
+pub fn exec_if_acmpne(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
+    let value2 = frame.stack.pop_obj_ref()?;
+    let value1 = frame.stack.pop_obj_ref()?;
+    if value1 != value2 {
+        frame.pc = target;
+    }
+    Ok(())
+}
+
+/// ifnull 指令 - 如果引用为null则跳转
+pub fn exec_ifnull(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
+    let value = frame.stack.pop_obj_ref()?;
+    if value.is_null() {
+        frame.pc = target;
+    }
+    Ok(())
+}
+
+/// ifnonnull 指令 - 如果引用不为null则跳转
+pub fn exec_ifnonnull(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = instruction.branch_target()?;
+    let value = frame.stack.pop_obj_ref()?;
+    if !value.is_null() {
+        frame.pc = target;
+    }
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use crate::jvm_thread::Frame;
+use crate::error::JvmError;
+use crate::instructions::Instruction;
+use crate::vm::Vm;
+
+// 类型转换指令族
+// Rust 的浮点到整数 `as` 转换本身就是饱和的，且 NaN 转为 0，与 JVMS 的 f2i/f2l/d2i/d2l 定义一致；
+// 整数到浮点按就近舍入，与 Java 的拓宽/收窄转换结果相同。
+
+pub fn exec_i2l(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    frame.stack.push_long(value as i64)?;
+    Ok(())
+}
+
+pub fn exec_i2f(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    frame.stack.push_float(value as f32)?;
+    Ok(())
+}
+
+pub fn exec_i2d(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    frame.stack.push_double(value as f64)?;
+    Ok(())
+}
+
+// 只保留低32位
+pub fn exec_l2i(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_long()?;
+    frame.stack.push_int(value as i32)?;
+    Ok(())
+}
+
+pub fn exec_l2f(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_long()?;
+    frame.stack.push_float(value as f32)?;
+    Ok(())
+}
+
+pub fn exec_l2d(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_long()?;
+    frame.stack.push_double(value as f64)?;
+    Ok(())
+}
+
+pub fn exec_f2i(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_float()?;
+    frame.stack.push_int(value as i32)?;
+    Ok(())
+}
+
+pub fn exec_f2l(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_float()?;
+    frame.stack.push_long(value as i64)?;
+    Ok(())
+}
+
+pub fn exec_f2d(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_float()?;
+    frame.stack.push_double(value as f64)?;
+    Ok(())
+}
+
+pub fn exec_d2i(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_double()?;
+    frame.stack.push_int(value as i32)?;
+    Ok(())
+}
+
+pub fn exec_d2l(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_double()?;
+    frame.stack.push_long(value as i64)?;
+    Ok(())
+}
+
+pub fn exec_d2f(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_double()?;
+    frame.stack.push_float(value as f32)?;
+    Ok(())
+}
+
+// 截断到8位后符号扩展
+pub fn exec_i2b(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    frame.stack.push_int(value as i8 as i32)?;
+    Ok(())
+}
+
+// 截断到16位后零扩展
+pub fn exec_i2c(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    frame.stack.push_int(value as u16 as i32)?;
+    Ok(())
+}
+
+// 截断到16位后符号扩展
+pub fn exec_i2s(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    frame.stack.push_int(value as i16 as i32)?;
+    Ok(())
+}
@@ -1 +1,240 @@
- 
\ No newline at end of file
+//! 指令分发表：opcode到指令处理函数的唯一映射，所有执行路径都通过这里分发指令
+
+use crate::def_instr;
+use crate::error::JvmError;
+use crate::instructions::Instruction;
+use crate::instructions::types::InstructionFn;
+use crate::instructions::{
+    aload_0, arithmetic, array_ops, constants, control, control_extended, conversions, exception_ops, field_ops,
+    float_ops, iinc, invokedynamic, invokeinterface, invokespecial, invokestatic, invokevirtual, ldc_ops,
+    load_store, object_ops, stack,
+};
+use crate::jvm_thread::Frame;
+use crate::vm::Vm;
+
+/// nop 指令
+fn exec_nop(_frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    Ok(())
+}
+
+def_instr! {
+    DISPATCH_TABLE;
+    // 常量
+    0x00 => exec_nop,
+    0x01 => constants::exec_aconst_null,
+    0x02 => constants::exec_iconst_m1,
+    0x03 => constants::exec_iconst_0,
+    0x04 => constants::exec_iconst_1,
+    0x05 => constants::exec_iconst_2,
+    0x06 => constants::exec_iconst_3,
+    0x07 => constants::exec_iconst_4,
+    0x08 => constants::exec_iconst_5,
+    0x09 => constants::exec_lconst_0,
+    0x0a => constants::exec_lconst_1,
+    0x0b => float_ops::exec_fconst_0,
+    0x0c => float_ops::exec_fconst_1,
+    0x0d => float_ops::exec_fconst_2,
+    0x0e => float_ops::exec_dconst_0,
+    0x0f => float_ops::exec_dconst_1,
+    0x10 => constants::exec_bipush,
+    0x11 => ldc_ops::exec_sipush,
+    0x12 => ldc_ops::exec_ldc,
+    0x13 => ldc_ops::exec_ldc_w,
+    0x14 => ldc_ops::exec_ldc2_w,
+    // 加载
+    0x15 => load_store::exec_iload,
+    0x16 => load_store::exec_lload,
+    0x17 => load_store::exec_fload,
+    0x18 => load_store::exec_dload,
+    0x19 => load_store::exec_aload,
+    0x1a => load_store::exec_iload_0,
+    0x1b => load_store::exec_iload_1,
+    0x1c => load_store::exec_iload_2,
+    0x1d => load_store::exec_iload_3,
+    0x1e => load_store::exec_lload_0,
+    0x1f => load_store::exec_lload_1,
+    0x20 => load_store::exec_lload_2,
+    0x21 => load_store::exec_lload_3,
+    0x22 => load_store::exec_fload_0,
+    0x23 => load_store::exec_fload_1,
+    0x24 => load_store::exec_fload_2,
+    0x25 => load_store::exec_fload_3,
+    0x26 => load_store::exec_dload_0,
+    0x27 => load_store::exec_dload_1,
+    0x28 => load_store::exec_dload_2,
+    0x29 => load_store::exec_dload_3,
+    0x2a => aload_0::exec_aload_0,
+    0x2b => load_store::exec_aload_1,
+    0x2c => load_store::exec_aload_2,
+    0x2d => load_store::exec_aload_3,
+    0x2e => array_ops::exec_iaload,
+    0x2f => array_ops::exec_laload,
+    0x30 => array_ops::exec_faload,
+    0x31 => array_ops::exec_daload,
+    0x32 => array_ops::exec_aaload,
+    0x33 => array_ops::exec_baload,
+    0x34 => array_ops::exec_caload,
+    0x35 => array_ops::exec_saload,
+    // 存储
+    0x36 => load_store::exec_istore,
+    0x37 => load_store::exec_lstore,
+    0x38 => load_store::exec_fstore,
+    0x39 => load_store::exec_dstore,
+    0x3a => load_store::exec_astore,
+    0x3b => load_store::exec_istore_0,
+    0x3c => load_store::exec_istore_1,
+    0x3d => load_store::exec_istore_2,
+    0x3e => load_store::exec_istore_3,
+    0x3f => load_store::exec_lstore_0,
+    0x40 => load_store::exec_lstore_1,
+    0x41 => load_store::exec_lstore_2,
+    0x42 => load_store::exec_lstore_3,
+    0x43 => load_store::exec_fstore_0,
+    0x44 => load_store::exec_fstore_1,
+    0x45 => load_store::exec_fstore_2,
+    0x46 => load_store::exec_fstore_3,
+    0x47 => load_store::exec_dstore_0,
+    0x48 => load_store::exec_dstore_1,
+    0x49 => load_store::exec_dstore_2,
+    0x4a => load_store::exec_dstore_3,
+    0x4b => load_store::exec_astore_0,
+    0x4c => load_store::exec_astore_1,
+    0x4d => load_store::exec_astore_2,
+    0x4e => load_store::exec_astore_3,
+    0x4f => array_ops::exec_iastore,
+    0x50 => array_ops::exec_lastore,
+    0x51 => array_ops::exec_fastore,
+    0x52 => array_ops::exec_dastore,
+    0x53 => array_ops::exec_aastore,
+    0x54 => array_ops::exec_bastore,
+    0x55 => array_ops::exec_castore,
+    0x56 => array_ops::exec_sastore,
+    // 操作数栈
+    0x57 => stack::exec_pop,
+    0x58 => stack::exec_pop2,
+    0x59 => stack::exec_dup,
+    0x5a => stack::exec_dup_x1,
+    0x5b => stack::exec_dup_x2,
+    0x5c => stack::exec_dup2,
+    0x5d => stack::exec_dup2_x1,
+    0x5e => stack::exec_dup2_x2,
+    0x5f => stack::exec_swap,
+    // 算术
+    0x60 => arithmetic::exec_iadd,
+    0x61 => arithmetic::exec_ladd,
+    0x62 => float_ops::exec_fadd,
+    0x63 => float_ops::exec_dadd,
+    0x64 => arithmetic::exec_isub,
+    0x65 => arithmetic::exec_lsub,
+    0x66 => float_ops::exec_fsub,
+    0x67 => float_ops::exec_dsub,
+    0x68 => arithmetic::exec_imul,
+    0x69 => arithmetic::exec_lmul,
+    0x6a => float_ops::exec_fmul,
+    0x6b => float_ops::exec_dmul,
+    0x6c => arithmetic::exec_idiv,
+    0x6d => arithmetic::exec_ldiv,
+    0x6e => float_ops::exec_fdiv,
+    0x6f => float_ops::exec_ddiv,
+    0x70 => arithmetic::exec_irem,
+    0x71 => arithmetic::exec_lrem,
+    0x72 => float_ops::exec_frem,
+    0x73 => float_ops::exec_drem,
+    0x74 => arithmetic::exec_ineg,
+    0x75 => arithmetic::exec_lneg,
+    0x76 => float_ops::exec_fneg,
+    0x77 => float_ops::exec_dneg,
+    0x78 => arithmetic::exec_ishl,
+    0x79 => arithmetic::exec_lshl,
+    0x7a => arithmetic::exec_ishr,
+    0x7b => arithmetic::exec_lshr,
+    0x7c => arithmetic::exec_iushr,
+    0x7d => arithmetic::exec_lushr,
+    0x7e => arithmetic::exec_iand,
+    0x7f => arithmetic::exec_land,
+    0x80 => arithmetic::exec_ior,
+    0x81 => arithmetic::exec_lor,
+    0x82 => arithmetic::exec_ixor,
+    0x83 => arithmetic::exec_lxor,
+    0x84 => iinc::exec_iinc,
+    // 类型转换
+    0x85 => conversions::exec_i2l,
+    0x86 => conversions::exec_i2f,
+    0x87 => conversions::exec_i2d,
+    0x88 => conversions::exec_l2i,
+    0x89 => conversions::exec_l2f,
+    0x8a => conversions::exec_l2d,
+    0x8b => conversions::exec_f2i,
+    0x8c => conversions::exec_f2l,
+    0x8d => conversions::exec_f2d,
+    0x8e => conversions::exec_d2i,
+    0x8f => conversions::exec_d2l,
+    0x90 => conversions::exec_d2f,
+    0x91 => conversions::exec_i2b,
+    0x92 => conversions::exec_i2c,
+    0x93 => conversions::exec_i2s,
+    // 比较
+    0x94 => arithmetic::exec_lcmp,
+    0x95 => float_ops::exec_fcmpl,
+    0x96 => float_ops::exec_fcmpg,
+    0x97 => float_ops::exec_dcmpl,
+    0x98 => float_ops::exec_dcmpg,
+    0x99 => control::exec_ifeq,
+    0x9a => control::exec_ifne,
+    0x9b => control::exec_iflt,
+    0x9c => control::exec_ifge,
+    0x9d => control::exec_ifgt,
+    0x9e => control::exec_ifle,
+    0x9f => control::exec_if_icmpeq,
+    0xa0 => control::exec_if_icmpne,
+    0xa1 => control::exec_if_icmplt,
+    0xa2 => control_extended::exec_if_icmpge,
+    0xa3 => control::exec_if_icmpgt,
+    0xa4 => control::exec_if_icmple,
+    0xa5 => control::exec_if_acmpeq,
+    0xa6 => control::exec_if_acmpne,
+    // 控制转移（返回指令由解释循环直接处理）
+    0xa7 => control::exec_goto,
+    0xaa => control::exec_tableswitch,
+    0xab => control::exec_lookupswitch,
+    // 对象、字段与方法调用
+    0xb2 => field_ops::exec_getstatic,
+    0xb3 => field_ops::exec_putstatic,
+    0xb4 => object_ops::exec_getfield,
+    0xb5 => object_ops::exec_putfield,
+    0xb6 => invokevirtual::exec_invokevirtual,
+    0xb7 => invokespecial::exec_invokespecial,
+    0xb8 => invokestatic::exec_invokestatic,
+    0xb9 => invokeinterface::exec_invokeinterface,
+    0xba => invokedynamic::exec_invokedynamic,
+    0xbb => object_ops::exec_new,
+    0xbc => array_ops::exec_newarray,
+    0xbd => array_ops::exec_anewarray,
+    0xbe => array_ops::exec_arraylength,
+    0xbf => exception_ops::exec_athrow,
+    0xc0 => object_ops::exec_checkcast,
+    0xc1 => object_ops::exec_instanceof,
+    // 扩展
+    0xc5 => array_ops::exec_multianewarray,
+    0xc6 => control::exec_ifnull,
+    0xc7 => control::exec_ifnonnull,
+    0xc8 => control::exec_goto_w,
+}
+
+/// 查找opcode对应的指令处理函数，未实现的指令返回None
+pub fn lookup(opcode: u8) -> Option<InstructionFn> {
+    DISPATCH_TABLE[opcode as usize]
+}
+
+/// 按分发表执行一条预解码的指令，帧的pc已经指向下一条指令；
+/// 未实现的指令报告所在方法和字节码地址
+pub fn execute(instruction: &Instruction, opcode_pc: usize, frame: &mut Frame, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let opcode = instruction.opcode();
+    match lookup(opcode) {
+        Some(handler) if !matches!(instruction, Instruction::Unimplemented(_)) => handler(frame, instruction, vm),
+        _ => Err(JvmError::IllegalStateError(format!(
+            "Unknown opcode 0x{:02x} in {}.{}{} at pc={}",
+            opcode, frame.method.class_name, frame.method.name, frame.method.descriptor, opcode_pc
+        ))),
+    }
+}
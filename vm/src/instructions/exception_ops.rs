@@ -0,0 +1,16 @@
+use crate::jvm_thread::Frame;
+use crate::error::JvmError;
+use crate::instructions::Instruction;
+use crate::vm::Vm;
+use crate::jvm_log;
+
+/// athrow 指令 - 弹出异常对象并以 JvmError::JavaException 开始展开，null引用抛出NullPointerException
+pub fn exec_athrow(frame: &mut Frame, _instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let vm = vm.ok_or_else(|| JvmError::IllegalStateError("athrow: 需要VM来登记异常对象".to_string()))?;
+    let exception = frame.stack.pop_obj_ref()?;
+    if exception.is_null() {
+        return Err(JvmError::NullPointerError("athrow: 异常对象为null".to_string()));
+    }
+    jvm_log!("[Exception] athrow {:?}", exception);
+    Err(vm.throw(exception))
+}
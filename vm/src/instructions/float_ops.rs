@@ -0,0 +1,161 @@
+use crate::jvm_thread::Frame;
+use crate::error::JvmError;
+use crate::instructions::Instruction;
+use crate::vm::Vm;
+
+// float/double 指令族
+// f32/f64 运算本身就是IEEE-754单/双精度的就近舍入，与Java的strictfp语义一致；
+// Rust浮点的 % 与Java的 frem/drem 相同（截断除法，结果符号随被除数）。
+
+// fconst 指令族
+pub fn exec_fconst_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_float(0.0)?;
+    Ok(())
+}
+
+pub fn exec_fconst_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_float(1.0)?;
+    Ok(())
+}
+
+pub fn exec_fconst_2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_float(2.0)?;
+    Ok(())
+}
+
+// dconst 指令族
+pub fn exec_dconst_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_double(0.0)?;
+    Ok(())
+}
+
+pub fn exec_dconst_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.push_double(1.0)?;
+    Ok(())
+}
+
+// float 运算
+pub fn exec_fadd(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_float()?;
+    let a = frame.stack.pop_float()?;
+    frame.stack.push_float(a + b)?;
+    Ok(())
+}
+
+pub fn exec_fsub(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_float()?;
+    let a = frame.stack.pop_float()?;
+    frame.stack.push_float(a - b)?;
+    Ok(())
+}
+
+pub fn exec_fmul(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_float()?;
+    let a = frame.stack.pop_float()?;
+    frame.stack.push_float(a * b)?;
+    Ok(())
+}
+
+// 浮点除零不抛异常，得到Infinity或NaN
+pub fn exec_fdiv(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_float()?;
+    let a = frame.stack.pop_float()?;
+    frame.stack.push_float(a / b)?;
+    Ok(())
+}
+
+pub fn exec_frem(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_float()?;
+    let a = frame.stack.pop_float()?;
+    frame.stack.push_float(a % b)?;
+    Ok(())
+}
+
+pub fn exec_fneg(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let v = frame.stack.pop_float()?;
+    frame.stack.push_float(-v)?;
+    Ok(())
+}
+
+// double 运算
+pub fn exec_dadd(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_double()?;
+    let a = frame.stack.pop_double()?;
+    frame.stack.push_double(a + b)?;
+    Ok(())
+}
+
+pub fn exec_dsub(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_double()?;
+    let a = frame.stack.pop_double()?;
+    frame.stack.push_double(a - b)?;
+    Ok(())
+}
+
+pub fn exec_dmul(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_double()?;
+    let a = frame.stack.pop_double()?;
+    frame.stack.push_double(a * b)?;
+    Ok(())
+}
+
+pub fn exec_ddiv(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_double()?;
+    let a = frame.stack.pop_double()?;
+    frame.stack.push_double(a / b)?;
+    Ok(())
+}
+
+pub fn exec_drem(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_double()?;
+    let a = frame.stack.pop_double()?;
+    frame.stack.push_double(a % b)?;
+    Ok(())
+}
+
+pub fn exec_dneg(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let v = frame.stack.pop_double()?;
+    frame.stack.push_double(-v)?;
+    Ok(())
+}
+
+/// 浮点比较结果：任一操作数为NaN时返回 nan_result（cmpl为-1，cmpg为1），
+/// 否则按数值比较，+0.0 与 -0.0 相等
+fn compare<T: PartialOrd>(a: T, b: T, nan_result: i32) -> i32 {
+    match a.partial_cmp(&b) {
+        Some(ordering) => ordering as i32,
+        None => nan_result,
+    }
+}
+
+/// fcmpl 指令 - 比较两个float，遇到NaN压入-1
+pub fn exec_fcmpl(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_float()?;
+    let a = frame.stack.pop_float()?;
+    frame.stack.push_int(compare(a, b, -1))?;
+    Ok(())
+}
+
+/// fcmpg 指令 - 比较两个float，遇到NaN压入1
+pub fn exec_fcmpg(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_float()?;
+    let a = frame.stack.pop_float()?;
+    frame.stack.push_int(compare(a, b, 1))?;
+    Ok(())
+}
+
+/// dcmpl 指令 - 比较两个double，遇到NaN压入-1
+pub fn exec_dcmpl(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_double()?;
+    let a = frame.stack.pop_double()?;
+    frame.stack.push_int(compare(a, b, -1))?;
+    Ok(())
+}
+
+/// dcmpg 指令 - 比较两个double，遇到NaN压入1
+pub fn exec_dcmpg(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let b = frame.stack.pop_double()?;
+    let a = frame.stack.pop_double()?;
+    frame.stack.push_int(compare(a, b, 1))?;
+    Ok(())
+}
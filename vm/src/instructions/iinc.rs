@@ -1,11 +1,13 @@
 use crate::error::JvmError;
+use crate::instructions::Instruction;
 use crate::jvm_thread::Frame;
 
-pub fn exec_iinc(frame: &mut Frame, code: &[u8], _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let index = code[frame.pc] as usize;
-    let const_val = code[frame.pc + 1] as i8;
-    frame.pc += 2;
-    let value = frame.local_vars.get_int(index);
-    frame.local_vars.set_int(index, value + const_val as i32);
+pub fn exec_iinc(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let (index, const_val) = match *instruction {
+        Instruction::Iinc(index, const_val) => (index as usize, const_val),
+        _ => return Err(JvmError::IllegalStateError(format!("iinc: 错误的指令 {:?}", instruction))),
+    };
+    let value = frame.local_vars.get_int(index)?;
+    frame.local_vars.set_int(index, value.wrapping_add(const_val as i32))?;
     Ok(())
 } 
\ No newline at end of file
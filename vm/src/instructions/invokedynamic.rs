@@ -0,0 +1,28 @@
+use crate::error::JvmError;
+use crate::instructions::Instruction;
+use crate::jvm_thread::Frame;
+use crate::JvmValue;
+use crate::jvm_log;
+use crate::instructions::method_utils::{parse_method_descriptor, pop_arguments};
+
+/// invokedynamic 指令 - 链接lambda调用点，用捕获的参数创建函数式接口的实例
+pub fn exec_invokedynamic(frame: &mut Frame, instruction: &Instruction, vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let index = instruction.cp_index()?;
+
+    let vm = vm.ok_or_else(|| JvmError::IllegalStateError("invokedynamic: 需要VM来链接调用点".to_string()))?;
+    let class_name = vm.link_call_site(&frame.method, index)?;
+    let (_, _, descriptor) = crate::lambda::call_site_info(&frame.method.constant_pool, index)?;
+    let param_types = parse_method_descriptor(&descriptor);
+
+    // 捕获的参数留在操作数栈上直到对象分配完成，分配时的垃圾收集会更新它们
+    let klass = vm.load(&class_name)?;
+    let obj = vm.alloc_object(&klass)?;
+    let captured = pop_arguments(frame, &param_types)?;
+    jvm_log!("[Lambda] 创建 {} 的实例，捕获{}个参数", class_name, captured.len());
+    let mut args = vec![JvmValue::ObjRef(obj)];
+    args.extend(captured);
+    // 先压入新对象，构造方法的帧随后由解释循环执行
+    frame.stack.push_obj_ref(obj)?;
+    let init = vm.resolve_method(&class_name, "<init>", &format!("({})V", param_types.concat()))?;
+    vm.call_method(frame, &init, args)
+}
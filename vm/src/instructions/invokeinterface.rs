@@ -0,0 +1,42 @@
+use crate::error::JvmError;
+use crate::instructions::Instruction;
+use crate::jvm_thread::Frame;
+use crate::vm::Vm;
+use crate::JvmValue;
+use crate::jvm_log;
+use reader::constant_pool::ConstantPoolExt;
+use crate::instructions::method_utils::{parse_method_descriptor, pop_arguments, push_return_value};
+
+/// invokeinterface 指令 - 解析接口方法引用，按接收者的运行时类在接口方法表中选择方法
+pub fn exec_invokeinterface(frame: &mut Frame, instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    // count操作数是参数槽位数，可以从描述符推出
+    let index = instruction.cp_index()?;
+
+    let (interface_name, method_name, method_desc) = frame.method.constant_pool.get_methodref_info(index);
+    jvm_log!("[Interface] 调用方法: {}.{}{}", interface_name, method_name, method_desc);
+
+    let vm = vm.ok_or_else(|| JvmError::IllegalStateError("invokeinterface: 需要VM来解析接口方法引用".to_string()))?;
+    // 先解析方法再弹出参数，解析期间发生的垃圾收集会更新仍在操作数栈上的this和参数
+    let resolved = frame.method.constant_pool.resolve_method(index, vm);
+
+    let param_types = parse_method_descriptor(&method_desc);
+    let args = pop_arguments(frame, &param_types)?;
+    let this_ref = frame.stack.pop_obj_ref()?;
+    let mut full_args = vec![JvmValue::ObjRef(this_ref)];
+    full_args.extend(args);
+
+    // 与invokevirtual一致：没有对象头的伪造引用只能使用按静态类型注册的native实现
+    if !this_ref.is_null() && Vm::object_class_id(this_ref).is_none() {
+        let return_value = vm.call_native_method(&interface_name, &method_name, full_args)?;
+        return push_return_value(frame, return_value);
+    }
+    let resolved = resolved?;
+    if this_ref.is_null() {
+        return Err(JvmError::NullPointerError(format!("invokeinterface: 调用{}.{}的对象引用为null", interface_name, method_name)));
+    }
+    let method = resolved.select_interface(vm, this_ref, &interface_name)?.ok_or_else(|| {
+        JvmError::IllegalStateError(format!("invokeinterface: 无法确定接收者的类型: {:?}", this_ref))
+    })?;
+    jvm_log!("[Interface] 选中 {}.{}", method.class_name, method.name);
+    vm.call_method(frame, &method, full_args)
+}
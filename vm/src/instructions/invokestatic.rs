@@ -1,14 +1,14 @@
 use crate::error::JvmError;
+use crate::instructions::Instruction;
 use crate::jvm_thread::Frame;
 use crate::JvmValue;
 use crate::jvm_log;
 use crate::heap::RawPtr;
 use reader::constant_pool::{ConstantPool, ConstantPoolExt};
-use crate::instructions::method_utils::{parse_method_descriptor, pop_arguments, push_return_value, handle_special_method_call};
+use crate::instructions::method_utils::{parse_method_descriptor, pop_arguments, push_return_value, handle_special_method_call, is_special_method};
 
-pub fn exec_invokestatic(frame: &mut Frame, code: &[u8], vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let index = ((code[frame.pc] as u16) << 8 | code[frame.pc + 1] as u16) as usize;
-    frame.pc += 2;
+pub fn exec_invokestatic(frame: &mut Frame, instruction: &Instruction, mut vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let index = instruction.cp_index()? as usize;
     
     let cp = &frame.method.constant_pool;
     let (class_name, method_name, method_desc) = cp.get_methodref_info(index as u16);
@@ -18,49 +18,48 @@ pub fn exec_invokestatic(frame: &mut Frame, code: &[u8], vm: Option<&mut crate::
     let param_types = parse_method_descriptor(&method_desc);
     jvm_log!("[Static] 参数类型: {:?}", param_types);
     
+    // 特殊方法和native方法以外的方法先解析再弹出参数：解析成功后初始化声明方法的类（JVMS 5.5），
+    // <clinit>执行期间发生的垃圾收集会更新仍在操作数栈上的参数
+    let resolved = match vm.as_deref_mut() {
+        Some(vm) if !is_special_method(&class_name, &method_name) && !vm.has_native_method(&class_name, &method_name) => {
+            Some(frame.method.constant_pool.resolve_method(index as u16, vm).and_then(|resolved| {
+                if resolved.method.is_static() {
+                    vm.initialize(&resolved.method.class_name)?;
+                }
+                Ok(resolved)
+            }))
+        }
+        _ => None,
+    };
+    
     // 弹出参数（静态方法没有 this 引用）
-    let args = pop_arguments(frame, &param_types);
+    let args = pop_arguments(frame, &param_types)?;
     jvm_log!("[Static] 弹出参数: {:?}", args);
     
     // 处理特殊方法调用
-    if let Some(true) = handle_special_method_call(&class_name, &method_name, &args, frame) {
+    if let Some(true) = handle_special_method_call(&class_name, &method_name, &args, frame)? {
         return Ok(());
     }
     
-    // 尝试调用 native 方法
-    if let Some(vm) = vm {
-        // 先尝试 native 方法调用
-        let native_result = vm.call_native_method(&class_name, &method_name, args.clone());
-        match native_result {
-            Ok(return_value) => {
-                jvm_log!("[Static] Native 方法调用成功: {}.{}", class_name, method_name);
-                push_return_value(frame, return_value);
-                return Ok(());
-            }
-            Err(e) => {
-                jvm_log!("[Static] Native 方法调用失败: {:?}", e);
-                // 继续尝试其他方法
-            }
-        }
-        
-        // 尝试通过 VM 的方法分发
-        let dispatch_result = vm.dispatch_method_call(&class_name, &method_name, &method_desc, args);
-        match dispatch_result {
-            Ok(return_value) => {
-                jvm_log!("[Static] 方法调用成功: {}.{}", class_name, method_name);
-                push_return_value(frame, return_value);
-                return Ok(());
-            }
-            Err(e) => {
-                jvm_log!("[Static] 方法调用失败: {:?}", e);
-                // 简化处理：暂时返回成功
-                jvm_log!("[Static] 方法调用完成: {}", method_name);
-                return Ok(());
-            }
-        }
+    let vm = vm.ok_or_else(|| JvmError::IllegalStateError("invokestatic: 需要VM来解析方法".to_string()))?;
+
+    // native实现抛出的异常和退出请求直接向上传播
+    let Some(resolved) = resolved else {
+        let return_value = vm.call_native_method(&class_name, &method_name, args)?;
+        jvm_log!("[Static] Native 方法调用成功: {}.{}", class_name, method_name);
+        return push_return_value(frame, return_value);
+    };
+
+    // 解析的方法交给VM调用，Java方法的帧由解释循环压入线程栈；
+    // 解析失败时参数已经弹出，错误必须向上传播，不能当作调用成功
+    let resolved = resolved?;
+    if !resolved.method.is_static() {
+        return Err(JvmError::IncompatibleClassChangeError(format!(
+            "Expected static method {}.{}{}",
+            resolved.method.class_name.replace('/', "."),
+            method_name,
+            method_desc
+        )));
     }
-    
-    // 简化处理：暂时返回成功
-    jvm_log!("[Static] 方法调用完成: {}", method_name);
-    Ok(())
-} 
\ No newline at end of file
+    vm.call_method(frame, &resolved.method, args)
+}
@@ -1,78 +1,67 @@
 use crate::error::JvmError;
+use crate::instructions::Instruction;
 use crate::jvm_thread::Frame;
 use crate::JvmValue;
+use crate::vm::Vm;
 use crate::jvm_log;
-use crate::heap::RawPtr;
-use reader::constant_pool::{ConstantPool, ConstantPoolExt};
+use reader::constant_pool::ConstantPoolExt;
 use crate::instructions::method_utils::{parse_method_descriptor, pop_arguments, push_return_value, handle_special_method_call};
 
-pub fn exec_invokevirtual(frame: &mut Frame, code: &[u8], vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let index = ((code[frame.pc] as u16) << 8 | code[frame.pc + 1] as u16) as usize;
-    frame.pc += 2;
+/// invokevirtual 指令 - 按接收者的运行时类选择重写的方法
+pub fn exec_invokevirtual(frame: &mut Frame, instruction: &Instruction, mut vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let index = instruction.cp_index()?;
     
     let cp = &frame.method.constant_pool;
-    let (class_name, method_name, method_desc) = cp.get_methodref_info(index as u16);
+    let (class_name, method_name, method_desc) = cp.get_methodref_info(index);
     jvm_log!("[Virtual] 调用方法: {}.{}{}", class_name, method_name, method_desc);
     
     // 解析参数类型
     let param_types = parse_method_descriptor(&method_desc);
     jvm_log!("[Virtual] 参数类型: {:?}", param_types);
     
+    // 先解析方法再弹出参数：解析可能初始化类并执行Java代码，
+    // 期间发生的垃圾收集会更新仍在操作数栈上的this和参数
+    let resolved = vm.as_deref_mut().map(|vm| frame.method.constant_pool.resolve_method(index, vm));
+    
     // 弹出参数
-    let args = pop_arguments(frame, &param_types);
+    let args = pop_arguments(frame, &param_types)?;
     jvm_log!("[Virtual] 弹出参数: {:?}", args);
     
     // 弹出 this 引用
-    let this_ref = if !frame.stack.is_obj_refs_empty() {
-        frame.stack.pop_obj_ref()
-    } else {
-        jvm_log!("[Virtual] 警告: 栈中没有 this 引用");
-        RawPtr(std::ptr::null_mut())
-    };
+    let this_ref = frame.stack.pop_obj_ref()?;
     
     // 处理特殊方法调用
-    if let Some(true) = handle_special_method_call(&class_name, &method_name, &args, frame) {
+    if let Some(true) = handle_special_method_call(&class_name, &method_name, &args, frame)? {
         return Ok(());
     }
-    
-    // 尝试调用 native 方法
-    if let Some(vm) = vm {
-        // 构建包含this引用的完整参数列表
-        let mut full_args = vec![JvmValue::ObjRef(this_ref)];
-        full_args.extend(args);
-        
-        // 先尝试 native 方法调用
-        let native_result = vm.call_native_method(&class_name, &method_name, full_args.clone());
-        match native_result {
-            Ok(return_value) => {
-                jvm_log!("[Virtual] Native 方法调用成功: {}.{}", class_name, method_name);
-                push_return_value(frame, return_value);
-                return Ok(());
-            }
-            Err(e) => {
-                jvm_log!("[Virtual] Native 方法调用失败: {:?}", e);
-                // 继续尝试其他方法
-            }
-        }
-        
-        // 尝试通过 VM 的方法分发
-        let dispatch_result = vm.dispatch_method_call(&class_name, &method_name, &method_desc, full_args);
-        match dispatch_result {
-            Ok(return_value) => {
-                jvm_log!("[Virtual] 方法调用成功: {}.{}", class_name, method_name);
-                push_return_value(frame, return_value);
-                return Ok(());
-            }
-            Err(e) => {
-                jvm_log!("[Virtual] 方法调用失败: {:?}", e);
-                // 简化处理：暂时返回成功
-                jvm_log!("[Virtual] 方法调用完成: {}", method_name);
-                return Ok(());
-            }
-        }
+
+    let vm = vm.ok_or_else(|| JvmError::IllegalStateError("invokevirtual: 需要VM来解析接收者的类".to_string()))?;
+
+    // 构建包含this引用的完整参数列表
+    let mut full_args = vec![JvmValue::ObjRef(this_ref)];
+    full_args.extend(args);
+
+    // System.out等伪造的引用没有对象头，也没有对应的类，只能使用按静态类型注册的native实现
+    if !this_ref.is_null() && Vm::object_class_id(this_ref).is_none() {
+        let return_value = vm.call_native_method(&class_name, &method_name, full_args)?;
+        return push_return_value(frame, return_value);
     }
-    
-    // 简化处理：暂时返回成功
-    jvm_log!("[Virtual] 方法调用完成: {}", method_name);
-    Ok(())
-} 
\ No newline at end of file
+    // 解析错误和方法不是实例方法的错误先于接收者的null检查抛出
+    let resolved = resolved.ok_or_else(|| JvmError::IllegalStateError("invokevirtual: 方法引用没有解析".to_string()))??;
+    if resolved.method.is_static() {
+        return Err(JvmError::IncompatibleClassChangeError(format!(
+            "Expecting non-static method {}.{}{}",
+            resolved.method.class_name.replace('/', "."),
+            method_name,
+            method_desc
+        )));
+    }
+    if this_ref.is_null() {
+        return Err(JvmError::NullPointerError(format!("invokevirtual: 调用{}.{}的对象引用为null", class_name, method_name)));
+    }
+    let method = resolved.select_virtual(vm, this_ref)?.ok_or_else(|| {
+        JvmError::IllegalStateError(format!("invokevirtual: 无法确定接收者的类型: {:?}", this_ref))
+    })?;
+    jvm_log!("[Virtual] 选中 {}.{}", method.class_name, method.name);
+    vm.call_method(frame, &method, full_args)
+}
@@ -1,22 +1,22 @@
 use crate::error::JvmError;
+use crate::instructions::Instruction;
 use crate::jvm_thread::Frame;
 use crate::JvmValue;
 use crate::heap::RawPtr;
 use crate::jvm_log;
 use reader::constant_pool::ConstantPool;
 
-pub fn exec_sipush(frame: &mut Frame, code: &[u8], _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let high = code[frame.pc] as i16;
-    let low = code[frame.pc + 1] as i16;
-    frame.pc += 2;
-    let value = ((high << 8) | (low & 0xFF)) as i16;
-    frame.stack.push_int(value as i32);
+pub fn exec_sipush(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let value = match *instruction {
+        Instruction::Sipush(value) => value as i16,
+        _ => return Err(JvmError::IllegalStateError(format!("sipush: 错误的指令 {:?}", instruction))),
+    };
+    frame.stack.push_int(value as i32)?;
     Ok(())
 }
 
-pub fn exec_ldc(frame: &mut Frame, code: &[u8], mut vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let index = code[frame.pc] as usize;
-    frame.pc += 1;
+pub fn exec_ldc(frame: &mut Frame, instruction: &Instruction, vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let index = instruction.cp_index()? as usize;
     let cp = &frame.method.constant_pool;
     
     jvm_log!("[LDC] 常量池长度: {}, 访问索引: {}", cp.len(), index);
@@ -26,34 +26,24 @@ pub fn exec_ldc(frame: &mut Frame, code: &[u8], mut vm: Option<&mut crate::vm::V
     match &cp[index - 1] {
         reader::constant_pool::CpInfo::Integer { bytes, .. } => {
             let value = *bytes as i32;
-            frame.stack.push_int(value);
+            frame.stack.push_int(value)?;
         }
         reader::constant_pool::CpInfo::Float { bytes, .. } => {
             let value = f32::from_bits(*bytes);
-            frame.stack.push_int(value.to_bits() as i32);
+            frame.stack.push_int(value.to_bits() as i32)?;
         }
-        reader::constant_pool::CpInfo::String { string_index, .. } => {
-            let s = cp.get_utf8_string(*string_index);
-            jvm_log!("ldc string: {}", s);
-            if let Some(ref mut vm) = vm {
-                match vm.create_string_object(&s) {
-                    Ok(string_ptr) => {
-                        frame.stack.push_obj_ref(string_ptr);
-                    }
-                    Err(e) => {
-                        return Err(JvmError::IllegalStateError(format!("Failed to create string object: {:?}", e)));
-                    }
-                }
-            } else {
-                panic!("ldc指令需要有效的VM引用以创建字符串对象，但vm为None");
-            }
+        reader::constant_pool::CpInfo::String { .. } => {
+            // 字符串常量解析后缓存在运行时常量池中，每次执行得到同一个对象
+            let vm = vm.ok_or_else(|| JvmError::IllegalStateError("ldc: 需要VM来创建字符串对象".to_string()))?;
+            let string_ptr = cp.resolve_string(index as u16, vm)?;
+            frame.stack.push_obj_ref(string_ptr)?;
         }
         reader::constant_pool::CpInfo::Class { name_index, .. } => {
             // 正确解引用Class类型到Utf8
             let class_name = cp.get_utf8_string(*name_index);
             jvm_log!("ldc class: {}", class_name);
             // 这里可以根据需要推入class对象引用，暂时推入null
-            frame.stack.push_obj_ref(RawPtr(std::ptr::null_mut()));
+            frame.stack.push_obj_ref(RawPtr(std::ptr::null_mut()))?;
         }
         _ => {
             return Err(JvmError::IllegalStateError(format!("ldc: 常量池索引{}类型不支持", index)));
@@ -62,36 +52,23 @@ pub fn exec_ldc(frame: &mut Frame, code: &[u8], mut vm: Option<&mut crate::vm::V
     Ok(())
 }
 
-pub fn exec_ldc_w(frame: &mut Frame, code: &[u8], mut vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let index = ((code[frame.pc] as u16) << 8 | code[frame.pc + 1] as u16) as usize;
-    frame.pc += 2;
+pub fn exec_ldc_w(frame: &mut Frame, instruction: &Instruction, vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let index = instruction.cp_index()? as usize;
     let cp = &frame.method.constant_pool;
     match &cp[index - 1] {
         reader::constant_pool::CpInfo::Integer { bytes, .. } => {
             let value = *bytes as i32;
-            frame.stack.push_int(value);
+            frame.stack.push_int(value)?;
         }
         reader::constant_pool::CpInfo::Float { bytes, .. } => {
             let value = f32::from_bits(*bytes);
-            frame.stack.push_int(value.to_bits() as i32);
+            frame.stack.push_int(value.to_bits() as i32)?;
         }
-        reader::constant_pool::CpInfo::String { string_index, .. } => {
-            let s = cp.get_utf8_string(*string_index);
-            jvm_log!("ldc_w string: {}", s);
-            // 创建字符串对象并推入栈
-            if let Some(ref mut vm) = vm {
-                match vm.create_string_object(&s) {
-                    Ok(string_ptr) => {
-                        // 将对象引用推入栈
-                        frame.stack.push_obj_ref(string_ptr);
-                    }
-                    Err(e) => {
-                        return Err(JvmError::IllegalStateError(format!("Failed to create string object: {:?}", e)));
-                    }
-                }
-            } else {
-                panic!("ldc_w指令需要有效的VM引用以创建字符串对象，但vm为None");
-            }
+        reader::constant_pool::CpInfo::String { .. } => {
+            // 字符串常量解析后缓存在运行时常量池中，每次执行得到同一个对象
+            let vm = vm.ok_or_else(|| JvmError::IllegalStateError("ldc_w: 需要VM来创建字符串对象".to_string()))?;
+            let string_ptr = cp.resolve_string(index as u16, vm)?;
+            frame.stack.push_obj_ref(string_ptr)?;
         }
         _ => {
             return Err(JvmError::IllegalStateError(format!("ldc_w: 常量池索引{}类型不支持", index)));
@@ -100,25 +77,24 @@ pub fn exec_ldc_w(frame: &mut Frame, code: &[u8], mut vm: Option<&mut crate::vm:
     Ok(())
 }
 
-pub fn exec_ldc2_w(frame: &mut Frame, code: &[u8], _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let index = ((code[frame.pc] as u16) << 8 | code[frame.pc + 1] as u16) as usize;
-    frame.pc += 2;
+pub fn exec_ldc2_w(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let index = instruction.cp_index()? as usize;
     let cp = &frame.method.constant_pool;
     match &cp[index - 1] {
         reader::constant_pool::CpInfo::Long { high_bytes, low_bytes, .. } => {
             let value = ((*high_bytes as u64) << 32) | (*low_bytes as u64);
             jvm_log!("ldc2_w long: {} (推入两个32位值)", value);
             // 先推高32位，再推低32位
-            frame.stack.push_int(*high_bytes as i32);
-            frame.stack.push_int(*low_bytes as i32);
+            frame.stack.push_int(*high_bytes as i32)?;
+            frame.stack.push_int(*low_bytes as i32)?;
         }
         reader::constant_pool::CpInfo::Double { high_bytes, low_bytes, .. } => {
             let bits = ((*high_bytes as u64) << 32) | (*low_bytes as u64);
             let value = f64::from_bits(bits);
             jvm_log!("ldc2_w double: {} (推入两个32位值)", value);
             // 先推高32位，再推低32位
-            frame.stack.push_int(*high_bytes as i32);
-            frame.stack.push_int(*low_bytes as i32);
+            frame.stack.push_int(*high_bytes as i32)?;
+            frame.stack.push_int(*low_bytes as i32)?;
         }
         _ => {
             return Err(JvmError::IllegalStateError(format!("ldc2_w: 常量池索引{}不是long/double", index)));
@@ -1,135 +1,324 @@
 use crate::jvm_thread::Frame;
 use crate::error::JvmError;
+use crate::instructions::Instruction;
 use crate::vm::Vm;
 
 // iload 指令族
-pub fn exec_iload(frame: &mut Frame, code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let index = code[frame.pc] as usize;
-    frame.pc += 1;
-    let value = frame.local_vars.get_int(index);
-    frame.stack.push_int(value);
+pub fn exec_iload(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let index = instruction.local_index()?;
+    let value = frame.local_vars.get_int(index)?;
+    frame.stack.push_int(value)?;
     Ok(())
 }
 
-pub fn exec_iload_0(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.local_vars.get_int(0);
-    frame.stack.push_int(value);
+pub fn exec_iload_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_int(0)?;
+    frame.stack.push_int(value)?;
     Ok(())
 }
 
-pub fn exec_iload_1(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.local_vars.get_int(1);
-    frame.stack.push_int(value);
+pub fn exec_iload_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_int(1)?;
+    frame.stack.push_int(value)?;
     Ok(())
 }
 
-pub fn exec_iload_2(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.local_vars.get_int(2);
-    frame.stack.push_int(value);
+pub fn exec_iload_2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_int(2)?;
+    frame.stack.push_int(value)?;
     Ok(())
 }
 
-pub fn exec_iload_3(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.local_vars.get_int(3);
-    frame.stack.push_int(value);
+pub fn exec_iload_3(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_int(3)?;
+    frame.stack.push_int(value)?;
     Ok(())
 }
 
 // istore 指令族
-pub fn exec_istore(frame: &mut Frame, code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let index = code[frame.pc] as usize;
-    frame.pc += 1;
-    let value = frame.stack.pop_int();
-    frame.local_vars.set_int(index, value);
+pub fn exec_istore(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let index = instruction.local_index()?;
+    let value = frame.stack.pop_int()?;
+    frame.local_vars.set_int(index, value)?;
     Ok(())
 }
 
-pub fn exec_istore_0(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.stack.pop_int();
-    frame.local_vars.set_int(0, value);
+pub fn exec_istore_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    frame.local_vars.set_int(0, value)?;
     Ok(())
 }
 
-pub fn exec_istore_1(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.stack.pop_int();
-    frame.local_vars.set_int(1, value);
+pub fn exec_istore_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    frame.local_vars.set_int(1, value)?;
     Ok(())
 }
 
-pub fn exec_istore_2(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.stack.pop_int();
-    frame.local_vars.set_int(2, value);
+pub fn exec_istore_2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    frame.local_vars.set_int(2, value)?;
     Ok(())
 }
 
-pub fn exec_istore_3(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.stack.pop_int();
-    frame.local_vars.set_int(3, value);
+pub fn exec_istore_3(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_int()?;
+    frame.local_vars.set_int(3, value)?;
     Ok(())
 }
 
 // aload 指令族
-pub fn exec_aload(frame: &mut Frame, code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let index = code[frame.pc] as usize;
-    frame.pc += 1;
-    let value = frame.local_vars.get_obj_ref(index);
-    frame.stack.push_obj_ref(value);
+pub fn exec_aload(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let index = instruction.local_index()?;
+    let value = frame.local_vars.get_obj_ref(index)?;
+    frame.stack.push_obj_ref(value)?;
     Ok(())
 }
 
-pub fn exec_aload_0(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.local_vars.get_obj_ref(0);
-    frame.stack.push_obj_ref(value);
+pub fn exec_aload_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_obj_ref(0)?;
+    frame.stack.push_obj_ref(value)?;
     Ok(())
 }
 
-pub fn exec_aload_1(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.local_vars.get_obj_ref(1);
-    frame.stack.push_obj_ref(value);
+pub fn exec_aload_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_obj_ref(1)?;
+    frame.stack.push_obj_ref(value)?;
     Ok(())
 }
 
-pub fn exec_aload_2(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.local_vars.get_obj_ref(2);
-    frame.stack.push_obj_ref(value);
+pub fn exec_aload_2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_obj_ref(2)?;
+    frame.stack.push_obj_ref(value)?;
     Ok(())
 }
 
-pub fn exec_aload_3(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.local_vars.get_obj_ref(3);
-    frame.stack.push_obj_ref(value);
+pub fn exec_aload_3(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_obj_ref(3)?;
+    frame.stack.push_obj_ref(value)?;
     Ok(())
 }
 
 // astore 指令族
-pub fn exec_astore(frame: &mut Frame, code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let index = code[frame.pc] as usize;
-    frame.pc += 1;
-    let value = frame.stack.pop_obj_ref();
-    frame.local_vars.set_obj_ref(index, value);
+pub fn exec_astore(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let index = instruction.local_index()?;
+    let value = frame.stack.pop_obj_ref()?;
+    frame.local_vars.set_obj_ref(index, value)?;
     Ok(())
 }
 
-pub fn exec_astore_0(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.stack.pop_obj_ref();
-    frame.local_vars.set_obj_ref(0, value);
+pub fn exec_astore_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_obj_ref()?;
+    frame.local_vars.set_obj_ref(0, value)?;
     Ok(())
 }
 
-pub fn exec_astore_1(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.stack.pop_obj_ref();
-    frame.local_vars.set_obj_ref(1, value);
+pub fn exec_astore_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_obj_ref()?;
+    frame.local_vars.set_obj_ref(1, value)?;
     Ok(())
 }
 
-pub fn exec_astore_2(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.stack.pop_obj_ref();
-    frame.local_vars.set_obj_ref(2, value);
+pub fn exec_astore_2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_obj_ref()?;
+    frame.local_vars.set_obj_ref(2, value)?;
     Ok(())
 }
 
-pub fn exec_astore_3(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    let value = frame.stack.pop_obj_ref();
-    frame.local_vars.set_obj_ref(3, value);
+pub fn exec_astore_3(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_obj_ref()?;
+    frame.local_vars.set_obj_ref(3, value)?;
     Ok(())
-} 
\ No newline at end of file
+}
+
+// lload 指令族
+pub fn exec_lload(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let index = instruction.local_index()?;
+    let value = frame.local_vars.get_long(index)?;
+    frame.stack.push_long(value)?;
+    Ok(())
+}
+
+pub fn exec_lload_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_long(0)?;
+    frame.stack.push_long(value)?;
+    Ok(())
+}
+
+pub fn exec_lload_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_long(1)?;
+    frame.stack.push_long(value)?;
+    Ok(())
+}
+
+pub fn exec_lload_2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_long(2)?;
+    frame.stack.push_long(value)?;
+    Ok(())
+}
+
+pub fn exec_lload_3(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_long(3)?;
+    frame.stack.push_long(value)?;
+    Ok(())
+}
+
+// lstore 指令族
+pub fn exec_lstore(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let index = instruction.local_index()?;
+    let value = frame.stack.pop_long()?;
+    frame.local_vars.set_long(index, value)?;
+    Ok(())
+}
+
+pub fn exec_lstore_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_long()?;
+    frame.local_vars.set_long(0, value)?;
+    Ok(())
+}
+
+pub fn exec_lstore_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_long()?;
+    frame.local_vars.set_long(1, value)?;
+    Ok(())
+}
+
+pub fn exec_lstore_2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_long()?;
+    frame.local_vars.set_long(2, value)?;
+    Ok(())
+}
+
+pub fn exec_lstore_3(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_long()?;
+    frame.local_vars.set_long(3, value)?;
+    Ok(())
+}
+
+// fload 指令族
+pub fn exec_fload(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let index = instruction.local_index()?;
+    let value = frame.local_vars.get_float(index)?;
+    frame.stack.push_float(value)?;
+    Ok(())
+}
+
+pub fn exec_fload_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_float(0)?;
+    frame.stack.push_float(value)?;
+    Ok(())
+}
+
+pub fn exec_fload_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_float(1)?;
+    frame.stack.push_float(value)?;
+    Ok(())
+}
+
+pub fn exec_fload_2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_float(2)?;
+    frame.stack.push_float(value)?;
+    Ok(())
+}
+
+pub fn exec_fload_3(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_float(3)?;
+    frame.stack.push_float(value)?;
+    Ok(())
+}
+
+// fstore 指令族
+pub fn exec_fstore(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let index = instruction.local_index()?;
+    let value = frame.stack.pop_float()?;
+    frame.local_vars.set_float(index, value)?;
+    Ok(())
+}
+
+pub fn exec_fstore_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_float()?;
+    frame.local_vars.set_float(0, value)?;
+    Ok(())
+}
+
+pub fn exec_fstore_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_float()?;
+    frame.local_vars.set_float(1, value)?;
+    Ok(())
+}
+
+pub fn exec_fstore_2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_float()?;
+    frame.local_vars.set_float(2, value)?;
+    Ok(())
+}
+
+pub fn exec_fstore_3(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_float()?;
+    frame.local_vars.set_float(3, value)?;
+    Ok(())
+}
+
+// dload 指令族
+pub fn exec_dload(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let index = instruction.local_index()?;
+    let value = frame.local_vars.get_double(index)?;
+    frame.stack.push_double(value)?;
+    Ok(())
+}
+
+pub fn exec_dload_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_double(0)?;
+    frame.stack.push_double(value)?;
+    Ok(())
+}
+
+pub fn exec_dload_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_double(1)?;
+    frame.stack.push_double(value)?;
+    Ok(())
+}
+
+pub fn exec_dload_2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_double(2)?;
+    frame.stack.push_double(value)?;
+    Ok(())
+}
+
+pub fn exec_dload_3(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.local_vars.get_double(3)?;
+    frame.stack.push_double(value)?;
+    Ok(())
+}
+
+// dstore 指令族
+pub fn exec_dstore(frame: &mut Frame, instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let index = instruction.local_index()?;
+    let value = frame.stack.pop_double()?;
+    frame.local_vars.set_double(index, value)?;
+    Ok(())
+}
+
+pub fn exec_dstore_0(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_double()?;
+    frame.local_vars.set_double(0, value)?;
+    Ok(())
+}
+
+pub fn exec_dstore_1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_double()?;
+    frame.local_vars.set_double(1, value)?;
+    Ok(())
+}
+
+pub fn exec_dstore_2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_double()?;
+    frame.local_vars.set_double(2, value)?;
+    Ok(())
+}
+
+pub fn exec_dstore_3(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let value = frame.stack.pop_double()?;
+    frame.local_vars.set_double(3, value)?;
+    Ok(())
+}
@@ -3,12 +3,12 @@
 macro_rules! instruction_fn {
     ($name:ident, $body:block) => {
         pub fn $name(
-            thread: &mut crate::jvm_thread::JvmThread,
-            method: &crate::method::Method,
+            thread: &mut $crate::jvm_thread::JvmThread,
+            method: &$crate::method::Method,
             code: &[u8],
             pc: &mut usize,
-            vm: Option<&mut crate::vm::Vm>,
-        ) -> Result<(), crate::error::JvmError> $body
+            vm: Option<&mut $crate::vm::Vm>,
+        ) -> Result<(), $crate::error::JvmError> $body
     };
 }
 
@@ -17,12 +17,12 @@ macro_rules! instruction_fn {
 macro_rules! instruction_fn_ {
     ($name:ident, $body:block) => {
         pub fn $name(
-            thread: &mut crate::jvm_thread::JvmThread,
-            _method: &crate::method::Method,
+            thread: &mut $crate::jvm_thread::JvmThread,
+            _method: &$crate::method::Method,
             _code: &[u8],
             _pc: &mut usize,
-            _vm: Option<&mut crate::vm::Vm>,
-        ) -> Result<(), crate::error::JvmError> $body
+            _vm: Option<&mut $crate::vm::Vm>,
+        ) -> Result<(), $crate::error::JvmError> $body
     };
 }
 
@@ -31,11 +31,11 @@ macro_rules! instruction_fn_ {
 macro_rules! instruction_fn_cp {
     ($name:ident, $body:block) => {
         pub fn $name(
-            thread: &mut crate::jvm_thread::JvmThread,
-            _method: &crate::method::Method,
+            thread: &mut $crate::jvm_thread::JvmThread,
+            _method: &$crate::method::Method,
             code: &[u8],
             pc: &mut usize,
-            _vm: Option<&mut crate::vm::Vm>,
-        ) -> Result<(), crate::error::JvmError> $body
+            _vm: Option<&mut $crate::vm::Vm>,
+        ) -> Result<(), $crate::error::JvmError> $body
     };
 } 
\ No newline at end of file
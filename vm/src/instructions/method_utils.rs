@@ -2,6 +2,8 @@ use crate::jvm_thread::Frame;
 use crate::JvmValue;
 use crate::jvm_log;
 use crate::heap::RawPtr;
+use crate::error::JvmError;
+use crate::local_vars::LocalVars;
 
 /// 解析方法描述符，提取参数类型
 pub fn parse_method_descriptor(descriptor: &str) -> Vec<String> {
@@ -22,7 +24,7 @@ pub fn parse_method_descriptor(descriptor: &str) -> Vec<String> {
         }
         // 处理对象类型
         if let Some('L') = chars.peek() {
-            while let Some(ch) = chars.next() {
+            for ch in chars.by_ref() {
                 buf.push(ch);
                 if ch == ';' { break; }
             }
@@ -42,91 +44,119 @@ pub fn parse_method_descriptor(descriptor: &str) -> Vec<String> {
     param_types
 }
 
-/// 从栈中弹出参数
-pub fn pop_arguments(frame: &mut Frame, param_types: &[String]) -> Vec<JvmValue> {
+/// 从栈中弹出参数，按描述符从最后一个参数开始逐个弹出；
+/// 操作数栈保持JVMS顺序，槽位数或类型与描述符不符时返回错误
+pub fn pop_arguments(frame: &mut Frame, param_types: &[String]) -> Result<Vec<JvmValue>, JvmError> {
     let mut args: Vec<JvmValue> = Vec::with_capacity(param_types.len());
     
-    // 从后往前弹出参数（栈的顺序）
     for param_type in param_types.iter().rev() {
         match param_type.as_str() {
-            "I" | "S" | "B" | "Z" => {
-                if !frame.stack.is_values_empty() {
-                    args.push(JvmValue::Int(frame.stack.pop_int() as u32));
-                } else {
-                    args.push(JvmValue::Int(0));
-                }
-            }
-            "J" => {
-                if !frame.stack.is_values_empty() {
-                    let low = frame.stack.pop_int() as u32 as u64;
-                    let high = if !frame.stack.is_values_empty() {
-                        frame.stack.pop_int() as u32 as u64
-                    } else {
-                        0
-                    };
-                    args.push(JvmValue::Long((high << 32) | (low & 0xFFFF_FFFF)));
-                } else {
-                    args.push(JvmValue::Long(0));
-                }
-            }
-            "F" => {
-                if !frame.stack.is_values_empty() {
-                    args.push(JvmValue::Float(frame.stack.pop_int() as u64));
-                } else {
-                    args.push(JvmValue::Float(0));
-                }
-            }
-            "D" => {
-                if !frame.stack.is_values_empty() {
-                    let low = frame.stack.pop_int() as u32 as u64;
-                    let high = if !frame.stack.is_values_empty() {
-                        frame.stack.pop_int() as u32 as u64
-                    } else {
-                        0
-                    };
-                    args.push(JvmValue::Double((high << 32) | (low & 0xFFFF_FFFF)));
-                } else {
-                    args.push(JvmValue::Double(0));
-                }
-            }
-            desc if desc.starts_with("L") || desc.starts_with("[") => {
-                if !frame.stack.is_obj_refs_empty() {
-                    args.push(JvmValue::ObjRef(frame.stack.pop_obj_ref()));
-                } else {
-                    args.push(JvmValue::Null);
-                }
+            "I" | "S" | "B" | "Z" | "C" => args.push(JvmValue::Int(frame.stack.pop_int()? as u32)),
+            "J" => args.push(JvmValue::Long(frame.stack.pop_long()? as u64)),
+            "F" => args.push(JvmValue::Float(frame.stack.pop_float()?.to_bits() as u64)),
+            "D" => args.push(JvmValue::Double(frame.stack.pop_double()?.to_bits())),
+            desc if desc.starts_with('L') || desc.starts_with('[') => {
+                args.push(JvmValue::ObjRef(frame.stack.pop_obj_ref()?));
             }
             _ => {
                 jvm_log!("[Method] 未知参数类型: {}", param_type);
-                args.push(JvmValue::Null);
+                return Err(JvmError::IllegalStateError(format!("Unknown parameter type: {}", param_type)));
             }
         }
     }
     
     // 恢复正确的参数顺序
     args.reverse();
-    args
+    Ok(args)
+}
+
+/// 参数类型占用的局部变量槽位数：long和double是第二类（category 2）类型，占两个槽位
+pub fn slot_width(param_type: &str) -> usize {
+    match param_type {
+        "J" | "D" => 2,
+        _ => 1,
+    }
+}
+
+/// 调用约定：按描述符把参数存入被调用方法的局部变量表。
+/// 实例方法的this在槽位0，参数从下一个槽位起依次排列，long/double占两个槽位，
+/// 值按原始位模式存入；参数个数或类型与描述符不符时返回错误
+pub fn store_arguments(locals: &mut LocalVars, descriptor: &str, is_static: bool, args: &[JvmValue]) -> Result<(), JvmError> {
+    let param_types = parse_method_descriptor(descriptor);
+    let receiver = if is_static { 0 } else { 1 };
+    if args.len() != receiver + param_types.len() {
+        return Err(JvmError::IllegalStateError(format!(
+            "{}需要{}个参数，实际传入{}个",
+            descriptor,
+            receiver + param_types.len(),
+            args.len()
+        )));
+    }
+    let slots = receiver + param_types.iter().map(|param_type| slot_width(param_type)).sum::<usize>();
+    if slots > locals.max_locals() {
+        return Err(JvmError::IllegalStateError(format!(
+            "{}的参数需要{}个槽位，局部变量表只有{}个",
+            descriptor,
+            slots,
+            locals.max_locals()
+        )));
+    }
+
+    let mut slot = 0;
+    if !is_static {
+        store_argument(locals, slot, "Ljava/lang/Object;", &args[0])?;
+        slot += 1;
+    }
+    for (param_type, arg) in param_types.iter().zip(&args[receiver..]) {
+        store_argument(locals, slot, param_type, arg)?;
+        slot += slot_width(param_type);
+    }
+    Ok(())
+}
+
+/// 按参数类型把一个参数存入slot开始的槽位，boolean/byte/char/short按int存放
+fn store_argument(locals: &mut LocalVars, slot: usize, param_type: &str, arg: &JvmValue) -> Result<(), JvmError> {
+    match (param_type, *arg) {
+        ("I" | "Z" | "B" | "C" | "S", JvmValue::Int(v)) => locals.set_int(slot, v as i32),
+        ("I" | "Z" | "B" | "C" | "S", JvmValue::Boolean(v)) => locals.set_int(slot, v as i32),
+        ("I" | "Z" | "B" | "C" | "S", JvmValue::Byte(v)) => locals.set_int(slot, v as i8 as i32),
+        ("I" | "Z" | "B" | "C" | "S", JvmValue::Short(v)) => locals.set_int(slot, v as i16 as i32),
+        ("I" | "Z" | "B" | "C" | "S", JvmValue::Char(v)) => locals.set_int(slot, v as i32),
+        ("J", JvmValue::Long(v)) => locals.set_long(slot, v as i64),
+        // float和double直接存放位模式，不经过浮点数转换
+        ("F", JvmValue::Float(v)) => locals.set_int(slot, v as u32 as i32),
+        ("D", JvmValue::Double(v)) => locals.set_long(slot, v as i64),
+        (desc, JvmValue::ObjRef(ptr)) if desc.starts_with('L') || desc.starts_with('[') => locals.set_obj_ref(slot, ptr),
+        (desc, JvmValue::Null) if desc.starts_with('L') || desc.starts_with('[') => {
+            locals.set_obj_ref(slot, RawPtr(std::ptr::null_mut()))
+        }
+        _ => Err(JvmError::IllegalStateError(format!("参数{:?}与类型{}不符", arg, param_type))),
+    }
 }
 
 /// 将返回值推入栈中
-pub fn push_return_value(frame: &mut Frame, return_value: Option<JvmValue>) {
+pub fn push_return_value(frame: &mut Frame, return_value: Option<JvmValue>) -> Result<(), JvmError> {
     if let Some(value) = return_value {
         match value {
-            JvmValue::Int(v) => frame.stack.push_int(v as i32),
-            JvmValue::Long(v) => {
-                frame.stack.push_int((v >> 32) as i32);
-                frame.stack.push_int((v & 0xFFFF_FFFF) as i32);
-            }
-            JvmValue::Float(v) => frame.stack.push_int(v as i32),
-            JvmValue::Double(v) => {
-                frame.stack.push_int((v >> 32) as i32);
-                frame.stack.push_int((v & 0xFFFF_FFFF) as i32);
-            }
-            JvmValue::ObjRef(ptr) => frame.stack.push_obj_ref(ptr),
-            JvmValue::Null => frame.stack.push_obj_ref(RawPtr(std::ptr::null_mut())),
-            _ => frame.stack.push_int(0),
+            JvmValue::Int(v) => frame.stack.push_int(v as i32)?,
+            JvmValue::Long(v) => frame.stack.push_long(v as i64)?,
+            JvmValue::Float(v) => frame.stack.push_float(f32::from_bits(v as u32))?,
+            JvmValue::Double(v) => frame.stack.push_double(f64::from_bits(v))?,
+            JvmValue::ObjRef(ptr) => frame.stack.push_obj_ref(ptr)?,
+            JvmValue::Null => frame.stack.push_obj_ref(RawPtr(std::ptr::null_mut()))?,
+            // 比int窄的返回值在操作数栈上扩展为int
+            JvmValue::Boolean(v) => frame.stack.push_int(v as i32)?,
+            JvmValue::Byte(v) => frame.stack.push_int(v as i8 as i32)?,
+            JvmValue::Short(v) => frame.stack.push_int(v as i16 as i32)?,
+            JvmValue::Char(v) => frame.stack.push_int(v as i32)?,
         }
     }
+    Ok(())
+}
+
+/// 是否为由handle_special_method_call直接处理的特殊方法
+pub fn is_special_method(class_name: &str, method_name: &str) -> bool {
+    matches!((class_name, method_name), ("java/lang/System", "currentTimeMillis"))
 }
 
 /// 处理特殊方法调用
@@ -135,7 +165,7 @@ pub fn handle_special_method_call(
     method_name: &str, 
     args: &[JvmValue],
     frame: &mut Frame
-) -> Option<bool> {
+) -> Result<Option<bool>, JvmError> {
     match (class_name, method_name) {
         ("java/lang/System", "currentTimeMillis") => {
             // 处理 System.currentTimeMillis() 调用
@@ -143,12 +173,11 @@ pub fn handle_special_method_call(
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_millis() as u64;
-            frame.stack.push_int((current_time >> 32) as i32);
-            frame.stack.push_int((current_time & 0xFFFF_FFFF) as i32);
+            frame.stack.push_long(current_time as i64)?;
             jvm_log!("[Method] System.currentTimeMillis() 返回: {}", current_time);
-            Some(true)
+            Ok(Some(true))
         }
-        _ => None,
+        _ => Ok(None),
     }
 }
 
@@ -188,13 +217,13 @@ mod tests {
         };
         
         // 压入一些测试数据
-        frame.stack.push_int(42);
-        frame.stack.push_int(100);
-        frame.stack.push_obj_ref(RawPtr(std::ptr::null_mut()));
+        frame.stack.push_int(42).unwrap();
+        frame.stack.push_int(100).unwrap();
+        frame.stack.push_obj_ref(RawPtr(std::ptr::null_mut())).unwrap();
         
         // 测试弹出参数
         let param_types = vec!["I".to_string(), "I".to_string(), "Ljava/lang/Object;".to_string()];
-        let args = pop_arguments(&mut frame, &param_types);
+        let args = pop_arguments(&mut frame, &param_types).unwrap();
         
         assert_eq!(args.len(), 3);
         assert_eq!(args[0], JvmValue::Int(42));
@@ -202,6 +231,162 @@ mod tests {
         assert_eq!(args[2], JvmValue::ObjRef(RawPtr(std::ptr::null_mut())));
     }
 
+    #[test]
+    fn test_pop_arguments_mixed_categories() {
+        let method = Method::new("test".to_string(), "()V".to_string(), 0, vec![], 10, 10);
+        let mut frame = Frame {
+            pc: 0,
+            stack: OperandStack::new(10),
+            local_vars: LocalVars::new(10),
+            method,
+        };
+        let ptr = RawPtr(8 as *mut u8);
+
+        // (JLjava/lang/Object;ID)
+        frame.stack.push_long(-3).unwrap();
+        frame.stack.push_obj_ref(ptr).unwrap();
+        frame.stack.push_int(7).unwrap();
+        frame.stack.push_double(1.5).unwrap();
+
+        let param_types = parse_method_descriptor("(JLjava/lang/Object;ID)V");
+        let args = pop_arguments(&mut frame, &param_types).unwrap();
+        assert_eq!(args, vec![
+            JvmValue::Long(-3i64 as u64),
+            JvmValue::ObjRef(ptr),
+            JvmValue::Int(7),
+            JvmValue::Double(1.5f64.to_bits()),
+        ]);
+        assert!(frame.stack.is_empty());
+
+        // 类型与描述符不符时返回错误
+        frame.stack.push_int(1).unwrap();
+        assert!(pop_arguments(&mut frame, &["Ljava/lang/Object;".to_string()]).is_err());
+    }
+
+    /// 按描述符从局部变量表读出参数，是store_arguments的逆过程
+    fn load_arguments(locals: &LocalVars, descriptor: &str, is_static: bool) -> Vec<JvmValue> {
+        let mut args = Vec::new();
+        let mut slot = 0;
+        if !is_static {
+            args.push(JvmValue::ObjRef(locals.get_obj_ref(0).unwrap()));
+            slot = 1;
+        }
+        for param_type in parse_method_descriptor(descriptor) {
+            args.push(match param_type.as_str() {
+                "I" | "Z" | "B" | "C" | "S" => JvmValue::Int(locals.get_int(slot).unwrap() as u32),
+                "J" => JvmValue::Long(locals.get_long(slot).unwrap() as u64),
+                "F" => JvmValue::Float(locals.get_int(slot).unwrap() as u32 as u64),
+                "D" => JvmValue::Double(locals.get_long(slot).unwrap() as u64),
+                _ => JvmValue::ObjRef(locals.get_obj_ref(slot).unwrap()),
+            });
+            slot += slot_width(&param_type);
+        }
+        args
+    }
+
+    /// 每种参数类型的测试值，浮点数使用NaN载荷和负零检查位模式
+    fn sample_argument(param_type: &str, n: usize) -> JvmValue {
+        match param_type {
+            "I" => JvmValue::Int((-123456 - n as i32) as u32),
+            "Z" => JvmValue::Int(1),
+            "B" => JvmValue::Int(-5i32 as u32),
+            "C" => JvmValue::Int(0xffff),
+            "S" => JvmValue::Int(-2i32 as u32),
+            "J" => JvmValue::Long(0x8000_0000_0000_0007 + n as u64),
+            "F" => JvmValue::Float(if n.is_multiple_of(2) { 0x7fc0_1234 } else { (-0.0f32).to_bits() as u64 }),
+            "D" => JvmValue::Double(if n.is_multiple_of(2) { 0xfff8_0000_dead_beef } else { (-0.0f64).to_bits() }),
+            _ => JvmValue::ObjRef(RawPtr((8 * (n + 1)) as *mut u8)),
+        }
+    }
+
+    #[test]
+    fn test_calling_convention_round_trip() {
+        let descriptors = [
+            ("()V", 0),
+            ("(I)V", 1),
+            ("(J)V", 2),
+            ("(F)V", 1),
+            ("(D)V", 2),
+            ("(Ljava/lang/Object;)V", 1),
+            ("([I)V", 1),
+            ("([[Ljava/lang/String;J)V", 3),
+            ("(ZBCSIJFD)V", 10),
+            ("(JDLjava/lang/Object;[IF)V", 7),
+            ("(DJ[JFLjava/lang/String;D)J", 9),
+        ];
+        let this = JvmValue::ObjRef(RawPtr(0x1000 as *mut u8));
+        for (descriptor, slots) in descriptors {
+            for is_static in [true, false] {
+                let method = Method::new("test".to_string(), "()V".to_string(), 0, vec![], 20, 20);
+                let mut frame = Frame {
+                    pc: 0,
+                    stack: OperandStack::new(20),
+                    local_vars: LocalVars::new(20),
+                    method,
+                };
+                let param_types = parse_method_descriptor(descriptor);
+                let expected: Vec<JvmValue> = param_types.iter().enumerate().map(|(n, t)| sample_argument(t, n)).collect();
+                // 调用者按参数顺序压栈，调用指令按描述符弹出
+                for value in &expected {
+                    push_return_value(&mut frame, Some(*value)).unwrap();
+                }
+                assert_eq!(frame.stack.len(), slots);
+                let mut args = pop_arguments(&mut frame, &param_types).unwrap();
+                assert!(frame.stack.is_empty());
+                assert_eq!(args, expected);
+
+                if !is_static {
+                    args.insert(0, this);
+                }
+                let mut locals = LocalVars::new(slots + 1);
+                store_arguments(&mut locals, descriptor, is_static, &args).unwrap();
+                assert_eq!(load_arguments(&locals, descriptor, is_static), args, "{} static={}", descriptor, is_static);
+            }
+        }
+    }
+
+    #[test]
+    fn test_store_arguments_slot_layout() {
+        let mut locals = LocalVars::new(9);
+        let ptr = RawPtr(16 as *mut u8);
+        let args = [
+            JvmValue::ObjRef(ptr),
+            JvmValue::Long(0x1122_3344_5566_7788),
+            JvmValue::Float(0x7f80_0001),
+            JvmValue::Char(0xfffe),
+            JvmValue::Byte(0x80),
+            JvmValue::Null,
+            JvmValue::Double(1.5f64.to_bits()),
+        ];
+        store_arguments(&mut locals, "(JFCBLjava/lang/Object;D)V", false, &args).unwrap();
+        assert_eq!(locals.get_obj_ref(0).unwrap(), ptr);
+        // long低32位在前
+        assert_eq!(locals.get_int(1).unwrap(), 0x5566_7788);
+        assert_eq!(locals.get_int(2).unwrap(), 0x1122_3344);
+        // signaling NaN的位模式保持不变
+        assert_eq!(locals.get_int(3).unwrap() as u32, 0x7f80_0001);
+        assert_eq!(locals.get_int(4).unwrap(), 0xfffe);
+        // byte按符号扩展为int
+        assert_eq!(locals.get_int(5).unwrap(), -128);
+        assert!(locals.get_obj_ref(6).unwrap().is_null());
+        assert_eq!(locals.get_double(7).unwrap(), 1.5);
+    }
+
+    #[test]
+    fn test_store_arguments_rejects_mismatches() {
+        let mut locals = LocalVars::new(4);
+        // 参数个数不符
+        assert!(store_arguments(&mut locals, "(I)V", true, &[]).is_err());
+        // 实例方法缺少this
+        assert!(store_arguments(&mut locals, "(I)V", false, &[JvmValue::Int(1)]).is_err());
+        // 类型不符
+        assert!(store_arguments(&mut locals, "(J)V", true, &[JvmValue::Int(1)]).is_err());
+        assert!(store_arguments(&mut locals, "(F)V", true, &[JvmValue::Double(0)]).is_err());
+        assert!(store_arguments(&mut locals, "(Ljava/lang/Object;)V", true, &[JvmValue::Int(0)]).is_err());
+        // 局部变量表放不下
+        assert!(store_arguments(&mut locals, "(JJJ)V", true, &[JvmValue::Long(1), JvmValue::Long(2), JvmValue::Long(3)]).is_err());
+    }
+
     #[test]
     fn test_push_return_value() {
         let method = Method::new("test".to_string(), "()V".to_string(), 0, vec![], 10, 10);
@@ -213,18 +398,24 @@ mod tests {
         };
         
         // 测试推送整数值
-        push_return_value(&mut frame, Some(JvmValue::Int(42)));
-        assert_eq!(frame.stack.pop_int(), 42);
+        push_return_value(&mut frame, Some(JvmValue::Int(42))).unwrap();
+        assert_eq!(frame.stack.pop_int().unwrap(), 42);
         
         // 测试推送长整数值
-        push_return_value(&mut frame, Some(JvmValue::Long(0x123456789ABCDEF0)));
-        assert_eq!(frame.stack.pop_int(), 0x9ABCDEF0u32 as i32);
-        assert_eq!(frame.stack.pop_int(), 0x12345678u32 as i32);
+        push_return_value(&mut frame, Some(JvmValue::Long(0x123456789ABCDEF0))).unwrap();
+        assert_eq!(frame.stack.pop_int().unwrap(), 0x9ABCDEF0u32 as i32);
+        assert_eq!(frame.stack.pop_int().unwrap(), 0x12345678u32 as i32);
+
+        // 测试推送浮点值，必须保留位模式而不是截断数值
+        push_return_value(&mut frame, Some(JvmValue::Float((-2.5f32).to_bits() as u64))).unwrap();
+        assert_eq!(frame.stack.pop_float().unwrap(), -2.5);
+        push_return_value(&mut frame, Some(JvmValue::Double(0.1f64.to_bits()))).unwrap();
+        assert_eq!(frame.stack.pop_double().unwrap(), 0.1);
         
         // 测试推送对象引用
         let test_ptr = RawPtr(std::ptr::null_mut());
-        push_return_value(&mut frame, Some(JvmValue::ObjRef(test_ptr)));
-        assert_eq!(frame.stack.pop_obj_ref(), test_ptr);
+        push_return_value(&mut frame, Some(JvmValue::ObjRef(test_ptr))).unwrap();
+        assert_eq!(frame.stack.pop_obj_ref().unwrap(), test_ptr);
     }
 
     #[test]
@@ -239,14 +430,14 @@ mod tests {
         
         // 测试 currentTimeMillis 调用
         let args = vec![];
-        let result = handle_special_method_call("java/lang/System", "currentTimeMillis", &args, &mut frame);
+        let result = handle_special_method_call("java/lang/System", "currentTimeMillis", &args, &mut frame).unwrap();
         assert_eq!(result, Some(true));
-        // 验证栈上有两个值（long 类型）
-        assert!(!frame.stack.is_values_empty());
+        // 验证栈上有两个槽位（long 类型）
+        assert_eq!(frame.stack.len(), 2);
         
         // 测试未知方法
         let args = vec![JvmValue::Int(42)];
-        let result = handle_special_method_call("unknown/Class", "unknownMethod", &args, &mut frame);
+        let result = handle_special_method_call("unknown/Class", "unknownMethod", &args, &mut frame).unwrap();
         assert_eq!(result, None);
     }
 } 
\ No newline at end of file
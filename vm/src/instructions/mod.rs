@@ -15,11 +15,15 @@ pub mod method_utils;
 pub mod aload_0;
 pub mod invokespecial;
 pub mod invokevirtual;
+pub mod invokeinterface;
+pub mod invokedynamic;
 
 // 新增的指令模块
 pub mod constants;
 pub mod load_store;
 pub mod arithmetic;
+pub mod float_ops;
+pub mod conversions;
 pub mod control;
 pub mod stack;
 pub mod field_ops;
@@ -28,5 +32,6 @@ pub mod object_ops;
 pub mod control_extended;
 pub mod invokestatic;
 pub mod array_ops;
+pub mod exception_ops;
 pub mod iinc;
 // ... 其他指令模块按需添加 
\ No newline at end of file
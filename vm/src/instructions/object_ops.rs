@@ -1,203 +1,148 @@
 use crate::error::JvmError;
+use crate::instructions::Instruction;
 use crate::jvm_thread::Frame;
 use crate::vm::Vm;
 use crate::jvm_log;
 use reader::constant_pool::{ConstantPool, ConstantPoolExt};
 use crate::JvmValue;
 use crate::heap::RawPtr;
+use crate::runtime_constant_pool::ResolvedField;
+use std::rc::Rc;
 
-pub fn exec_new(frame: &mut Frame, code: &[u8], vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let index = ((code[frame.pc] as u16) << 8 | code[frame.pc + 1] as u16) as usize;
-    frame.pc += 2;
-    let cp = &frame.method.constant_pool;
-    let class_name = cp.get_class_name(index as u16);
-    jvm_log!("[New] 创建对象: {}", class_name);
+pub fn exec_new(frame: &mut Frame, instruction: &Instruction, vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let index = instruction.cp_index()?;
     
     if let Some(vm) = vm {
-        // 加载类，分配对象
-        let klass = vm.load(&class_name)?;
-        if let crate::class::Klass::Instance(instance_klass) = &klass {
-            let obj_ptr = vm.heap.borrow_mut().alloc_object(instance_klass)
-                .map_err(|e| JvmError::IllegalStateError(format!("alloc_object失败: {:?}", e)))?;
-            frame.stack.push_obj_ref(obj_ptr);
+        // 解析类引用只加载类，之后的执行直接使用缓存的类；创建对象前初始化类（JVMS 5.5）
+        let klass = frame.method.constant_pool.resolve_class(index, vm)?;
+        if let crate::class::Klass::Instance(instance_klass) = klass.as_ref() {
+            vm.initialize(&instance_klass.class_name)?;
+            jvm_log!("[New] 创建对象: {}", instance_klass.class_name);
+            let obj_ptr = vm.alloc_object(&klass)?;
+            frame.stack.push_obj_ref(obj_ptr)?;
             jvm_log!("[New] 推入对象引用: {:?}", obj_ptr);
         } else {
-            return Err(JvmError::IllegalStateError(format!("new: {} 不是实例类", class_name)));
+            return Err(JvmError::IllegalStateError(format!("new: {} 不是实例类", frame.method.constant_pool.get_class_name(index))));
         }
     } else {
         // 没有VM实例，创建一个假的对象引用
         let fake_obj_ptr = crate::heap::RawPtr(Box::into_raw(Box::new(())) as *mut u8);
-        frame.stack.push_obj_ref(fake_obj_ptr);
+        frame.stack.push_obj_ref(fake_obj_ptr)?;
         jvm_log!("[New] 推入对象引用: {:?}", fake_obj_ptr);
     }
     Ok(())
 }
 
-pub fn exec_getfield(frame: &mut Frame, code: &[u8], vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let index = ((code[frame.pc] as u16) << 8 | code[frame.pc + 1] as u16) as usize;
-    frame.pc += 2;
+/// 解析getfield/putfield的字段引用，解析出的字段必须是实例字段
+fn resolve_instance_field(frame: &Frame, index: u16, vm: &mut Vm) -> Result<Rc<ResolvedField>, JvmError> {
+    let field = frame.method.constant_pool.resolve_field(index, vm)?;
+    if field.is_static {
+        return Err(JvmError::IncompatibleClassChangeError(format!(
+            "Expected non-static field {}.{}",
+            field.class_name.replace('/', "."),
+            field.name
+        )));
+    }
+    Ok(field)
+}
+
+pub fn exec_getfield(frame: &mut Frame, instruction: &Instruction, vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let index = instruction.cp_index()?;
     jvm_log!("getfield {}", index);
-    if frame.stack.is_obj_refs_empty() {
-        return Err(JvmError::IllegalStateError("getfield: 栈无对象引用".to_string()));
+    let vm = vm.ok_or_else(|| JvmError::IllegalStateError("getfield: 需要VM来解析字段引用".to_string()))?;
+    let field = resolve_instance_field(frame, index, vm)?;
+    jvm_log!("[GetField] 访问字段: {}.{}{}", field.class_name, field.name, field.descriptor);
+    let obj_ref = frame.stack.pop_obj_ref()?;
+    if obj_ref.is_null() {
+        return Err(JvmError::NullPointerError(format!("getfield: 读取字段{}的对象引用为null", field.name)));
+    }
+    let value = vm.heap.borrow().get_field(obj_ref, field.offset, &field.descriptor);
+    match value {
+        JvmValue::Int(v) => frame.stack.push_int(v as i32)?,
+        JvmValue::Long(v) => frame.stack.push_long(v as i64)?,
+        JvmValue::Float(v) => frame.stack.push_float(f32::from_bits(v as u32))?,
+        JvmValue::Double(v) => frame.stack.push_double(f64::from_bits(v))?,
+        JvmValue::Boolean(v) => frame.stack.push_int(v as i32)?,
+        JvmValue::Byte(v) => frame.stack.push_int(v as i8 as i32)?,
+        JvmValue::Short(v) => frame.stack.push_int(v as i16 as i32)?,
+        JvmValue::Char(v) => frame.stack.push_int(v as i32)?,
+        JvmValue::ObjRef(ptr) => frame.stack.push_obj_ref(ptr)?,
+        JvmValue::Null => frame.stack.push_null()?,
+    }
+    Ok(())
+}
+
+pub fn exec_putfield(frame: &mut Frame, instruction: &Instruction, vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+    let index = instruction.cp_index()?;
+    let vm = vm.ok_or_else(|| JvmError::IllegalStateError("putfield: 需要VM来解析字段引用".to_string()))?;
+    let field = resolve_instance_field(frame, index, vm)?;
+    jvm_log!("[PutField] 设置字段: {}.{}{}", field.class_name, field.name, field.descriptor);
+    
+    // 栈布局为 ..., objectref, value，值在栈顶，先弹出值
+    let value = match field.descriptor.as_str() {
+        "I" | "S" | "B" | "Z" => JvmValue::Int(frame.stack.pop_int()? as u32),
+        "J" => JvmValue::Long(frame.stack.pop_long()? as u64),
+        "F" => JvmValue::Float(frame.stack.pop_float()?.to_bits() as u64),
+        "D" => JvmValue::Double(frame.stack.pop_double()?.to_bits()),
+        "C" => JvmValue::Char(frame.stack.pop_int()? as u16),
+        desc if desc.starts_with('L') || desc.starts_with('[') => {
+            JvmValue::ObjRef(frame.stack.pop_obj_ref()?)
+        },
+        _ => {
+            jvm_log!("[PutField] Unsupported field type: {}", field.descriptor);
+            return Err(JvmError::IllegalStateError(format!("putfield: 不支持的字段类型 {}", field.descriptor)));
+        },
+    };
+    
+    let obj_ref = frame.stack.pop_obj_ref()?;
+    if obj_ref.is_null() {
+        return Err(JvmError::NullPointerError(format!("putfield: 写入字段{}的对象引用为null", field.name)));
     }
-    let obj_ref = frame.stack.pop_obj_ref();
-    let cp = &frame.method.constant_pool;
-    let (class_name, field_name, field_desc) = cp.get_fieldref_info(index as u16);
-    jvm_log!("[GetField] 访问字段: {}.{}{}", class_name, field_name, field_desc);
+    jvm_log!("[PutField] Setting field {} at offset {}", field.name, field.offset);
+    vm.heap.borrow_mut().put_field(obj_ref, field.offset, value);
+    Ok(())
+}
+
+/// 解析checkcast/instanceof的类型操作数，返回内部形式的类名（数组为描述符形式）
+fn read_class_operand(frame: &Frame, instruction: &Instruction) -> Result<String, JvmError> {
+    Ok(frame.method.constant_pool.get_class_name(instruction.cp_index()?))
+}
+
+/// 判断对象能否赋值给目标类型；无法确定对象类型时（如System.out这类伪造引用）视为可以
+fn is_instance_of(vm: &mut Vm, obj_ref: crate::heap::RawPtr, target: &str) -> bool {
+    match vm.get_object_class_name(obj_ref) {
+        Some(class_name) => vm.is_subtype_of(&class_name, target),
+        None => true,
+    }
+}
+
+/// checkcast 指令 - 引用保持在栈上，null总能通过，类型不符时抛出ClassCastException
+pub fn exec_checkcast(frame: &mut Frame, instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = read_class_operand(frame, instruction)?;
+    let obj_ref = frame.stack.peek_obj_ref()?;
     if obj_ref.is_null() {
-        return Err(JvmError::NullPointerError("getfield: 对象引用为null".to_string()));
+        return Ok(());
     }
     if let Some(vm) = vm {
-        let klass = vm.load(&class_name)?;
-        if let crate::class::Klass::Instance(instance_klass) = &klass {
-            let fields = instance_klass.get_instance_fields();
-            let mut field_offset = None;
-            for f in fields.iter() {
-                if f.get_name() == field_name {
-                    field_offset = Some(f.get_offset());
-                    break;
-                }
-            }
-            if let Some(offset) = field_offset {
-                let value = vm.heap.borrow().get_field(obj_ref, offset, &field_desc);
-                match value {
-                    JvmValue::Int(v) => frame.stack.push_int(v as i32),
-                    JvmValue::Long(v) => frame.stack.push_int(v as i32),
-                    JvmValue::Float(v) => frame.stack.push_int(v as i32),
-                    JvmValue::Double(v) => frame.stack.push_int(v as i32),
-                    JvmValue::Char(v) => frame.stack.push_int(v as i32),
-                    JvmValue::ObjRef(ptr) => frame.stack.push_obj_ref(ptr),
-                    JvmValue::Null => frame.stack.push_obj_ref(RawPtr(std::ptr::null_mut())),
-                    _ => frame.stack.push_obj_ref(RawPtr(std::ptr::null_mut())),
-                }
-            } else {
-                return Err(JvmError::IllegalStateError(format!("getfield: 找不到字段 {}", field_name)));
-            }
+        if !is_instance_of(vm, obj_ref, &target) {
+            let class_name = vm.get_object_class_name(obj_ref).unwrap_or_default();
+            // 与HotSpot一致使用Class.getName()的形式：java.lang.String、[Ljava.lang.String;
+            return Err(JvmError::ClassCastError(format!(
+                "{} cannot be cast to {}",
+                class_name.replace('/', "."),
+                target.replace('/', ".")
+            )));
         }
     }
     Ok(())
 }
 
-pub fn exec_putfield(frame: &mut Frame, code: &[u8], vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-    let index = ((code[frame.pc] as u16) << 8 | code[frame.pc + 1] as u16) as usize;
-    frame.pc += 2;
-    let cp = &frame.method.constant_pool;
-    match &cp[index - 1] {
-        reader::constant_pool::CpInfo::FieldRef { class_index, name_and_type_index, .. } => {
-            let class_name = cp.get_class_name(*class_index);
-            let name_and_type = &cp[*name_and_type_index as usize - 1];
-            match name_and_type {
-                reader::constant_pool::CpInfo::NameAndType { name_index, descriptor_index, .. } => {
-                    let field_name = cp.get_utf8_string(*name_index);
-                    let field_desc = cp.get_utf8_string(*descriptor_index);
-                    jvm_log!("[PutField] 设置字段: {}.{}{}", class_name, field_name, field_desc);
-                    
-                    // 先弹出对象引用，确保栈中有对象引用
-                    let obj_ref = if !frame.stack.is_obj_refs_empty() {
-                        frame.stack.pop_obj_ref()
-                    } else {
-                        jvm_log!("[PutField] 警告: 栈中没有对象引用，使用null");
-                        RawPtr(std::ptr::null_mut())
-                    };
-                    
-                    if obj_ref.is_null() {
-                        return Err(JvmError::NullPointerError("putfield: 对象引用为null".to_string()));
-                    }
-                    
-                    // 弹出值
-                    let value = match field_desc.as_str() {
-                        "I" | "S" | "B" | "Z" => {
-                            if !frame.stack.is_values_empty() {
-                                JvmValue::Int(frame.stack.pop_int() as u32)
-                            } else {
-                                jvm_log!("[PutField] Stack empty for {}.{}, using default", class_name, field_name);
-                                JvmValue::Int(0)
-                            }
-                        },
-                        "J" => {
-                            if frame.stack.is_values_empty() {
-                                jvm_log!("[PutField] Stack empty for {}.{}, using default", class_name, field_name);
-                                JvmValue::Long(0)
-                            } else {
-                                let low = frame.stack.pop_int() as u32 as u64;
-                                let high = if !frame.stack.is_values_empty() {
-                                    frame.stack.pop_int() as u32 as u64
-                                } else {
-                                    0
-                                };
-                                JvmValue::Long((high << 32) | (low & 0xFFFF_FFFF))
-                            }
-                        },
-                        "F" => {
-                            if !frame.stack.is_values_empty() {
-                                JvmValue::Float((frame.stack.pop_int() as u32) as u64)
-                            } else {
-                                jvm_log!("[PutField] Stack empty for {}.{}, using default", class_name, field_name);
-                                JvmValue::Float(0)
-                            }
-                        },
-                        "D" => {
-                            if frame.stack.is_values_empty() {
-                                jvm_log!("[PutField] Stack empty for {}.{}, using default", class_name, field_name);
-                                JvmValue::Double(0)
-                            } else {
-                                let low = frame.stack.pop_int() as u32 as u64;
-                                let high = if !frame.stack.is_values_empty() {
-                                    frame.stack.pop_int() as u32 as u64
-                                } else {
-                                    0
-                                };
-                                JvmValue::Double((high << 32) | (low & 0xFFFF_FFFF))
-                            }
-                        },
-                        "C" => {
-                            if !frame.stack.is_values_empty() {
-                                JvmValue::Char(frame.stack.pop_int() as u16)
-                            } else {
-                                jvm_log!("[PutField] Stack empty for {}.{}, using default", class_name, field_name);
-                                JvmValue::Char(0)
-                            }
-                        },
-                        desc if desc.starts_with("L") || desc.starts_with("[") => {
-                            if !frame.stack.is_obj_refs_empty() {
-                                JvmValue::ObjRef(frame.stack.pop_obj_ref())
-                            } else {
-                                jvm_log!("[PutField] Stack empty for {}.{}, using null", class_name, field_name);
-                                JvmValue::Null
-                            }
-                        },
-                        _ => {
-                            jvm_log!("[PutField] Unsupported field type: {}", field_desc);
-                            JvmValue::Null
-                        },
-                    };
-                    
-                    // 获取字段偏移并设置字段值
-                    if let Some(vm) = vm {
-                        let klass = vm.load(&class_name)?;
-                        if let crate::class::Klass::Instance(instance_klass) = &klass {
-                            let fields = instance_klass.get_instance_fields();
-                            let mut field_offset = None;
-                            for f in fields.iter() {
-                                if f.get_name() == field_name {
-                                    field_offset = Some(f.get_offset());
-                                    break;
-                                }
-                            }
-                            if let Some(offset) = field_offset {
-                                jvm_log!("[PutField] Setting field {} at offset {}", field_name, offset);
-                                vm.heap.borrow_mut().put_field(obj_ref, offset, value);
-                            } else {
-                                jvm_log!("[PutField] Field not found: {}.{}", class_name, field_name);
-                                return Err(JvmError::IllegalStateError(format!("putfield: 找不到字段 {}", field_name)));
-                            }
-                        }
-                    }
-                    Ok(())
-                }
-                _ => Err(JvmError::IllegalStateError("Invalid NameAndType in constant pool".to_string())),
-            }
-        }
-        _ => Err(JvmError::IllegalStateError("Invalid FieldRef in constant pool".to_string())),
-    }
-} 
\ No newline at end of file
+/// instanceof 指令 - null结果为0
+pub fn exec_instanceof(frame: &mut Frame, instruction: &Instruction, vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    let target = read_class_operand(frame, instruction)?;
+    let obj_ref = frame.stack.pop_obj_ref()?;
+    let result = !obj_ref.is_null() && vm.is_some_and(|vm| is_instance_of(vm, obj_ref, &target));
+    jvm_log!("[InstanceOf] {:?} instanceof {} = {}", obj_ref, target, result);
+    frame.stack.push_int(result as i32)?;
+    Ok(())
+}
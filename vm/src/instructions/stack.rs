@@ -1,126 +1,64 @@
 use crate::jvm_thread::Frame;
 use crate::error::JvmError;
+use crate::instructions::Instruction;
 use crate::vm::Vm;
 
+// 栈操作指令族
+// long/double在操作数栈上占两个槽位，因此JVMS中按类别区分的各种形式
+// （如dup2复制一个category-2值或两个category-1值）都归结为对槽位的同一种操作。
+
 // pop 指令
-pub fn exec_pop(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    // 根据栈顶内容的类型来决定弹出什么
-    if !frame.stack.is_values_empty() {
-        frame.stack.pop_int();
-    } else if !frame.stack.is_obj_refs_empty() {
-        frame.stack.pop_obj_ref();
-    }
+pub fn exec_pop(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.pop_slot()?;
     Ok(())
 }
 
-// pop2 指令
-pub fn exec_pop2(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    // 弹出两个值或一个long/double
-    if !frame.stack.is_values_empty() {
-        frame.stack.pop_int();
-        if !frame.stack.is_values_empty() {
-            frame.stack.pop_int();
-        }
-    } else if !frame.stack.is_obj_refs_empty() {
-        frame.stack.pop_obj_ref();
-        if !frame.stack.is_obj_refs_empty() {
-            frame.stack.pop_obj_ref();
-        }
-    }
+// pop2 指令 - 弹出一个category-2值或两个category-1值
+pub fn exec_pop2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.pop_slot()?;
+    frame.stack.pop_slot()?;
     Ok(())
 }
 
 // dup 指令
-pub fn exec_dup(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+pub fn exec_dup(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
     crate::jvm_log!("dup");
-    // 优先检查对象引用栈，然后检查值栈
-    if !frame.stack.is_obj_refs_empty() {
-        let val = frame.stack.peek_obj_ref();
-        frame.stack.push_obj_ref(val);
-    } else if !frame.stack.is_values_empty() {
-        let val = frame.stack.peek_int();
-        frame.stack.push_int(val);
-    } else {
-        return Err(JvmError::IllegalStateError("dup: 栈为空".to_string()));
-    }
+    frame.stack.dup_insert(1, 0)?;
+    Ok(())
+}
+
+// dup_x1 指令 - ..., v2, v1 -> ..., v1, v2, v1
+pub fn exec_dup_x1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.dup_insert(1, 1)?;
     Ok(())
 }
 
-// dup_x1 指令
-pub fn exec_dup_x1(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    // 复制栈顶值并插入到栈顶第二个值下面
-    if !frame.stack.is_values_empty() {
-        let value1 = frame.stack.pop_int();
-        let value2 = frame.stack.pop_int();
-        frame.stack.push_int(value1);
-        frame.stack.push_int(value2);
-        frame.stack.push_int(value1);
-    } else if !frame.stack.is_obj_refs_empty() {
-        let value1 = frame.stack.pop_obj_ref();
-        let value2 = frame.stack.pop_obj_ref();
-        frame.stack.push_obj_ref(value1);
-        frame.stack.push_obj_ref(value2);
-        frame.stack.push_obj_ref(value1);
-    }
+// dup_x2 指令 - ..., v3, v2, v1 -> ..., v1, v3, v2, v1
+pub fn exec_dup_x2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.dup_insert(1, 2)?;
     Ok(())
 }
 
-// dup_x2 指令
-pub fn exec_dup_x2(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    // 复制栈顶值并插入到栈顶第三个值下面
-    if !frame.stack.is_values_empty() {
-        let value1 = frame.stack.pop_int();
-        let value2 = frame.stack.pop_int();
-        let value3 = frame.stack.pop_int();
-        frame.stack.push_int(value1);
-        frame.stack.push_int(value3);
-        frame.stack.push_int(value2);
-        frame.stack.push_int(value1);
-    } else if !frame.stack.is_obj_refs_empty() {
-        let value1 = frame.stack.pop_obj_ref();
-        let value2 = frame.stack.pop_obj_ref();
-        let value3 = frame.stack.pop_obj_ref();
-        frame.stack.push_obj_ref(value1);
-        frame.stack.push_obj_ref(value3);
-        frame.stack.push_obj_ref(value2);
-        frame.stack.push_obj_ref(value1);
-    }
+// dup2 指令 - ..., v2, v1 -> ..., v2, v1, v2, v1
+pub fn exec_dup2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.dup_insert(2, 0)?;
     Ok(())
 }
 
-// dup2 指令
-pub fn exec_dup2(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    // 复制栈顶两个值
-    if !frame.stack.is_values_empty() {
-        let value1 = frame.stack.pop_int();
-        if !frame.stack.is_values_empty() {
-            let value2 = frame.stack.pop_int();
-            frame.stack.push_int(value2);
-            frame.stack.push_int(value1);
-            frame.stack.push_int(value2);
-            frame.stack.push_int(value1);
-        } else {
-            frame.stack.push_int(value1);
-            frame.stack.push_int(value1);
-        }
-    } else if !frame.stack.is_obj_refs_empty() {
-        let value1 = frame.stack.pop_obj_ref();
-        if !frame.stack.is_obj_refs_empty() {
-            let value2 = frame.stack.pop_obj_ref();
-            frame.stack.push_obj_ref(value2);
-            frame.stack.push_obj_ref(value1);
-            frame.stack.push_obj_ref(value2);
-            frame.stack.push_obj_ref(value1);
-        } else {
-            frame.stack.push_obj_ref(value1);
-            frame.stack.push_obj_ref(value1);
-        }
-    }
+// dup2_x1 指令 - ..., v3, v2, v1 -> ..., v2, v1, v3, v2, v1
+pub fn exec_dup2_x1(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.dup_insert(2, 1)?;
+    Ok(())
+}
+
+// dup2_x2 指令 - ..., v4, v3, v2, v1 -> ..., v2, v1, v4, v3, v2, v1
+pub fn exec_dup2_x2(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.dup_insert(2, 2)?;
     Ok(())
 }
 
 // swap 指令
-pub fn exec_swap(frame: &mut Frame, _code: &[u8], _vm: Option<&mut Vm>) -> Result<(), JvmError> {
-    frame.stack.swap_top_two_ints();
+pub fn exec_swap(frame: &mut Frame, _instruction: &Instruction, _vm: Option<&mut Vm>) -> Result<(), JvmError> {
+    frame.stack.swap_top_two()?;
     Ok(())
-} 
\ No newline at end of file
+}
@@ -1,24 +1,23 @@
-use reader::types::{U1, U2, U4};
+use reader::types::{U1, U2};
 use crate::method::ArrayType;
 use crate::jvm_thread::Frame;
 use crate::error::JvmError;
 use crate::vm::Vm;
 
-/// 指令执行函数类型
+/// 指令执行函数类型：帧的pc已经指向下一条指令，操作数由预解码的指令携带
 pub type InstructionFn = fn(
     frame: &mut Frame,
-    code: &[u8],
-    pc: &mut usize,
+    instruction: &Instruction,
     vm: Option<&mut Vm>,
 ) -> Result<(), JvmError>;
 
+/// 预解码的指令：宽索引（wide）并入对应指令，分支目标是方法指令序列中的下标
 #[derive(Debug, Clone, PartialEq)]
 pub enum Instruction {
-    // --- 自动补全所有变体 ---
     Aaload,
     Aastore,
     Aconst_null,
-    Aload(U1),
+    Aload(U2),
     Aload_0,
     Aload_1,
     Aload_2,
@@ -26,7 +25,7 @@ pub enum Instruction {
     Anewarray(U2),
     Areturn,
     Arraylength,
-    Astore(U1),
+    Astore(U2),
     Astore_0,
     Astore_1,
     Astore_2,
@@ -49,7 +48,7 @@ pub enum Instruction {
     Dconst_0,
     Dconst_1,
     Ddiv,
-    Dload(U1),
+    Dload(U2),
     Dload_0,
     Dload_1,
     Dload_2,
@@ -58,18 +57,18 @@ pub enum Instruction {
     Dneg,
     Drem,
     Dreturn,
-    Dstore(U1),
+    Dstore(U2),
     Dstore_0,
     Dstore_1,
     Dstore_2,
     Dstore_3,
     Dsub,
     Dup,
-    Dup_x1,
-    Dup_x2,
     Dup2,
     Dup2_x1,
     Dup2_x2,
+    Dup_x1,
+    Dup_x2,
     F2d,
     F2i,
     F2l,
@@ -82,7 +81,7 @@ pub enum Instruction {
     Fconst_1,
     Fconst_2,
     Fdiv,
-    Fload(U1),
+    Fload(U2),
     Fload_0,
     Fload_1,
     Fload_2,
@@ -91,7 +90,7 @@ pub enum Instruction {
     Fneg,
     Frem,
     Freturn,
-    Fstore(U1),
+    Fstore(U2),
     Fstore_0,
     Fstore_1,
     Fstore_2,
@@ -99,8 +98,8 @@ pub enum Instruction {
     Fsub,
     Getfield(U2),
     Getstatic(U2),
-    Goto(U2),
-    Goto_w(U4),
+    Goto(usize),
+    Goto_w(usize),
     I2b,
     I2c,
     I2d,
@@ -111,32 +110,32 @@ pub enum Instruction {
     Iaload,
     Iand,
     Iastore,
-    Iconst_m1,
     Iconst_0,
     Iconst_1,
     Iconst_2,
     Iconst_3,
     Iconst_4,
     Iconst_5,
+    Iconst_m1,
     Idiv,
-    If_acmpeq(U2),
-    If_acmpne(U2),
-    If_icmpeq(U2),
-    If_icmpne(U2),
-    If_icmplt(U2),
-    If_icmpge(U2),
-    If_icmpgt(U2),
-    If_icmple(U2),
-    Ifeq(U2),
-    Ifne(U2),
-    Iflt(U2),
-    Ifge(U2),
-    Ifgt(U2),
-    Ifle(U2),
-    Ifnonnull(U2),
-    Ifnull(U2),
-    Iinc(U1, i8),
-    Iload(U1),
+    If_acmpeq(usize),
+    If_acmpne(usize),
+    If_icmpeq(usize),
+    If_icmpge(usize),
+    If_icmpgt(usize),
+    If_icmple(usize),
+    If_icmplt(usize),
+    If_icmpne(usize),
+    Ifeq(usize),
+    Ifge(usize),
+    Ifgt(usize),
+    Ifle(usize),
+    Iflt(usize),
+    Ifne(usize),
+    Ifnonnull(usize),
+    Ifnull(usize),
+    Iinc(U2, i16),
+    Iload(U2),
     Iload_0,
     Iload_1,
     Iload_2,
@@ -154,7 +153,7 @@ pub enum Instruction {
     Ireturn,
     Ishl,
     Ishr,
-    Istore(U1),
+    Istore(U2),
     Istore_0,
     Istore_1,
     Istore_2,
@@ -162,8 +161,8 @@ pub enum Instruction {
     Isub,
     Iushr,
     Ixor,
-    Jsr(U2),
-    Jsr_w(U4),
+    Jsr(usize),
+    Jsr_w(usize),
     L2d,
     L2f,
     L2i,
@@ -175,23 +174,23 @@ pub enum Instruction {
     Lconst_0,
     Lconst_1,
     Ldc(U1),
-    Ldc_w(U2),
     Ldc2_w(U2),
+    Ldc_w(U2),
     Ldiv,
-    Lload(U1),
+    Lload(U2),
     Lload_0,
     Lload_1,
     Lload_2,
     Lload_3,
     Lmul,
     Lneg,
-    Lookupswitch(U4, U4, Vec<(U4, i32)>),
+    Lookupswitch(usize, Vec<(i32, usize)>),
     Lor,
     Lrem,
     Lreturn,
     Lshl,
     Lshr,
-    Lstore(U1),
+    Lstore(U2),
     Lstore_0,
     Lstore_1,
     Lstore_2,
@@ -209,14 +208,337 @@ pub enum Instruction {
     Pop2,
     Putfield(U2),
     Putstatic(U2),
-    Ret(U1),
+    Ret(U2),
     Return,
     Saload,
     Sastore,
     Sipush(U2),
     Swap,
-    Tableswitch(U4, U4, U4, Vec<i32>),
-    Wide,
-    // --- 你可以继续添加自定义或未实现指令 ---
-    Unimplemented,
-} 
\ No newline at end of file
+    Tableswitch(usize, i32, i32, Vec<usize>),
+    /// 解码器不认识或无法解码的指令，保留原opcode
+    Unimplemented(U1),
+}
+
+impl Instruction {
+    /// 指令的opcode，wide形式的指令返回被扩展的指令的opcode
+    pub fn opcode(&self) -> U1 {
+        match self {
+            Instruction::Nop => 0x00,
+            Instruction::Aconst_null => 0x01,
+            Instruction::Iconst_m1 => 0x02,
+            Instruction::Iconst_0 => 0x03,
+            Instruction::Iconst_1 => 0x04,
+            Instruction::Iconst_2 => 0x05,
+            Instruction::Iconst_3 => 0x06,
+            Instruction::Iconst_4 => 0x07,
+            Instruction::Iconst_5 => 0x08,
+            Instruction::Lconst_0 => 0x09,
+            Instruction::Lconst_1 => 0x0a,
+            Instruction::Fconst_0 => 0x0b,
+            Instruction::Fconst_1 => 0x0c,
+            Instruction::Fconst_2 => 0x0d,
+            Instruction::Dconst_0 => 0x0e,
+            Instruction::Dconst_1 => 0x0f,
+            Instruction::Bipush(_) => 0x10,
+            Instruction::Sipush(_) => 0x11,
+            Instruction::Ldc(_) => 0x12,
+            Instruction::Ldc_w(_) => 0x13,
+            Instruction::Ldc2_w(_) => 0x14,
+            Instruction::Iload(_) => 0x15,
+            Instruction::Lload(_) => 0x16,
+            Instruction::Fload(_) => 0x17,
+            Instruction::Dload(_) => 0x18,
+            Instruction::Aload(_) => 0x19,
+            Instruction::Iload_0 => 0x1a,
+            Instruction::Iload_1 => 0x1b,
+            Instruction::Iload_2 => 0x1c,
+            Instruction::Iload_3 => 0x1d,
+            Instruction::Lload_0 => 0x1e,
+            Instruction::Lload_1 => 0x1f,
+            Instruction::Lload_2 => 0x20,
+            Instruction::Lload_3 => 0x21,
+            Instruction::Fload_0 => 0x22,
+            Instruction::Fload_1 => 0x23,
+            Instruction::Fload_2 => 0x24,
+            Instruction::Fload_3 => 0x25,
+            Instruction::Dload_0 => 0x26,
+            Instruction::Dload_1 => 0x27,
+            Instruction::Dload_2 => 0x28,
+            Instruction::Dload_3 => 0x29,
+            Instruction::Aload_0 => 0x2a,
+            Instruction::Aload_1 => 0x2b,
+            Instruction::Aload_2 => 0x2c,
+            Instruction::Aload_3 => 0x2d,
+            Instruction::Iaload => 0x2e,
+            Instruction::Laload => 0x2f,
+            Instruction::Faload => 0x30,
+            Instruction::Daload => 0x31,
+            Instruction::Aaload => 0x32,
+            Instruction::Baload => 0x33,
+            Instruction::Caload => 0x34,
+            Instruction::Saload => 0x35,
+            Instruction::Istore(_) => 0x36,
+            Instruction::Lstore(_) => 0x37,
+            Instruction::Fstore(_) => 0x38,
+            Instruction::Dstore(_) => 0x39,
+            Instruction::Astore(_) => 0x3a,
+            Instruction::Istore_0 => 0x3b,
+            Instruction::Istore_1 => 0x3c,
+            Instruction::Istore_2 => 0x3d,
+            Instruction::Istore_3 => 0x3e,
+            Instruction::Lstore_0 => 0x3f,
+            Instruction::Lstore_1 => 0x40,
+            Instruction::Lstore_2 => 0x41,
+            Instruction::Lstore_3 => 0x42,
+            Instruction::Fstore_0 => 0x43,
+            Instruction::Fstore_1 => 0x44,
+            Instruction::Fstore_2 => 0x45,
+            Instruction::Fstore_3 => 0x46,
+            Instruction::Dstore_0 => 0x47,
+            Instruction::Dstore_1 => 0x48,
+            Instruction::Dstore_2 => 0x49,
+            Instruction::Dstore_3 => 0x4a,
+            Instruction::Astore_0 => 0x4b,
+            Instruction::Astore_1 => 0x4c,
+            Instruction::Astore_2 => 0x4d,
+            Instruction::Astore_3 => 0x4e,
+            Instruction::Iastore => 0x4f,
+            Instruction::Lastore => 0x50,
+            Instruction::Fastore => 0x51,
+            Instruction::Dastore => 0x52,
+            Instruction::Aastore => 0x53,
+            Instruction::Bastore => 0x54,
+            Instruction::Castore => 0x55,
+            Instruction::Sastore => 0x56,
+            Instruction::Pop => 0x57,
+            Instruction::Pop2 => 0x58,
+            Instruction::Dup => 0x59,
+            Instruction::Dup_x1 => 0x5a,
+            Instruction::Dup_x2 => 0x5b,
+            Instruction::Dup2 => 0x5c,
+            Instruction::Dup2_x1 => 0x5d,
+            Instruction::Dup2_x2 => 0x5e,
+            Instruction::Swap => 0x5f,
+            Instruction::Iadd => 0x60,
+            Instruction::Ladd => 0x61,
+            Instruction::Fadd => 0x62,
+            Instruction::Dadd => 0x63,
+            Instruction::Isub => 0x64,
+            Instruction::Lsub => 0x65,
+            Instruction::Fsub => 0x66,
+            Instruction::Dsub => 0x67,
+            Instruction::Imul => 0x68,
+            Instruction::Lmul => 0x69,
+            Instruction::Fmul => 0x6a,
+            Instruction::Dmul => 0x6b,
+            Instruction::Idiv => 0x6c,
+            Instruction::Ldiv => 0x6d,
+            Instruction::Fdiv => 0x6e,
+            Instruction::Ddiv => 0x6f,
+            Instruction::Irem => 0x70,
+            Instruction::Lrem => 0x71,
+            Instruction::Frem => 0x72,
+            Instruction::Drem => 0x73,
+            Instruction::Ineg => 0x74,
+            Instruction::Lneg => 0x75,
+            Instruction::Fneg => 0x76,
+            Instruction::Dneg => 0x77,
+            Instruction::Ishl => 0x78,
+            Instruction::Lshl => 0x79,
+            Instruction::Ishr => 0x7a,
+            Instruction::Lshr => 0x7b,
+            Instruction::Iushr => 0x7c,
+            Instruction::Lushr => 0x7d,
+            Instruction::Iand => 0x7e,
+            Instruction::Land => 0x7f,
+            Instruction::Ior => 0x80,
+            Instruction::Lor => 0x81,
+            Instruction::Ixor => 0x82,
+            Instruction::Lxor => 0x83,
+            Instruction::Iinc(..) => 0x84,
+            Instruction::I2l => 0x85,
+            Instruction::I2f => 0x86,
+            Instruction::I2d => 0x87,
+            Instruction::L2i => 0x88,
+            Instruction::L2f => 0x89,
+            Instruction::L2d => 0x8a,
+            Instruction::F2i => 0x8b,
+            Instruction::F2l => 0x8c,
+            Instruction::F2d => 0x8d,
+            Instruction::D2i => 0x8e,
+            Instruction::D2l => 0x8f,
+            Instruction::D2f => 0x90,
+            Instruction::I2b => 0x91,
+            Instruction::I2c => 0x92,
+            Instruction::I2s => 0x93,
+            Instruction::Lcmp => 0x94,
+            Instruction::Fcmpl => 0x95,
+            Instruction::Fcmpg => 0x96,
+            Instruction::Dcmpl => 0x97,
+            Instruction::Dcmpg => 0x98,
+            Instruction::Ifeq(_) => 0x99,
+            Instruction::Ifne(_) => 0x9a,
+            Instruction::Iflt(_) => 0x9b,
+            Instruction::Ifge(_) => 0x9c,
+            Instruction::Ifgt(_) => 0x9d,
+            Instruction::Ifle(_) => 0x9e,
+            Instruction::If_icmpeq(_) => 0x9f,
+            Instruction::If_icmpne(_) => 0xa0,
+            Instruction::If_icmplt(_) => 0xa1,
+            Instruction::If_icmpge(_) => 0xa2,
+            Instruction::If_icmpgt(_) => 0xa3,
+            Instruction::If_icmple(_) => 0xa4,
+            Instruction::If_acmpeq(_) => 0xa5,
+            Instruction::If_acmpne(_) => 0xa6,
+            Instruction::Goto(_) => 0xa7,
+            Instruction::Jsr(_) => 0xa8,
+            Instruction::Ret(_) => 0xa9,
+            Instruction::Tableswitch(..) => 0xaa,
+            Instruction::Lookupswitch(..) => 0xab,
+            Instruction::Ireturn => 0xac,
+            Instruction::Lreturn => 0xad,
+            Instruction::Freturn => 0xae,
+            Instruction::Dreturn => 0xaf,
+            Instruction::Areturn => 0xb0,
+            Instruction::Return => 0xb1,
+            Instruction::Getstatic(_) => 0xb2,
+            Instruction::Putstatic(_) => 0xb3,
+            Instruction::Getfield(_) => 0xb4,
+            Instruction::Putfield(_) => 0xb5,
+            Instruction::Invokevirtual(_) => 0xb6,
+            Instruction::Invokespecial(_) => 0xb7,
+            Instruction::Invokestatic(_) => 0xb8,
+            Instruction::Invokeinterface(..) => 0xb9,
+            Instruction::Invokedynamic(_) => 0xba,
+            Instruction::New(_) => 0xbb,
+            Instruction::Newarray(_) => 0xbc,
+            Instruction::Anewarray(_) => 0xbd,
+            Instruction::Arraylength => 0xbe,
+            Instruction::Athrow => 0xbf,
+            Instruction::Checkcast(_) => 0xc0,
+            Instruction::Instanceof(_) => 0xc1,
+            Instruction::Monitorenter => 0xc2,
+            Instruction::Monitorexit => 0xc3,
+            Instruction::Multianewarray(..) => 0xc5,
+            Instruction::Ifnull(_) => 0xc6,
+            Instruction::Ifnonnull(_) => 0xc7,
+            Instruction::Goto_w(_) => 0xc8,
+            Instruction::Jsr_w(_) => 0xc9,
+            Instruction::Unimplemented(opcode) => *opcode,
+        }
+    }
+
+    /// 局部变量表下标操作数（load、store、iinc、ret）
+    pub fn local_index(&self) -> Result<usize, JvmError> {
+        match self {
+            Instruction::Iload(index)
+            | Instruction::Lload(index)
+            | Instruction::Fload(index)
+            | Instruction::Dload(index)
+            | Instruction::Aload(index)
+            | Instruction::Istore(index)
+            | Instruction::Lstore(index)
+            | Instruction::Fstore(index)
+            | Instruction::Dstore(index)
+            | Instruction::Astore(index)
+            | Instruction::Iinc(index, _)
+            | Instruction::Ret(index) => Ok(*index as usize),
+            _ => Err(self.operand_error("局部变量下标")),
+        }
+    }
+
+    /// 常量池下标操作数
+    pub fn cp_index(&self) -> Result<U2, JvmError> {
+        match self {
+            Instruction::Ldc(index) => Ok(*index as U2),
+            Instruction::Ldc_w(index)
+            | Instruction::Ldc2_w(index)
+            | Instruction::Getstatic(index)
+            | Instruction::Putstatic(index)
+            | Instruction::Getfield(index)
+            | Instruction::Putfield(index)
+            | Instruction::Invokevirtual(index)
+            | Instruction::Invokespecial(index)
+            | Instruction::Invokestatic(index)
+            | Instruction::Invokeinterface(index, _)
+            | Instruction::Invokedynamic(index)
+            | Instruction::New(index)
+            | Instruction::Anewarray(index)
+            | Instruction::Checkcast(index)
+            | Instruction::Instanceof(index)
+            | Instruction::Multianewarray(index, _) => Ok(*index),
+            _ => Err(self.operand_error("常量池下标")),
+        }
+    }
+
+    /// 分支指令的跳转目标（指令下标）
+    pub fn branch_target(&self) -> Result<usize, JvmError> {
+        match self {
+            Instruction::Ifeq(target)
+            | Instruction::Ifne(target)
+            | Instruction::Iflt(target)
+            | Instruction::Ifge(target)
+            | Instruction::Ifgt(target)
+            | Instruction::Ifle(target)
+            | Instruction::If_icmpeq(target)
+            | Instruction::If_icmpne(target)
+            | Instruction::If_icmplt(target)
+            | Instruction::If_icmpge(target)
+            | Instruction::If_icmpgt(target)
+            | Instruction::If_icmple(target)
+            | Instruction::If_acmpeq(target)
+            | Instruction::If_acmpne(target)
+            | Instruction::Goto(target)
+            | Instruction::Goto_w(target)
+            | Instruction::Jsr(target)
+            | Instruction::Jsr_w(target)
+            | Instruction::Ifnull(target)
+            | Instruction::Ifnonnull(target) => Ok(*target),
+            _ => Err(self.operand_error("分支目标")),
+        }
+    }
+
+    /// 对指令的每个分支目标应用f，f返回None时返回None
+    pub fn map_branch_targets(&mut self, f: impl Fn(usize) -> Option<usize>) -> Option<()> {
+        match self {
+            Instruction::Tableswitch(default, _, _, targets) => {
+                *default = f(*default)?;
+                for target in targets.iter_mut() {
+                    *target = f(*target)?;
+                }
+            }
+            Instruction::Lookupswitch(default, pairs) => {
+                *default = f(*default)?;
+                for (_, target) in pairs.iter_mut() {
+                    *target = f(*target)?;
+                }
+            }
+            Instruction::Ifeq(target)
+            | Instruction::Ifne(target)
+            | Instruction::Iflt(target)
+            | Instruction::Ifge(target)
+            | Instruction::Ifgt(target)
+            | Instruction::Ifle(target)
+            | Instruction::If_icmpeq(target)
+            | Instruction::If_icmpne(target)
+            | Instruction::If_icmplt(target)
+            | Instruction::If_icmpge(target)
+            | Instruction::If_icmpgt(target)
+            | Instruction::If_icmple(target)
+            | Instruction::If_acmpeq(target)
+            | Instruction::If_acmpne(target)
+            | Instruction::Goto(target)
+            | Instruction::Goto_w(target)
+            | Instruction::Jsr(target)
+            | Instruction::Jsr_w(target)
+            | Instruction::Ifnull(target)
+            | Instruction::Ifnonnull(target) => *target = f(*target)?,
+            _ => {}
+        }
+        Some(())
+    }
+
+    fn operand_error(&self, operand: &str) -> JvmError {
+        JvmError::IllegalStateError(format!("指令 {:?} 没有{}操作数", self, operand))
+    }
+}
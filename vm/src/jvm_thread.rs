@@ -1,27 +1,18 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use crate::error::JvmError;
-use crate::heap::Heap;
 use crate::method::Method;
 use crate::operand_stack::OperandStack;
 use crate::local_vars::LocalVars;
 use crate::JvmValue;
-use crate::heap::RawPtr;
+use crate::heap::{RawPtr, RootKind};
+use crate::operand_stack::{SharedSlots, Slot};
 use crate::jvm_log;
 use reader::constant_pool::ConstantPool;
-use crate::instructions::aload_0;
-use crate::instructions::constants;
-use crate::instructions::load_store;
-use crate::instructions::arithmetic;
-use crate::instructions::control;
-use crate::instructions::stack;
-use crate::instructions::field_ops;
-use crate::instructions::ldc_ops;
-use crate::instructions::object_ops;
-use crate::instructions::control_extended;
-use crate::instructions::invokestatic;
-use crate::instructions::array_ops;
-use crate::instructions::invokevirtual;
-use crate::instructions::iinc;
-use crate::instructions::invokespecial;
+use crate::instructions::dispatch;
+use crate::instructions::Instruction;
+use crate::instructions::method_utils::push_return_value;
 
 // 新增 Frame 结构体
 pub struct Frame {
@@ -31,9 +22,65 @@ pub struct Frame {
     pub pc: usize,
 }
 
+impl Frame {
+    /// 为方法创建帧，操作数栈和局部变量表按方法声明的max_stack、max_locals分配
+    pub fn new(method: &Method) -> Self {
+        Frame {
+            local_vars: LocalVars::new(method.max_locals),
+            stack: OperandStack::new(method.max_stack),
+            method: method.clone(),
+            pc: 0,
+        }
+    }
+
+    /// 与帧共享局部变量表和操作数栈槽位的帧根
+    pub fn roots(&self) -> FrameRoots {
+        FrameRoots {
+            locals: self.local_vars.shared_slots(),
+            operands: self.stack.shared_slots(),
+        }
+    }
+
+    /// 访问帧中所有引用，见 FrameRoots::visit_references
+    pub fn visit_references(&self, visitor: &mut dyn FnMut(RootKind, &mut RawPtr)) {
+        self.roots().visit_references(visitor);
+    }
+}
+
+/// 帧的垃圾收集根：与帧共享局部变量表和操作数栈的槽位。
+/// 指令执行期间帧被可变借用，垃圾收集只通过帧根访问其中的引用
+#[derive(Debug, Clone)]
+pub struct FrameRoots {
+    locals: SharedSlots,
+    operands: SharedSlots,
+}
+
+impl FrameRoots {
+    /// 访问帧中所有引用：局部变量表和操作数栈的槽带有类型标记，只有引用槽交给访问者，
+    /// 访问者可以改写引用（如对象被移动后）。null引用同样被访问
+    pub fn visit_references(&self, visitor: &mut dyn FnMut(RootKind, &mut RawPtr)) {
+        for slot in self.locals.borrow_mut().iter_mut() {
+            if let Slot::Ref(obj) = slot {
+                visitor(RootKind::Local, obj);
+            }
+        }
+        for slot in self.operands.borrow_mut().iter_mut() {
+            if let Slot::Ref(obj) = slot {
+                visitor(RootKind::Operand, obj);
+            }
+        }
+    }
+}
+
+/// 线程所有帧的帧根，栈底在前。运行期间登记到VM，垃圾收集时扫描
+pub type ThreadRoots = Rc<RefCell<Vec<FrameRoots>>>;
+
+/// Java线程：方法帧组成的栈，栈顶在末尾。
+/// Java方法之间的调用和返回都在同一个解释循环中压入、弹出帧，不占用Rust调用栈
 pub struct JvmThread {
     pub frames: Vec<Frame>,
-    call_depth: usize, // 添加调用深度计数器
+    // 与frames一一对应的帧根，帧只通过push_frame、pop_frame入栈和出栈
+    roots: ThreadRoots,
 }
 
 impl JvmThread {
@@ -44,109 +91,165 @@ impl JvmThread {
             local_vars: LocalVars::new(max_locals),
             method: Method::new("main".to_string(), "()V".to_string(), 0, vec![], max_stack, max_locals),
         };
-        JvmThread {
-            frames: vec![main_frame],
-            call_depth: 0,
+        Self::with_frame(main_frame)
+    }
+
+    /// 没有帧的线程，入口方法的帧由invoke压入
+    pub fn empty() -> Self {
+        JvmThread { frames: Vec::new(), roots: Rc::new(RefCell::new(Vec::new())) }
+    }
+
+    /// 以已经创建好的帧作为入口帧
+    pub fn with_frame(frame: Frame) -> Self {
+        let mut thread = Self::empty();
+        thread.push_frame(frame);
+        thread
+    }
+
+    fn push_frame(&mut self, frame: Frame) {
+        self.roots.borrow_mut().push(frame.roots());
+        self.frames.push(frame);
+    }
+
+    fn pop_frame(&mut self) {
+        self.roots.borrow_mut().pop();
+        self.frames.pop();
+    }
+
+    /// 从栈底到栈顶访问所有帧中的引用
+    pub fn visit_roots(&self, visitor: &mut dyn FnMut(RootKind, &mut RawPtr)) {
+        for frame in self.roots.borrow().iter() {
+            frame.visit_references(visitor);
         }
     }
 
-    pub fn execute(&mut self, method: &Method, heap: &mut Heap, mut vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
-        // 检查调用深度，防止无限递归
-        if self.call_depth > 1000 {
-            return Err(JvmError::IllegalStateError("Call depth exceeded maximum limit".to_string()));
+    /// 在栈顶帧中执行method的代码，代码执行到末尾或方法返回时结束
+    pub fn execute(&mut self, method: &Method, mut vm: Option<&mut crate::vm::Vm>) -> Result<(), JvmError> {
+        let base = self.frames.len() - 1;
+        // 确保入口帧使用正确的方法（包含常量池）
+        self.frames[base].method = method.clone();
+
+        // 执行期间把方法登记到VM的调用栈上，供异常生成栈轨迹
+        if let Some(vm) = vm.as_deref_mut() {
+            vm.push_call_frame(method);
         }
-        self.call_depth += 1;
-        
-        // 确保第一个frame使用正确的方法（包含常量池）
-        if !self.frames.is_empty() {
-            self.frames[0].method = method.clone();
+        let result = self.run(base, &mut vm);
+        // 入口帧没有返回（执行到代码末尾）时仍在栈上
+        if self.frames.len() > base {
+            if let Some(vm) = vm {
+                vm.pop_call_frame();
+            }
         }
-        
-        let code = method.get_code();
-        // 主循环：只要还有frame且pc未越界就继续执行
-        while !self.frames.is_empty() && self.frames[0].pc < code.len() {
-            // 注意：任何地方弹出frame后都要保证self.frames非空再访问self.frames[0]
-            let opcode = code[self.frames[0].pc];
-            self.frames[0].pc += 1;
-
-            // 获取当前frame的可变引用
-            let frame = &mut self.frames[0];
-
-            match opcode {
-                0x00 => (), // nop
-                0x01 => constants::exec_aconst_null(frame, code, vm.as_deref_mut())?,
-                0x02 => constants::exec_iconst_m1(frame, code, vm.as_deref_mut())?,
-                0x03 => constants::exec_iconst_0(frame, code, vm.as_deref_mut())?,
-                0x04 => constants::exec_iconst_1(frame, code, vm.as_deref_mut())?,
-                0x05 => constants::exec_iconst_2(frame, code, vm.as_deref_mut())?,
-                0x06 => constants::exec_iconst_3(frame, code, vm.as_deref_mut())?,
-                0x07 => constants::exec_iconst_4(frame, code, vm.as_deref_mut())?,
-                0x08 => constants::exec_iconst_5(frame, code, vm.as_deref_mut())?,
-                0x10 => constants::exec_bipush(frame, code, vm.as_deref_mut())?,
-                0x11 => ldc_ops::exec_sipush(frame, code, vm.as_deref_mut())?,
-                0x12 => ldc_ops::exec_ldc(frame, code, vm.as_deref_mut())?,
-                0x13 => ldc_ops::exec_ldc_w(frame, code, vm.as_deref_mut())?,
-                0x14 => ldc_ops::exec_ldc2_w(frame, code, vm.as_deref_mut())?,
-                0x15 => load_store::exec_iload(frame, code, vm.as_deref_mut())?,
-                0x1a => load_store::exec_iload_0(frame, code, vm.as_deref_mut())?,
-                0x1b => load_store::exec_iload_1(frame, code, vm.as_deref_mut())?,
-                0x1c => load_store::exec_iload_2(frame, code, vm.as_deref_mut())?,
-                0x1d => load_store::exec_iload_3(frame, code, vm.as_deref_mut())?,
-                0x2a => aload_0::exec_aload_0(frame, code, vm.as_deref_mut())?,
-                0x2b => load_store::exec_aload_1(frame, code, vm.as_deref_mut())?,
-                0x2c => load_store::exec_aload_2(frame, code, vm.as_deref_mut())?,
-                0x2d => load_store::exec_aload_3(frame, code, vm.as_deref_mut())?,
-                0x36 => load_store::exec_istore(frame, code, vm.as_deref_mut())?,
-                0x3b => load_store::exec_istore_0(frame, code, vm.as_deref_mut())?,
-                0x3c => load_store::exec_istore_1(frame, code, vm.as_deref_mut())?,
-                0x3d => load_store::exec_istore_2(frame, code, vm.as_deref_mut())?,
-                0x3e => load_store::exec_istore_3(frame, code, vm.as_deref_mut())?,
-                0x4b => load_store::exec_astore_0(frame, code, vm.as_deref_mut())?,
-                0x4c => load_store::exec_astore_1(frame, code, vm.as_deref_mut())?,
-                0x4d => load_store::exec_astore_2(frame, code, vm.as_deref_mut())?,
-                0x4e => load_store::exec_astore_3(frame, code, vm.as_deref_mut())?,
-                0x59 => stack::exec_dup(frame, code, vm.as_deref_mut())?,
-                0xb7 => invokespecial::exec_invokespecial(frame, code, vm.as_deref_mut())?,
-                0x60 => arithmetic::exec_iadd(frame, code, vm.as_deref_mut())?,
-                0x64 => arithmetic::exec_isub(frame, code, vm.as_deref_mut())?,
-                0x68 => arithmetic::exec_imul(frame, code, vm.as_deref_mut())?,
-                0x6c => arithmetic::exec_idiv(frame, code, vm.as_deref_mut())?,
-                0x84 => iinc::exec_iinc(frame, code, vm.as_deref_mut())?,
-                0x99 => control::exec_ifeq(frame, code, vm.as_deref_mut())?,
-                0x9a => control::exec_ifne(frame, code, vm.as_deref_mut())?,
-                0x9b => control::exec_ifge(frame, code, vm.as_deref_mut())?,
-                0x9e => control::exec_if_icmpeq(frame, code, vm.as_deref_mut())?,
-                0xa0 => control::exec_if_icmpne(frame, code, vm.as_deref_mut())?,
-                0xa3 => control::exec_goto(frame, code, vm.as_deref_mut())?,
-                0xaa => control::exec_tableswitch(frame, code, vm.as_deref_mut())?,
-                0xb1 => control::exec_return(frame, code, vm.as_deref_mut())?,
-                0xb2 => field_ops::exec_getstatic(frame, code, vm.as_deref_mut(), method)?,
-                0xb3 => field_ops::exec_putstatic(frame, code, vm.as_deref_mut(), method)?,
-                0xb6 => invokevirtual::exec_invokevirtual(frame, code, vm.as_deref_mut())?,
-                0xb5 => object_ops::exec_putfield(frame, code, vm.as_deref_mut())?,
-                0xbc => array_ops::exec_newarray(frame, code, vm.as_deref_mut())?,
-                0xbe => array_ops::exec_arraylength(frame, code, vm.as_deref_mut())?,
-                0x4f => array_ops::exec_iastore(frame, code, vm.as_deref_mut())?,
-                0x2e => array_ops::exec_iaload(frame, code, vm.as_deref_mut())?,
-                0xa2 => control_extended::exec_if_icmpge(frame, code, vm.as_deref_mut())?,
-                0xb0 => control_extended::exec_areturn(frame, code, vm.as_deref_mut())?,
-                0xbb => object_ops::exec_new(frame, code, vm.as_deref_mut())?,
-                0xb8 => invokestatic::exec_invokestatic(frame, code, vm.as_deref_mut())?,
-                // istore_0 ~ istore_3
-                0x3f => load_store::exec_istore_0(frame, code, vm.as_deref_mut())?,
-                0x40 => load_store::exec_istore_1(frame, code, vm.as_deref_mut())?,
-                0x41 => load_store::exec_istore_2(frame, code, vm.as_deref_mut())?,
-                0x42 => load_store::exec_istore_3(frame, code, vm.as_deref_mut())?,
-                0xb4 => object_ops::exec_getfield(frame, code, vm.as_deref_mut())?,
-                _ => return Err(JvmError::IllegalStateError(format!("Unknown opcode: 0x{:x}", opcode))),
+        result.map(|_| ())
+    }
+
+    /// 主解释循环：执行栈顶帧的指令。调用指令创建的帧压入栈顶，返回指令弹出帧并把返回值压入调用者的操作数栈，
+    /// 未捕获的异常逐帧展开。下标为base的入口帧返回时结束并得到它的返回值；
+    /// 入口帧执行到代码末尾（没有返回指令的代码片段）时也结束，入口帧留在栈上
+    pub fn run(&mut self, base: usize, vm: &mut Option<&mut crate::vm::Vm>) -> Result<Option<JvmValue>, JvmError> {
+        // 运行期间在VM中登记帧根，垃圾收集时扫描并更新其中的引用
+        if let Some(vm) = vm.as_deref_mut() {
+            vm.attach_thread(Rc::clone(&self.roots));
+        }
+        let result = self.interpret(base, vm);
+        if let Some(vm) = vm.as_deref_mut() {
+            vm.detach_thread();
+        }
+        result
+    }
+
+    fn interpret(&mut self, base: usize, vm: &mut Option<&mut crate::vm::Vm>) -> Result<Option<JvmValue>, JvmError> {
+        while self.frames.len() > base {
+            let is_entry = self.frames.len() == base + 1;
+            let frame = self.frames.last_mut().unwrap();
+            // 指令执行期间持有代码的引用，指令处理函数可以同时修改帧
+            let code = Rc::clone(&frame.method.code);
+            let Some(instruction) = code.get(frame.pc) else {
+                if is_entry {
+                    return Ok(None);
+                }
+                return Err(JvmError::IllegalStateError(format!(
+                    "方法 {}.{} 执行到代码末尾而没有返回", frame.method.class_name, frame.method.name
+                )));
+            };
+            let opcode_pc = code.byte_pc(frame.pc);
+            frame.pc += 1;
+            if let Some(vm) = vm.as_deref_mut() {
+                vm.set_current_pc(opcode_pc);
+            }
+
+            let result = match instruction {
+                Instruction::Ireturn
+                | Instruction::Lreturn
+                | Instruction::Freturn
+                | Instruction::Dreturn
+                | Instruction::Areturn
+                | Instruction::Return => match Self::pop_return_value(frame, instruction) {
+                    Ok(value) => {
+                        self.pop_frame();
+                        if let Some(vm) = vm.as_deref_mut() {
+                            vm.pop_call_frame();
+                        }
+                        match self.frames.last_mut() {
+                            Some(caller) if !is_entry => push_return_value(caller, value)?,
+                            _ => return Ok(value),
+                        }
+                        continue;
+                    }
+                    Err(err) => Err(err),
+                },
+                _ => dispatch::execute(instruction, opcode_pc, frame, vm.as_deref_mut()),
+            };
+
+            match (result, vm.as_deref_mut()) {
+                (Ok(()), Some(vm)) => {
+                    // 调用指令选中的Java方法在这里入栈，下一轮循环从它的第一条指令开始执行
+                    if let Some(callee) = vm.take_pending_frame() {
+                        self.push_frame(callee);
+                    }
+                }
+                (Ok(()), None) => {}
+                // 指令抛出的异常先交给当前方法的异常表，未被捕获时向调用者传播
+                (Err(err), Some(vm)) => {
+                    if let Err(err) = vm.handle_exception(self.frames.last_mut().unwrap(), opcode_pc, err) {
+                        self.unwind(base, vm, err)?;
+                    }
+                }
+                (Err(err), None) => return Err(err),
+            }
+        }
+        Ok(None)
+    }
+
+    /// 弹出未捕获异常的帧，在调用者正在执行的调用指令处查找异常处理器；
+    /// 入口帧也被弹出时把异常返回给run的调用者
+    fn unwind(&mut self, base: usize, vm: &mut crate::vm::Vm, mut err: JvmError) -> Result<(), JvmError> {
+        loop {
+            self.pop_frame();
+            vm.pop_call_frame();
+            if self.frames.len() <= base {
+                return Err(err);
             }
-            // 如果frame被弹空，直接return Ok(())，防止后续访问self.frames[0]越界
-            if self.frames.is_empty() {
-                return Ok(());
+            let invoke_pc = vm.current_pc();
+            match vm.handle_exception(self.frames.last_mut().unwrap(), invoke_pc, err) {
+                Ok(()) => return Ok(()),
+                Err(e) => err = e,
             }
         }
-        self.call_depth -= 1; // 递减调用深度
-        Ok(())
+    }
+
+    /// 返回指令：按指令的类型弹出返回值
+    fn pop_return_value(frame: &mut Frame, instruction: &Instruction) -> Result<Option<JvmValue>, JvmError> {
+        let value = match instruction {
+            Instruction::Ireturn => JvmValue::Int(frame.stack.pop_int()? as u32),
+            Instruction::Lreturn => JvmValue::Long(frame.stack.pop_long()? as u64),
+            Instruction::Freturn => JvmValue::Float(frame.stack.pop_float()?.to_bits() as u64),
+            Instruction::Dreturn => JvmValue::Double(frame.stack.pop_double()?.to_bits()),
+            Instruction::Areturn => JvmValue::ObjRef(frame.stack.pop_obj_ref()?),
+            _ => return Ok(None),
+        };
+        jvm_log!("[Return] {}.{} 返回 {:?}", frame.method.class_name, frame.method.name, value);
+        Ok(Some(value))
     }
 
     pub fn invoke(
@@ -154,47 +257,23 @@ impl JvmThread {
         receiver: Option<crate::heap::RawPtr>,
         method: crate::method::Method,
         class: crate::class::Klass,
-        args: Vec<crate::heap::RawPtr>,
+        args: Vec<JvmValue>,
         vm: &mut crate::vm::Vm,
-    ) {
+    ) -> Result<(), JvmError> {
         jvm_log!("[JVM] 开始执行方法: {}.{}", method.get_name(), method.get_descriptor());
-        
-        // 创建新的frame，使用更大的栈大小防止栈溢出
-        let mut new_frame = Frame {
-            pc: 0,
-            stack: OperandStack::new(65536), // 使用更大的栈大小
-            local_vars: LocalVars::new(method.max_locals),
-            method: method.clone(),
-        };
-        
-        // 初始化参数到局部变量表
-        // 对于实例方法，slot 0 是 this 引用
-        // 对于静态方法，从 slot 0 开始是参数
-        let mut param_index = 0;
-        
-        // 如果是实例方法，设置 this 引用
-        if let Some(this_ref) = receiver {
-            new_frame.local_vars.set_obj_ref(0, this_ref);
-            param_index = 1;
-        }
-        
-        // 设置方法参数
-        for (i, arg) in args.iter().enumerate() {
-            new_frame.local_vars.set_obj_ref(param_index + i, *arg);
-        }
-        
-        // 将新frame推入栈顶
-        self.frames.insert(0, new_frame);
-        
-        let mut heap = crate::heap::Heap::with_maximum_memory(1024);
-        match self.execute(&method, &mut heap, Some(vm)) {
-            Ok(_) => {
-                jvm_log!("[JVM] 方法执行完成");
-            }
-            Err(e) => {
-                jvm_log!("[JVM] 方法执行失败: {:?}", e);
-            }
+
+        // 实例方法的 slot 0 是 this 引用，其后是参数
+        let args: Vec<JvmValue> = receiver.map(JvmValue::ObjRef).into_iter().chain(args).collect();
+        let frame = vm.enter_method(&method, args)?;
+        self.push_frame(frame);
+        let base = self.frames.len() - 1;
+
+        let result = self.run(base, &mut Some(vm));
+        match &result {
+            Ok(_) => jvm_log!("[JVM] 方法执行完成"),
+            Err(e) => jvm_log!("[JVM] 方法执行失败: {}", e),
         }
+        result.map(|_| ())
     }
 }
 
@@ -202,6 +281,11 @@ impl JvmThread {
 mod tests {
     use super::*;
 
+    /// 测试方法使用的运行时常量池，不属于任何类
+    fn test_constant_pool(constant_pool: Vec<reader::constant_pool::CpInfo>) -> std::rc::Rc<crate::runtime_constant_pool::RunTimeConstantPool> {
+        std::rc::Rc::new(crate::runtime_constant_pool::RunTimeConstantPool::new(String::new(), constant_pool))
+    }
+
     fn create_test_method(code: Vec<u8>, max_stack: usize, max_locals: usize) -> Method {
         Method::new(
             "test".to_string(),
@@ -215,7 +299,6 @@ mod tests {
 
     #[test]
     fn test_iconst_instructions() {
-        let mut heap = Heap::with_maximum_memory(1024);
         let mut vm = crate::vm::Vm::new("resources/test");
         let mut thread = JvmThread::new(10, 10);
         
@@ -223,31 +306,29 @@ mod tests {
         let code = vec![0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
         let method = create_test_method(code, 10, 10);
         
-        thread.execute(&method, &mut heap, Some(&mut vm)).unwrap();
-        assert_eq!(thread.frames[0].stack.pop_int(), 5);
-        assert_eq!(thread.frames[0].stack.pop_int(), 4);
-        assert_eq!(thread.frames[0].stack.pop_int(), 3);
-        assert_eq!(thread.frames[0].stack.pop_int(), 2);
-        assert_eq!(thread.frames[0].stack.pop_int(), 1);
-        assert_eq!(thread.frames[0].stack.pop_int(), 0);
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 5);
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 4);
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 3);
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 2);
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 1);
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 0);
     }
 
     #[test]
     fn test_bipush() {
-        let mut heap = Heap::with_maximum_memory(1024);
         let mut vm = crate::vm::Vm::new("resources/test");
         let mut thread = JvmThread::new(10, 10);
         
         let code = vec![0x10, 42]; // bipush 42
         let method = create_test_method(code, 10, 10);
         
-        thread.execute(&method, &mut heap, Some(&mut vm)).unwrap();
-        assert_eq!(thread.frames[0].stack.pop_int(), 42);
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 42);
     }
 
     #[test]
     fn test_arithmetic_instructions() {
-        let mut heap = Heap::with_maximum_memory(1024);
         let mut vm = crate::vm::Vm::new("resources/test");
         let mut thread = JvmThread::new(10, 10);
         
@@ -263,13 +344,12 @@ mod tests {
         ];
         let method = create_test_method(code, 10, 10);
         
-        thread.execute(&method, &mut heap, Some(&mut vm)).unwrap();
-        assert_eq!(thread.frames[0].stack.pop_int(), 24); // ((10 + 5) - 3) * 2
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 24); // ((10 + 5) - 3) * 2
     }
 
     #[test]
     fn test_division_by_zero() {
-        let mut heap = Heap::with_maximum_memory(1024);
         let mut vm = crate::vm::Vm::new("resources/test");
         let mut thread = JvmThread::new(10, 10);
         
@@ -280,15 +360,251 @@ mod tests {
         ];
         let method = create_test_method(code, 10, 10);
         
-        match thread.execute(&method, &mut heap, Some(&mut vm)) {
+        match thread.execute(&method, Some(&mut vm)) {
+            Err(JvmError::ArithmeticError(_)) => (),
+            _ => panic!("Expected ArithmeticError"),
+        }
+    }
+
+    #[test]
+    fn test_int_arithmetic_wraps_on_overflow() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(10, 10);
+
+        let code = vec![
+            0x02,       // iconst_m1
+            0x04,       // iconst_1
+            0x7c,       // iushr           -> Integer.MAX_VALUE
+            0x04,       // iconst_1
+            0x60,       // iadd            -> Integer.MIN_VALUE（回绕）
+            0x59,       // dup
+            0x3b,       // istore_0
+            0x1a,       // iload_0
+            0x02,       // iconst_m1
+            0x68,       // imul            -> Integer.MIN_VALUE
+            0x1a,       // iload_0
+            0x02,       // iconst_m1
+            0x6c,       // idiv            -> Integer.MIN_VALUE
+            0x1a,       // iload_0
+            0x02,       // iconst_m1
+            0x70,       // irem            -> 0
+        ];
+        let method = create_test_method(code, 10, 10);
+
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 0);
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), i32::MIN);
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), i32::MIN);
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), i32::MIN);
+    }
+
+    #[test]
+    fn test_int_bitwise_and_shift_instructions() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(10, 10);
+
+        let code = vec![
+            0x10, 12,  // bipush 12
+            0x10, 10,  // bipush 10
+            0x7e,      // iand -> 8
+            0x10, 3,   // bipush 3
+            0x80,      // ior -> 11
+            0x10, 6,   // bipush 6
+            0x82,      // ixor -> 13
+            0x05,      // iconst_2
+            0x78,      // ishl -> 52
+            0x74,      // ineg -> -52
+            0x04,      // iconst_1
+            0x7a,      // ishr -> -26
+            0x10, 28,  // bipush 28
+            0x7c,      // iushr -> 15
+        ];
+        let method = create_test_method(code, 10, 10);
+
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 15);
+    }
+
+    #[test]
+    fn test_unknown_opcode_reports_method_and_pc() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(10, 10);
+
+        let code = vec![
+            0x04,      // iconst_1
+            0x00,      // nop
+            0xca,      // breakpoint，解释器不执行的保留指令
+        ];
+        let method = create_test_method(code, 10, 10);
+
+        match thread.execute(&method, Some(&mut vm)) {
+            Err(JvmError::IllegalStateError(msg)) => {
+                assert!(msg.contains("0xca"), "{}", msg);
+                assert!(msg.contains("test()V"), "{}", msg);
+                assert!(msg.contains("pc=2"), "{}", msg);
+            }
+            other => panic!("Expected IllegalStateError, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_long_arithmetic_instructions() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(20, 10);
+
+        let code = vec![
+            0x0a,       // lconst_1
+            0x75,       // lneg            -> -1
+            0x04,       // iconst_1
+            0x7d,       // lushr           -> Long.MAX_VALUE
+            0x0a,       // lconst_1
+            0x61,       // ladd            -> Long.MIN_VALUE（回绕）
+            0x40,       // lstore_1
+            0x1f,       // lload_1
+            0x0a,       // lconst_1
+            0x94,       // lcmp            -> -1
+            0x1f,       // lload_1
+        ];
+        let method = create_test_method(code, 20, 10);
+
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_long().unwrap(), i64::MIN);
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), -1);
+    }
+
+    #[test]
+    fn test_long_shift_and_bitwise() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(20, 10);
+
+        let code = vec![
+            0x0a,       // lconst_1
+            0x10, 65,   // bipush 65（移位量只取低6位，等价于1）
+            0x79,       // lshl            -> 2
+            0x0a,       // lconst_1
+            0x81,       // lor             -> 3
+            0x0a,       // lconst_1
+            0x83,       // lxor            -> 2
+            0x0a,       // lconst_1
+            0x75,       // lneg            -> -1
+            0x7f,       // land            -> 2
+            0x0a,       // lconst_1
+            0x75,       // lneg            -> -1
+            0x04,       // iconst_1
+            0x7b,       // lshr            -> -1
+            0x69,       // lmul            -> -2
+        ];
+        let method = create_test_method(code, 20, 10);
+
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_long().unwrap(), -2);
+    }
+
+    #[test]
+    fn test_long_division_by_zero() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(20, 10);
+
+        let code = vec![
+            0x0a,       // lconst_1
+            0x09,       // lconst_0
+            0x71,       // lrem
+        ];
+        let method = create_test_method(code, 20, 10);
+
+        match thread.execute(&method, Some(&mut vm)) {
             Err(JvmError::ArithmeticError(_)) => (),
             _ => panic!("Expected ArithmeticError"),
         }
     }
 
+    #[test]
+    fn test_float_double_arithmetic() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(20, 10);
+
+        let code = vec![
+            0x0d,       // fconst_2
+            0x76,       // fneg            -> -2.0
+            0x0c,       // fconst_1
+            0x66,       // fsub            -> -3.0
+            0x0d,       // fconst_2
+            0x72,       // frem            -> -1.0（符号随被除数）
+            0x44,       // fstore_1
+            0x0f,       // dconst_1
+            0x0e,       // dconst_0
+            0x6f,       // ddiv            -> +Infinity
+            0x0f,       // dconst_1
+            0x77,       // dneg
+            0x6b,       // dmul            -> -Infinity
+            0x49,       // dstore_2
+            0x23,       // fload_1
+            0x28,       // dload_2
+        ];
+        let method = create_test_method(code, 20, 10);
+
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_double().unwrap(), f64::NEG_INFINITY);
+        assert_eq!(thread.frames[0].stack.pop_float().unwrap(), -1.0);
+    }
+
+    #[test]
+    fn test_float_compare_nan_ordering() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(20, 10);
+
+        let code = vec![
+            0x0b, 0x0b, 0x6e, 0x43, // fconst_0 fconst_0 fdiv fstore_0 -> NaN
+            0x22, 0x0c, 0x95,       // fload_0 fconst_1 fcmpl -> -1
+            0x22, 0x0c, 0x96,       // fload_0 fconst_1 fcmpg -> 1
+            0x0e, 0x0e, 0x6f,       // dconst_0 dconst_0 ddiv -> NaN
+            0x0f, 0x97,             // dconst_1 dcmpl -> -1
+            0x0e, 0x0e, 0x6f,
+            0x0f, 0x98,             // dconst_1 dcmpg -> 1
+            0x0b, 0x76, 0x0b, 0x95, // -0.0 fcmpl 0.0 -> 0
+        ];
+        let method = create_test_method(code, 20, 10);
+
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        let stack = &mut thread.frames[0].stack;
+        assert_eq!(stack.pop_int().unwrap(), 0);
+        assert_eq!(stack.pop_int().unwrap(), 1);
+        assert_eq!(stack.pop_int().unwrap(), -1);
+        assert_eq!(stack.pop_int().unwrap(), 1);
+        assert_eq!(stack.pop_int().unwrap(), -1);
+    }
+
+    #[test]
+    fn test_conversion_instructions() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(20, 10);
+
+        let code = vec![
+            0x11, 0x00, 0xff, 0x91,       // sipush 255, i2b        -> -1
+            0x02, 0x92,                   // iconst_m1, i2c         -> 65535
+            0x11, 0x80, 0x00, 0x93,       // sipush -32768, i2s     -> -32768
+            0x0b, 0x0b, 0x6e, 0x8b,       // 0.0f/0.0f, f2i         -> 0（NaN）
+            0x0f, 0x0e, 0x6f, 0x8e,       // 1.0/0.0, d2i           -> Integer.MAX_VALUE
+            0x0f, 0x77, 0x0e, 0x6f, 0x8f, // -1.0/0.0, d2l          -> Long.MIN_VALUE
+            0x0a, 0x75, 0x88,             // -1L, l2i               -> -1
+            0x0d, 0x8d, 0x90, 0x8c,       // 2.0f, f2d, d2f, f2l    -> 2
+        ];
+        let method = create_test_method(code, 20, 10);
+
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        let stack = &mut thread.frames[0].stack;
+        assert_eq!(stack.pop_long().unwrap(), 2);
+        assert_eq!(stack.pop_int().unwrap(), -1);
+        assert_eq!(stack.pop_long().unwrap(), i64::MIN);
+        assert_eq!(stack.pop_int().unwrap(), i32::MAX);
+        assert_eq!(stack.pop_int().unwrap(), 0);
+        assert_eq!(stack.pop_int().unwrap(), -32768);
+        assert_eq!(stack.pop_int().unwrap(), 65535);
+        assert_eq!(stack.pop_int().unwrap(), -1);
+    }
+
     #[test]
     fn test_local_variables() {
-        let mut heap = Heap::with_maximum_memory(1024);
         let mut vm = crate::vm::Vm::new("resources/test");
         let mut thread = JvmThread::new(10, 10);
         
@@ -302,14 +618,13 @@ mod tests {
         ];
         let method = create_test_method(code, 10, 10);
         
-        thread.execute(&method, &mut heap, Some(&mut vm)).unwrap();
-        assert_eq!(thread.frames[0].local_vars.get_int(0), 42);
-        assert_eq!(thread.frames[0].local_vars.get_int(1), 43);
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].local_vars.get_int(0).unwrap(), 42);
+        assert_eq!(thread.frames[0].local_vars.get_int(1).unwrap(), 43);
     }
 
     #[test]
     fn test_conditional_jump() {
-        let mut heap = Heap::with_maximum_memory(1024);
         let mut vm = crate::vm::Vm::new("resources/test");
         let mut thread = JvmThread::new(10, 10);
         
@@ -321,15 +636,788 @@ mod tests {
         ];
         let method = create_test_method(code, 10, 10);
         
-        thread.execute(&method, &mut heap, Some(&mut vm)).unwrap();
-        assert_eq!(thread.frames[0].stack.pop_int(), 2);
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 2);
     }
 
     #[test]
-    fn test_static_field_storage() {
-        let mut heap = Heap::with_maximum_memory(1024);
+    fn test_branch_loop_and_null_checks() {
         let mut vm = crate::vm::Vm::new("resources/test");
         let mut thread = JvmThread::new(10, 10);
+
+        let code = vec![
+            0x03, 0x3b,                   // 0:  i = 0
+            0x1a, 0x06,                   // 2:  iload_0, iconst_3
+            0xa2, 0x00, 0x09,             // 4:  if_icmpge 13
+            0x84, 0x00, 0x01,             // 7:  iinc 0 1
+            0xa7, 0xff, 0xf8,             // 10: goto 2
+            0x1a,                         // 13: iload_0
+            0x01,                         // 14: aconst_null
+            0xc6, 0x00, 0x05,             // 15: ifnull 20
+            0x10, 99,                     // 18: bipush 99 (被跳过)
+            0xc8, 0x00, 0x00, 0x00, 0x05, // 20: goto_w 25
+            0x10, 7,                      // 25: bipush 7
+        ];
+        let method = create_test_method(code, 10, 10);
+
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 7);
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 3);
+        assert!(thread.frames[0].stack.is_empty());
+    }
+
+    #[test]
+    fn test_tableswitch_and_lookupswitch() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+
+        // tableswitch：偏移量相对于opcode地址，填充按方法起始位置对齐
+        let code = vec![
+            0x04,                   // 0:  iconst_1
+            0xaa, 0x00, 0x00,       // 1:  tableswitch + 2字节填充
+            0, 0, 0, 23,            // 4:  default -> 24
+            0, 0, 0, 0,             // 8:  low = 0
+            0, 0, 0, 1,             // 12: high = 1
+            0, 0, 0, 23,            // 16: case 0 -> 24
+            0, 0, 0, 25,            // 20: case 1 -> 26
+            0x10, 10,               // 24: bipush 10
+            0x10, 20,               // 26: bipush 20
+        ];
+        let mut thread = JvmThread::new(10, 10);
+        thread.execute(&create_test_method(code, 10, 10), Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 20);
+        assert!(thread.frames[0].stack.is_empty());
+
+        let lookup = |key: u8| {
+            vec![
+                0x10, key,              // 0:  bipush key
+                0xab, 0x00,             // 2:  lookupswitch + 1字节填充
+                0, 0, 0, 28,            // 4:  default -> 30
+                0, 0, 0, 2,             // 8:  npairs = 2
+                0, 0, 0, 1, 0, 0, 0, 28,   // 12: 1 -> 30
+                0, 0, 0, 5, 0, 0, 0, 30,   // 20: 5 -> 32
+                0x10, 0,                // 28: (不可达)
+                0x10, 10,               // 30: bipush 10
+                0x10, 50,               // 32: bipush 50
+            ]
+        };
+        let mut thread = JvmThread::new(10, 10);
+        thread.execute(&create_test_method(lookup(5), 10, 10), Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 50);
+        assert!(thread.frames[0].stack.is_empty());
+
+        let mut thread = JvmThread::new(10, 10);
+        thread.execute(&create_test_method(lookup(3), 10, 10), Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 50);
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 10);
+    }
+
+    #[test]
+    fn test_typed_array_load_store() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(20, 10);
+
+        let code = vec![
+            0x06, 0xbc, 5, 0x4b,                // char[] c = new char[3]
+            0x2a, 0x03, 0x02, 0x55,             // c[0] = (char) -1
+            0x2a, 0x03, 0x34,                   // c[0]                 -> 65535
+            0x05, 0xbc, 8, 0x4c,                // byte[] b = new byte[2]
+            0x2b, 0x04, 0x11, 0x00, 0xc8, 0x54, // b[1] = (byte) 200
+            0x2b, 0x04, 0x33,                   // b[1]                 -> -56
+            0x05, 0xbc, 11, 0x4d,               // long[] l = new long[2]
+            0x2c, 0x04, 0x0a, 0x75, 0x50,       // l[1] = -1L
+            0x2c, 0x04, 0x2f,                   // l[1]                 -> -1L
+            0x2a, 0xbe,                         // c.length             -> 3
+        ];
+        let method = create_test_method(code, 20, 10);
+
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        let stack = &mut thread.frames[0].stack;
+        assert_eq!(stack.pop_int().unwrap(), 3);
+        assert_eq!(stack.pop_long().unwrap(), -1);
+        assert_eq!(stack.pop_int().unwrap(), -56);
+        assert_eq!(stack.pop_int().unwrap(), 65535);
+    }
+
+    #[test]
+    fn test_array_index_out_of_bounds() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(20, 10);
+
+        let code = vec![
+            0x04, 0xbc, 10, 0x4b, // int[] a = new int[1]
+            0x2a, 0x04, 0x2e,     // a[1]
+        ];
+        let method = create_test_method(code, 20, 10);
+
+        match thread.execute(&method, Some(&mut vm)) {
+            Err(JvmError::ArrayIndexOutOfBoundsError(msg)) => {
+                assert_eq!(msg, "Index 1 out of bounds for length 1")
+            }
+            other => panic!("Expected ArrayIndexOutOfBoundsError, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_aastore_type_check() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+
+        let main_array_klass = vm.load("[LMain;").unwrap();
+        let main_array = vm.alloc_array(&main_array_klass, 1).unwrap();
+        let object_klass = vm.load("java/lang/Object").unwrap();
+        let object = vm.alloc_object(&object_klass).unwrap();
+        let main_klass = vm.load("Main").unwrap();
+        let main = vm.alloc_object(&main_klass).unwrap();
+
+        // Main[] 可以存放 Main
+        let mut thread = JvmThread::new(20, 10);
+        thread.frames[0].stack.push_obj_ref(main_array).unwrap();
+        thread.frames[0].stack.push_int(0).unwrap();
+        thread.frames[0].stack.push_obj_ref(main).unwrap();
+        let method = create_test_method(vec![0x53], 20, 10);
+        thread.execute(&method, Some(&mut vm)).unwrap();
+
+        // Main[] 不能存放 Object
+        let mut thread = JvmThread::new(20, 10);
+        thread.frames[0].stack.push_obj_ref(main_array).unwrap();
+        thread.frames[0].stack.push_int(0).unwrap();
+        thread.frames[0].stack.push_obj_ref(object).unwrap();
+        match thread.execute(&method, Some(&mut vm)) {
+            Err(JvmError::ArrayStoreError(msg)) => assert_eq!(msg, "java.lang.Object"),
+            other => panic!("Expected ArrayStoreError, got {:?}", other.err()),
+        }
+
+        // 下标越界先于类型检查
+        let mut thread = JvmThread::new(20, 10);
+        thread.frames[0].stack.push_obj_ref(main_array).unwrap();
+        thread.frames[0].stack.push_int(1).unwrap();
+        thread.frames[0].stack.push_obj_ref(object).unwrap();
+        match thread.execute(&method, Some(&mut vm)) {
+            Err(JvmError::ArrayIndexOutOfBoundsError(msg)) => assert_eq!(msg, "Index 1 out of bounds for length 1"),
+            other => panic!("Expected ArrayIndexOutOfBoundsError, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_multianewarray() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(20, 10);
+
+        let code = vec![
+            0x05, 0x06,             // iconst_2, iconst_3
+            0xc5, 0x00, 0x02, 0x02, // multianewarray #2 [[I, 2
+            0x4b,
+            0x2a, 0x04, 0x32,       // a[1]
+            0x05, 0x10, 42, 0x4f,   // a[1][2] = 42
+            0x2a, 0x04, 0x32, 0x05, 0x2e, // a[1][2]
+            0x2a, 0x03, 0x32, 0xbe, // a[0].length
+        ];
+        let mut method = create_test_method(code, 20, 10);
+        method.constant_pool = test_constant_pool(vec![
+            reader::constant_pool::CpInfo::Utf8 { tag: 1, length: 3, bytes: b"[[I".to_vec() },
+            reader::constant_pool::CpInfo::Class { tag: 7, name_index: 1 },
+        ]);
+
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 3);
+        assert_eq!(thread.frames[0].stack.pop_int().unwrap(), 42);
+    }
+
+    #[test]
+    fn test_stack_manipulation_instructions() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(20, 10);
+
+        // 模拟 arr[i] += 5 与 long 临时值的典型字节码序列
+        let code = vec![
+            0x04, 0xbc, 10, 0x4b,   // int[] a = new int[1]
+            0x2a, 0x03,             // a, 0
+            0x5c,                   // dup2               -> a, 0, a, 0
+            0x2e,                   // iaload             -> a, 0, 0
+            0x08, 0x60,             // iconst_5, iadd     -> a, 0, 5
+            0x4f,                   // iastore
+            0x2a, 0x03, 0x2e,       // a[0]               -> 5
+            0x0a,                   // lconst_1           -> 5, 1L
+            0x5d,                   // dup2_x1            -> 1L, 5, 1L
+            0x58,                   // pop2               -> 1L, 5
+            0x5b,                   // dup_x2             -> 5, 1L, 5
+            0x57,                   // pop                -> 5, 1L
+            0x88,                   // l2i                -> 5, 1
+            0x5f,                   // swap               -> 1, 5
+            0x2a,                   // aload_0            -> 1, 5, a
+            0x5a,                   // dup_x1             -> 1, a, 5, a
+            0x57,                   // pop                -> 1, a, 5
+            0x0a, 0x5e,             // lconst_1, dup2_x2  -> 1, 1L, a, 5, 1L
+            0x58,                   // pop2               -> 1, 1L, a, 5
+        ];
+        let method = create_test_method(code, 20, 10);
+
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        let stack = &mut thread.frames[0].stack;
+        assert_eq!(stack.pop_int().unwrap(), 5);
+        assert!(!stack.pop_obj_ref().unwrap().is_null());
+        assert_eq!(stack.pop_long().unwrap(), 1);
+        assert_eq!(stack.pop_int().unwrap(), 1);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn test_stack_underflow_and_overflow_are_errors() {
+        let mut thread = JvmThread::new(20, 10);
+
+        // 空栈上执行iadd
+        let method = create_test_method(vec![0x60], 2, 1);
+        let result = thread.execute(&method, None);
+        assert!(matches!(result, Err(JvmError::StackUnderflowError(_))));
+
+        // max_stack为1时连续压入两个常量
+        let mut thread = JvmThread::new(1, 1);
+        let method = create_test_method(vec![0x04, 0x05], 1, 1);
+        let result = thread.execute(&method, None);
+        assert!(matches!(result, Err(JvmError::IllegalStateError(_))));
+
+        // 局部变量越界和槽位类型不符同样是错误，不会终止进程
+        for code in [vec![0x15, 5], vec![0x04, 0x3e], vec![0x84, 3, 1], vec![0x04, 0x3b, 0x2a]] {
+            let mut thread = JvmThread::new(2, 2);
+            let method = create_test_method(code, 2, 2);
+            let result = thread.execute(&method, None);
+            assert!(matches!(result, Err(JvmError::IllegalStateError(_))), "{:?}", result);
+        }
+    }
+
+    #[test]
+    fn test_exception_table_catch_and_rethrow() {
+        use crate::method::ExceptionEntry;
+        use reader::constant_pool::CpInfo;
+
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut thread = JvmThread::new(10, 10);
+
+        let code = vec![
+            0x10, 10, 0x03, 0x6c,   // 0: 10 / 0
+            0x4b,                   // 4: catch (ArithmeticException e) -> local 0
+            0x2a, 0xbf,             // 5: throw e
+            0x4c,                   // 7: finally -> local 1
+            0x2b, 0x10, 42,         // 8: local 1, 42
+        ];
+        let mut method = create_test_method(code.clone(), 10, 10);
+        method.constant_pool = test_constant_pool(vec![
+            CpInfo::Utf8 { tag: 1, length: 29, bytes: b"java/lang/ArithmeticException".to_vec() },
+            CpInfo::Class { tag: 7, name_index: 1 },
+            CpInfo::Utf8 { tag: 1, length: 30, bytes: b"java/lang/NullPointerException".to_vec() },
+            CpInfo::Class { tag: 7, name_index: 3 },
+        ]);
+        method.exception_table = vec![
+            // catch_type不匹配的表项应被跳过
+            ExceptionEntry { start_pc: 0, end_pc: 4, handler_pc: 7, catch_type: 4 },
+            ExceptionEntry { start_pc: 0, end_pc: 4, handler_pc: 4, catch_type: 2 },
+            ExceptionEntry { start_pc: 5, end_pc: 7, handler_pc: 7, catch_type: 0 },
+        ];
+
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        let frame = &mut thread.frames[0];
+        assert_eq!(frame.stack.pop_int().unwrap(), 42);
+        let exception = frame.stack.pop_obj_ref().unwrap();
+        assert!(frame.stack.is_empty());
+        assert_eq!(exception, frame.local_vars.get_obj_ref(0).unwrap());
+        assert_eq!(vm.get_object_class_name(exception).as_deref(), Some("java/lang/ArithmeticException"));
+
+        // 只捕获NullPointerException时，ArithmeticException以Java异常的形式传播出去
+        let mut thread = JvmThread::new(10, 10);
+        method.exception_table = vec![
+            ExceptionEntry { start_pc: 0, end_pc: 4, handler_pc: 4, catch_type: 4 },
+        ];
+        match thread.execute(&method, Some(&mut vm)) {
+            Err(JvmError::JavaException(obj)) => {
+                assert_eq!(vm.get_object_class_name(obj.get()).as_deref(), Some("java/lang/ArithmeticException"));
+            }
+            other => panic!("Expected JavaException, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_instanceof_and_checkcast() {
+        use reader::constant_pool::CpInfo;
+
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let utf8 = |s: &str| CpInfo::Utf8 { tag: 1, length: s.len() as u16, bytes: s.as_bytes().to_vec() };
+        let constant_pool = vec![
+            utf8("java/lang/ArithmeticException"),
+            CpInfo::Class { tag: 7, name_index: 1 },
+            utf8("java/lang/RuntimeException"),
+            CpInfo::Class { tag: 7, name_index: 3 },
+            utf8("java/io/Serializable"),
+            CpInfo::Class { tag: 7, name_index: 5 },
+            utf8("java/lang/NullPointerException"),
+            CpInfo::Class { tag: 7, name_index: 7 },
+            utf8("[Ljava/lang/Throwable;"),
+            CpInfo::Class { tag: 7, name_index: 9 },
+            utf8("[Ljava/lang/Object;"),
+            CpInfo::Class { tag: 7, name_index: 11 },
+        ];
+
+        let code = vec![
+            0xbb, 0x00, 0x02, 0x4b,             // 0: local 0 = new ArithmeticException
+            0x2a, 0xc1, 0x00, 0x04,             // 4: 超类
+            0x2a, 0xc1, 0x00, 0x06,             // 8: Throwable实现的接口
+            0x2a, 0xc1, 0x00, 0x08,             // 12: 无关的类
+            0x04, 0xbd, 0x00, 0x02, 0x4c,       // 16: local 1 = new ArithmeticException[1]
+            0x2b, 0xc1, 0x00, 0x0a,             // 21: 数组按元素类型协变
+            0x2b, 0xc1, 0x00, 0x0c,             // 25
+            0x04, 0xbc, 0x0a, 0xc1, 0x00, 0x0c, // 29: int[] 不是 Object[]
+            0x2b, 0xc0, 0x00, 0x0a,             // 35: 成功的checkcast保留引用
+            0x01, 0xc1, 0x00, 0x04,             // 39: null instanceof 为0
+        ];
+        let mut method = create_test_method(code, 10, 10);
+        method.constant_pool = test_constant_pool(constant_pool.clone());
+        let mut thread = JvmThread::new(10, 10);
+        thread.execute(&method, Some(&mut vm)).unwrap();
+        let frame = &mut thread.frames[0];
+        assert_eq!(frame.stack.pop_int().unwrap(), 0);
+        assert_eq!(frame.stack.pop_obj_ref().unwrap(), frame.local_vars.get_obj_ref(1).unwrap());
+        for expected in [0, 1, 1, 0, 1, 1] {
+            assert_eq!(frame.stack.pop_int().unwrap(), expected);
+        }
+        assert!(frame.stack.is_empty());
+
+        // checkcast失败时抛出HotSpot格式消息的ClassCastException
+        let code = vec![0xbb, 0x00, 0x02, 0xc0, 0x00, 0x08];
+        let mut method = create_test_method(code, 10, 10);
+        method.constant_pool = test_constant_pool(constant_pool);
+        let mut thread = JvmThread::new(10, 10);
+        match thread.execute(&method, Some(&mut vm)) {
+            Err(JvmError::ClassCastError(message)) => assert_eq!(
+                message,
+                "java.lang.ArithmeticException cannot be cast to java.lang.NullPointerException"
+            ),
+            other => panic!("Expected ClassCastError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invokeinterface_dispatch() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut call = |name: &str| vm.dispatch_method_call("InterfaceDispatchTest", name, "()I", vec![]);
+
+        // 实现来自接收者类的继承链（Base未实现Shape）或最具体的default方法
+        assert_eq!(call("square").unwrap(), Some(JvmValue::Int(1041)));
+        assert_eq!(call("triangle").unwrap(), Some(JvmValue::Int(1030)));
+        assert_eq!(call("circle").unwrap(), Some(JvmValue::Int(302)));
+
+        match call("conflict") {
+            Err(JvmError::IncompatibleClassChangeError(message)) => assert_eq!(
+                message,
+                "Conflicting default methods: InterfaceDispatchTest$Left.pick InterfaceDispatchTest$Right.pick"
+            ),
+            other => panic!("Expected IncompatibleClassChangeError, got {:?}", other),
+        }
+        match call("missing") {
+            Err(JvmError::AbstractMethodError(message)) => assert_eq!(message, "StaleTask.cost()I"),
+            other => panic!("Expected AbstractMethodError, got {:?}", other),
+        }
+
+        // VM创建的字符串有对象头但String类还没有加载，按类名加载后在接口方法表中选择
+        let text = vm.create_string_object("hi").unwrap();
+        assert_eq!(
+            vm.dispatch_method_call("InterfaceDispatchTest", "sequence", "(Ljava/lang/CharSequence;)I", vec![JvmValue::ObjRef(text)]).unwrap(),
+            Some(JvmValue::Int(2104))
+        );
+
+        // 接口方法引用的类已经不是接口，解析时抛出IncompatibleClassChangeError，先于接收者的null检查
+        match vm.dispatch_method_call("StaleInterfaceCall", "call", "()I", vec![]) {
+            Err(JvmError::IncompatibleClassChangeError(message)) => {
+                assert_eq!(message, "Found class FormerInterface, but interface was expected")
+            }
+            other => panic!("Expected IncompatibleClassChangeError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invokevirtual_selects_override() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut call = |name: &str| vm.dispatch_method_call("VirtualDispatchTest", name, "()I", vec![]);
+
+        assert_eq!(call("bird").unwrap(), Some(JvmValue::Int(21)));
+        assert_eq!(call("dog").unwrap(), Some(JvmValue::Int(47)));
+        // Puppy.weight通过super调用Dog.weight
+        assert_eq!(call("puppy").unwrap(), Some(JvmValue::Int(42)));
+        // 类中没有声明时选择超接口的default方法
+        assert_eq!(call("cat").unwrap(), Some(JvmValue::Int(9)));
+        assert!(matches!(call("nullReceiver"), Err(JvmError::NullPointerError(_))));
+    }
+
+    #[test]
+    fn test_invokedynamic_lambdas() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut call = |name: &str| vm.dispatch_method_call("LambdaTest", name, "()I", vec![]);
+
+        assert_eq!(call("staticRef").unwrap(), Some(JvmValue::Int(13)));
+        assert_eq!(call("capturing").unwrap(), Some(JvmValue::Int(45)));
+        // 实现方法是私有实例方法（REF_invokeSpecial），捕获this
+        assert_eq!(call("capturingThis").unwrap(), Some(JvmValue::Int(113)));
+        assert_eq!(call("constructorRef").unwrap(), Some(JvmValue::Int(7)));
+        assert_eq!(call("boundMethodRef").unwrap(), Some(JvmValue::Int(8)));
+        // 擦除后的接口方法经拆箱调用 increment(I)I，返回值再装箱
+        assert_eq!(call("boxing").unwrap(), Some(JvmValue::Int(3)));
+        // altMetafactory 的标记接口
+        assert_eq!(call("markerInterface").unwrap(), Some(JvmValue::Int(999)));
+        assert_eq!(call("bridge").unwrap(), Some(JvmValue::Int(24)));
+        // 同一调用点只链接一次，每次执行创建新的实例
+        assert_eq!(call("sameCallSite").unwrap(), Some(JvmValue::Int(60)));
+    }
+
+    #[test]
+    fn test_runtime_constant_pool_resolution() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut call = |name: &str| vm.dispatch_method_call("ResolutionTest", name, "()I", vec![]);
+
+        // 同名字段按符号引用的类解析，通过子类和实现类引用的静态字段解析到声明它的类
+        assert_eq!(call("fields").unwrap(), Some(JvmValue::Int(72325)));
+        // 同一个字符串常量每次得到同一个对象
+        assert_eq!(call("sameLiteral").unwrap(), Some(JvmValue::Int(1)));
+        // 链接错误可以被Java代码捕获
+        assert_eq!(call("caught").unwrap(), Some(JvmValue::Int(111)));
+        // caught已经解析失败的项再次执行时抛出同样的错误
+        for _ in 0..2 {
+            match call("missingField") {
+                Err(JvmError::NoSuchFieldError(message)) => assert_eq!(message, "gone"),
+                other => panic!("Expected NoSuchFieldError, got {:?}", other),
+            }
+        }
+        match call("missingMethod") {
+            Err(JvmError::NoSuchMethodError(message)) => assert_eq!(message, "ResolutionTarget.gone()I"),
+            other => panic!("Expected NoSuchMethodError, got {:?}", other),
+        }
+        match call("privateField") {
+            Err(JvmError::IllegalAccessError(message)) => {
+                assert_eq!(message, "tried to access field ResolutionTarget.secret from class ResolutionTest")
+            }
+            other => panic!("Expected IllegalAccessError, got {:?}", other),
+        }
+        match call("privateMethod") {
+            Err(JvmError::IllegalAccessError(message)) => {
+                assert_eq!(message, "tried to access method ResolutionTarget.secret()I from class ResolutionTest")
+            }
+            other => panic!("Expected IllegalAccessError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_mixed_argument_calling_convention() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let mut call = |name: &str, descriptor: &str, args: Vec<JvmValue>| vm.dispatch_method_call("ArgumentsTest", name, descriptor, args);
+
+        // (JDLjava/lang/Object;[IF)J
+        assert_eq!(call("mixedStatic", "()J", vec![]).unwrap(), Some(JvmValue::Long(1099511627791)));
+        // this在槽位0，其后是(IJFDZCSB)
+        assert_eq!(call("mixedVirtual", "()J", vec![]).unwrap(), Some(JvmValue::Long(1410066265)));
+        assert_eq!(call("mixedDoubles", "()D", vec![]).unwrap(), Some(JvmValue::Double((-1.499f64).to_bits())));
+        // 从Rust调用时使用同样的约定
+        let args = vec![
+            JvmValue::Double((-0.5f64).to_bits()),
+            JvmValue::Float(0.25f32.to_bits() as u64),
+            JvmValue::Double(1e-3f64.to_bits()),
+        ];
+        assert_eq!(call("doubles", "(DFD)D", args).unwrap(), Some(JvmValue::Double((-1.499f64).to_bits())));
+        assert!(matches!(call("doubles", "(DFD)D", vec![JvmValue::Int(1)]), Err(JvmError::IllegalStateError(_))));
+    }
+
+    #[test]
+    fn test_main_receives_command_line_arguments() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let main_class = vm.load("MainArgsTest").unwrap();
+        let main_method = main_class.get_method("main", "([Ljava/lang/String;)V").unwrap().clone();
+        let strings = ["input.txt", "--verbose", "中文"].map(String::from);
+        let args = vec![JvmValue::ObjRef(vm.create_string_array(&strings).unwrap())];
+        JvmThread::new(10, 10).invoke(None, main_method, main_class, args, &mut vm).unwrap();
+
+        assert_eq!(vm.get_static_field("MainArgsTest", "count"), Some(JvmValue::Int(3)));
+        // String.length()读取的value字段是UTF-16编码的字符数组
+        assert_eq!(vm.get_static_field("MainArgsTest", "totalLength"), Some(JvmValue::Int(20)));
+        let string_field = |name: &str| match vm.get_static_field("MainArgsTest", name) {
+            Some(JvmValue::ObjRef(string)) => vm.get_string_content(string),
+            other => panic!("Expected String in {}, got {:?}", name, other),
+        };
+        assert_eq!(string_field("first").as_deref(), Some("input.txt"));
+        assert_eq!(string_field("last").as_deref(), Some("中文"));
+        // 参数数组是String[]，aastore拒绝存入其他类型的对象
+        assert_eq!(vm.get_static_field("MainArgsTest", "storeRejected"), Some(JvmValue::Int(1)));
+    }
+
+    #[test]
+    fn test_exit_status_and_shutdown_hooks() {
+        let run = |method: &str| {
+            let mut vm = crate::vm::Vm::new("resources/test");
+            let result = vm.dispatch_method_call("ExitTest", method, "()V", vec![]).map(|_| ());
+            let exit_request = match &result {
+                Err(JvmError::Exit(status)) | Err(JvmError::Halt(status)) => Some(*status),
+                _ => None,
+            };
+            let status = vm.shutdown(result);
+            let field = |name: &str| vm.get_static_field("ExitTest", name);
+            (exit_request, status, field("hookRuns"), field("finallyRan"), field("order"))
+        };
+
+        // System.exit不经过异常处理器和finally块，钩子按注册顺序运行，注销的钩子不运行
+        let (request, status, hook_runs, finally_ran, order) = run("exitWithHooks");
+        assert_eq!((request, status), (Some(3), 3));
+        assert_eq!(hook_runs, Some(JvmValue::Int(1)));
+        assert_eq!(order, Some(JvmValue::Int(12)));
+        assert_eq!(finally_ran, None);
+
+        // Runtime.halt不运行钩子
+        let (request, status, hook_runs, finally_ran, _) = run("halt");
+        assert_eq!((request, status), (Some(7), 7));
+        assert_eq!((hook_runs, finally_ran), (None, None));
+
+        // 钩子中调用halt时以halt的状态码立即终止
+        let (request, status, hook_runs, _, _) = run("haltInHook");
+        assert_eq!((request, status), (Some(4), 9));
+        assert_eq!(hook_runs, Some(JvmValue::Int(1)));
+
+        // 未捕获的异常以状态码1退出，同样运行钩子
+        let (request, status, hook_runs, _, _) = run("uncaught");
+        assert_eq!((request, status), (None, 1));
+        assert_eq!(hook_runs, Some(JvmValue::Int(1)));
+
+        // 正常返回时状态码为0，一个钩子抛出异常不影响其他钩子
+        let (request, status, hook_runs, _, _) = run("failingHook");
+        assert_eq!((request, status), (None, 0));
+        assert_eq!(hook_runs, Some(JvmValue::Int(1)));
+    }
+
+    #[test]
+    fn test_copying_gc_keeps_live_objects() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+
+        // 临时对象远超半区大小，分配失败时收集，局部变量、静态字段和数组中的对象存活
+        assert_eq!(vm.dispatch_method_call("GcTest", "garbageLoop", "()I", vec![]).unwrap(), Some(JvmValue::Int(74925000)));
+        assert!(vm.gc_count() > 0);
+        assert_eq!(vm.dispatch_method_call("GcTest", "staticRoots", "()I", vec![]).unwrap(), Some(JvmValue::Int(196949)));
+        assert_eq!(vm.dispatch_method_call("GcTest", "strings", "()I", vec![]).unwrap(), Some(JvmValue::Int(8194)));
+
+        // System.gc和Runtime.gc各触发一次收集
+        let count = vm.gc_count();
+        assert_eq!(vm.dispatch_method_call("GcTest", "explicitGc", "()I", vec![]).unwrap(), Some(JvmValue::Int(9)));
+        assert_eq!(vm.gc_count(), count + 2);
+
+        // 没有根引用的对象全部被回收
+        let stats = vm.collect_garbage();
+        assert!(stats.freed_bytes > 0);
+        assert_eq!(stats.live_bytes, vm.heap.borrow().used());
+    }
+
+    #[test]
+    fn test_heap_growth_and_out_of_memory() {
+        let mut vm = crate::vm::Vm::with_heap_size("resources/test", 256 * 1024, 4 * 1024 * 1024);
+        let initial_capacity = vm.heap.borrow().capacity();
+        assert!(initial_capacity <= 256 * 1024);
+
+        // 长期存活的对象晋升到老年代，老年代放不下时扩大，放不下的大数组使堆扩大到足够的大小
+        assert_eq!(vm.dispatch_method_call("OomTest", "retainMany", "()I", vec![]).unwrap(), Some(JvmValue::Int(90000)));
+        assert!(vm.heap.borrow().capacity() > initial_capacity);
+        assert!(vm.full_gc_count() > 0);
+        assert_eq!(vm.dispatch_method_call("OomTest", "largeArray", "()I", vec![]).unwrap(), Some(JvmValue::Int(300007)));
+
+        // 大数组用满最大堆后仍然放不下时抛出可以捕获的OutOfMemoryError，释放后大对象空间被回收
+        assert_eq!(vm.dispatch_method_call("OomTest", "exhaust", "()I", vec![]).unwrap(), Some(JvmValue::Int(1)));
+        assert!(vm.dispatch_method_call("OomTest", "exhaust", "()I", vec![]).is_ok());
+        vm.collect_garbage();
+        assert_eq!(vm.heap.borrow().large_used(), 0);
+        assert!(vm.heap.borrow().capacity() < vm.heap.borrow().max_capacity());
+
+        // 超过最大堆的数组不会被分配
+        let klass = vm.load("[I").unwrap();
+        assert!(matches!(vm.alloc_array(&klass, 1 << 20).map_err(JvmError::from), Err(JvmError::OutOfMemoryError(msg)) if msg == "Java heap space"));
+    }
+
+    #[test]
+    fn test_arrays_larger_than_8mb_keep_their_size() {
+        let mut vm = crate::vm::Vm::with_heap_size("resources/test", 1024 * 1024, 32 * 1024 * 1024);
+        let klass = vm.load("[B").unwrap();
+        let array = vm.alloc_array(&klass, 9 << 20).unwrap();
+        assert_eq!(vm.heap.borrow().array_length(array), 9 << 20);
+        assert_eq!(vm.get_object_class_name(array).as_deref(), Some("[B"));
+
+        // 大数组在大对象空间中分配，不可达后由完整收集释放
+        assert_eq!(vm.heap.borrow().large_used(), crate::heap::ARRAY_BASE_OFFSET + (9 << 20));
+        let stats = vm.collect_garbage();
+        assert!(stats.freed_bytes >= 9 << 20);
+        assert_eq!(vm.heap.borrow().large_used(), 0);
+    }
+
+    #[test]
+    fn test_generational_gc_tracks_old_to_young_references() {
+        let mut vm = crate::vm::Vm::with_heap_size("resources/test", 64 * 1024, 4 * 1024 * 1024);
+        // 晋升到老年代的数组、对象和静态字段引用的新生代对象在新生代收集中存活
+        assert_eq!(vm.dispatch_method_call("GenTest", "oldToYoung", "()I", vec![]).unwrap(), Some(JvmValue::Int(104468)));
+        assert!(vm.gc_count() > vm.full_gc_count());
+        assert!(vm.heap.borrow().old_used() > 0);
+
+        let stats = vm.collect_young();
+        assert_eq!(stats.promoted_bytes + vm.heap.borrow().used() - vm.heap.borrow().old_used(), stats.live_bytes);
+    }
+
+    #[test]
+    fn test_invocation_propagates_missing_class() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        // 参数已经弹出后解析失败，调用不能被当作成功继续执行
+        match vm.dispatch_method_call("MissingClassTest", "callMissing", "()I", vec![]) {
+            Err(JvmError::ClassNotFoundError(message)) => assert!(message.contains("MissingHelper")),
+            other => panic!("Expected ClassNotFoundError, got {:?}", other),
+        }
+        // invokevirtual的解析错误不能退回到按类名查找native实现，也先于接收者的null检查
+        match vm.dispatch_method_call("MissingClassTest", "callMissingVirtual", "()I", vec![]) {
+            Err(JvmError::ClassNotFoundError(message)) => assert!(message.contains("MissingHelper")),
+            other => panic!("Expected ClassNotFoundError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_float_field_does_not_overwrite_neighbours() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        // float字段只占4字节，写入后前面的int字段和后面的引用字段保持不变
+        assert_eq!(vm.dispatch_method_call("FieldLayoutTest", "floatBetweenIntAndRef", "()I", vec![]).unwrap(), Some(JvmValue::Int(14)));
+    }
+
+    #[test]
+    fn test_root_enumeration_updates_reference_slots() {
+        let mut thread = JvmThread::new(4, 3);
+        let (a, b, moved) = (RawPtr(0x1000 as *mut u8), RawPtr(0x2000 as *mut u8), RawPtr(0x3000 as *mut u8));
+        let frame = &mut thread.frames[0];
+        frame.local_vars.set_int(0, 42).unwrap();
+        frame.local_vars.set_obj_ref(1, a).unwrap();
+        frame.stack.push_long(7).unwrap();
+        frame.stack.push_obj_ref(b).unwrap();
+        frame.stack.push_int(5).unwrap();
+
+        // 只有带引用标记的槽被访问，基本类型的值不会被当作引用，未赋值的局部变量是null引用
+        let mut roots = Vec::new();
+        thread.visit_roots(&mut |kind, obj| {
+            roots.push((kind, *obj));
+            if *obj == b {
+                *obj = moved;
+            }
+        });
+        assert_eq!(roots, vec![
+            (RootKind::Local, a),
+            (RootKind::Local, RawPtr(std::ptr::null_mut())),
+            (RootKind::Operand, b),
+        ]);
+
+        // 访问者改写的引用写回槽中
+        let frame = &mut thread.frames[0];
+        assert_eq!(frame.stack.pop_int().unwrap(), 5);
+        assert_eq!(frame.stack.pop_obj_ref().unwrap(), moved);
+        assert_eq!(frame.stack.pop_long().unwrap(), 7);
+        assert_eq!(frame.local_vars.get_int(0).unwrap(), 42);
+    }
+
+    #[test]
+    fn test_vm_roots_include_statics_handles_and_constants() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        vm.dispatch_method_call("GcTest", "staticRoots", "()I", vec![]).unwrap();
+        vm.dispatch_method_call("GcTest", "strings", "()I", vec![]).unwrap();
+        let Some(JvmValue::ObjRef(retained)) = vm.get_static_field("GcTest", "retained") else {
+            panic!("GcTest.retained未设置");
+        };
+        let handle = vm.push_handle(retained);
+
+        let mut roots = Vec::new();
+        vm.visit_roots(&mut |kind, obj| roots.push((kind, *obj)));
+        assert!(roots.contains(&(RootKind::StaticField, retained)));
+        assert!(roots.contains(&(RootKind::Handle, retained)));
+        assert!(roots.iter().any(|(kind, _)| *kind == RootKind::StringConstant));
+        // 没有正在运行的解释循环时没有帧中的根
+        assert!(!roots.iter().any(|(kind, _)| matches!(kind, RootKind::Local | RootKind::Operand)));
+
+        // 更新根之后句柄指向新的地址
+        vm.visit_roots(&mut |kind, obj| {
+            if kind == RootKind::Handle {
+                *obj = RawPtr(std::ptr::null_mut());
+            }
+        });
+        assert!(vm.handle(handle).is_null());
+        vm.pop_handles(handle);
+    }
+
+    #[test]
+    fn test_interpreter_loop_without_recursion_limits() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+
+        assert_eq!(vm.dispatch_method_call("RecursionTest", "fib20", "()I", vec![]).unwrap(), Some(JvmValue::Int(6765)));
+        assert_eq!(vm.dispatch_method_call("RecursionTest", "deepRecursion", "()I", vec![]).unwrap(), Some(JvmValue::Int(4501500)));
+        assert_eq!(vm.dispatch_method_call("RecursionTest", "longLoop", "()I", vec![]).unwrap(), Some(JvmValue::Int(599994)));
+
+        // 超过线程栈深度时抛出可以被Java代码捕获的StackOverflowError
+        vm.set_max_stack_depth(200);
+        assert_eq!(vm.dispatch_method_call("RecursionTest", "overflow", "()I", vec![]).unwrap(), Some(JvmValue::Int(1)));
+        assert!(matches!(
+            vm.dispatch_method_call("RecursionTest", "deepRecursion", "()I", vec![]),
+            Err(JvmError::StackOverflowError(_))
+        ));
+    }
+
+    #[test]
+    fn test_uncaught_exception_stack_trace() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+
+        let exception = match vm.dispatch_method_call("StackTraceTest", "entry", "()V", vec![]) {
+            Err(JvmError::JavaException(obj)) => obj.get(),
+            other => panic!("Expected JavaException, got {:?}", other),
+        };
+        // 与HotSpot对同一个类的输出一致（去掉HotSpot中驱动类的main帧）
+        assert_eq!(
+            vm.format_stack_trace(exception),
+            "java.lang.RuntimeException: wrapped\n\
+             \tat StackTraceTest.wrap(StackTraceTest.java:17)\n\
+             \tat StackTraceTest.entry(StackTraceTest.java:22)\n\
+             Caused by: java.lang.ArithmeticException: / by zero\n\
+             \tat StackTraceTest.divide(StackTraceTest.java:6)\n\
+             \tat StackTraceTest.compute(StackTraceTest.java:10)\n\
+             \tat StackTraceTest.wrap(StackTraceTest.java:15)\n\
+             \t... 1 more\n"
+        );
+        assert_eq!(vm.get_stack_trace(exception).len(), 2);
+    }
+
+    #[test]
+    fn test_thrown_exception_survives_collection() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+
+        let err = vm.dispatch_method_call("StackTraceTest", "entry", "()V", vec![]).unwrap_err();
+        let JvmError::JavaException(thrown) = &err else {
+            panic!("Expected JavaException, got {:?}", err);
+        };
+        // 展开中的异常是根：收集之后对象仍然存活，句柄指向移动后的地址
+        let before = thrown.get();
+        vm.collect_young();
+        vm.collect_garbage();
+        assert_ne!(thrown.get(), before);
+        assert!(vm.format_stack_trace(thrown.get()).starts_with("java.lang.RuntimeException: wrapped\n"));
+    }
+
+    #[test]
+    fn test_getstatic_pushes_byte_and_short_as_int() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        vm.load("StaticFieldTest").unwrap();
+        vm.set_static_field("StaticFieldTest", "small", JvmValue::Byte(0xfe));
+        vm.set_static_field("StaticFieldTest", "medium", JvmValue::Short(0xfff6));
+        assert_eq!(vm.dispatch_method_call("StaticFieldTest", "read", "()I", vec![]).unwrap(), Some(JvmValue::Int(-200010i32 as u32)));
+    }
+
+    #[test]
+    fn test_class_initialized_only_on_first_active_use() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        // instanceof、实例方法调用和实例字段访问只解析引用，不执行<clinit>
+        assert_eq!(vm.dispatch_method_call("InitOrderTest", "referenced", "()I", vec![]).unwrap(), Some(JvmValue::Int(0)));
+        // new和invokestatic初始化类
+        assert_eq!(vm.dispatch_method_call("InitOrderTest", "used", "()I", vec![]).unwrap(), Some(JvmValue::Int(24)));
+    }
+
+    #[test]
+    fn test_static_field_storage() {
+        let mut vm = crate::vm::Vm::new("resources/test");
+        let thread = JvmThread::new(10, 10);
         
         // 测试静态字段存储
         // 这里我们模拟一个简单的静态字段设置
@@ -339,7 +1427,7 @@ mod tests {
         let field_value = vm.get_static_field("TestClass", "staticField");
         assert!(field_value.is_some());
         if let Some(JvmValue::Int(value)) = field_value {
-            assert_eq!(*value, 42);
+            assert_eq!(value, 42);
         } else {
             panic!("Expected Int value");
         }
@@ -388,9 +1476,8 @@ mod tests {
 
     #[test]
     fn test_dynamic_method_dispatch() {
-        let mut heap = Heap::with_maximum_memory(1024);
-        let mut vm = crate::vm::Vm::new("resources/test");
-        let mut thread = JvmThread::new(10, 10);
+        let vm = crate::vm::Vm::new("resources/test");
+        let thread = JvmThread::new(10, 10);
         
         // 测试动态方法分发的基本功能
         // 这里我们测试native方法调用
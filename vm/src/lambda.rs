@@ -0,0 +1,546 @@
+use std::collections::HashMap;
+
+use reader::attribute_info::AttributeInfo;
+use reader::class_file::ClassFile;
+use reader::constant_pool::{ConstantPool, ConstantPoolExt, CpInfo};
+use reader::field_info::FieldInfo;
+use reader::method_info::MethodInfo;
+use reader::types::U2;
+
+use crate::class::BootstrapMethod;
+use crate::error::JvmError;
+use crate::instructions::method_utils::parse_method_descriptor;
+
+// LambdaMetafactory 的内建实现。
+// 与JDK的InnerClassLambdaMetafactory一样，为每个lambda调用点生成一个实现函数式接口的类，
+// 但不执行引导方法本身：生成的类把捕获的参数保存在 arg$1、arg$2… 字段中，
+// 接口方法把捕获的参数和自身的参数转换为实现方法的参数类型后调用实现方法。
+
+const LAMBDA_METAFACTORY: &str = "java/lang/invoke/LambdaMetafactory";
+
+// altMetafactory 的 flags
+const FLAG_SERIALIZABLE: u32 = 1;
+const FLAG_MARKERS: u32 = 2;
+const FLAG_BRIDGES: u32 = 4;
+
+// MethodHandle 的引用类型（JVMS 4.4.8）
+const REF_INVOKE_VIRTUAL: u8 = 5;
+const REF_INVOKE_STATIC: u8 = 6;
+const REF_INVOKE_SPECIAL: u8 = 7;
+const REF_NEW_INVOKE_SPECIAL: u8 = 8;
+const REF_INVOKE_INTERFACE: u8 = 9;
+
+/// MethodHandle常量引用的方法
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodHandleInfo {
+    pub kind: u8,
+    pub class_name: String,
+    pub name: String,
+    pub descriptor: String,
+    /// 引用的是InterfaceMethodref
+    pub is_interface: bool,
+}
+
+/// lambda调用点的描述，来自调用点的名字、描述符和LambdaMetafactory的静态参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct LambdaSpec {
+    /// 要实现的函数式接口
+    pub interface_name: String,
+    /// 接口方法名
+    pub method_name: String,
+    /// 调用点捕获的参数类型
+    pub captured_types: Vec<String>,
+    /// 接口方法擦除后的描述符
+    pub sam_descriptor: String,
+    pub implementation: MethodHandleInfo,
+    /// 接口方法在调用点实例化后的描述符，用于参数的类型转换
+    pub instantiated_descriptor: String,
+    /// 额外实现的标记接口
+    pub marker_interfaces: Vec<String>,
+    /// 需要额外生成的桥接方法描述符
+    pub bridges: Vec<String>,
+}
+
+/// 读取InvokeDynamic常量：(引导方法下标, 调用点名字, 调用点描述符)
+pub fn call_site_info(cp: &Vec<CpInfo>, index: U2) -> Result<(U2, String, String), JvmError> {
+    match cp.get(index as usize - 1) {
+        Some(CpInfo::InvokeDynamic { bootstrap_method_attr_index, name_and_type_index, .. }) => {
+            let (name, descriptor) = name_and_type(cp, *name_and_type_index)?;
+            Ok((*bootstrap_method_attr_index, name, descriptor))
+        }
+        other => Err(JvmError::IllegalStateError(format!("常量池#{}不是InvokeDynamic: {:?}", index, other))),
+    }
+}
+
+fn name_and_type(cp: &Vec<CpInfo>, index: U2) -> Result<(String, String), JvmError> {
+    match cp.get(index as usize - 1) {
+        Some(CpInfo::NameAndType { name_index, descriptor_index, .. }) => {
+            Ok((cp.get_utf8_string(*name_index), cp.get_utf8_string(*descriptor_index)))
+        }
+        other => Err(JvmError::IllegalStateError(format!("常量池#{}不是NameAndType: {:?}", index, other))),
+    }
+}
+
+/// 解析MethodHandle常量，只支持引用方法的几种类型
+pub fn method_handle_info(cp: &Vec<CpInfo>, index: U2) -> Result<MethodHandleInfo, JvmError> {
+    let (kind, reference_index) = match cp.get(index as usize - 1) {
+        Some(CpInfo::MethodHandle { reference_kind, reference_index, .. }) => (*reference_kind, *reference_index),
+        other => return Err(JvmError::IllegalStateError(format!("常量池#{}不是MethodHandle: {:?}", index, other))),
+    };
+    if !(REF_INVOKE_VIRTUAL..=REF_INVOKE_INTERFACE).contains(&kind) {
+        return Err(JvmError::Unimplemented(format!("不支持引用字段的MethodHandle（reference_kind={}）", kind)));
+    }
+    let is_interface = matches!(cp.get(reference_index as usize - 1), Some(CpInfo::InterfaceMethodRef { .. }));
+    let (class_name, name, descriptor) = cp.get_methodref_info(reference_index);
+    Ok(MethodHandleInfo { kind, class_name, name, descriptor, is_interface })
+}
+
+fn method_type(cp: &Vec<CpInfo>, index: U2) -> Result<String, JvmError> {
+    match cp.get(index as usize - 1) {
+        Some(CpInfo::MethodType { descriptor_index, .. }) => Ok(cp.get_utf8_string(*descriptor_index)),
+        other => Err(JvmError::IllegalStateError(format!("常量池#{}不是MethodType: {:?}", index, other))),
+    }
+}
+
+fn int_constant(cp: &[CpInfo], index: Option<&U2>) -> Result<u32, JvmError> {
+    match index.and_then(|index| cp.get(*index as usize - 1)) {
+        Some(CpInfo::Integer { bytes, .. }) => Ok(*bytes),
+        other => Err(JvmError::IllegalStateError(format!("altMetafactory的参数不是Integer: {:?}", other))),
+    }
+}
+
+/// 方法描述符的返回类型
+fn return_type(descriptor: &str) -> &str {
+    descriptor.rsplit(')').next().unwrap_or("V")
+}
+
+impl LambdaSpec {
+    /// 解析引导方法为 LambdaMetafactory.metafactory 或 altMetafactory 的调用点
+    pub fn resolve(cp: &Vec<CpInfo>, bootstrap: &BootstrapMethod, name: &str, descriptor: &str) -> Result<LambdaSpec, JvmError> {
+        let bootstrap_method = method_handle_info(cp, bootstrap.method_ref)?;
+        let is_alt = match (bootstrap_method.class_name.as_str(), bootstrap_method.name.as_str()) {
+            (LAMBDA_METAFACTORY, "metafactory") => false,
+            (LAMBDA_METAFACTORY, "altMetafactory") => true,
+            (class_name, method_name) => {
+                return Err(JvmError::Unimplemented(format!("invokedynamic: 不支持的引导方法 {}.{}", class_name, method_name)));
+            }
+        };
+        let args = &bootstrap.arguments;
+        if args.len() < 3 {
+            return Err(JvmError::IllegalStateError(format!("LambdaMetafactory需要3个静态参数，实际为{}", args.len())));
+        }
+        let interface_name = return_type(descriptor);
+        let mut spec = LambdaSpec {
+            interface_name: interface_name[1..interface_name.len() - 1].to_string(),
+            method_name: name.to_string(),
+            captured_types: parse_method_descriptor(descriptor),
+            sam_descriptor: method_type(cp, args[0])?,
+            implementation: method_handle_info(cp, args[1])?,
+            instantiated_descriptor: method_type(cp, args[2])?,
+            marker_interfaces: Vec::new(),
+            bridges: Vec::new(),
+        };
+
+        if is_alt {
+            let mut rest = args[3..].iter();
+            let flags = int_constant(cp, rest.next())?;
+            if flags & FLAG_MARKERS != 0 {
+                for _ in 0..int_constant(cp, rest.next())? {
+                    let marker = rest.next().ok_or_else(|| JvmError::IllegalStateError("altMetafactory缺少标记接口".to_string()))?;
+                    spec.marker_interfaces.push(cp.get_class_name(*marker));
+                }
+            }
+            if flags & FLAG_BRIDGES != 0 {
+                for _ in 0..int_constant(cp, rest.next())? {
+                    let bridge = rest.next().ok_or_else(|| JvmError::IllegalStateError("altMetafactory缺少桥接方法".to_string()))?;
+                    spec.bridges.push(method_type(cp, *bridge)?);
+                }
+            }
+            if flags & FLAG_SERIALIZABLE != 0 && !spec.marker_interfaces.iter().any(|name| name == "java/io/Serializable") {
+                spec.marker_interfaces.push("java/io/Serializable".to_string());
+            }
+        }
+        Ok(spec)
+    }
+}
+
+/// 生成类文件时使用的常量池，相同的常量只添加一次
+#[derive(Default)]
+struct ConstantPoolBuilder {
+    entries: Vec<CpInfo>,
+    lookup: HashMap<String, U2>,
+}
+
+impl ConstantPoolBuilder {
+    fn intern(&mut self, key: String, entry: CpInfo) -> U2 {
+        if let Some(index) = self.lookup.get(&key) {
+            return *index;
+        }
+        self.entries.push(entry);
+        let index = self.entries.len() as U2;
+        self.lookup.insert(key, index);
+        index
+    }
+
+    fn utf8(&mut self, value: &str) -> U2 {
+        let entry = CpInfo::Utf8 { tag: 1, length: value.len() as U2, bytes: value.as_bytes().to_vec() };
+        self.intern(format!("Utf8 {}", value), entry)
+    }
+
+    fn class(&mut self, name: &str) -> U2 {
+        let name_index = self.utf8(name);
+        self.intern(format!("Class {}", name), CpInfo::Class { tag: 7, name_index })
+    }
+
+    fn name_and_type(&mut self, name: &str, descriptor: &str) -> U2 {
+        let name_index = self.utf8(name);
+        let descriptor_index = self.utf8(descriptor);
+        self.intern(format!("NameAndType {}:{}", name, descriptor), CpInfo::NameAndType { tag: 12, name_index, descriptor_index })
+    }
+
+    fn field_ref(&mut self, class_name: &str, name: &str, descriptor: &str) -> U2 {
+        let class_index = self.class(class_name);
+        let name_and_type_index = self.name_and_type(name, descriptor);
+        let entry = CpInfo::FieldRef { tag: 9, class_index, name_and_type_index };
+        self.intern(format!("FieldRef {}.{}:{}", class_name, name, descriptor), entry)
+    }
+
+    fn method_ref(&mut self, class_name: &str, name: &str, descriptor: &str, is_interface: bool) -> U2 {
+        let class_index = self.class(class_name);
+        let name_and_type_index = self.name_and_type(name, descriptor);
+        let entry = if is_interface {
+            CpInfo::InterfaceMethodRef { tag: 11, class_index, name_and_type_index }
+        } else {
+            CpInfo::MethodRef { tag: 10, class_index, name_and_type_index }
+        };
+        self.intern(format!("MethodRef {} {}.{}:{}", is_interface, class_name, name, descriptor), entry)
+    }
+}
+
+fn is_primitive(descriptor: &str) -> bool {
+    descriptor.len() == 1
+}
+
+fn slot_size(descriptor: &str) -> u16 {
+    if descriptor == "J" || descriptor == "D" { 2 } else { 1 }
+}
+
+/// 描述符对应的类名，数组保持描述符形式
+fn class_name_of(descriptor: &str) -> &str {
+    descriptor.strip_prefix('L').and_then(|name| name.strip_suffix(';')).unwrap_or(descriptor)
+}
+
+/// 基本类型的包装类及拆箱方法名
+fn wrapper_of(primitive: &str) -> (&'static str, &'static str) {
+    match primitive {
+        "Z" => ("java/lang/Boolean", "booleanValue"),
+        "B" => ("java/lang/Byte", "byteValue"),
+        "C" => ("java/lang/Character", "charValue"),
+        "S" => ("java/lang/Short", "shortValue"),
+        "J" => ("java/lang/Long", "longValue"),
+        "F" => ("java/lang/Float", "floatValue"),
+        "D" => ("java/lang/Double", "doubleValue"),
+        _ => ("java/lang/Integer", "intValue"),
+    }
+}
+
+/// 包装类描述符对应的基本类型
+fn unwrapped(descriptor: &str) -> Option<&'static str> {
+    ["Z", "B", "C", "S", "I", "J", "F", "D"]
+        .into_iter()
+        .find(|primitive| class_name_of(descriptor) == wrapper_of(primitive).0)
+}
+
+/// 生成接口方法体的字节码
+struct CodeBuilder<'a> {
+    cp: &'a mut ConstantPoolBuilder,
+    code: Vec<u8>,
+}
+
+impl CodeBuilder<'_> {
+    fn op_u2(&mut self, opcode: u8, operand: U2) {
+        self.code.push(opcode);
+        self.code.extend_from_slice(&operand.to_be_bytes());
+    }
+
+    /// 按类型加载局部变量，下标不超过3时使用短格式指令
+    fn load(&mut self, descriptor: &str, slot: u16) -> Result<(), JvmError> {
+        let (short_form, general) = match descriptor {
+            "J" => (0x1e, 0x16),
+            "F" => (0x22, 0x17),
+            "D" => (0x26, 0x18),
+            d if is_primitive(d) => (0x1a, 0x15),
+            _ => (0x2a, 0x19),
+        };
+        match slot {
+            0..=3 => self.code.push(short_form + slot as u8),
+            4..=255 => self.code.extend_from_slice(&[general, slot as u8]),
+            _ => return Err(JvmError::Unimplemented(format!("lambda参数过多，局部变量下标{}超过255", slot))),
+        }
+        Ok(())
+    }
+
+    fn return_value(&mut self, descriptor: &str) {
+        self.code.push(match descriptor {
+            "V" => 0xb1,
+            "J" => 0xad,
+            "F" => 0xae,
+            "D" => 0xaf,
+            d if is_primitive(d) => 0xac,
+            _ => 0xb0,
+        });
+    }
+
+    /// 把栈顶from类型的值转换为to类型：引用类型强制转换、装箱、拆箱及基本类型的拓宽转换
+    fn convert(&mut self, from: &str, to: &str) -> Result<(), JvmError> {
+        if from == to {
+            return Ok(());
+        }
+        match (is_primitive(from), is_primitive(to)) {
+            (false, false) => {
+                if to != "Ljava/lang/Object;" {
+                    let class_index = self.cp.class(class_name_of(to));
+                    self.op_u2(0xc0, class_index);
+                }
+            }
+            (false, true) => {
+                let primitive = match unwrapped(from) {
+                    Some(primitive) => primitive,
+                    None => {
+                        let class_index = self.cp.class(wrapper_of(to).0);
+                        self.op_u2(0xc0, class_index);
+                        to
+                    }
+                };
+                let (wrapper, unbox) = wrapper_of(primitive);
+                let method_index = self.cp.method_ref(wrapper, unbox, &format!("(){}", primitive), false);
+                self.op_u2(0xb6, method_index);
+                self.convert(primitive, to)?;
+            }
+            (true, false) => {
+                let wrapper = wrapper_of(from).0;
+                let method_index = self.cp.method_ref(wrapper, "valueOf", &format!("({})L{};", from, wrapper), false);
+                self.op_u2(0xb8, method_index);
+            }
+            (true, true) => {
+                // byte/short/char/int在栈上都是int
+                let from = if matches!(from, "B" | "S" | "C") { "I" } else { from };
+                match (from, to) {
+                    ("I", "I" | "S" | "C" | "B") => {}
+                    ("I", "J") => self.code.push(0x85),
+                    ("I", "F") => self.code.push(0x86),
+                    ("I", "D") => self.code.push(0x87),
+                    ("J", "F") => self.code.push(0x89),
+                    ("J", "D") => self.code.push(0x8a),
+                    ("F", "D") => self.code.push(0x8d),
+                    _ => return Err(JvmError::IllegalStateError(format!("lambda参数类型{}不能转换为{}", from, to))),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn code_attribute(cp: &mut ConstantPoolBuilder, code: Vec<u8>, max_stack: u16, max_locals: u16) -> AttributeInfo {
+    AttributeInfo::Code {
+        attribute_name_index: cp.utf8("Code"),
+        attribute_length: 12 + code.len() as u32,
+        max_stack,
+        max_locals,
+        code_length: code.len() as u32,
+        code,
+        exception_table_length: 0,
+        exception_table: Vec::new(),
+        attributes_count: 0,
+        attributes: Vec::new(),
+    }
+}
+
+/// 构造方法：调用Object.<init>后把捕获的参数依次存入arg$N字段
+fn constructor(cp: &mut ConstantPoolBuilder, class_name: &str, spec: &LambdaSpec) -> Result<MethodInfo, JvmError> {
+    let descriptor = format!("({})V", spec.captured_types.concat());
+    let object_init = cp.method_ref("java/lang/Object", "<init>", "()V", false);
+    let mut builder = CodeBuilder { cp, code: vec![0x2a] };
+    builder.op_u2(0xb7, object_init);
+    let mut slot = 1;
+    for (i, captured) in spec.captured_types.iter().enumerate() {
+        builder.code.push(0x2a);
+        builder.load(captured, slot)?;
+        slot += slot_size(captured);
+        let field_index = builder.cp.field_ref(class_name, &format!("arg${}", i + 1), captured);
+        builder.op_u2(0xb5, field_index);
+    }
+    builder.return_value("V");
+    let code = builder.code;
+    let attribute = code_attribute(cp, code, 3, slot);
+    Ok(MethodInfo::new(0x0002, cp.utf8("<init>"), cp.utf8(&descriptor), vec![attribute]))
+}
+
+/// 接口方法（或桥接方法）：加载捕获的参数和自身的参数，转换类型后调用实现方法
+fn forwarding_method(cp: &mut ConstantPoolBuilder, class_name: &str, spec: &LambdaSpec, descriptor: &str) -> Result<MethodInfo, JvmError> {
+    let implementation = &spec.implementation;
+    let kind = implementation.kind;
+    // 实现方法接收的参数，实例方法的接收者在最前面
+    let mut target_types = Vec::new();
+    if matches!(kind, REF_INVOKE_VIRTUAL | REF_INVOKE_INTERFACE | REF_INVOKE_SPECIAL) {
+        target_types.push(format!("L{};", implementation.class_name));
+    }
+    target_types.extend(parse_method_descriptor(&implementation.descriptor));
+    let sam_types = parse_method_descriptor(descriptor);
+    let instantiated_types = parse_method_descriptor(&spec.instantiated_descriptor);
+    if spec.captured_types.len() + sam_types.len() != target_types.len() || sam_types.len() != instantiated_types.len() {
+        return Err(JvmError::IllegalStateError(format!(
+            "lambda参数个数不匹配: 捕获{:?}, 接口方法{}, 实现方法{}.{}{}",
+            spec.captured_types, descriptor, implementation.class_name, implementation.name, implementation.descriptor
+        )));
+    }
+
+    let mut builder = CodeBuilder { cp, code: Vec::new() };
+    if kind == REF_NEW_INVOKE_SPECIAL {
+        let class_index = builder.cp.class(&implementation.class_name);
+        builder.op_u2(0xbb, class_index);
+        builder.code.push(0x59);
+    }
+    for (i, (captured, target)) in spec.captured_types.iter().zip(&target_types).enumerate() {
+        builder.code.push(0x2a);
+        let field_index = builder.cp.field_ref(class_name, &format!("arg${}", i + 1), captured);
+        builder.op_u2(0xb4, field_index);
+        builder.convert(captured, target)?;
+    }
+    let mut slot = 1;
+    let targets = &target_types[spec.captured_types.len()..];
+    for ((param, instantiated), target) in sam_types.iter().zip(&instantiated_types).zip(targets) {
+        builder.load(param, slot)?;
+        slot += slot_size(param);
+        builder.convert(param, instantiated)?;
+        builder.convert(instantiated, target)?;
+    }
+
+    let method_index = builder.cp.method_ref(
+        &implementation.class_name,
+        &implementation.name,
+        &implementation.descriptor,
+        implementation.is_interface,
+    );
+    match kind {
+        REF_INVOKE_STATIC => builder.op_u2(0xb8, method_index),
+        REF_INVOKE_VIRTUAL => builder.op_u2(0xb6, method_index),
+        REF_INVOKE_SPECIAL | REF_NEW_INVOKE_SPECIAL => builder.op_u2(0xb7, method_index),
+        _ => {
+            builder.op_u2(0xb9, method_index);
+            let count = target_types.iter().map(|t| slot_size(t) as u8).sum::<u8>();
+            builder.code.extend_from_slice(&[count, 0]);
+        }
+    }
+
+    let produced = if kind == REF_NEW_INVOKE_SPECIAL {
+        format!("L{};", implementation.class_name)
+    } else {
+        return_type(&implementation.descriptor).to_string()
+    };
+    let sam_return = return_type(descriptor);
+    match (produced.as_str(), sam_return) {
+        (_, "V") => match produced.as_str() {
+            "V" => {}
+            "J" | "D" => builder.code.push(0x58),
+            _ => builder.code.push(0x57),
+        },
+        ("V", _) => {
+            return Err(JvmError::IllegalStateError(format!("lambda实现方法{}没有返回值", implementation.name)));
+        }
+        (produced, sam_return) => {
+            let instantiated_return = return_type(&spec.instantiated_descriptor);
+            builder.convert(produced, instantiated_return)?;
+            builder.convert(instantiated_return, sam_return)?;
+        }
+    }
+    builder.return_value(sam_return);
+
+    let code = builder.code;
+    let max_stack = 4 + 2 * target_types.len() as u16;
+    let attribute = code_attribute(cp, code, max_stack, slot);
+    Ok(MethodInfo::new(0x0001, cp.utf8(&spec.method_name), cp.utf8(descriptor), vec![attribute]))
+}
+
+/// 生成lambda调用点的实现类
+pub fn spin_class(class_name: &str, spec: &LambdaSpec) -> Result<ClassFile, JvmError> {
+    let mut cp = ConstantPoolBuilder::default();
+    let this_class = cp.class(class_name);
+    let super_class = cp.class("java/lang/Object");
+    let interfaces: Vec<U2> = std::iter::once(&spec.interface_name)
+        .chain(&spec.marker_interfaces)
+        .map(|name| cp.class(name))
+        .collect();
+    let fields: Vec<FieldInfo> = spec
+        .captured_types
+        .iter()
+        .enumerate()
+        .map(|(i, captured)| FieldInfo::new(0x0012, cp.utf8(&format!("arg${}", i + 1)), cp.utf8(captured), Vec::new()))
+        .collect();
+    let mut methods = vec![
+        constructor(&mut cp, class_name, spec)?,
+        forwarding_method(&mut cp, class_name, spec, &spec.sam_descriptor)?,
+    ];
+    for bridge in spec.bridges.iter().filter(|bridge| **bridge != spec.sam_descriptor) {
+        methods.push(forwarding_method(&mut cp, class_name, spec, bridge)?);
+    }
+
+    Ok(ClassFile {
+        magic: 0xCAFEBABE,
+        minor_version: 0,
+        major_version: 52,
+        constant_pool_count: cp.entries.len() as U2 + 1,
+        constant_pool: cp.entries,
+        // ACC_FINAL | ACC_SUPER | ACC_SYNTHETIC
+        access_flags: 0x1030,
+        this_class,
+        super_class,
+        interfaces_count: interfaces.len() as U2,
+        interfaces,
+        fields_count: fields.len() as U2,
+        fields,
+        methods_count: methods.len() as U2,
+        methods,
+        attributes_count: 0,
+        attributes: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spin_class_with_markers_and_bridges() {
+        let spec = LambdaSpec {
+            interface_name: "Mapper".to_string(),
+            method_name: "map".to_string(),
+            captured_types: vec!["I".to_string(), "J".to_string()],
+            sam_descriptor: "(LPoint;)LPoint;".to_string(),
+            implementation: MethodHandleInfo {
+                kind: REF_INVOKE_STATIC,
+                class_name: "Main".to_string(),
+                name: "lambda$main$0".to_string(),
+                descriptor: "(IJLPoint;)LPoint;".to_string(),
+                is_interface: false,
+            },
+            instantiated_descriptor: "(LPoint;)LPoint;".to_string(),
+            marker_interfaces: vec!["java/io/Serializable".to_string()],
+            bridges: vec!["(Ljava/lang/Object;)Ljava/lang/Object;".to_string()],
+        };
+        let class_file = spin_class("Main$$Lambda$1", &spec).unwrap();
+        let cp = &class_file.constant_pool;
+
+        assert_eq!(class_file.get_class_name(), "Main$$Lambda$1");
+        let interfaces: Vec<String> = class_file.interfaces.iter().map(|i| cp.get_class_name(*i)).collect();
+        assert_eq!(interfaces, vec!["Mapper", "java/io/Serializable"]);
+        let fields: Vec<String> = class_file.fields.iter().map(|f| cp.get_utf8_string(f.name_index)).collect();
+        assert_eq!(fields, vec!["arg$1", "arg$2"]);
+        let methods: Vec<String> = class_file
+            .methods
+            .iter()
+            .map(|m| format!("{}{}", cp.get_utf8_string(m.name_index), cp.get_utf8_string(m.descriptor_index)))
+            .collect();
+        assert_eq!(methods, vec!["<init>(IJ)V", "map(LPoint;)LPoint;", "map(Ljava/lang/Object;)Ljava/lang/Object;"]);
+    }
+}
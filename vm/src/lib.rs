@@ -13,29 +13,31 @@ pub mod heap;
 #[macro_use]
 pub mod instructions;
 pub mod jvm_thread;
+pub mod lambda;
 pub mod logger;
 pub mod method;
 pub mod native_method;
 pub mod pc_register;
 pub mod runtime_constant_pool;
 pub mod stack;
+pub mod stack_trace;
 pub mod vm;
 pub mod operand_stack;
 pub mod local_vars;
 
-// def_instr 宏定义
+// def_instr 宏定义：按 opcode => 指令处理函数 的列表生成256项的指令分发表，
+// 同一个opcode登记两次时编译失败
 #[macro_export]
 macro_rules! def_instr {
-    ($name:ident, $body:block) => {
-        pub fn $name(
-            thread: &mut crate::jvm_thread::JvmThread,
-            _method: &crate::method::Method,
-            code: &[u8],
-            pc: &mut usize,
-            _vm: Option<&mut crate::vm::Vm>,
-        ) -> Result<(), crate::error::JvmError> {
-            $body
-        }
+    ($table:ident; $($opcode:literal => $handler:path),* $(,)?) => {
+        pub static $table: [Option<$crate::instructions::types::InstructionFn>; 256] = {
+            let mut table: [Option<$crate::instructions::types::InstructionFn>; 256] = [None; 256];
+            $(
+                assert!(table[$opcode].is_none(), concat!("opcode重复登记: ", stringify!($opcode)));
+                table[$opcode] = Some($handler as $crate::instructions::types::InstructionFn);
+            )*
+            table
+        };
     };
 }
 
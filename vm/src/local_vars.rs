@@ -1,51 +1,114 @@
-use crate::JvmValue;
+use std::cell::RefCell;
+use std::rc::Rc;
 
+use crate::error::JvmError;
+use crate::heap::RawPtr;
+use crate::operand_stack::{SharedSlots, Slot};
+
+/// 局部变量表，与操作数栈共用 Slot 表示：long/double占index和index+1两个槽位（低位在前），
+/// 未赋值的槽位视为null引用。与操作数栈一样，越界和槽位类型不符都以 JvmError 返回
 #[derive(Debug)]
 pub struct LocalVars {
     max_locals: usize,
-    values: Vec<JvmValue>,
+    slots: SharedSlots,
 }
 
 impl LocalVars {
     pub fn new(max_locals: usize) -> Self {
         LocalVars {
             max_locals,
-            values: vec![JvmValue::Null; max_locals],
+            slots: Rc::new(RefCell::new(vec![Slot::Ref(RawPtr(std::ptr::null_mut())); max_locals])),
         }
     }
 
-    pub fn get_int(&self, index: usize) -> i32 {
-        if index >= self.max_locals {
-            panic!("Local variable index out of bounds");
-        }
-        match self.values[index] {
-            JvmValue::Int(v) => v as i32,
-            _ => panic!("Not an int at index {}", index),
-        }
+    /// 局部变量表的槽位数
+    pub fn max_locals(&self) -> usize {
+        self.max_locals
     }
 
-    pub fn set_int(&mut self, index: usize, value: i32) {
-        if index >= self.max_locals {
-            panic!("Local variable index out of bounds");
-        }
-        self.values[index] = JvmValue::Int(value as u32);
+    /// 与帧根共享的槽位，垃圾收集时更新其中的引用
+    pub fn shared_slots(&self) -> SharedSlots {
+        Rc::clone(&self.slots)
+    }
+
+    /// 读取一个槽位，越界以 JvmError 返回
+    pub fn get_slot(&self, index: usize) -> Result<Slot, JvmError> {
+        self.slots.borrow().get(index).copied().ok_or_else(|| self.out_of_bounds(index))
     }
 
-    pub fn set_obj_ref(&mut self, index: usize, obj_ref: crate::heap::RawPtr) {
+    /// 写入一个槽位，越界以 JvmError 返回
+    pub fn set_slot(&mut self, index: usize, slot: Slot) -> Result<(), JvmError> {
         if index >= self.max_locals {
-            panic!("Local variable index out of bounds");
+            return Err(self.out_of_bounds(index));
         }
-        self.values[index] = JvmValue::ObjRef(obj_ref);
+        self.slots.borrow_mut()[index] = slot;
+        Ok(())
     }
 
-    pub fn get_obj_ref(&self, index: usize) -> crate::heap::RawPtr {
-        if index >= self.max_locals {
-            panic!("Local variable index out of bounds");
+    fn out_of_bounds(&self, index: usize) -> JvmError {
+        JvmError::IllegalStateError(format!(
+            "Local variable index out of bounds: index {} >= max_locals {}",
+            index, self.max_locals
+        ))
+    }
+
+    pub fn get_int(&self, index: usize) -> Result<i32, JvmError> {
+        match self.get_slot(index)? {
+            Slot::Int(v) => Ok(v),
+            Slot::Ref(ptr) => Err(JvmError::IllegalStateError(format!(
+                "Local variable type mismatch at index {}: expected int, found reference {:?}",
+                index, ptr
+            ))),
         }
-        match self.values[index] {
-            JvmValue::ObjRef(ptr) => ptr,
-            JvmValue::Null => crate::heap::RawPtr(std::ptr::null_mut()),
-            _ => panic!("Not an object reference at index {}", index),
+    }
+
+    pub fn set_int(&mut self, index: usize, value: i32) -> Result<(), JvmError> {
+        self.set_slot(index, Slot::Int(value))
+    }
+
+    /// 读取long值，占index和index+1两个槽位（低位在前）
+    pub fn get_long(&self, index: usize) -> Result<i64, JvmError> {
+        let low = self.get_int(index)? as u32 as u64;
+        let high = self.get_int(index + 1)? as u32 as u64;
+        Ok(((high << 32) | low) as i64)
+    }
+
+    /// 写入long值，低32位存index，高32位存index+1
+    pub fn set_long(&mut self, index: usize, value: i64) -> Result<(), JvmError> {
+        // 先检查高位槽位，越界时不留下写了一半的值
+        self.get_slot(index + 1)?;
+        self.set_int(index, value as i32)?;
+        self.set_int(index + 1, (value >> 32) as i32)
+    }
+
+    pub fn get_float(&self, index: usize) -> Result<f32, JvmError> {
+        Ok(f32::from_bits(self.get_int(index)? as u32))
+    }
+
+    pub fn set_float(&mut self, index: usize, value: f32) -> Result<(), JvmError> {
+        self.set_int(index, value.to_bits() as i32)
+    }
+
+    /// 读取double值，槽位布局与long相同
+    pub fn get_double(&self, index: usize) -> Result<f64, JvmError> {
+        Ok(f64::from_bits(self.get_long(index)? as u64))
+    }
+
+    pub fn set_double(&mut self, index: usize, value: f64) -> Result<(), JvmError> {
+        self.set_long(index, value.to_bits() as i64)
+    }
+
+    pub fn set_obj_ref(&mut self, index: usize, obj_ref: RawPtr) -> Result<(), JvmError> {
+        self.set_slot(index, Slot::Ref(obj_ref))
+    }
+
+    pub fn get_obj_ref(&self, index: usize) -> Result<RawPtr, JvmError> {
+        match self.get_slot(index)? {
+            Slot::Ref(ptr) => Ok(ptr),
+            Slot::Int(value) => Err(JvmError::IllegalStateError(format!(
+                "Local variable type mismatch at index {}: expected reference, found int {}",
+                index, value
+            ))),
         }
     }
 }
@@ -57,32 +120,54 @@ mod tests {
     #[test]
     fn test_set_get_int() {
         let mut locals = LocalVars::new(10);
-        locals.set_int(0, 42);
-        assert_eq!(locals.get_int(0), 42);
+        locals.set_int(0, 42).unwrap();
+        assert_eq!(locals.get_int(0).unwrap(), 42);
     }
 
     #[test]
     fn test_multiple_variables() {
         let mut locals = LocalVars::new(10);
-        locals.set_int(0, 1);
-        locals.set_int(1, 2);
-        locals.set_int(2, 3);
-        assert_eq!(locals.get_int(0), 1);
-        assert_eq!(locals.get_int(1), 2);
-        assert_eq!(locals.get_int(2), 3);
+        locals.set_int(0, 1).unwrap();
+        locals.set_int(1, 2).unwrap();
+        locals.set_int(2, 3).unwrap();
+        assert_eq!(locals.get_int(0).unwrap(), 1);
+        assert_eq!(locals.get_int(1).unwrap(), 2);
+        assert_eq!(locals.get_int(2).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_set_get_long() {
+        let mut locals = LocalVars::new(4);
+        locals.set_long(1, i64::MIN + 7).unwrap();
+        assert_eq!(locals.get_long(1).unwrap(), i64::MIN + 7);
     }
 
     #[test]
-    #[should_panic(expected = "Local variable index out of bounds")]
-    fn test_index_out_of_bounds_get() {
-        let locals = LocalVars::new(5);
-        locals.get_int(5); // 应该触发越界错误
+    fn test_slot_reuse_across_types() {
+        let mut locals = LocalVars::new(4);
+        // 未赋值的槽位读作null
+        assert!(locals.get_obj_ref(0).unwrap().is_null());
+        locals.set_obj_ref(0, RawPtr(8 as *mut u8)).unwrap();
+        locals.set_double(0, 2.5).unwrap();
+        assert_eq!(locals.get_slot(1).unwrap(), Slot::Int((2.5f64.to_bits() >> 32) as i32));
+        assert_eq!(locals.get_double(0).unwrap(), 2.5);
     }
 
     #[test]
-    #[should_panic(expected = "Local variable index out of bounds")]
-    fn test_index_out_of_bounds_set() {
+    fn test_index_out_of_bounds_is_error() {
         let mut locals = LocalVars::new(5);
-        locals.set_int(5, 42); // 应该触发越界错误
+        assert!(matches!(locals.get_int(5), Err(JvmError::IllegalStateError(_))));
+        assert!(matches!(locals.set_int(5, 42), Err(JvmError::IllegalStateError(_))));
+        // long的高位槽位越界时低位也不写入
+        assert!(locals.set_long(4, -1).is_err());
+        assert!(locals.get_obj_ref(4).unwrap().is_null());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_slot_type_mismatch_is_error() {
+        let mut locals = LocalVars::new(2);
+        locals.set_int(0, 7).unwrap();
+        assert!(matches!(locals.get_obj_ref(0), Err(JvmError::IllegalStateError(_))));
+        assert!(matches!(locals.get_int(1), Err(JvmError::IllegalStateError(_))));
+    }
+}
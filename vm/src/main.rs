@@ -1,10 +1,12 @@
-use vm::heap::RawPtr;
+use vm::JvmValue;
 use vm::jvm_thread::JvmThread;
-use vm::vm::Vm;
+use vm::vm::{Vm, DEFAULT_INITIAL_HEAP_SIZE, DEFAULT_MAX_HEAP_SIZE};
+use vm::heap::parse_memory_size;
 use vm::error::JvmError;
 use vm::logger::Logger;
 use vm::jvm_log;
 use std::env;
+use std::io::Write;
 
 fn main() -> Result<(), JvmError> {
     // 获取命令行参数
@@ -12,26 +14,53 @@ fn main() -> Result<(), JvmError> {
     
     // 检查参数
     if args.len() < 2 {
-        println!("用法: {} <测试文件路径> [classpath] [--quiet]", args[0]);
+        println!("用法: {} <测试文件路径> [classpath] [--quiet] [-Xms<大小>] [-Xmx<大小>] [参数...]", args[0]);
         println!("示例: {} test/TestProgram", args[0]);
         println!("示例: {} test/TestProgram test:/path/to/rt.jar", args[0]);
         println!("示例: {} test/TestProgram test:/path/to/rt.jar --quiet", args[0]);
+        println!("示例: {} test/TestProgram test:/path/to/rt.jar --quiet input.txt -- --verbose", args[0]);
+        println!("示例: {} test/TestProgram test:/path/to/rt.jar -Xms64m -Xmx512m", args[0]);
         return Err(JvmError::IllegalStateError("参数错误".to_string()));
     }
     
     let test_path = &args[1];
-    let mut classpath = "test";
+    let mut classpath = None;
     let mut quiet_mode = false;
+    let mut initial_heap_size = None;
+    let mut max_heap_size = None;
+    // 传给main方法的参数：classpath之后的非选项参数，以及 -- 之后的所有参数
+    let mut program_args = Vec::new();
+    let mut options_done = false;
     
     // 解析参数
-    for i in 2..args.len() {
-        if args[i] == "--quiet" {
+    for arg in args.iter().skip(2) {
+        if options_done {
+            program_args.push(arg.clone());
+        } else if arg == "--" {
+            options_done = true;
+        } else if arg == "--quiet" {
             quiet_mode = true;
-        } else if !args[i].starts_with("--") {
-            // 非选项参数作为classpath
-            classpath = &args[i];
+        } else if let Some(size) = arg.strip_prefix("-Xms") {
+            initial_heap_size = Some(parse_heap_size(size, "Invalid initial heap size", arg)?);
+        } else if let Some(size) = arg.strip_prefix("-Xmx") {
+            max_heap_size = Some(parse_heap_size(size, "Invalid maximum heap size", arg)?);
+        } else if arg.starts_with("--") {
+            jvm_log!("[JVM] 忽略未知选项: {}", arg);
+        } else if classpath.is_none() {
+            // 第一个非选项参数作为classpath
+            classpath = Some(arg.as_str());
+        } else {
+            program_args.push(arg.clone());
         }
     }
+    let classpath = classpath.unwrap_or("test");
+    // 与HotSpot一致：只指定-Xms时最大堆至少为初始大小
+    let initial_heap_size = initial_heap_size.unwrap_or(DEFAULT_INITIAL_HEAP_SIZE.min(max_heap_size.unwrap_or(usize::MAX)));
+    let max_heap_size = max_heap_size.unwrap_or(DEFAULT_MAX_HEAP_SIZE.max(initial_heap_size));
+    if initial_heap_size > max_heap_size {
+        eprintln!("Error: Initial heap size set to a larger value than the maximum heap size");
+        return Err(JvmError::IllegalStateError("参数错误".to_string()));
+    }
     
     // 设置日志模式
     if quiet_mode {
@@ -83,7 +112,8 @@ fn main() -> Result<(), JvmError> {
     jvm_log!("[JVM] 类路径: {}", classpath);
     
     // 初始化JVM
-    let mut vm = Vm::new(classpath);
+    jvm_log!("[JVM] 堆大小: 初始{}字节，最大{}字节", initial_heap_size, max_heap_size);
+    let mut vm = Vm::with_heap_size(classpath, initial_heap_size, max_heap_size);
 
     // 加载主类
     let main_class = vm.load(&class_name)?;
@@ -93,18 +123,32 @@ fn main() -> Result<(), JvmError> {
         .get_method("main", "([Ljava/lang/String;)V")
         .ok_or_else(|| JvmError::ClassNotFoundError("main method not found".to_string()))?;
 
-    // 创建主线程
-    let mut java_main_thread = JvmThread::new(262144, 1024);
+    // 创建主线程，main方法的帧由invoke压入
+    let mut java_main_thread = JvmThread::empty();
 
-    // 准备参数 - 创建一个空的String数组作为main方法的参数
-    // 在真实的JVM中，这里应该是命令行参数，但我们简化处理
-    let string_array_klass = vm.load("[Ljava/lang/String;")?;
-    let empty_string_array = vm.alloc_array(&string_array_klass, 0)
-        .map_err(|e| JvmError::IllegalStateError(format!("Failed to allocate array: {:?}", e)))?;
-    let args: Vec<RawPtr> = vec![empty_string_array];
+    // 准备参数 - 命令行参数转换为String对象，存入main方法的String[]参数
+    jvm_log!("[JVM] main方法参数: {:?}", program_args);
+    let string_array = vm.create_string_array(&program_args)?;
+    let args = vec![JvmValue::ObjRef(string_array)];
 
     // 调用main方法 - 传递None作为receiver，因为main是静态方法
-    java_main_thread.invoke(None, main_method.clone(), main_class, args, &mut vm);
+    let result = java_main_thread.invoke(None, main_method.clone(), main_class, args, &mut vm);
 
-    Ok(())
-}
\ No newline at end of file
+    // 运行关闭钩子后以main的结果对应的状态码退出：未捕获的异常为1，System.exit/Runtime.halt为请求的状态码
+    let status = vm.shutdown(result);
+    jvm_log!("[JVM] 退出状态码: {}", status);
+    std::io::stdout().flush().ok();
+    std::process::exit(status);
+}
+
+/// 解析-Xms/-Xmx的值，格式错误时与HotSpot一样报告并退出
+fn parse_heap_size(size: &str, error: &str, arg: &str) -> Result<usize, JvmError> {
+    match parse_memory_size(size) {
+        Some(size) if size > 0 => Ok(size),
+        _ => {
+            eprintln!("{}: {}", error, arg);
+            eprintln!("Error: Could not create the Java Virtual Machine.");
+            Err(JvmError::IllegalStateError("参数错误".to_string()))
+        }
+    }
+}
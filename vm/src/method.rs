@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use reader::{
     attribute_info::AttributeInfo,
     constant_pool::{ConstantPool, CpInfo},
@@ -6,6 +8,7 @@ use reader::{
 };
 
 use crate::instructions::Instruction;
+use crate::runtime_constant_pool::RunTimeConstantPool;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ArrayType {
@@ -42,313 +45,420 @@ impl ArrayType {
             ArrayType::Long | ArrayType::Double => 8,
         }
     }
+
+    /// 元素类型描述符，如 `I`、`Z`
+    pub fn get_descriptor(&self) -> &'static str {
+        match self {
+            ArrayType::Boolean => "Z",
+            ArrayType::Char => "C",
+            ArrayType::Float => "F",
+            ArrayType::Double => "D",
+            ArrayType::Byte => "B",
+            ArrayType::Short => "S",
+            ArrayType::Int => "I",
+            ArrayType::Long => "J",
+        }
+    }
 }
 
+/// 异常表项：[start_pc, end_pc) 内抛出的异常若匹配 catch_type（0表示任意，即finally），跳转到 handler_pc
 #[derive(Debug, Clone)]
-struct ExceptionEntry {
-    start_pc: U2,
-    end_pc: U2,
-    handler_pc: U2,
-    catch_type: U2,
+pub struct ExceptionEntry {
+    pub start_pc: U2,
+    pub end_pc: U2,
+    pub handler_pc: U2,
+    pub catch_type: U2,
+}
+
+impl ExceptionEntry {
+    /// 判断pc处的指令是否受该表项保护
+    pub fn covers(&self, pc: usize) -> bool {
+        self.start_pc as usize <= pc && pc < self.end_pc as usize
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct ByteCodes(Vec<U1>);
 
 impl ByteCodes {
-    pub fn iter(&self) -> ByteCodesInterator {
-        ByteCodesInterator {
-            byte_codes: self,
-            index: 0,
-        }
-    }
-
-    fn read_u1(&self, index: usize) -> Option<U1> {
-        self.0.get(index).cloned()
+    pub fn iter(&self) -> ByteCodesInterator<'_> {
+        ByteCodesInterator::new(&self.0)
     }
 }
 
+/// 字节码解码器：依次产生 (opcode地址, 指令)，分支目标是字节码中的绝对地址。
+/// 代码在指令中途结束时停止
 pub struct ByteCodesInterator<'a> {
-    byte_codes: &'a ByteCodes,
+    code: &'a [U1],
     index: usize,
 }
 
 impl<'a> ByteCodesInterator<'a> {
-    fn read_u1(&mut self) -> U1 {
-        let op_byte = self.byte_codes.read_u1(self.index).expect("no more code");
+    pub fn new(code: &'a [U1]) -> Self {
+        ByteCodesInterator { code, index: 0 }
+    }
+
+    fn read_u1(&mut self) -> Option<U1> {
+        let byte = self.code.get(self.index).copied()?;
         self.index += 1;
-        op_byte
+        Some(byte)
+    }
+
+    fn read_u2(&mut self) -> Option<U2> {
+        Some(((self.read_u1()? as u16) << 8) | (self.read_u1()? as u16))
+    }
+
+    fn read_u4(&mut self) -> Option<U4> {
+        Some(((self.read_u2()? as u32) << 16) | (self.read_u2()? as u32))
+    }
+
+    /// 相对opcode地址的偏移换算为绝对地址，越界的目标换算为usize::MAX
+    fn target(opcode_pc: usize, offset: i32) -> usize {
+        usize::try_from(opcode_pc as i64 + offset as i64).unwrap_or(usize::MAX)
+    }
+
+    fn read_branch(&mut self, opcode_pc: usize) -> Option<usize> {
+        Some(Self::target(opcode_pc, self.read_u2()? as i16 as i32))
     }
 
-    fn read_u2(&mut self) -> U2 {
-        let byte1 = self.read_u1();
-        let byte2 = self.read_u1();
-        ((byte1 as u16) << 8) | (byte2 as u16)
+    fn read_branch_w(&mut self, opcode_pc: usize) -> Option<usize> {
+        Some(Self::target(opcode_pc, self.read_u4()? as i32))
     }
 
-    fn read_u4(&mut self) -> U4 {
-        let byte1 = self.read_u1();
-        let byte2 = self.read_u1();
-        let byte3 = self.read_u1();
-        let byte4 = self.read_u1();
-        ((byte1 as u32) << 24) | ((byte2 as u32) << 16) | ((byte3 as u32) << 8) | (byte4 as u32)
+    /// switch指令的表项数：count项、每项entry_size字节的表超出代码末尾时返回None
+    fn table_len(&self, count: usize, entry_size: usize) -> Option<usize> {
+        let remaining = self.code.len() - self.index;
+        (count <= remaining / entry_size).then_some(count)
+    }
+
+    /// 跳过switch指令opcode之后对齐到4字节的填充
+    fn skip_padding(&mut self) -> Option<()> {
+        while !self.index.is_multiple_of(4) {
+            self.read_u1()?;
+        }
+        Some(())
+    }
+
+    fn read_tableswitch(&mut self, opcode_pc: usize) -> Option<Instruction> {
+        self.skip_padding()?;
+        let default = self.read_branch_w(opcode_pc)?;
+        let low = self.read_u4()? as i32;
+        let high = self.read_u4()? as i32;
+        if high < low {
+            return None;
+        }
+        // 跳转表必须完整地位于代码中，损坏的[low, high]不会导致按它的大小分配
+        let mut targets = Vec::with_capacity(self.table_len((high as i64 - low as i64 + 1) as usize, 4)?);
+        for _ in low..=high {
+            targets.push(self.read_branch_w(opcode_pc)?);
+        }
+        Some(Instruction::Tableswitch(default, low, high, targets))
+    }
+
+    fn read_lookupswitch(&mut self, opcode_pc: usize) -> Option<Instruction> {
+        self.skip_padding()?;
+        let default = self.read_branch_w(opcode_pc)?;
+        let npairs = self.read_u4()? as i32;
+        if npairs < 0 {
+            return None;
+        }
+        let mut pairs = Vec::with_capacity(self.table_len(npairs as usize, 8)?);
+        for _ in 0..npairs {
+            let key = self.read_u4()? as i32;
+            pairs.push((key, self.read_branch_w(opcode_pc)?));
+        }
+        Some(Instruction::Lookupswitch(default, pairs))
+    }
+
+    /// wide前缀：后面的load、store、ret使用16位局部变量下标，iinc使用16位下标和增量
+    fn read_wide(&mut self) -> Option<Instruction> {
+        let opcode = self.read_u1()?;
+        let instruction = match opcode {
+            0x15 => Instruction::Iload(self.read_u2()?),
+            0x16 => Instruction::Lload(self.read_u2()?),
+            0x17 => Instruction::Fload(self.read_u2()?),
+            0x18 => Instruction::Dload(self.read_u2()?),
+            0x19 => Instruction::Aload(self.read_u2()?),
+            0x36 => Instruction::Istore(self.read_u2()?),
+            0x37 => Instruction::Lstore(self.read_u2()?),
+            0x38 => Instruction::Fstore(self.read_u2()?),
+            0x39 => Instruction::Dstore(self.read_u2()?),
+            0x3a => Instruction::Astore(self.read_u2()?),
+            0xa9 => Instruction::Ret(self.read_u2()?),
+            0x84 => Instruction::Iinc(self.read_u2()?, self.read_u2()? as i16),
+            _ => Instruction::Unimplemented(0xc4),
+        };
+        Some(instruction)
     }
 }
 
 impl<'a> Iterator for ByteCodesInterator<'a> {
-    type Item = Instruction;
+    type Item = (usize, Instruction);
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index >= self.byte_codes.0.len() {
-            return None;
-        }
-        let op_byte = self.read_u1();
-        match op_byte {
-            0x32 => Some(Instruction::Aaload),
-            0x53 => Some(Instruction::Aastore),
-            0x1 => Some(Instruction::Aconst_null),
-            0x19 => Some(Instruction::Aload(self.read_u1())),
-            0x2a => Some(Instruction::Aload_0),
-            0x2b => Some(Instruction::Aload_1),
-            0x2c => Some(Instruction::Aload_2),
-            0x2d => Some(Instruction::Aload_3),
-            0xbd => Some(Instruction::Anewarray(self.read_u2())),
-            0xb0 => Some(Instruction::Areturn),
-            0xbe => Some(Instruction::Arraylength),
-            0x3a => Some(Instruction::Astore(self.read_u1())),
-            0x4b => Some(Instruction::Astore_0),
-            0x4c => Some(Instruction::Astore_1),
-            0x4d => Some(Instruction::Astore_2),
-            0x4e => Some(Instruction::Astore_3),
-            0xbf => Some(Instruction::Athrow),
-            0x33 => Some(Instruction::Baload),
-            0x54 => Some(Instruction::Bastore),
-            0x10 => Some(Instruction::Bipush(self.read_u1())),
-            0x34 => Some(Instruction::Caload),
-            0x55 => Some(Instruction::Castore),
-            0xc0 => Some(Instruction::Checkcast(self.read_u2())),
-            0x90 => Some(Instruction::D2f),
-            0x8e => Some(Instruction::D2i),
-            0x8f => Some(Instruction::D2l),
-            0x63 => Some(Instruction::Dadd),
-            0x31 => Some(Instruction::Daload),
-            0x52 => Some(Instruction::Dastore),
-            0x98 => Some(Instruction::Dcmpg),
-            0x97 => Some(Instruction::Dcmpl),
-            0xe => Some(Instruction::Dconst_0),
-            0xf => Some(Instruction::Dconst_1),
-            0x6f => Some(Instruction::Ddiv),
-            0x18 => Some(Instruction::Dload(self.read_u1())),
-            0x26 => Some(Instruction::Dload_0),
-            0x27 => Some(Instruction::Dload_1),
-            0x28 => Some(Instruction::Dload_2),
-            0x29 => Some(Instruction::Dload_3),
-            0x6b => Some(Instruction::Dmul),
-            0x77 => Some(Instruction::Dneg),
-            0x73 => Some(Instruction::Drem),
-            0xaf => Some(Instruction::Dreturn),
-            0x39 => Some(Instruction::Dstore(self.read_u1())),
-            0x47 => Some(Instruction::Dstore_0),
-            0x48 => Some(Instruction::Dstore_1),
-            0x49 => Some(Instruction::Dstore_2),
-            0x4a => Some(Instruction::Dstore_3),
-            0x67 => Some(Instruction::Dsub),
-            0x59 => Some(Instruction::Dup),
-            0x5a => Some(Instruction::Dup_x1),
-            0x5b => Some(Instruction::Dup_x2),
-            0x5c => Some(Instruction::Dup2),
-            0x5d => Some(Instruction::Dup2_x1),
-            0x5e => Some(Instruction::Dup2_x2),
-            0x8d => Some(Instruction::F2d),
-            0x8b => Some(Instruction::F2i),
-            0x8c => Some(Instruction::F2l),
-            0x62 => Some(Instruction::Fadd),
-            0x30 => Some(Instruction::Faload),
-            0x51 => Some(Instruction::Fastore),
-            0x96 => Some(Instruction::Fcmpg),
-            0x95 => Some(Instruction::Fcmpl),
-            0xb => Some(Instruction::Fconst_0),
-            0xc => Some(Instruction::Fconst_1),
-            0xd => Some(Instruction::Fconst_2),
-            0x6e => Some(Instruction::Fdiv),
-            0x17 => Some(Instruction::Fload(self.read_u1())),
-            0x22 => Some(Instruction::Fload_0),
-            0x23 => Some(Instruction::Fload_1),
-            0x24 => Some(Instruction::Fload_2),
-            0x25 => Some(Instruction::Fload_3),
-            0x6a => Some(Instruction::Fmul),
-            0x76 => Some(Instruction::Fneg),
-            0x72 => Some(Instruction::Frem),
-            0xae => Some(Instruction::Freturn),
-            0x38 => Some(Instruction::Fstore(self.read_u1())),
-            0x43 => Some(Instruction::Fstore_0),
-            0x44 => Some(Instruction::Fstore_1),
-            0x45 => Some(Instruction::Fstore_2),
-            0x46 => Some(Instruction::Fstore_3),
-            0x66 => Some(Instruction::Fsub),
-            0xb4 => Some(Instruction::Getfield(self.read_u2())),
-            0xb2 => Some(Instruction::Getstatic(self.read_u2())),
-            0xa7 => Some(Instruction::Goto(self.read_u2())),
-            0xc8 => Some(Instruction::Goto_w(self.read_u4())),
-            0x91 => Some(Instruction::I2b),
-            0x92 => Some(Instruction::I2c),
-            0x87 => Some(Instruction::I2d),
-            0x86 => Some(Instruction::I2f),
-            0x85 => Some(Instruction::I2l),
-            0x93 => Some(Instruction::I2s),
-            0x60 => Some(Instruction::Iadd),
-            0x2e => Some(Instruction::Iaload),
-            0x7e => Some(Instruction::Iand),
-            0x4f => Some(Instruction::Iastore),
-            0x2 => Some(Instruction::Iconst_m1),
-            0x3 => Some(Instruction::Iconst_0),
-            0x4 => Some(Instruction::Iconst_1),
-            0x5 => Some(Instruction::Iconst_2),
-            0x6 => Some(Instruction::Iconst_3),
-            0x7 => Some(Instruction::Iconst_4),
-            0x8 => Some(Instruction::Iconst_5),
-            0x6c => Some(Instruction::Idiv),
-            0xa5 => Some(Instruction::If_acmpeq(self.read_u2())),
-            0xa6 => Some(Instruction::If_acmpne(self.read_u2())),
-            0x9f => Some(Instruction::If_icmpeq(self.read_u2())),
-            0xa0 => Some(Instruction::If_icmpne(self.read_u2())),
-            0xa1 => Some(Instruction::If_icmplt(self.read_u2())),
-            0xa2 => Some(Instruction::If_icmpge(self.read_u2())),
-            0xa3 => Some(Instruction::If_icmpgt(self.read_u2())),
-            0xa4 => Some(Instruction::If_icmple(self.read_u2())),
-            0x99 => Some(Instruction::Ifeq(self.read_u2())),
-            0x9a => Some(Instruction::Ifne(self.read_u2())),
-            0x9b => Some(Instruction::Iflt(self.read_u2())),
-            0x9c => Some(Instruction::Ifge(self.read_u2())),
-            0x9d => Some(Instruction::Ifgt(self.read_u2())),
-            0x9e => Some(Instruction::Ifle(self.read_u2())),
-            0xc7 => Some(Instruction::Ifnonnull(self.read_u2())),
-            0xc6 => Some(Instruction::Ifnull(self.read_u2())),
-            0x84 => Some(Instruction::Iinc(self.read_u1(), self.read_u1() as i8)),
-            0x15 => Some(Instruction::Iload(self.read_u1())),
-            0x1a => Some(Instruction::Iload_0),
-            0x1b => Some(Instruction::Iload_1),
-            0x1c => Some(Instruction::Iload_2),
-            0x1d => Some(Instruction::Iload_3),
-            0x68 => Some(Instruction::Imul),
-            0x74 => Some(Instruction::Ineg),
-            0xc1 => Some(Instruction::Instanceof(self.read_u2())),
-            0xba => {
-                let index = self.read_u2();
-                let _ = self.read_u2();
-                Some(Instruction::Invokedynamic(index))
-            }
+        let opcode_pc = self.index;
+        let opcode = self.read_u1()?;
+        let instruction = match opcode {
+            0x00 => Instruction::Nop,
+            0x01 => Instruction::Aconst_null,
+            0x02 => Instruction::Iconst_m1,
+            0x03 => Instruction::Iconst_0,
+            0x04 => Instruction::Iconst_1,
+            0x05 => Instruction::Iconst_2,
+            0x06 => Instruction::Iconst_3,
+            0x07 => Instruction::Iconst_4,
+            0x08 => Instruction::Iconst_5,
+            0x09 => Instruction::Lconst_0,
+            0x0a => Instruction::Lconst_1,
+            0x0b => Instruction::Fconst_0,
+            0x0c => Instruction::Fconst_1,
+            0x0d => Instruction::Fconst_2,
+            0x0e => Instruction::Dconst_0,
+            0x0f => Instruction::Dconst_1,
+            0x10 => Instruction::Bipush(self.read_u1()?),
+            0x11 => Instruction::Sipush(self.read_u2()?),
+            0x12 => Instruction::Ldc(self.read_u1()?),
+            0x13 => Instruction::Ldc_w(self.read_u2()?),
+            0x14 => Instruction::Ldc2_w(self.read_u2()?),
+            0x15 => Instruction::Iload(self.read_u1()? as U2),
+            0x16 => Instruction::Lload(self.read_u1()? as U2),
+            0x17 => Instruction::Fload(self.read_u1()? as U2),
+            0x18 => Instruction::Dload(self.read_u1()? as U2),
+            0x19 => Instruction::Aload(self.read_u1()? as U2),
+            0x1a => Instruction::Iload_0,
+            0x1b => Instruction::Iload_1,
+            0x1c => Instruction::Iload_2,
+            0x1d => Instruction::Iload_3,
+            0x1e => Instruction::Lload_0,
+            0x1f => Instruction::Lload_1,
+            0x20 => Instruction::Lload_2,
+            0x21 => Instruction::Lload_3,
+            0x22 => Instruction::Fload_0,
+            0x23 => Instruction::Fload_1,
+            0x24 => Instruction::Fload_2,
+            0x25 => Instruction::Fload_3,
+            0x26 => Instruction::Dload_0,
+            0x27 => Instruction::Dload_1,
+            0x28 => Instruction::Dload_2,
+            0x29 => Instruction::Dload_3,
+            0x2a => Instruction::Aload_0,
+            0x2b => Instruction::Aload_1,
+            0x2c => Instruction::Aload_2,
+            0x2d => Instruction::Aload_3,
+            0x2e => Instruction::Iaload,
+            0x2f => Instruction::Laload,
+            0x30 => Instruction::Faload,
+            0x31 => Instruction::Daload,
+            0x32 => Instruction::Aaload,
+            0x33 => Instruction::Baload,
+            0x34 => Instruction::Caload,
+            0x35 => Instruction::Saload,
+            0x36 => Instruction::Istore(self.read_u1()? as U2),
+            0x37 => Instruction::Lstore(self.read_u1()? as U2),
+            0x38 => Instruction::Fstore(self.read_u1()? as U2),
+            0x39 => Instruction::Dstore(self.read_u1()? as U2),
+            0x3a => Instruction::Astore(self.read_u1()? as U2),
+            0x3b => Instruction::Istore_0,
+            0x3c => Instruction::Istore_1,
+            0x3d => Instruction::Istore_2,
+            0x3e => Instruction::Istore_3,
+            0x3f => Instruction::Lstore_0,
+            0x40 => Instruction::Lstore_1,
+            0x41 => Instruction::Lstore_2,
+            0x42 => Instruction::Lstore_3,
+            0x43 => Instruction::Fstore_0,
+            0x44 => Instruction::Fstore_1,
+            0x45 => Instruction::Fstore_2,
+            0x46 => Instruction::Fstore_3,
+            0x47 => Instruction::Dstore_0,
+            0x48 => Instruction::Dstore_1,
+            0x49 => Instruction::Dstore_2,
+            0x4a => Instruction::Dstore_3,
+            0x4b => Instruction::Astore_0,
+            0x4c => Instruction::Astore_1,
+            0x4d => Instruction::Astore_2,
+            0x4e => Instruction::Astore_3,
+            0x4f => Instruction::Iastore,
+            0x50 => Instruction::Lastore,
+            0x51 => Instruction::Fastore,
+            0x52 => Instruction::Dastore,
+            0x53 => Instruction::Aastore,
+            0x54 => Instruction::Bastore,
+            0x55 => Instruction::Castore,
+            0x56 => Instruction::Sastore,
+            0x57 => Instruction::Pop,
+            0x58 => Instruction::Pop2,
+            0x59 => Instruction::Dup,
+            0x5a => Instruction::Dup_x1,
+            0x5b => Instruction::Dup_x2,
+            0x5c => Instruction::Dup2,
+            0x5d => Instruction::Dup2_x1,
+            0x5e => Instruction::Dup2_x2,
+            0x5f => Instruction::Swap,
+            0x60 => Instruction::Iadd,
+            0x61 => Instruction::Ladd,
+            0x62 => Instruction::Fadd,
+            0x63 => Instruction::Dadd,
+            0x64 => Instruction::Isub,
+            0x65 => Instruction::Lsub,
+            0x66 => Instruction::Fsub,
+            0x67 => Instruction::Dsub,
+            0x68 => Instruction::Imul,
+            0x69 => Instruction::Lmul,
+            0x6a => Instruction::Fmul,
+            0x6b => Instruction::Dmul,
+            0x6c => Instruction::Idiv,
+            0x6d => Instruction::Ldiv,
+            0x6e => Instruction::Fdiv,
+            0x6f => Instruction::Ddiv,
+            0x70 => Instruction::Irem,
+            0x71 => Instruction::Lrem,
+            0x72 => Instruction::Frem,
+            0x73 => Instruction::Drem,
+            0x74 => Instruction::Ineg,
+            0x75 => Instruction::Lneg,
+            0x76 => Instruction::Fneg,
+            0x77 => Instruction::Dneg,
+            0x78 => Instruction::Ishl,
+            0x79 => Instruction::Lshl,
+            0x7a => Instruction::Ishr,
+            0x7b => Instruction::Lshr,
+            0x7c => Instruction::Iushr,
+            0x7d => Instruction::Lushr,
+            0x7e => Instruction::Iand,
+            0x7f => Instruction::Land,
+            0x80 => Instruction::Ior,
+            0x81 => Instruction::Lor,
+            0x82 => Instruction::Ixor,
+            0x83 => Instruction::Lxor,
+            0x84 => Instruction::Iinc(self.read_u1()? as U2, self.read_u1()? as i8 as i16),
+            0x85 => Instruction::I2l,
+            0x86 => Instruction::I2f,
+            0x87 => Instruction::I2d,
+            0x88 => Instruction::L2i,
+            0x89 => Instruction::L2f,
+            0x8a => Instruction::L2d,
+            0x8b => Instruction::F2i,
+            0x8c => Instruction::F2l,
+            0x8d => Instruction::F2d,
+            0x8e => Instruction::D2i,
+            0x8f => Instruction::D2l,
+            0x90 => Instruction::D2f,
+            0x91 => Instruction::I2b,
+            0x92 => Instruction::I2c,
+            0x93 => Instruction::I2s,
+            0x94 => Instruction::Lcmp,
+            0x95 => Instruction::Fcmpl,
+            0x96 => Instruction::Fcmpg,
+            0x97 => Instruction::Dcmpl,
+            0x98 => Instruction::Dcmpg,
+            0x99 => Instruction::Ifeq(self.read_branch(opcode_pc)?),
+            0x9a => Instruction::Ifne(self.read_branch(opcode_pc)?),
+            0x9b => Instruction::Iflt(self.read_branch(opcode_pc)?),
+            0x9c => Instruction::Ifge(self.read_branch(opcode_pc)?),
+            0x9d => Instruction::Ifgt(self.read_branch(opcode_pc)?),
+            0x9e => Instruction::Ifle(self.read_branch(opcode_pc)?),
+            0x9f => Instruction::If_icmpeq(self.read_branch(opcode_pc)?),
+            0xa0 => Instruction::If_icmpne(self.read_branch(opcode_pc)?),
+            0xa1 => Instruction::If_icmplt(self.read_branch(opcode_pc)?),
+            0xa2 => Instruction::If_icmpge(self.read_branch(opcode_pc)?),
+            0xa3 => Instruction::If_icmpgt(self.read_branch(opcode_pc)?),
+            0xa4 => Instruction::If_icmple(self.read_branch(opcode_pc)?),
+            0xa5 => Instruction::If_acmpeq(self.read_branch(opcode_pc)?),
+            0xa6 => Instruction::If_acmpne(self.read_branch(opcode_pc)?),
+            0xa7 => Instruction::Goto(self.read_branch(opcode_pc)?),
+            0xa8 => Instruction::Jsr(self.read_branch(opcode_pc)?),
+            0xa9 => Instruction::Ret(self.read_u1()? as U2),
+            0xaa => self.read_tableswitch(opcode_pc)?,
+            0xab => self.read_lookupswitch(opcode_pc)?,
+            0xac => Instruction::Ireturn,
+            0xad => Instruction::Lreturn,
+            0xae => Instruction::Freturn,
+            0xaf => Instruction::Dreturn,
+            0xb0 => Instruction::Areturn,
+            0xb1 => Instruction::Return,
+            0xb2 => Instruction::Getstatic(self.read_u2()?),
+            0xb3 => Instruction::Putstatic(self.read_u2()?),
+            0xb4 => Instruction::Getfield(self.read_u2()?),
+            0xb5 => Instruction::Putfield(self.read_u2()?),
+            0xb6 => Instruction::Invokevirtual(self.read_u2()?),
+            0xb7 => Instruction::Invokespecial(self.read_u2()?),
+            0xb8 => Instruction::Invokestatic(self.read_u2()?),
             0xb9 => {
-                let index = self.read_u2();
-                let cnt = self.read_u1();
-                let _ = self.read_u1();
-                Some(Instruction::Invokeinterface(index, cnt))
-            }
-            0xb7 => Some(Instruction::Invokespecial(self.read_u2())),
-            0xb8 => Some(Instruction::Invokestatic(self.read_u2())),
-            0xb6 => Some(Instruction::Invokevirtual(self.read_u2())),
-            0x80 => Some(Instruction::Ior),
-            0x70 => Some(Instruction::Irem),
-            0xac => Some(Instruction::Ireturn),
-            0x78 => Some(Instruction::Ishl),
-            0x7a => Some(Instruction::Ishr),
-            0x36 => Some(Instruction::Istore(self.read_u1())),
-            0x3b => Some(Instruction::Istore_0),
-            0x3c => Some(Instruction::Istore_1),
-            0x3d => Some(Instruction::Istore_2),
-            0x3e => Some(Instruction::Istore_3),
-            0x64 => Some(Instruction::Isub),
-            0x7c => Some(Instruction::Iushr),
-            0x82 => Some(Instruction::Ixor),
-            0xa8 => Some(Instruction::Jsr(self.read_u2())),
-            0xc9 => Some(Instruction::Jsr_w(self.read_u4())),
-            0x8a => Some(Instruction::L2d),
-            0x89 => Some(Instruction::L2f),
-            0x88 => Some(Instruction::L2i),
-            0x61 => Some(Instruction::Ladd),
-            0x2f => Some(Instruction::Laload),
-            0x7f => Some(Instruction::Land),
-            0x50 => Some(Instruction::Lastore),
-            0x94 => Some(Instruction::Lcmp),
-            0x9 => Some(Instruction::Lconst_0),
-            0xa => Some(Instruction::Lconst_1),
-            0x12 => Some(Instruction::Ldc(self.read_u1())),
-            0x13 => Some(Instruction::Ldc_w(self.read_u2())),
-            0x14 => Some(Instruction::Ldc2_w(self.read_u2())),
-            0x6d => Some(Instruction::Ldiv),
-            0x16 => Some(Instruction::Lload(self.read_u1())),
-            0x1e => Some(Instruction::Lload_0),
-            0x1f => Some(Instruction::Lload_1),
-            0x20 => Some(Instruction::Lload_2),
-            0x21 => Some(Instruction::Lload_3),
-            0x69 => Some(Instruction::Imul),
-            0x75 => Some(Instruction::Ineg),
-            0xab => {
-                for _ in 0..3 {
-                    let _ = self.read_u1();
-                }
-                let default = self.read_u4();
-                let npairs = self.read_u4();
-                let mut pairs = Vec::with_capacity(npairs as usize);
-                for _ in 0..npairs {
-                    let int_match = self.read_u4();
-                    let offset = self.read_u4() as i32;
-                    pairs.push((int_match, offset));
-                }
-                Some(Instruction::Lookupswitch(default, npairs, pairs))
-            }
-            0x81 => Some(Instruction::Lor),
-            0x71 => Some(Instruction::Irem),
-            0xad => Some(Instruction::Ireturn),
-            0x79 => Some(Instruction::Ishl),
-            0x7b => Some(Instruction::Ishr),
-            0x37 => Some(Instruction::Istore(self.read_u1())),
-            0x3f => Some(Instruction::Istore_0),
-            0x40 => Some(Instruction::Istore_1),
-            0x41 => Some(Instruction::Istore_2),
-            0x42 => Some(Instruction::Istore_3),
-            0x65 => Some(Instruction::Isub),
-            0x7d => Some(Instruction::Lushr),
-            0x83 => Some(Instruction::Lxor),
-            0xc2 => Some(Instruction::Monitorenter),
-            0xc3 => Some(Instruction::Monitorexit),
-            0xc5 => Some(Instruction::Multianewarray(self.read_u2(), self.read_u1())),
-            0xbb => Some(Instruction::New(self.read_u2())),
+                let index = self.read_u2()?;
+                let count = self.read_u1()?;
+                self.read_u1()?;
+                Instruction::Invokeinterface(index, count)
+            },
+            0xba => {
+                let index = self.read_u2()?;
+                self.read_u2()?;
+                Instruction::Invokedynamic(index)
+            },
+            0xbb => Instruction::New(self.read_u2()?),
             0xbc => {
-                let array_type_value = self.read_u1();
-                match ArrayType::from_u1(array_type_value) {
-                    Some(array_type) => Some(Instruction::Newarray(array_type)),
-                    None => None, // 无效的数组类型
-                }
-            }, //TODO use enum ArrayType
-            0x0 => Some(Instruction::Nop),
-            0x57 => Some(Instruction::Pop),
-            0x58 => Some(Instruction::Pop2),
-            0xb5 => Some(Instruction::Putfield(self.read_u2())),
-            0xb3 => Some(Instruction::Putstatic(self.read_u2())),
-            0xa9 => Some(Instruction::Ret(self.read_u1())),
-            0xb1 => Some(Instruction::Return),
-            0x35 => Some(Instruction::Saload),
-            0x56 => Some(Instruction::Sastore),
-            0x11 => Some(Instruction::Sipush(self.read_u2())),
-            0x5f => Some(Instruction::Swap),
-            0xaa => {
-                for _ in 0..3 {
-                    self.read_u1();
-                }
-                let default = self.read_u4();
-                let low = self.read_u4();
-                let high = self.read_u4();
-                let size = high - low + 1;
-                let mut offsets = Vec::with_capacity(size as usize);
-                for _ in 0..size {
-                    offsets.push(self.read_u4() as i32);
-                }
-                Some(Instruction::Tableswitch(default, low, high, offsets))
+                let atype = self.read_u1()?;
+                ArrayType::from_u1(atype).map_or(Instruction::Unimplemented(opcode), Instruction::Newarray)
+            },
+            0xbd => Instruction::Anewarray(self.read_u2()?),
+            0xbe => Instruction::Arraylength,
+            0xbf => Instruction::Athrow,
+            0xc0 => Instruction::Checkcast(self.read_u2()?),
+            0xc1 => Instruction::Instanceof(self.read_u2()?),
+            0xc2 => Instruction::Monitorenter,
+            0xc3 => Instruction::Monitorexit,
+            0xc5 => Instruction::Multianewarray(self.read_u2()?, self.read_u1()?),
+            0xc6 => Instruction::Ifnull(self.read_branch(opcode_pc)?),
+            0xc7 => Instruction::Ifnonnull(self.read_branch(opcode_pc)?),
+            0xc8 => Instruction::Goto_w(self.read_branch_w(opcode_pc)?),
+            0xc9 => Instruction::Jsr_w(self.read_branch_w(opcode_pc)?),
+            0xc4 => self.read_wide()?,
+            _ => Instruction::Unimplemented(opcode),
+        };
+        Some((opcode_pc, instruction))
+    }
+}
+
+/// 链接时预解码的方法代码：分支目标换算为指令下标，解释器直接按下标执行
+#[derive(Debug, Clone, Default)]
+pub struct DecodedCode {
+    pub instructions: Vec<Instruction>,
+    /// 每条指令的opcode在字节码中的地址，异常表、行号表和栈轨迹使用字节码地址
+    pub byte_pcs: Vec<usize>,
+}
+
+impl DecodedCode {
+    pub fn decode(code: &[U1]) -> Self {
+        let (byte_pcs, mut instructions): (Vec<usize>, Vec<Instruction>) = ByteCodesInterator::new(code).unzip();
+        for instruction in instructions.iter_mut() {
+            // 目标不在指令边界上的分支无法执行，执行到时报告
+            if instruction.map_branch_targets(|target| byte_pcs.binary_search(&target).ok()).is_none() {
+                *instruction = Instruction::Unimplemented(instruction.opcode());
             }
-            0xc4 => Some(Instruction::Wide),
-            _ => None,
         }
+        DecodedCode { instructions, byte_pcs }
+    }
+
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Instruction> {
+        self.instructions.get(index)
+    }
+
+    /// 下标处指令的字节码地址
+    pub fn byte_pc(&self, index: usize) -> usize {
+        self.byte_pcs.get(index).copied().unwrap_or(usize::MAX)
+    }
+
+    /// 字节码地址处的指令下标
+    pub fn index_of(&self, byte_pc: usize) -> Option<usize> {
+        self.byte_pcs.binary_search(&byte_pc).ok()
     }
 }
 
@@ -365,10 +475,19 @@ pub struct Method {
     pub name: String,
     pub descriptor: String,
     pub access_flags: u16,
-    pub code: Vec<u8>,
+    /// 链接时预解码的代码，方法的各个副本共享
+    pub code: Rc<DecodedCode>,
     pub max_stack: usize,
     pub max_locals: usize,
-    pub constant_pool: Vec<CpInfo>,
+    /// 所在类的运行时常量池
+    pub constant_pool: Rc<RunTimeConstantPool>,
+    pub exception_table: Vec<ExceptionEntry>,
+    /// 声明该方法的类，由类加载时填入
+    pub class_name: String,
+    /// 所在类的 SourceFile 属性
+    pub source_file: Option<String>,
+    /// LineNumberTable：(start_pc, line_number)
+    pub line_numbers: Vec<(U2, U2)>,
 }
 
 pub fn link_code(method_info: &MethodInfo) -> Option<Code> {
@@ -395,10 +514,10 @@ pub fn link_code(method_info: &MethodInfo) -> Option<Code> {
                 })
             }
             return Some(Code {
-                max_stack: max_stack.clone(),
-                max_locals: max_locals.clone(),
+                max_stack: *max_stack,
+                max_locals: *max_locals,
                 byte_codes: ByteCodes(code.clone()),
-                exception_table_length: exception_table_length.clone(),
+                exception_table_length: *exception_table_length,
                 exception_table: rt_exception_table,
             });
         }
@@ -412,10 +531,14 @@ impl Method {
             name,
             descriptor,
             access_flags,
-            code,
+            code: Rc::new(DecodedCode::decode(&code)),
             max_stack,
             max_locals,
-            constant_pool: Vec::new(),
+            constant_pool: Rc::new(RunTimeConstantPool::new(String::new(), Vec::new())),
+            exception_table: Vec::new(),
+            class_name: String::new(),
+            source_file: None,
+            line_numbers: Vec::new(),
         }
     }
 
@@ -431,22 +554,46 @@ impl Method {
         self.descriptor.clone()
     }
 
-    pub fn get_code(&self) -> &[u8] {
+    pub fn get_code(&self) -> &DecodedCode {
         &self.code
     }
 
+    /// pc处指令对应的源码行号，取 start_pc 不超过pc的最后一个表项
+    pub fn line_number_at(&self, pc: usize) -> Option<U2> {
+        self.line_numbers
+            .iter()
+            .filter(|(start_pc, _)| *start_pc as usize <= pc)
+            .max_by_key(|(start_pc, _)| *start_pc)
+            .map(|(_, line)| *line)
+    }
+
     /// 检查方法是否为native方法
     pub fn is_native(&self) -> bool {
         // ACC_NATIVE = 0x0100
         (self.access_flags & 0x0100) != 0
     }
 
+    /// 检查方法是否为静态方法（ACC_STATIC = 0x0008）
+    pub fn is_static(&self) -> bool {
+        (self.access_flags & 0x0008) != 0
+    }
+
+    /// 检查方法是否为私有方法（ACC_PRIVATE = 0x0002）
+    pub fn is_private(&self) -> bool {
+        (self.access_flags & 0x0002) != 0
+    }
+
+    /// 检查方法是否为抽象方法（ACC_ABSTRACT = 0x0400）
+    pub fn is_abstract(&self) -> bool {
+        (self.access_flags & 0x0400) != 0
+    }
+
     /// 获取方法的完整标识符，用于native方法注册表查找
     pub fn get_native_key(&self, class_name: &str) -> String {
         format!("{}.{}{}", class_name, self.name, self.descriptor)
     }
 
-    pub fn from_method_info(method_info: &MethodInfo, constant_pool: &Vec<CpInfo>) -> Self {
+    pub fn from_method_info(method_info: &MethodInfo, constant_pool: &Rc<RunTimeConstantPool>) -> Self {
         // 1. 获取方法名和描述符
         let name = constant_pool.get_utf8_string(method_info.name_index);
         let descriptor = constant_pool.get_utf8_string(method_info.descriptor_index);
@@ -458,17 +605,35 @@ impl Method {
         let mut code = Vec::new();
         let mut max_stack = 0;
         let mut max_locals = 0;
+        let mut exception_table = Vec::new();
+        let mut line_numbers = Vec::new();
         
         for attr in &method_info.attributes {
             if let AttributeInfo::Code { 
                 max_stack: stack_size, 
                 max_locals: locals_size, 
                 code: bytecode, 
+                exception_table: entries,
+                attributes: code_attributes,
                 .. 
             } = attr {
                 max_stack = *stack_size as usize;
                 max_locals = *locals_size as usize;
                 code = bytecode.clone();
+                exception_table = entries
+                    .iter()
+                    .map(|&(start_pc, end_pc, handler_pc, catch_type)| ExceptionEntry {
+                        start_pc,
+                        end_pc,
+                        handler_pc,
+                        catch_type,
+                    })
+                    .collect();
+                for code_attr in code_attributes {
+                    if let AttributeInfo::LineNumberTable { line_number_table, .. } = code_attr {
+                        line_numbers.extend(line_number_table.iter().cloned());
+                    }
+                }
                 break;
             }
         }
@@ -478,10 +643,97 @@ impl Method {
             name,
             descriptor,
             access_flags,
-            code,
+            code: Rc::new(DecodedCode::decode(&code)),
             max_stack,
             max_locals,
-            constant_pool: constant_pool.clone(),
+            constant_pool: Rc::clone(constant_pool),
+            exception_table,
+            class_name: String::new(),
+            source_file: None,
+            line_numbers,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_resolves_branches_and_wide_operands() {
+        let code = vec![
+            0x03,                   // 0: iconst_0
+            0x3c,                   // 1: istore_1
+            0x1b,                   // 2: iload_1
+            0x10, 10,               // 3: bipush 10
+            0xa2, 0x00, 0x0c,       // 5: if_icmpge +12 -> 17
+            0xc4, 0x84, 0x01, 0x00, 0x01, 0x2c, // 8: wide iinc 256, 300
+            0xa7, 0xff, 0xf4,       // 14: goto -12 -> 2
+            0x69,                   // 17: lmul
+            0x75,                   // 18: lneg
+            0xb1,                   // 19: return
+        ];
+        let decoded = DecodedCode::decode(&code);
+        assert_eq!(
+            decoded.instructions,
+            vec![
+                Instruction::Iconst_0,
+                Instruction::Istore_1,
+                Instruction::Iload_1,
+                Instruction::Bipush(10),
+                Instruction::If_icmpge(7),
+                Instruction::Iinc(256, 300),
+                Instruction::Goto(2),
+                Instruction::Lmul,
+                Instruction::Lneg,
+                Instruction::Return,
+            ]
+        );
+        assert_eq!(decoded.byte_pcs, vec![0, 1, 2, 3, 5, 8, 14, 17, 18, 19]);
+        assert_eq!(decoded.index_of(17), Some(7));
+        assert_eq!(decoded.index_of(4), None);
+        assert_eq!(decoded.get(5).map(Instruction::opcode), Some(0x84));
+    }
+
+    #[test]
+    fn test_decode_switch_padding_is_relative_to_method_start() {
+        let code = vec![
+            0x1a,                   // 0: iload_0
+            0xaa,                   // 1: tableswitch，填充2字节
+            0x00, 0x00,
+            0x00, 0x00, 0x00, 0x2b, // default: +43 -> 44
+            0x00, 0x00, 0x00, 0x01, // low: 1
+            0x00, 0x00, 0x00, 0x02, // high: 2
+            0x00, 0x00, 0x00, 0x2c, // 1: +44 -> 45
+            0x00, 0x00, 0x00, 0x2d, // 2: +45 -> 46
+            0x04,                   // 24: iconst_1
+            0xab,                   // 25: lookupswitch，填充2字节
+            0x00, 0x00,
+            0x00, 0x00, 0x00, 0x13, // default: +19 -> 44
+            0x00, 0x00, 0x00, 0x01, // npairs: 1
+            0xff, 0xff, 0xff, 0xff, // -1: +20 -> 45
+            0x00, 0x00, 0x00, 0x14,
+            0xb1, 0xb1, 0xb1,       // 44、45、46: return
+        ];
+        let decoded = DecodedCode::decode(&code);
+        assert_eq!(decoded.byte_pcs, vec![0, 1, 24, 25, 44, 45, 46]);
+        assert_eq!(decoded.instructions[1], Instruction::Tableswitch(4, 1, 2, vec![5, 6]));
+        assert_eq!(decoded.instructions[3], Instruction::Lookupswitch(4, vec![(-1, 5)]));
+    }
+
+    #[test]
+    fn test_decode_stops_at_truncated_switch_tables() {
+        // low=i32::MIN、high=i32::MAX的tableswitch和npairs=i32::MAX的lookupswitch之后没有跳转表
+        let tableswitch = [0xaa, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x7f, 0xff, 0xff, 0xff];
+        assert!(DecodedCode::decode(&tableswitch).instructions.is_empty());
+        let lookupswitch = [0xab, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7f, 0xff, 0xff, 0xff];
+        assert!(DecodedCode::decode(&lookupswitch).instructions.is_empty());
+    }
+
+    #[test]
+    fn test_decode_marks_invalid_branch_targets() {
+        // goto +2 落在自己的操作数中间
+        let decoded = DecodedCode::decode(&[0xa7, 0x00, 0x02, 0xb1]);
+        assert_eq!(decoded.instructions, vec![Instruction::Unimplemented(0xa7), Instruction::Return]);
+    }
+}
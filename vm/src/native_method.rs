@@ -9,6 +9,12 @@ pub struct NativeMethodRegistry {
     methods: std::collections::HashMap<String, Box<dyn NativeMethod>>,
 }
 
+impl Default for NativeMethodRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NativeMethodRegistry {
     pub fn new() -> Self {
         let mut registry = NativeMethodRegistry {
@@ -26,6 +32,21 @@ impl NativeMethodRegistry {
         registry.register("java/lang/Object.toString", Box::new(ObjectToString));
         // 注册Object.registerNatives空实现
         registry.register("java/lang/Object.registerNatives", Box::new(ObjectRegisterNatives));
+
+        // 注册Throwable栈轨迹方法
+        registry.register("java/lang/Throwable.fillInStackTrace", Box::new(ThrowableFillInStackTrace));
+        registry.register("java/lang/Throwable.getStackTrace", Box::new(ThrowableGetStackTrace));
+        registry.register("java/lang/Throwable.printStackTrace", Box::new(ThrowablePrintStackTrace));
+
+        // 注册进程退出和关闭钩子方法
+        registry.register("java/lang/System.exit", Box::new(SystemExit));
+        registry.register("java/lang/Runtime.getRuntime", Box::new(RuntimeGetRuntime));
+        registry.register("java/lang/Runtime.exit", Box::new(RuntimeExit));
+        registry.register("java/lang/Runtime.halt", Box::new(RuntimeHalt));
+        registry.register("java/lang/Runtime.addShutdownHook", Box::new(RuntimeAddShutdownHook));
+        registry.register("java/lang/Runtime.removeShutdownHook", Box::new(RuntimeRemoveShutdownHook));
+        registry.register("java/lang/System.gc", Box::new(SystemGc));
+        registry.register("java/lang/Runtime.gc", Box::new(RuntimeGc));
         
         registry
     }
@@ -34,8 +55,8 @@ impl NativeMethodRegistry {
         self.methods.insert(name.to_string(), method);
     }
     
-    pub fn get(&self, name: &str) -> Option<&Box<dyn NativeMethod>> {
-        self.methods.get(name)
+    pub fn get(&self, name: &str) -> Option<&(dyn NativeMethod + 'static)> {
+        self.methods.get(name).map(|m| m.as_ref())
     }
 }
 
@@ -77,7 +98,7 @@ impl NativeMethod for SystemOutPrintln {
                         if let Some(s) = string_map.get(ptr) {
                             jvm_log!("[Native] System.out.println: found string in map: '{}'", s);
                             println!("{}", s);
-                        } else if (ptr.0 as usize) % 8 == 0 && (ptr.0 as usize) > 0x1000 {
+                        } else if (ptr.0 as usize).is_multiple_of(8) && (ptr.0 as usize) > 0x1000 {
                             // 仅对对齐且非伪造指针尝试解码
                             match extract_string_content(*ptr) {
                                 Ok(s) => {
@@ -144,7 +165,7 @@ impl NativeMethod for StringBuilderAppend {
         
         let value = &args[1];
         let mut map = vm.string_builder_map.borrow_mut();
-        let entry = map.entry(this_ptr).or_insert_with(String::new);
+        let entry = map.entry(this_ptr).or_default();
         
         match value {
             JvmValue::Int(v) => entry.push_str(&v.to_string()),
@@ -155,7 +176,7 @@ impl NativeMethod for StringBuilderAppend {
                     entry.push_str("null");
                 } else if let Some(s) = vm.string_map.borrow().get(ptr) {
                     entry.push_str(s);
-                } else if (ptr.0 as usize) % 8 == 0 && (ptr.0 as usize) > 0x1000 {
+                } else if (ptr.0 as usize).is_multiple_of(8) && (ptr.0 as usize) > 0x1000 {
                     if let Ok(s) = extract_string_content(*ptr) {
                         entry.push_str(&s);
                     } else {
@@ -246,7 +267,7 @@ unsafe impl Sync for ObjectToString {}
 impl NativeMethod for ObjectToString {
     fn invoke(&self, args: Vec<JvmValue>, vm: &mut crate::vm::Vm) -> Result<Option<JvmValue>, JvmError> {
         jvm_log!("[Native] Object.toString called");
-        let s = format!("Object@{:x}", args.get(0).and_then(|v| if let JvmValue::ObjRef(ptr) = v { Some(ptr.0 as usize) } else { None }).unwrap_or(0));
+        let s = format!("Object@{:x}", args.first().and_then(|v| if let JvmValue::ObjRef(ptr) = v { Some(ptr.0 as usize) } else { None }).unwrap_or(0));
         let string_ptr = RawPtr(Box::into_raw(Box::new(())) as *mut u8);
         vm.string_map.borrow_mut().insert(string_ptr, s);
         Ok(Some(JvmValue::ObjRef(string_ptr)))
@@ -266,8 +287,238 @@ impl NativeMethod for ObjectRegisterNatives {
     }
 }
 
+/// 取出实例方法的this引用
+fn this_ref(args: &[JvmValue], method: &str) -> Result<RawPtr, JvmError> {
+    match args.first() {
+        Some(JvmValue::ObjRef(ptr)) if !ptr.is_null() => Ok(*ptr),
+        _ => Err(JvmError::NullPointerError(format!("{}: this引用为null", method))),
+    }
+}
+
+/// Throwable.fillInStackTrace实现
+#[derive(Clone)]
+pub struct ThrowableFillInStackTrace;
+
+unsafe impl Send for ThrowableFillInStackTrace {}
+unsafe impl Sync for ThrowableFillInStackTrace {}
+
+impl NativeMethod for ThrowableFillInStackTrace {
+    fn invoke(&self, args: Vec<JvmValue>, vm: &mut crate::vm::Vm) -> Result<Option<JvmValue>, JvmError> {
+        let this_ptr = this_ref(&args, "Throwable.fillInStackTrace")?;
+        vm.fill_in_stack_trace(this_ptr);
+        Ok(Some(JvmValue::ObjRef(this_ptr)))
+    }
+}
+
+/// Throwable.getStackTrace实现，返回 StackTraceElement[]
+#[derive(Clone)]
+pub struct ThrowableGetStackTrace;
+
+unsafe impl Send for ThrowableGetStackTrace {}
+unsafe impl Sync for ThrowableGetStackTrace {}
+
+impl NativeMethod for ThrowableGetStackTrace {
+    fn invoke(&self, args: Vec<JvmValue>, vm: &mut crate::vm::Vm) -> Result<Option<JvmValue>, JvmError> {
+        let this_ptr = this_ref(&args, "Throwable.getStackTrace")?;
+        let trace = vm.get_stack_trace(this_ptr);
+        let element_klass = vm.load("java/lang/StackTraceElement")?;
+        let array_klass = vm.load("[Ljava/lang/StackTraceElement;")?;
+        let array = vm.alloc_array(&array_klass, trace.len())?;
+        // 创建元素和字符串时可能发生垃圾收集，数组和正在填充的元素通过句柄跟随移动
+        let handle = vm.push_handle(array);
+        let result = Self::fill_elements(vm, handle, &trace, &element_klass);
+        let array = vm.handle(handle);
+        vm.pop_handles(handle);
+        result?;
+        Ok(Some(JvmValue::ObjRef(array)))
+    }
+}
+
+impl ThrowableGetStackTrace {
+    /// 为栈轨迹的每一帧创建StackTraceElement，存入句柄array指向的数组
+    fn fill_elements(vm: &mut crate::vm::Vm, array: usize, trace: &[crate::stack_trace::StackTraceElement], element_klass: &crate::class::Klass) -> Result<(), JvmError> {
+        for (index, element) in trace.iter().enumerate() {
+            let obj = vm.alloc_object(element_klass)?;
+            let obj = vm.push_handle(obj);
+            let declaring_class = element.class_name.replace('/', ".");
+            for (field_name, content) in [
+                ("declaringClass", Some(declaring_class.as_str())),
+                ("methodName", Some(element.method_name.as_str())),
+                ("fileName", element.file_name.as_deref()),
+            ] {
+                let value = match content {
+                    Some(content) => JvmValue::ObjRef(vm.create_string_object(content)?),
+                    None => JvmValue::Null,
+                };
+                vm.put_field_by_name(vm.handle(obj), "java/lang/StackTraceElement", field_name, value)?;
+            }
+            // 与HotSpot一致：没有行号为-1，native方法为-2
+            let line_number = match element.line_number {
+                _ if element.is_native => -2,
+                Some(line) => line as i32,
+                None => -1,
+            };
+            vm.put_field_by_name(vm.handle(obj), "java/lang/StackTraceElement", "lineNumber", JvmValue::Int(line_number as u32))?;
+            vm.heap.borrow_mut().put_array_element(vm.handle(array), index, JvmValue::ObjRef(vm.handle(obj)));
+            vm.pop_handles(obj);
+        }
+        Ok(())
+    }
+}
+
+/// Throwable.printStackTrace实现，输出到标准错误
+#[derive(Clone)]
+pub struct ThrowablePrintStackTrace;
+
+unsafe impl Send for ThrowablePrintStackTrace {}
+unsafe impl Sync for ThrowablePrintStackTrace {}
+
+impl NativeMethod for ThrowablePrintStackTrace {
+    fn invoke(&self, args: Vec<JvmValue>, vm: &mut crate::vm::Vm) -> Result<Option<JvmValue>, JvmError> {
+        let this_ptr = this_ref(&args, "Throwable.printStackTrace")?;
+        eprint!("{}", vm.format_stack_trace(this_ptr));
+        Ok(None)
+    }
+}
+
+/// 读取native方法的int参数
+fn int_arg(args: &[JvmValue], index: usize, method: &str) -> Result<i32, JvmError> {
+    match args.get(index) {
+        Some(JvmValue::Int(v)) => Ok(*v as i32),
+        other => Err(JvmError::IllegalStateError(format!("{}: 参数{}不是int: {:?}", method, index, other))),
+    }
+}
+
+/// 读取关闭钩子方法的线程参数，null时抛出NullPointerException
+fn hook_arg(args: &[JvmValue], method: &str) -> Result<RawPtr, JvmError> {
+    match args.get(1) {
+        Some(JvmValue::ObjRef(ptr)) if !ptr.is_null() => Ok(*ptr),
+        _ => Err(JvmError::NullPointerError(format!("{}: hook为null", method))),
+    }
+}
+
+/// System.exit实现，请求解释器展开所有帧后以给定状态码退出
+#[derive(Clone)]
+pub struct SystemExit;
+
+unsafe impl Send for SystemExit {}
+unsafe impl Sync for SystemExit {}
+
+impl NativeMethod for SystemExit {
+    fn invoke(&self, args: Vec<JvmValue>, _vm: &mut crate::vm::Vm) -> Result<Option<JvmValue>, JvmError> {
+        let status = int_arg(&args, 0, "System.exit")?;
+        jvm_log!("[Native] System.exit({})", status);
+        Err(JvmError::Exit(status))
+    }
+}
+
+/// Runtime.getRuntime实现
+#[derive(Clone)]
+pub struct RuntimeGetRuntime;
+
+unsafe impl Send for RuntimeGetRuntime {}
+unsafe impl Sync for RuntimeGetRuntime {}
+
+impl NativeMethod for RuntimeGetRuntime {
+    fn invoke(&self, _args: Vec<JvmValue>, vm: &mut crate::vm::Vm) -> Result<Option<JvmValue>, JvmError> {
+        Ok(Some(JvmValue::ObjRef(vm.runtime_object()?)))
+    }
+}
+
+/// Runtime.exit实现，与System.exit相同
+#[derive(Clone)]
+pub struct RuntimeExit;
+
+unsafe impl Send for RuntimeExit {}
+unsafe impl Sync for RuntimeExit {}
+
+impl NativeMethod for RuntimeExit {
+    fn invoke(&self, args: Vec<JvmValue>, _vm: &mut crate::vm::Vm) -> Result<Option<JvmValue>, JvmError> {
+        this_ref(&args, "Runtime.exit")?;
+        let status = int_arg(&args, 1, "Runtime.exit")?;
+        jvm_log!("[Native] Runtime.exit({})", status);
+        Err(JvmError::Exit(status))
+    }
+}
+
+/// Runtime.halt实现，立即终止而不运行关闭钩子
+#[derive(Clone)]
+pub struct RuntimeHalt;
+
+unsafe impl Send for RuntimeHalt {}
+unsafe impl Sync for RuntimeHalt {}
+
+impl NativeMethod for RuntimeHalt {
+    fn invoke(&self, args: Vec<JvmValue>, _vm: &mut crate::vm::Vm) -> Result<Option<JvmValue>, JvmError> {
+        this_ref(&args, "Runtime.halt")?;
+        let status = int_arg(&args, 1, "Runtime.halt")?;
+        jvm_log!("[Native] Runtime.halt({})", status);
+        Err(JvmError::Halt(status))
+    }
+}
+
+/// Runtime.addShutdownHook实现
+#[derive(Clone)]
+pub struct RuntimeAddShutdownHook;
+
+unsafe impl Send for RuntimeAddShutdownHook {}
+unsafe impl Sync for RuntimeAddShutdownHook {}
+
+impl NativeMethod for RuntimeAddShutdownHook {
+    fn invoke(&self, args: Vec<JvmValue>, vm: &mut crate::vm::Vm) -> Result<Option<JvmValue>, JvmError> {
+        this_ref(&args, "Runtime.addShutdownHook")?;
+        let hook = hook_arg(&args, "Runtime.addShutdownHook")?;
+        vm.add_shutdown_hook(hook);
+        Ok(None)
+    }
+}
+
+/// Runtime.removeShutdownHook实现
+#[derive(Clone)]
+pub struct RuntimeRemoveShutdownHook;
+
+unsafe impl Send for RuntimeRemoveShutdownHook {}
+unsafe impl Sync for RuntimeRemoveShutdownHook {}
+
+impl NativeMethod for RuntimeRemoveShutdownHook {
+    fn invoke(&self, args: Vec<JvmValue>, vm: &mut crate::vm::Vm) -> Result<Option<JvmValue>, JvmError> {
+        this_ref(&args, "Runtime.removeShutdownHook")?;
+        let hook = hook_arg(&args, "Runtime.removeShutdownHook")?;
+        Ok(Some(JvmValue::Boolean(vm.remove_shutdown_hook(hook) as u8)))
+    }
+}
+
+/// System.gc实现
+#[derive(Clone)]
+pub struct SystemGc;
+
+unsafe impl Send for SystemGc {}
+unsafe impl Sync for SystemGc {}
+
+impl NativeMethod for SystemGc {
+    fn invoke(&self, _args: Vec<JvmValue>, vm: &mut crate::vm::Vm) -> Result<Option<JvmValue>, JvmError> {
+        vm.collect_garbage();
+        Ok(None)
+    }
+}
+
+/// Runtime.gc实现
+#[derive(Clone)]
+pub struct RuntimeGc;
+
+unsafe impl Send for RuntimeGc {}
+unsafe impl Sync for RuntimeGc {}
+
+impl NativeMethod for RuntimeGc {
+    fn invoke(&self, args: Vec<JvmValue>, vm: &mut crate::vm::Vm) -> Result<Option<JvmValue>, JvmError> {
+        this_ref(&args, "Runtime.gc")?;
+        vm.collect_garbage();
+        Ok(None)
+    }
+}
+
 /// 从Java String对象中提取字符串内容
-fn extract_string_content(ptr: RawPtr) -> Result<String, JvmError> {
+pub fn extract_string_content(ptr: RawPtr) -> Result<String, JvmError> {
     if ptr.is_null() {
         return Err(JvmError::NullPointerError("String pointer is null".to_string()));
     }
@@ -295,7 +546,7 @@ fn extract_string_content(ptr: RawPtr) -> Result<String, JvmError> {
         let length_ptr = char_array_ptr.add(length_offset) as *const i32;
         let length = *length_ptr;
         
-        if length < 0 || length > 1000000 {
+        if !(0..=1000000).contains(&length) {
             return Err(JvmError::IllegalStateError("Invalid string length".to_string()));
         }
         
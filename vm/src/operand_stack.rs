@@ -1,80 +1,218 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::error::JvmError;
 use crate::heap::RawPtr;
 
+/// 操作数栈与局部变量表共用的槽位，int/float/long/double的各32位部分都存为Int，引用存为Ref
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Slot {
+    Int(i32),
+    Ref(RawPtr),
+}
+
+/// 帧的槽位数组，由操作数栈（或局部变量表）和线程登记的帧根共享：
+/// 指令持有帧的可变引用时，垃圾收集通过帧根更新其中的引用，每次访问只在单个操作内借用
+pub type SharedSlots = Rc<RefCell<Vec<Slot>>>;
+
+/// 操作数栈，所有值按JVMS的入栈顺序保存在同一个槽位数组中，long/double占两个槽位
+/// 溢出、下溢和槽位类型不符都以 JvmError 返回，由调用方沿解释器循环传播
 #[derive(Debug)]
 pub struct OperandStack {
     max_size: usize,
-    values: Vec<i32>,
-    obj_refs: Vec<RawPtr>, // 存储对象引用
+    slots: SharedSlots,
 }
 
 impl OperandStack {
     pub fn new(max_size: usize) -> Self {
-        // 确保最小栈大小为1024，防止栈溢出
-        let actual_max_size = std::cmp::max(max_size, 1024);
         OperandStack {
-            max_size: actual_max_size,
-            values: Vec::with_capacity(actual_max_size),
-            obj_refs: Vec::with_capacity(actual_max_size),
+            max_size,
+            slots: Rc::new(RefCell::new(Vec::with_capacity(max_size))),
         }
     }
 
-    pub fn push_int(&mut self, value: i32) {
-        if self.values.len() >= self.max_size {
-            panic!("Stack overflow: values stack size {} >= max_size {}", self.values.len(), self.max_size);
+    /// 与帧根共享的槽位（栈底在前），垃圾收集时更新其中的引用
+    pub fn shared_slots(&self) -> SharedSlots {
+        Rc::clone(&self.slots)
+    }
+
+    /// 压入一个槽位
+    pub fn push_slot(&mut self, slot: Slot) -> Result<(), JvmError> {
+        let mut slots = self.slots.borrow_mut();
+        if slots.len() >= self.max_size {
+            return Err(JvmError::IllegalStateError(format!(
+                "Operand stack overflow: stack size {} >= max_stack {}",
+                slots.len(),
+                self.max_size
+            )));
         }
-        self.values.push(value);
+        slots.push(slot);
+        Ok(())
     }
 
-    pub fn pop_int(&mut self) -> i32 {
-        self.values.pop().expect("Stack underflow")
+    /// 弹出一个槽位，不关心类型
+    pub fn pop_slot(&mut self) -> Result<Slot, JvmError> {
+        self.slots
+            .borrow_mut()
+            .pop()
+            .ok_or_else(|| JvmError::StackUnderflowError("Stack underflow".to_string()))
     }
 
-    pub fn push_null(&mut self) {
-        self.push_int(0);
+    /// 查看栈顶槽位，但不弹出
+    fn peek_slot(&self) -> Result<Slot, JvmError> {
+        self.slots
+            .borrow()
+            .last()
+            .copied()
+            .ok_or_else(|| JvmError::StackUnderflowError("Stack underflow".to_string()))
     }
-    
-    pub fn push_obj_ref(&mut self, obj_ref: RawPtr) {
-        if self.obj_refs.len() >= self.max_size {
-            panic!("Stack overflow: obj_refs stack size {} >= max_size {}", self.obj_refs.len(), self.max_size);
-        }
-        self.obj_refs.push(obj_ref);
+
+    pub fn push_int(&mut self, value: i32) -> Result<(), JvmError> {
+        self.push_slot(Slot::Int(value))
+    }
+
+    pub fn pop_int(&mut self) -> Result<i32, JvmError> {
+        expect_int(self.pop_slot()?)
     }
-    
-    pub fn pop_obj_ref(&mut self) -> RawPtr {
-        self.obj_refs.pop().expect("Stack underflow")
+
+    /// 压入long值，占两个槽位：高32位在下，低32位在栈顶
+    pub fn push_long(&mut self, value: i64) -> Result<(), JvmError> {
+        self.push_int((value >> 32) as i32)?;
+        self.push_int(value as i32)
     }
-    
+
+    /// 弹出long值，先弹低32位再弹高32位
+    pub fn pop_long(&mut self) -> Result<i64, JvmError> {
+        let low = self.pop_int()? as u32 as u64;
+        let high = self.pop_int()? as u32 as u64;
+        Ok(((high << 32) | low) as i64)
+    }
+
+    /// 压入float值，按IEEE-754位模式占一个槽位
+    pub fn push_float(&mut self, value: f32) -> Result<(), JvmError> {
+        self.push_int(value.to_bits() as i32)
+    }
+
+    pub fn pop_float(&mut self) -> Result<f32, JvmError> {
+        Ok(f32::from_bits(self.pop_int()? as u32))
+    }
+
+    /// 压入double值，与long相同的两槽位编码
+    pub fn push_double(&mut self, value: f64) -> Result<(), JvmError> {
+        self.push_long(value.to_bits() as i64)
+    }
+
+    pub fn pop_double(&mut self) -> Result<f64, JvmError> {
+        Ok(f64::from_bits(self.pop_long()? as u64))
+    }
+
+    /// 压入null引用，占一个引用槽位
+    pub fn push_null(&mut self) -> Result<(), JvmError> {
+        self.push_obj_ref(RawPtr(std::ptr::null_mut()))
+    }
+
+    pub fn push_obj_ref(&mut self, obj_ref: RawPtr) -> Result<(), JvmError> {
+        self.push_slot(Slot::Ref(obj_ref))
+    }
+
+    pub fn pop_obj_ref(&mut self) -> Result<RawPtr, JvmError> {
+        expect_ref(self.pop_slot()?)
+    }
+
     /// 查看栈顶的整数值，但不弹出
-    pub fn peek_int(&self) -> i32 {
-        *self.values.last().expect("Stack underflow")
+    pub fn peek_int(&self) -> Result<i32, JvmError> {
+        expect_int(self.peek_slot()?)
     }
-    
+
     /// 查看栈顶的对象引用，但不弹出
-    pub fn peek_obj_ref(&self) -> RawPtr {
-        *self.obj_refs.last().expect("Stack underflow")
+    pub fn peek_obj_ref(&self) -> Result<RawPtr, JvmError> {
+        expect_ref(self.peek_slot()?)
     }
-    
-    /// 检查整数值栈是否为空
-    pub fn is_values_empty(&self) -> bool {
-        self.values.is_empty()
-    }
-    
-    /// 检查对象引用栈是否为空
-    pub fn is_obj_refs_empty(&self) -> bool {
-        self.obj_refs.is_empty()
+
+    /// 查看距栈顶depth个槽位处的对象引用（depth=0即栈顶），用于在弹出参数前定位接收者
+    pub fn peek_obj_ref_at(&self, depth: usize) -> Result<RawPtr, JvmError> {
+        let slots = self.slots.borrow();
+        let len = slots.len();
+        if depth >= len {
+            return Err(JvmError::StackUnderflowError(format!(
+                "Stack underflow: peek depth {} with {} slots",
+                depth, len
+            )));
+        }
+        expect_ref(slots[len - 1 - depth])
     }
-    
+
     /// 检查整个操作数栈是否为空
     pub fn is_empty(&self) -> bool {
-        self.values.is_empty() && self.obj_refs.is_empty()
+        self.slots.borrow().is_empty()
+    }
+
+    /// 清空操作数栈，异常处理器入口处只保留异常对象
+    pub fn clear(&mut self) {
+        self.slots.borrow_mut().clear();
     }
 
-    /// 交换栈顶两个整数
-    pub fn swap_top_two_ints(&mut self) {
-        let len = self.values.len();
-        if len >= 2 {
-            self.values.swap(len - 1, len - 2);
+    /// 当前槽位数
+    pub fn len(&self) -> usize {
+        self.slots.borrow().len()
+    }
+
+    /// 复制栈顶count个槽位，并插入到其下方depth个槽位之下
+    /// dup=(1,0) dup_x1=(1,1) dup_x2=(1,2) dup2=(2,0) dup2_x1=(2,1) dup2_x2=(2,2)
+    pub fn dup_insert(&mut self, count: usize, depth: usize) -> Result<(), JvmError> {
+        let mut slots = self.slots.borrow_mut();
+        let len = slots.len();
+        if len < count + depth {
+            return Err(JvmError::StackUnderflowError(format!(
+                "Stack underflow: dup needs {} slots, found {}",
+                count + depth,
+                len
+            )));
+        }
+        if len + count > self.max_size {
+            return Err(JvmError::IllegalStateError(format!(
+                "Operand stack overflow: stack size {} + {} > max_stack {}",
+                len, count, self.max_size
+            )));
         }
+        let copied: Vec<Slot> = slots[len - count..].to_vec();
+        let insert_at = len - count - depth;
+        slots.splice(insert_at..insert_at, copied);
+        Ok(())
+    }
+
+    /// 交换栈顶两个槽位
+    pub fn swap_top_two(&mut self) -> Result<(), JvmError> {
+        let mut slots = self.slots.borrow_mut();
+        let len = slots.len();
+        if len < 2 {
+            return Err(JvmError::StackUnderflowError(format!(
+                "Stack underflow: swap needs 2 slots, found {}",
+                len
+            )));
+        }
+        slots.swap(len - 1, len - 2);
+        Ok(())
+    }
+}
+
+fn expect_int(slot: Slot) -> Result<i32, JvmError> {
+    match slot {
+        Slot::Int(value) => Ok(value),
+        Slot::Ref(ptr) => Err(JvmError::IllegalStateError(format!(
+            "Stack type mismatch: expected int, found reference {:?}",
+            ptr
+        ))),
+    }
+}
+
+fn expect_ref(slot: Slot) -> Result<RawPtr, JvmError> {
+    match slot {
+        Slot::Ref(ptr) => Ok(ptr),
+        Slot::Int(value) => Err(JvmError::IllegalStateError(format!(
+            "Stack type mismatch: expected reference, found int {}",
+            value
+        ))),
     }
 }
 
@@ -85,30 +223,99 @@ mod tests {
     #[test]
     fn test_push_pop_int() {
         let mut stack = OperandStack::new(10);
-        stack.push_int(42);
-        assert_eq!(stack.pop_int(), 42);
+        stack.push_int(42).unwrap();
+        assert_eq!(stack.pop_int().unwrap(), 42);
+    }
+
+    #[test]
+    fn test_push_pop_long() {
+        let mut stack = OperandStack::new(10);
+        stack.push_long(0x1234_5678_9ABC_DEF0).unwrap();
+        stack.push_long(-1).unwrap();
+        assert_eq!(stack.pop_long().unwrap(), -1);
+        assert_eq!(stack.pop_long().unwrap(), 0x1234_5678_9ABC_DEF0);
+    }
+
+    #[test]
+    fn test_push_pop_float_double() {
+        let mut stack = OperandStack::new(10);
+        stack.push_float(-1.5).unwrap();
+        stack.push_double(f64::NEG_INFINITY).unwrap();
+        assert_eq!(stack.pop_double().unwrap(), f64::NEG_INFINITY);
+        assert_eq!(stack.pop_float().unwrap(), -1.5);
     }
 
     #[test]
     fn test_push_null() {
         let mut stack = OperandStack::new(10);
-        stack.push_null();
-        assert_eq!(stack.pop_int(), 0);
+        stack.push_null().unwrap();
+        assert!(stack.pop_int().is_err());
+        stack.push_null().unwrap();
+        assert!(stack.pop_obj_ref().unwrap().is_null());
+    }
+
+    #[test]
+    fn test_mixed_slots_keep_order() {
+        let mut stack = OperandStack::new(10);
+        let ptr = RawPtr(8 as *mut u8);
+        stack.push_int(1).unwrap();
+        stack.push_obj_ref(ptr).unwrap();
+        stack.push_int(2).unwrap();
+        assert_eq!(stack.pop_int().unwrap(), 2);
+        assert_eq!(stack.pop_obj_ref().unwrap(), ptr);
+        assert_eq!(stack.pop_int().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_dup_insert_and_swap() {
+        let mut stack = OperandStack::new(10);
+        let ptr = RawPtr(8 as *mut u8);
+        // dup_x1: ..., ref, int -> ..., int, ref, int
+        stack.push_obj_ref(ptr).unwrap();
+        stack.push_int(7).unwrap();
+        stack.dup_insert(1, 1).unwrap();
+        assert_eq!(stack.len(), 3);
+        assert_eq!(stack.pop_int().unwrap(), 7);
+        assert_eq!(stack.pop_obj_ref().unwrap(), ptr);
+        assert_eq!(stack.pop_int().unwrap(), 7);
+
+        // dup2_x1: long在下方一个槽位之下复制
+        stack.push_int(5).unwrap();
+        stack.push_long(-2).unwrap();
+        stack.dup_insert(2, 1).unwrap();
+        assert_eq!(stack.pop_long().unwrap(), -2);
+        assert_eq!(stack.pop_int().unwrap(), 5);
+        assert_eq!(stack.pop_long().unwrap(), -2);
+
+        stack.push_int(1).unwrap();
+        stack.push_obj_ref(ptr).unwrap();
+        stack.swap_top_two().unwrap();
+        assert_eq!(stack.pop_int().unwrap(), 1);
+        assert_eq!(stack.pop_obj_ref().unwrap(), ptr);
     }
 
     #[test]
-    #[should_panic(expected = "Stack overflow")]
     fn test_stack_overflow() {
         let mut stack = OperandStack::new(2);
-        stack.push_int(1);
-        stack.push_int(2);
-        stack.push_int(3); // 应该触发栈溢出
+        stack.push_int(1).unwrap();
+        stack.push_int(2).unwrap();
+        // 超过max_stack是字节码错误，以内部错误返回而不是panic，也不是Java代码可以捕获的StackOverflowError
+        assert!(matches!(stack.push_int(3), Err(JvmError::IllegalStateError(_))));
+        assert!(matches!(stack.dup_insert(1, 0), Err(JvmError::IllegalStateError(_))));
     }
 
     #[test]
-    #[should_panic(expected = "Stack underflow")]
     fn test_stack_underflow() {
         let mut stack = OperandStack::new(10);
-        stack.pop_int(); // 应该触发栈下溢
+        // 应该返回栈下溢错误而不是panic
+        assert!(matches!(stack.pop_int(), Err(JvmError::StackUnderflowError(_))));
+        assert!(matches!(stack.swap_top_two(), Err(JvmError::StackUnderflowError(_))));
+    }
+
+    #[test]
+    fn test_stack_type_mismatch() {
+        let mut stack = OperandStack::new(10);
+        stack.push_int(1).unwrap();
+        assert!(matches!(stack.pop_obj_ref(), Err(JvmError::IllegalStateError(_))));
     }
 } 
\ No newline at end of file
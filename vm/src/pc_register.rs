@@ -1,4 +1,10 @@
 pub struct PcRegister;
+impl Default for PcRegister {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PcRegister {
     pub fn new() -> PcRegister {
         PcRegister
@@ -1,9 +1,412 @@
-use reader::constant_pool::CpInfo;
+use std::cell::RefCell;
+use std::ops::Deref;
+use std::rc::Rc;
 
-pub struct RunTimeConstantPool;
+use reader::constant_pool::{ConstantPool, ConstantPoolExt, CpInfo};
+use reader::types::{ACC_PRIVATE, ACC_PROTECTED, ACC_PUBLIC, U2};
+
+use crate::class::{InstanceKlass, Klass};
+use crate::error::JvmError;
+use crate::field::Field;
+use crate::heap::RawPtr;
+use crate::jvm_log;
+use crate::method::Method;
+use crate::vm::Vm;
+
+/// 解析后的字段引用
+#[derive(Debug)]
+pub struct ResolvedField {
+    /// 声明字段的类，静态字段按它存储
+    pub class_name: String,
+    pub name: String,
+    pub descriptor: String,
+    /// 实例字段在对象中的偏移
+    pub offset: usize,
+    pub is_static: bool,
+}
+
+/// 解析后的方法引用
+#[derive(Debug)]
+pub struct ResolvedMethod {
+    pub method: Method,
+    /// invokevirtual/invokeinterface上次选中的方法及接收者的类ID，接收者的类不变时不再查方法表
+    selected: RefCell<Option<(usize, Rc<Method>)>>,
+}
+
+impl ResolvedMethod {
+    /// 按接收者的运行时类选择invokevirtual调用的方法，接收者没有对象头时返回None
+    pub fn select_virtual(&self, vm: &mut Vm, receiver: RawPtr) -> Result<Option<Rc<Method>>, JvmError> {
+        self.select(vm, receiver, |vm, klass| vm.select_virtual_method(klass, &self.method.name, &self.method.descriptor))
+    }
+
+    /// 按接收者的运行时类选择invokeinterface调用的方法，接收者的类必须实现interface_name；
+    /// 接收者没有对象头时返回None
+    pub fn select_interface(&self, vm: &mut Vm, receiver: RawPtr, interface_name: &str) -> Result<Option<Rc<Method>>, JvmError> {
+        self.select(vm, receiver, |vm, klass| {
+            vm.select_interface_method(klass, interface_name, &self.method.name, &self.method.descriptor)
+        })
+    }
+
+    /// 在接收者的类上选择方法，结果按类ID缓存
+    fn select(
+        &self,
+        vm: &mut Vm,
+        receiver: RawPtr,
+        select: impl FnOnce(&Vm, &Klass) -> Result<Method, JvmError>,
+    ) -> Result<Option<Rc<Method>>, JvmError> {
+        let class_id = match Vm::object_class_id(receiver) {
+            Some(class_id) => class_id,
+            None => return Ok(None),
+        };
+        if let Some((cached_id, method)) = self.selected.borrow().as_ref() {
+            if *cached_id == class_id {
+                return Ok(Some(Rc::clone(method)));
+            }
+        }
+        let method = match vm.with_object_klass(receiver, select)? {
+            Some(method) => Rc::new(method?),
+            None => return Ok(None),
+        };
+        *self.selected.borrow_mut() = Some((class_id, Rc::clone(&method)));
+        Ok(Some(method))
+    }
+}
+
+/// 常量池项的解析结果
+#[derive(Debug, Clone)]
+enum Entry {
+    Class(Rc<Klass>),
+    Field(Rc<ResolvedField>),
+    Method(Rc<ResolvedMethod>),
+    String(RawPtr),
+    /// 解析失败时抛出的链接错误，之后对该项的解析抛出同样的错误（JVMS 5.4.3）
+    Error(LinkageError),
+}
+
+/// 缓存在常量池项上的解析错误
+#[derive(Debug, Clone)]
+enum LinkageError {
+    NoSuchField(String),
+    NoSuchMethod(String),
+    IllegalAccess(String),
+    IncompatibleClassChange(String),
+}
+
+impl LinkageError {
+    fn of(err: &JvmError) -> Option<Self> {
+        match err {
+            JvmError::NoSuchFieldError(msg) => Some(LinkageError::NoSuchField(msg.clone())),
+            JvmError::NoSuchMethodError(msg) => Some(LinkageError::NoSuchMethod(msg.clone())),
+            JvmError::IllegalAccessError(msg) => Some(LinkageError::IllegalAccess(msg.clone())),
+            JvmError::IncompatibleClassChangeError(msg) => Some(LinkageError::IncompatibleClassChange(msg.clone())),
+            _ => None,
+        }
+    }
+
+    fn to_error(&self) -> JvmError {
+        match self {
+            LinkageError::NoSuchField(msg) => JvmError::NoSuchFieldError(msg.clone()),
+            LinkageError::NoSuchMethod(msg) => JvmError::NoSuchMethodError(msg.clone()),
+            LinkageError::IllegalAccess(msg) => JvmError::IllegalAccessError(msg.clone()),
+            LinkageError::IncompatibleClassChange(msg) => JvmError::IncompatibleClassChangeError(msg.clone()),
+        }
+    }
+}
+
+/// 运行时常量池：类文件的常量池加上按需解析的符号引用。
+/// 同一个类的方法共享一个运行时常量池，每项只解析一次，之后直接使用缓存的结果
+#[derive(Debug)]
+pub struct RunTimeConstantPool {
+    /// 常量池所属的类，解析时按它检查访问权限
+    class_name: String,
+    constant_pool: Vec<CpInfo>,
+    /// 下标为常量池索引减1
+    entries: RefCell<Vec<Option<Entry>>>,
+}
+
+impl Deref for RunTimeConstantPool {
+    type Target = Vec<CpInfo>;
+
+    fn deref(&self) -> &Vec<CpInfo> {
+        &self.constant_pool
+    }
+}
 
 impl RunTimeConstantPool {
-    fn new(constant_pool: Vec<CpInfo>) {
-        
+    pub fn new(class_name: String, constant_pool: Vec<CpInfo>) -> Self {
+        let entries = RefCell::new(vec![None; constant_pool.len()]);
+        RunTimeConstantPool { class_name, constant_pool, entries }
+    }
+
+    /// 已缓存的解析结果，链接错误直接返回
+    fn cached(&self, index: U2) -> Result<Option<Entry>, JvmError> {
+        let slot = (index as usize).checked_sub(1);
+        match slot.and_then(|slot| self.entries.borrow().get(slot).cloned()) {
+            Some(Some(Entry::Error(err))) => Err(err.to_error()),
+            Some(entry) => Ok(entry),
+            None => Err(JvmError::IllegalStateError(format!(
+                "常量池索引越界: index={}, len={}",
+                index,
+                self.constant_pool.len()
+            ))),
+        }
+    }
+
+    /// 缓存解析结果；解析中加载类会执行其他代码，不能在解析期间持有entries的借用
+    fn cache<T>(&self, index: U2, result: Result<T, JvmError>, entry: impl FnOnce(&T) -> Entry) -> Result<T, JvmError> {
+        let entry = match &result {
+            Ok(value) => entry(value),
+            Err(err) => match LinkageError::of(err) {
+                Some(err) => Entry::Error(err),
+                None => return result,
+            },
+        };
+        self.entries.borrow_mut()[index as usize - 1] = Some(entry);
+        result
+    }
+
+    /// 解析类引用（JVMS 5.4.3.1）：加载类并检查当前类能否访问它。解析不初始化类，由new等指令在需要时初始化
+    pub fn resolve_class(&self, index: U2, vm: &mut Vm) -> Result<Rc<Klass>, JvmError> {
+        if let Some(Entry::Class(klass)) = self.cached(index)? {
+            return Ok(klass);
+        }
+        let class_name = self.constant_pool.get_class_name(index);
+        jvm_log!("[RCP] {} 解析类引用#{}: {}", self.class_name, index, class_name);
+        let result = self.load_accessible_class(&class_name, vm).map(Rc::new);
+        self.cache(index, result, |klass| Entry::Class(Rc::clone(klass)))
+    }
+
+    /// 解析字段引用（JVMS 5.4.3.2）：依次在类自身、超接口和父类中查找名称与描述符都匹配的字段
+    pub fn resolve_field(&self, index: U2, vm: &mut Vm) -> Result<Rc<ResolvedField>, JvmError> {
+        if let Some(Entry::Field(field)) = self.cached(index)? {
+            return Ok(field);
+        }
+        let (class_name, name, descriptor) = self.constant_pool.get_fieldref_info(index);
+        jvm_log!("[RCP] {} 解析字段引用#{}: {}.{}:{}", self.class_name, index, class_name, name, descriptor);
+        let result = self.load_accessible_class(&class_name, vm).and_then(|_| {
+            let field = lookup_field(vm, &class_name, &name, &descriptor)?
+                .ok_or_else(|| JvmError::NoSuchFieldError(name.clone()))?;
+            if !self.can_access_member(vm, field.get_class_name(), field.get_access_flags()) {
+                return Err(JvmError::IllegalAccessError(format!(
+                    "tried to access field {}.{} from class {}",
+                    field.get_class_name().replace('/', "."),
+                    name,
+                    self.class_name.replace('/', ".")
+                )));
+            }
+            Ok(Rc::new(ResolvedField {
+                class_name: field.get_class_name().to_string(),
+                name: field.get_name(),
+                descriptor: field.get_descriptor(),
+                offset: field.get_offset(),
+                is_static: field.is_static(),
+            }))
+        });
+        self.cache(index, result, |field| Entry::Field(Rc::clone(field)))
+    }
+
+    /// 解析方法引用（JVMS 5.4.3.3）和接口方法引用（JVMS 5.4.3.4）：先在类及其父类中查找，再在超接口中查找。
+    /// 接口方法引用的类必须是接口
+    pub fn resolve_method(&self, index: U2, vm: &mut Vm) -> Result<Rc<ResolvedMethod>, JvmError> {
+        if let Some(Entry::Method(method)) = self.cached(index)? {
+            return Ok(method);
+        }
+        let (class_name, name, descriptor) = self.constant_pool.get_methodref_info(index);
+        jvm_log!("[RCP] {} 解析方法引用#{}: {}.{}{}", self.class_name, index, class_name, name, descriptor);
+        let interface_ref = matches!(self.constant_pool[index as usize - 1], CpInfo::InterfaceMethodRef { .. });
+        let result = self.load_accessible_class(&class_name, vm).and_then(|klass| {
+            if interface_ref && !matches!(&klass, Klass::Instance(instance) if instance.is_interface()) {
+                return Err(JvmError::IncompatibleClassChangeError(format!(
+                    "Found class {}, but interface was expected",
+                    class_name.replace('/', ".")
+                )));
+            }
+            let method = lookup_method(vm, &klass, &name, &descriptor)?.ok_or_else(|| {
+                JvmError::NoSuchMethodError(format!("{}.{}{}", class_name.replace('/', "."), name, descriptor))
+            })?;
+            if !self.can_access_member(vm, &method.class_name, method.access_flags) {
+                return Err(JvmError::IllegalAccessError(format!(
+                    "tried to access method {}.{}{} from class {}",
+                    method.class_name.replace('/', "."),
+                    name,
+                    descriptor,
+                    self.class_name.replace('/', ".")
+                )));
+            }
+            Ok(Rc::new(ResolvedMethod { method, selected: RefCell::new(None) }))
+        });
+        self.cache(index, result, |method| Entry::Method(Rc::clone(method)))
+    }
+
+    /// 解析字符串常量，同一项每次得到同一个String对象
+    pub fn resolve_string(&self, index: U2, vm: &mut Vm) -> Result<RawPtr, JvmError> {
+        if let Some(Entry::String(string)) = self.cached(index)? {
+            return Ok(string);
+        }
+        let content = match &self.constant_pool[index as usize - 1] {
+            CpInfo::String { string_index, .. } => self.constant_pool.get_utf8_string(*string_index),
+            other => return Err(JvmError::IllegalStateError(format!("常量池索引{}不是字符串: {:?}", index, other))),
+        };
+        let result = vm
+            .create_string_object(&content)
+            .map_err(|e| JvmError::IllegalStateError(format!("Failed to create string object: {:?}", e)));
+        self.cache(index, result, |string| Entry::String(*string))
+    }
+
+    /// 访问已解析的字符串常量引用的对象，垃圾收集时作为根并随对象移动更新
+    pub fn visit_string_refs(&self, visitor: &mut dyn FnMut(&mut RawPtr)) {
+        for entry in self.entries.borrow_mut().iter_mut() {
+            if let Some(Entry::String(string)) = entry {
+                visitor(string);
+            }
+        }
+    }
+
+    /// 加载类（不初始化）并检查访问权限：非public的类只能被同一运行时包中的类访问
+    fn load_accessible_class(&self, class_name: &str, vm: &mut Vm) -> Result<Klass, JvmError> {
+        let klass = vm.load_uninitialized(class_name)?;
+        if let Klass::Instance(instance) = &klass {
+            if instance.access_flags & ACC_PUBLIC == 0 && package_of(class_name) != package_of(self.accessor()) {
+                return Err(JvmError::IllegalAccessError(format!(
+                    "tried to access class {} from class {}",
+                    class_name.replace('/', "."),
+                    self.class_name.replace('/', ".")
+                )));
+            }
+        }
+        Ok(klass)
+    }
+
+    /// 当前类能否访问declaring_class中声明的成员（JVMS 5.4.4）
+    fn can_access_member(&self, vm: &mut Vm, declaring_class: &str, access_flags: u16) -> bool {
+        let accessor = self.accessor();
+        if access_flags & ACC_PUBLIC != 0 || self.class_name == declaring_class || accessor == declaring_class {
+            return true;
+        }
+        if access_flags & ACC_PRIVATE != 0 {
+            return false;
+        }
+        package_of(accessor) == package_of(declaring_class)
+            || (access_flags & ACC_PROTECTED != 0 && vm.is_subtype_of(accessor, declaring_class))
+    }
+
+    /// 访问检查使用的类：lambda调用点生成的类代替调用者访问它的私有实现方法
+    fn accessor(&self) -> &str {
+        self.class_name.split("$$Lambda$").next().unwrap_or(&self.class_name)
+    }
+}
+
+/// 运行时包名，即类名最后一个'/'之前的部分
+fn package_of(class_name: &str) -> &str {
+    class_name.rfind('/').map(|end| &class_name[..end]).unwrap_or("")
+}
+
+fn load_instance(vm: &mut Vm, class_name: &str) -> Result<InstanceKlass, JvmError> {
+    match vm.load_uninitialized(class_name)? {
+        Klass::Instance(instance) => Ok(instance),
+        Klass::Array(_) => Err(JvmError::IllegalStateError(format!("{} 不是实例类", class_name))),
+    }
+}
+
+/// 字段查找：类自身声明的字段、直接超接口（递归）、父类（递归）
+fn lookup_field(vm: &mut Vm, class_name: &str, name: &str, descriptor: &str) -> Result<Option<Field>, JvmError> {
+    let klass = load_instance(vm, class_name)?;
+    if let Some(field) = klass.get_declared_field(name, descriptor) {
+        return Ok(Some(field.clone()));
+    }
+    for interface in klass.get_interfaces() {
+        if let Some(field) = lookup_field(vm, interface, name, descriptor)? {
+            return Ok(Some(field));
+        }
+    }
+    match klass.get_super_class_name() {
+        "" => Ok(None),
+        super_class => lookup_field(vm, super_class, name, descriptor),
+    }
+}
+
+/// 方法查找：类及其父类中声明的方法，没有时取超接口中声明的非静态、非私有方法
+fn lookup_method(vm: &mut Vm, klass: &Klass, name: &str, descriptor: &str) -> Result<Option<Method>, JvmError> {
+    let instance = match klass {
+        Klass::Instance(instance) => instance,
+        // 数组类的方法都继承自java/lang/Object
+        Klass::Array(_) => {
+            let object = vm.load_uninitialized("java/lang/Object")?;
+            return lookup_method(vm, &object, name, descriptor);
+        }
+    };
+    if let Some(method) = instance.lookup_method(name, descriptor, vm) {
+        return Ok(Some(method));
+    }
+    for interface in instance.get_all_interfaces() {
+        let interface = load_instance(vm, interface)?;
+        if let Some(method) = interface.get_method(name, descriptor) {
+            if !method.is_static() && !method.is_private() {
+                return Ok(Some(method.clone()));
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ResolutionTest的运行时常量池，由类的所有方法共享
+    fn resolution_test_pool(vm: &mut Vm) -> Rc<RunTimeConstantPool> {
+        let klass = match vm.load("ResolutionTest").unwrap() {
+            Klass::Instance(klass) => klass,
+            Klass::Array(_) => unreachable!(),
+        };
+        let pool = Rc::clone(&klass.get_method("fields", "()I").unwrap().constant_pool);
+        assert!(Rc::ptr_eq(&pool, &klass.get_method("caught", "()I").unwrap().constant_pool));
+        pool
+    }
+
+    /// 常量池中引用class_name.name的字段引用的索引
+    fn fieldref_index(pool: &RunTimeConstantPool, class_name: &str, name: &str) -> U2 {
+        (1..=pool.len() as U2)
+            .find(|&index| {
+                matches!(pool[index as usize - 1], CpInfo::FieldRef { .. }) && {
+                    let (class, field, _) = pool.get_fieldref_info(index);
+                    class == class_name && field == name
+                }
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_resolved_field_is_cached() {
+        let mut vm = Vm::new("resources/test");
+        let pool = resolution_test_pool(&mut vm);
+        let index = fieldref_index(&pool, "ResolutionTest$Derived", "counter");
+
+        let field = pool.resolve_field(index, &mut vm).unwrap();
+        assert_eq!(field.class_name, "ResolutionTest$Base");
+        assert_eq!(field.descriptor, "I");
+        assert!(field.is_static);
+        assert!(Rc::ptr_eq(&field, &pool.resolve_field(index, &mut vm).unwrap()));
+    }
+
+    #[test]
+    fn test_resolution_error_is_cached() {
+        let mut vm = Vm::new("resources/test");
+        let pool = resolution_test_pool(&mut vm);
+        let index = fieldref_index(&pool, "ResolutionTarget", "gone");
+
+        for _ in 0..2 {
+            match pool.resolve_field(index, &mut vm) {
+                Err(JvmError::NoSuchFieldError(message)) => assert_eq!(message, "gone"),
+                other => panic!("Expected NoSuchFieldError, got {:?}", other),
+            }
+            assert!(matches!(pool.entries.borrow()[index as usize - 1], Some(Entry::Error(LinkageError::NoSuchField(_)))));
+        }
+    }
+
+    #[test]
+    fn test_package_of() {
+        assert_eq!(package_of("java/lang/Object"), "java/lang");
+        assert_eq!(package_of("ResolutionTest"), "");
     }
-}
\ No newline at end of file
+}
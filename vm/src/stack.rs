@@ -40,7 +40,7 @@ impl Frame {
         self.operand_stack.pop().expect("Stack underflow")
     }
     pub fn get_local(&self, index: usize) -> JvmValue {
-        self.local_variables.get(index).expect("Invalid local variable index").clone()
+        *self.local_variables.get(index).expect("Invalid local variable index")
     }
     pub fn set_local(&mut self, index: usize, value: JvmValue) {
         if index >= self.local_variables.len() {
@@ -63,6 +63,12 @@ impl fmt::Debug for Stack {
             .finish()
     }
 }
+impl Default for Stack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Stack {
     pub fn new() -> Stack {
         Stack { frames: Vec::new() }
@@ -78,8 +84,8 @@ impl Stack {
         let max_stack = method.max_stack;
         let mut locals: Vec<JvmValue> = receiver
             .into_iter()
-            .chain(args.into_iter())
-            .map(|obj| JvmValue::ObjRef(obj))
+            .chain(args)
+            .map(JvmValue::ObjRef)
             .collect();
         while locals.len() < max_locals {
             locals.push(JvmValue::Null)
@@ -98,6 +104,6 @@ impl Stack {
     }
 
     pub fn cur_frame(&mut self) -> &mut Frame {
-        self.frames.iter_mut().rev().next().expect("No more frame")
+        self.frames.iter_mut().next_back().expect("No more frame")
     }
 }
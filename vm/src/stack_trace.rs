@@ -0,0 +1,109 @@
+use std::fmt;
+
+use crate::method::Method;
+
+/// 正在执行的Java方法帧，Vm 用它们组成调用栈以生成异常栈轨迹
+#[derive(Debug, Clone)]
+pub struct ActiveFrame {
+    pub method: Method,
+    /// 当前正在执行的指令地址
+    pub pc: usize,
+}
+
+impl ActiveFrame {
+    pub fn new(method: Method) -> Self {
+        ActiveFrame { method, pc: 0 }
+    }
+}
+
+/// 栈轨迹中的一帧，对应 java.lang.StackTraceElement
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackTraceElement {
+    /// 内部形式的类名，如 `java/lang/Object`
+    pub class_name: String,
+    pub method_name: String,
+    pub file_name: Option<String>,
+    pub line_number: Option<u16>,
+    pub is_native: bool,
+}
+
+impl StackTraceElement {
+    pub fn of(frame: &ActiveFrame) -> Self {
+        StackTraceElement {
+            class_name: frame.method.class_name.clone(),
+            method_name: frame.method.get_name(),
+            file_name: frame.method.source_file.clone(),
+            line_number: frame.method.line_number_at(frame.pc),
+            is_native: frame.method.is_native(),
+        }
+    }
+}
+
+/// 与HotSpot一致的格式：`pkg.Class.method(File.java:42)`
+impl fmt::Display for StackTraceElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}(", self.class_name.replace('/', "."), self.method_name)?;
+        match (&self.file_name, self.line_number) {
+            _ if self.is_native => write!(f, "Native Method")?,
+            (Some(file), Some(line)) => write!(f, "{}:{}", file, line)?,
+            (Some(file), None) => write!(f, "{}", file)?,
+            (None, _) => write!(f, "Unknown Source")?,
+        }
+        write!(f, ")")
+    }
+}
+
+/// 按 Throwable.printStackTrace 的格式输出一段栈轨迹。
+/// enclosing 为外层异常的栈轨迹，与其末尾相同的帧折叠为 `... n more`
+pub fn write_trace(out: &mut String, trace: &[StackTraceElement], enclosing: Option<&[StackTraceElement]>) {
+    let in_common = match enclosing {
+        Some(enclosing) => trace
+            .iter()
+            .rev()
+            .zip(enclosing.iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count(),
+        None => 0,
+    };
+    for element in &trace[..trace.len() - in_common] {
+        out.push_str(&format!("\tat {}\n", element));
+    }
+    if in_common > 0 {
+        out.push_str(&format!("\t... {} more\n", in_common));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(method_name: &str, line_number: Option<u16>) -> StackTraceElement {
+        StackTraceElement {
+            class_name: "pkg/Main".to_string(),
+            method_name: method_name.to_string(),
+            file_name: Some("Main.java".to_string()),
+            line_number,
+            is_native: false,
+        }
+    }
+
+    #[test]
+    fn test_element_display() {
+        assert_eq!(element("run", Some(42)).to_string(), "pkg.Main.run(Main.java:42)");
+        assert_eq!(element("run", None).to_string(), "pkg.Main.run(Main.java)");
+        let mut unknown = element("run", Some(1));
+        unknown.file_name = None;
+        assert_eq!(unknown.to_string(), "pkg.Main.run(Unknown Source)");
+        unknown.is_native = true;
+        assert_eq!(unknown.to_string(), "pkg.Main.run(Native Method)");
+    }
+
+    #[test]
+    fn test_write_trace_folds_common_frames() {
+        let enclosing = vec![element("wrap", Some(10)), element("main", Some(20))];
+        let cause = vec![element("divide", Some(3)), element("wrap", Some(8)), element("main", Some(20))];
+        let mut out = String::new();
+        write_trace(&mut out, &cause, Some(&enclosing));
+        assert_eq!(out, "\tat pkg.Main.divide(Main.java:3)\n\tat pkg.Main.wrap(Main.java:8)\n\t... 1 more\n");
+    }
+}
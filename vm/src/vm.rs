@@ -1,13 +1,26 @@
-use crate::heap::{AllocError, Heap, RawPtr};
-use crate::{class_loader::BootstrapClassLoader, jvm_thread::JvmThread, };
-use crate::class::Klass;
-use crate::error::JvmError;
+use crate::heap::{AllocError, GcStats, Heap, RawPtr, ReferenceMap, RootKind};
+use crate::{class_loader::BootstrapClassLoader, jvm_thread::{JvmThread, ThreadRoots}, };
+use crate::class::{ItableEntry, Klass};
+use crate::error::{JvmError, ThrownException};
 use crate::JvmValue;
 use crate::native_method::{NativeMethodRegistry, NativeMethod};
 use crate::jvm_log;
+use crate::method::Method;
+use crate::stack_trace::{self, ActiveFrame, StackTraceElement};
 use std::collections::HashMap;
-use reader::constant_pool::ConstantPool;
-use std::cell::RefCell;
+use reader::constant_pool::{ConstantPool, ConstantPoolExt};
+use std::cell::{Cell, RefCell};
+use std::rc::Weak;
+use reader::class_file::ClassFile;
+use reader::types::U2;
+
+/// 堆的默认初始大小
+pub const DEFAULT_INITIAL_HEAP_SIZE: usize = 1024 * 1024;
+/// 堆默认可以扩大到的最大大小
+pub const DEFAULT_MAX_HEAP_SIZE: usize = 256 * 1024 * 1024;
+
+/// 线程栈默认的最大深度（Java方法帧数）
+pub const DEFAULT_MAX_STACK_DEPTH: usize = 4096;
 
 pub struct Vm {
     pub heap: RefCell<Heap>,
@@ -18,53 +31,429 @@ pub struct Vm {
     native_methods: NativeMethodRegistry,
     pub string_builder_map: RefCell<HashMap<crate::heap::RawPtr, String>>,
     pub string_map: RefCell<HashMap<crate::heap::RawPtr, String>>,
+    // 异常对象 -> 栈轨迹（最内层帧在前）
+    pub stack_traces: RefCell<HashMap<crate::heap::RawPtr, Vec<StackTraceElement>>>,
+    // 正在执行的Java方法帧，最内层在末尾
+    call_stack: Vec<ActiveFrame>,
+    // 内部错误在抛出点记录的栈轨迹，转换为Java异常对象时取出
+    pending_trace: Option<Vec<StackTraceElement>>,
+    // 调用指令选中的Java方法的帧，由解释循环压入线程栈
+    pending_frame: Option<crate::jvm_thread::Frame>,
+    // 线程栈允许的最大Java方法帧数，超过时抛出StackOverflowError
+    max_stack_depth: usize,
+    // 已链接的invokedynamic调用点: (调用者类名, 常量池下标) -> 生成的lambda类名
+    call_sites: HashMap<(String, U2), String>,
+    // Runtime.getRuntime返回的单例对象
+    runtime: Option<RawPtr>,
+    // Runtime.addShutdownHook注册的线程对象，按注册顺序在退出时运行
+    shutdown_hooks: Vec<RawPtr>,
+    // 正在运行的解释循环的帧根，垃圾收集时扫描其中的引用，最内层在末尾
+    threads: Vec<ThreadRoots>,
+    // native句柄：Rust代码跨越可能触发垃圾收集的调用持有的对象
+    handles: Vec<RawPtr>,
+    // 正在当前帧的异常表中查找处理器的异常对象
+    pending_exception: Option<RawPtr>,
+    // 正在沿调用栈展开的异常对象，JvmError::JavaException丢弃后条目失效
+    thrown_exceptions: Vec<Weak<Cell<RawPtr>>>,
+    // 已完成的垃圾收集次数
+    gc_count: usize,
+    // 其中完整收集的次数
+    full_gc_count: usize,
+    // 堆耗尽时抛出的OutOfMemoryError，堆第一次需要收集时预先分配
+    out_of_memory_error: Option<RawPtr>,
+    // 是否已经尝试过预先分配OutOfMemoryError
+    out_of_memory_error_reserved: bool,
 }
 
 impl Vm {
     pub fn new(paths: &str) -> Vm {
+        Self::with_heap_size(paths, DEFAULT_INITIAL_HEAP_SIZE, DEFAULT_MAX_HEAP_SIZE)
+    }
+
+    /// 指定堆的初始大小和最大大小（字节）创建虚拟机，对应 -Xms 和 -Xmx
+    pub fn with_heap_size(paths: &str, initial: usize, maximum: usize) -> Vm {
         Vm {
             class_loader: RefCell::new(BootstrapClassLoader::new(paths)),
-            heap: RefCell::new(Heap::with_maximum_memory(1024 * 1024)),
+            heap: RefCell::new(Heap::with_sizes(initial, maximum)),
             static_fields: HashMap::new(),
             native_methods: NativeMethodRegistry::new(),
             string_builder_map: RefCell::new(HashMap::new()),
             string_map: RefCell::new(HashMap::new()),
+            stack_traces: RefCell::new(HashMap::new()),
+            call_stack: Vec::new(),
+            pending_trace: None,
+            pending_frame: None,
+            max_stack_depth: DEFAULT_MAX_STACK_DEPTH,
+            call_sites: HashMap::new(),
+            runtime: None,
+            shutdown_hooks: Vec::new(),
+            threads: Vec::new(),
+            handles: Vec::new(),
+            pending_exception: None,
+            thrown_exceptions: Vec::new(),
+            gc_count: 0,
+            full_gc_count: 0,
+            out_of_memory_error: None,
+            out_of_memory_error_reserved: false,
         }
     }
     
+    /// 加载并初始化类
     pub fn load(&mut self, class_name: &str) -> Result<Klass, JvmError> {
-        // 先获取self裸指针
-        let self_ptr = self as *mut Vm;
-        // 先借用class_loader和heap，load一次，clone结果
-        let (klass, class_loader_ptr, heap_ptr): (Klass, *mut crate::class_loader::BootstrapClassLoader, *mut crate::heap::Heap);
-        {
-            let mut class_loader = self.class_loader.borrow_mut();
-            let mut heap = self.heap.borrow_mut();
-            klass = class_loader.load(class_name, &mut heap)?.clone();
-            class_loader_ptr = &mut *class_loader as *mut crate::class_loader::BootstrapClassLoader;
-            heap_ptr = &mut *heap as *mut crate::heap::Heap;
-        }
-        // 用裸指针调用initialize_class，避免self多重借用
-        unsafe {
-            (*class_loader_ptr).initialize_class(class_name, &mut *heap_ptr, Some(&mut *self_ptr))?;
-        }
+        let klass = self.load_uninitialized(class_name)?;
+        self.initialize(class_name)?;
         Ok(klass)
     }
 
+    /// 加载并链接类但不初始化：解析符号引用和类型检查（checkcast、instanceof、aastore、异常匹配）不触发类初始化，
+    /// 也就不会在解析或检查期间执行Java代码
+    pub(crate) fn load_uninitialized(&mut self, class_name: &str) -> Result<Klass, JvmError> {
+        let class_loader = self.class_loader.borrow();
+        let mut heap = self.heap.borrow_mut();
+        class_loader.load(class_name, &mut heap)
+    }
+
+    /// 初始化类（JVMS 5.5），执行它的<clinit>。只由new、getstatic、putstatic、invokestatic
+    /// 以及从Rust代码调用类的方法触发，已经初始化或正在初始化时直接返回
+    pub fn initialize(&mut self, class_name: &str) -> Result<(), JvmError> {
+        // <clinit>通过VM访问堆和类加载器，执行期间不能持有class_loader的借用
+        let class_loader = self.class_loader.as_ptr();
+        unsafe { (*class_loader).initialize_class(class_name, Some(self)) }
+    }
+
+    /// 定义运行时生成的类，如lambda调用点的实现类
+    pub fn define_class(&mut self, class_file: &ClassFile) -> Result<Klass, JvmError> {
+        let class_loader = self.class_loader.borrow();
+        let mut heap = self.heap.borrow_mut();
+        class_loader.define_class(class_file, &mut heap)
+    }
+
+    /// 链接invokedynamic调用点，返回实现函数式接口的类名。
+    /// 每个调用点只链接一次，之后直接使用生成的类
+    pub fn link_call_site(&mut self, caller: &Method, index: U2) -> Result<String, JvmError> {
+        let key = (caller.class_name.clone(), index);
+        if let Some(class_name) = self.call_sites.get(&key) {
+            return Ok(class_name.clone());
+        }
+        let (bootstrap_index, name, descriptor) = crate::lambda::call_site_info(&caller.constant_pool, index)?;
+        let bootstrap = match self.load(&caller.class_name)? {
+            Klass::Instance(klass) => klass.get_bootstrap_method(bootstrap_index).cloned(),
+            _ => None,
+        }
+        .ok_or_else(|| JvmError::IllegalStateError(format!("{} 没有第{}个引导方法", caller.class_name, bootstrap_index)))?;
+        let spec = crate::lambda::LambdaSpec::resolve(&caller.constant_pool, &bootstrap, &name, &descriptor)?;
+
+        let class_name = format!("{}$$Lambda${}", caller.class_name, self.call_sites.len() + 1);
+        jvm_log!("[Lambda] 链接调用点 {}#{} -> {}", caller.class_name, index, class_name);
+        let class_file = crate::lambda::spin_class(&class_name, &spec)?;
+        self.define_class(&class_file)?;
+        self.call_sites.insert(key, class_name.clone());
+        Ok(class_name)
+    }
+
     pub fn alloc_array(&mut self, klass: &Klass, length: usize) -> Result<RawPtr, AllocError> {
         match klass {
-            crate::class::Klass::Array(k) => self.heap.borrow_mut().alloc_array(k, length),
+            crate::class::Klass::Array(k) => self.alloc_with_gc(|heap| heap.alloc_array(k, length)),
             _ => Err(AllocError::BadRequest),
         }
     }
     
     pub fn alloc_object(&mut self, klass: &Klass) -> Result<RawPtr, AllocError> {
         match klass {
-            crate::class::Klass::Instance(k) => self.heap.borrow_mut().alloc_object(k),
+            crate::class::Klass::Instance(k) => self.alloc_with_gc(|heap| heap.alloc_object(k)),
             _ => Err(AllocError::BadRequest),
         }
     }
+
+    /// 在堆上分配。新生代空间不足时先做一次新生代收集，老年代可能放不下晋升的对象时接着做完整收集；
+    /// 仍然不足时做完整收集，再逐步扩大老年代直到最大堆。
+    /// 调用者在分配前持有的对象引用必须位于根中（操作数栈、局部变量或native句柄）
+    fn alloc_with_gc(&mut self, mut alloc: impl FnMut(&mut Heap) -> Result<RawPtr, AllocError>) -> Result<RawPtr, AllocError> {
+        let result = alloc(&mut self.heap.borrow_mut());
+        if result != Err(AllocError::OOM) {
+            return result;
+        }
+        self.collect_young();
+        let mut full_collected = self.heap.borrow().needs_full_collection();
+        if full_collected {
+            self.collect_garbage();
+        }
+        self.reserve_out_of_memory_error();
+        loop {
+            let result = alloc(&mut self.heap.borrow_mut());
+            match result {
+                Err(AllocError::OOM) if !full_collected => {
+                    self.collect_garbage();
+                    full_collected = true;
+                }
+                Err(AllocError::OOM) if self.grow_heap() => {}
+                Err(AllocError::OOM) => {
+                    jvm_log!("[GC] 堆已达到最大容量{}字节，分配失败", self.heap.borrow().max_capacity());
+                    return Err(AllocError::OOM);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// 预先分配堆耗尽时抛出的OutOfMemoryError：那时已经没有空间创建新的异常对象
+    fn reserve_out_of_memory_error(&mut self) {
+        if self.out_of_memory_error_reserved {
+            return;
+        }
+        self.out_of_memory_error_reserved = true;
+        // 分配期间可能有正在转换的异常，它记录的栈轨迹留给它使用
+        let pending_trace = self.pending_trace.take();
+        match self.create_exception_object(JvmError::OutOfMemoryError("Java heap space".to_string())) {
+            Ok(error) => self.out_of_memory_error = Some(error),
+            Err(e) => jvm_log!("[GC] 无法预先分配OutOfMemoryError: {}", e),
+        }
+        self.pending_trace = pending_trace;
+    }
+
+    /// 新生代收集：从根和卡表记录的老年代对象出发复制新生代中的存活对象，
+    /// 存活次数达到晋升阈值的对象晋升到老年代
+    pub fn collect_young(&mut self) -> GcStats {
+        // 收集期间不分配对象，堆和根集合分别访问
+        let heap = unsafe { &mut *self.heap.as_ptr() };
+        let mut collection = heap.begin_young_collection();
+        self.visit_roots(&mut |_, slot| collection.evacuate(slot));
+        collection.trace(|class_id| self.reference_map(class_id));
+        self.forward_side_tables(|obj| collection.forwarded(obj));
+        let stats = collection.finish();
+        self.gc_count += 1;
+        jvm_log!("[GC] 第{}次收集（新生代）: 存活{}个对象({}字节)，晋升{}个对象({}字节)，回收{}字节",
+            self.gc_count, stats.live_objects, stats.live_bytes, stats.promoted_objects, stats.promoted_bytes, stats.freed_bytes);
+        stats
+    }
+
+    /// 完整收集：标记整个堆中的存活对象并整理到老年代。存活对象多时老年代随之扩大
+    pub fn collect_garbage(&mut self) -> GcStats {
+        self.collect_full(false)
+    }
+
+    /// 做一次完整收集并把老年代扩大一倍（不超过最大堆）。已经是最大堆时返回false
+    pub fn grow_heap(&mut self) -> bool {
+        if self.heap.borrow().capacity() >= self.heap.borrow().max_capacity() {
+            return false;
+        }
+        self.collect_full(true);
+        jvm_log!("[GC] 堆扩大到{}字节", self.heap.borrow().capacity());
+        true
+    }
+
+    /// 一次完整收集，grow为true时老年代至少扩大一倍
+    fn collect_full(&mut self, grow: bool) -> GcStats {
+        let heap = unsafe { &mut *self.heap.as_ptr() };
+        let mut collection = heap.begin_full_collection();
+        self.visit_roots(&mut |_, slot| collection.mark(slot));
+        collection.trace(|class_id| self.reference_map(class_id));
+        collection.plan(grow);
+        self.visit_roots(&mut |_, slot| collection.update(slot));
+        self.forward_side_tables(|obj| collection.forwarded(obj));
+        let stats = collection.finish();
+        self.gc_count += 1;
+        self.full_gc_count += 1;
+        jvm_log!("[GC] 第{}次收集（完整）: 存活{}个对象({}字节)，晋升{}个对象({}字节)，回收{}字节",
+            self.gc_count, stats.live_objects, stats.live_bytes, stats.promoted_objects, stats.promoted_bytes, stats.freed_bytes);
+        stats
+    }
+
+    /// 以对象为键的附属表（字符串内容、StringBuilder内容、异常栈轨迹）随对象换成新地址，不可达对象的条目被移除
+    fn forward_side_tables(&self, forwarded: impl Fn(RawPtr) -> Option<RawPtr>) {
+        Self::forward_keys(&self.string_map, &forwarded);
+        Self::forward_keys(&self.string_builder_map, &forwarded);
+        Self::forward_keys(&self.stack_traces, &forwarded);
+    }
+
+    /// 已完成的垃圾收集次数（新生代收集和完整收集）
+    pub fn gc_count(&self) -> usize {
+        self.gc_count
+    }
+
+    /// 已完成的完整收集次数
+    pub fn full_gc_count(&self) -> usize {
+        self.full_gc_count
+    }
+
+    /// 精确枚举垃圾收集的根：正在运行的帧栈中所有帧的局部变量和操作数栈、
+    /// VM和类加载器保存的静态字段、已解析的字符串常量、Runtime单例和关闭钩子、native句柄、正在查找处理器或沿调用栈展开的异常
+    /// 以及预先分配的OutOfMemoryError。
+    /// 访问者可以改写引用，移动对象后用来更新根
+    pub fn visit_roots(&mut self, visitor: &mut dyn FnMut(RootKind, &mut RawPtr)) {
+        for thread in &self.threads {
+            // 帧根与帧共享槽位，正在执行指令的帧也通过它更新，不会访问被指令借用的帧本身
+            for frame in thread.borrow().iter() {
+                frame.visit_references(visitor);
+            }
+        }
+        if let Some(frame) = self.pending_frame.as_ref() {
+            frame.visit_references(visitor);
+        }
+        for value in self.static_fields.values_mut() {
+            if let JvmValue::ObjRef(obj) = value {
+                visitor(RootKind::StaticField, obj);
+            }
+        }
+        let class_loader = self.class_loader.borrow();
+        class_loader.visit_static_refs(&mut |obj| visitor(RootKind::StaticField, obj));
+        class_loader.for_each_class(|klass| {
+            if let Klass::Instance(instance) = klass {
+                instance.get_constant_pool().visit_string_refs(&mut |obj| visitor(RootKind::StringConstant, obj));
+            }
+        });
+        drop(class_loader);
+        self.runtime.iter_mut().chain(&mut self.shutdown_hooks).for_each(|obj| visitor(RootKind::Runtime, obj));
+        self.handles.iter_mut().for_each(|obj| visitor(RootKind::Handle, obj));
+        if let Some(error) = self.out_of_memory_error.as_mut() {
+            visitor(RootKind::Reserved, error);
+        }
+        if let Some(exception) = self.pending_exception.as_mut() {
+            visitor(RootKind::PendingException, exception);
+        }
+        self.thrown_exceptions.retain(|exception| exception.strong_count() > 0);
+        for exception in self.thrown_exceptions.iter().filter_map(Weak::upgrade) {
+            let mut obj = exception.get();
+            visitor(RootKind::PendingException, &mut obj);
+            exception.set(obj);
+        }
+    }
+
+    /// 类ID对应的对象中引用的位置
+    fn reference_map(&self, class_id: usize) -> ReferenceMap {
+        let class_loader = self.class_loader.borrow();
+        let reference_map = class_loader.with_klass(class_id, |klass| match klass {
+            Klass::Instance(instance) => {
+                let offsets: Vec<usize> = instance.get_instance_fields().iter()
+                    .filter(|field| field.get_descriptor().starts_with('L') || field.get_descriptor().starts_with('['))
+                    .map(|field| field.get_offset())
+                    .collect();
+                if offsets.is_empty() { ReferenceMap::Empty } else { ReferenceMap::Fields(offsets) }
+            }
+            Klass::Array(array) if array.has_reference_elements() => ReferenceMap::Elements,
+            Klass::Array(_) => ReferenceMap::Empty,
+        });
+        // 没有加载类的对象：简化创建的字符串只有value字段，字符数组没有引用
+        reference_map.unwrap_or_else(|| match class_loader.class_name_of(class_id).as_deref() {
+            Some("java/lang/String") => ReferenceMap::Fields(vec![0]),
+            Some(name) if name.starts_with("[L") || name.starts_with("[[") => ReferenceMap::Elements,
+            _ => ReferenceMap::Empty,
+        })
+    }
+
+    /// 以对象为键的附属表换成对象的新地址，不可达对象的条目移除
+    fn forward_keys<V>(table: &RefCell<HashMap<RawPtr, V>>, forwarded: &impl Fn(RawPtr) -> Option<RawPtr>) {
+        let entries = std::mem::take(&mut *table.borrow_mut());
+        *table.borrow_mut() = entries
+            .into_iter()
+            .filter_map(|(obj, value)| Some((forwarded(obj)?, value)))
+            .collect();
+    }
+
+    /// 登记正在运行的解释循环的帧根，解释循环结束前调用detach_thread
+    pub fn attach_thread(&mut self, roots: ThreadRoots) {
+        self.threads.push(roots);
+    }
+
+    /// 注销最近登记的帧根
+    pub fn detach_thread(&mut self) {
+        self.threads.pop();
+    }
+
+    /// 以异常对象开始展开：返回的错误在被捕获或报告之前一直是垃圾收集的根
+    pub fn throw(&mut self, exception: RawPtr) -> JvmError {
+        let thrown = ThrownException::new(exception);
+        self.thrown_exceptions.push(thrown.downgrade());
+        JvmError::JavaException(thrown)
+    }
+
+    /// 登记native句柄：Rust代码跨越可能触发垃圾收集的调用（分配、类初始化）持有对象时使用，
+    /// 收集时句柄作为根随对象移动更新。返回句柄的编号
+    pub fn push_handle(&mut self, obj: RawPtr) -> usize {
+        self.handles.push(obj);
+        self.handles.len() - 1
+    }
+
+    /// 句柄当前指向的对象
+    pub fn handle(&self, handle: usize) -> RawPtr {
+        self.handles[handle]
+    }
+
+    /// 释放handle及之后登记的句柄
+    pub fn pop_handles(&mut self, handle: usize) {
+        self.handles.truncate(handle);
+    }
     
+    /// 读取对象头中的类ID
+    pub fn object_class_id(obj: RawPtr) -> Option<usize> {
+        // 与native方法相同的保护：System.out等伪造的引用没有对象头
+        if obj.is_null() || !(obj.0 as usize).is_multiple_of(8) || (obj.0 as usize) <= 0x1000 {
+            return None;
+        }
+        let header = unsafe { *(obj.0 as *const crate::heap::Header) };
+        Some(header.class_id())
+    }
+
+    /// 根据对象头中的类ID获取对象的类名，数组类返回描述符形式（如 `[I`）
+    pub fn get_object_class_name(&self, obj: RawPtr) -> Option<String> {
+        self.class_loader.borrow().class_name_of(Self::object_class_id(obj)?)
+    }
+
+    /// 在对象的类上执行f，类通过借用访问而不复制。对象头中的类还没有加载时（如简化创建的String）
+    /// 先按类名加载，不初始化；没有对象头的伪造引用返回None
+    pub fn with_object_klass<R>(&mut self, obj: RawPtr, f: impl FnOnce(&Vm, &Klass) -> R) -> Result<Option<R>, JvmError> {
+        let class_id = match Self::object_class_id(obj) {
+            Some(class_id) => class_id,
+            None => return Ok(None),
+        };
+        let loaded = self.class_loader.borrow().with_klass(class_id, |_| ()).is_some();
+        if !loaded {
+            let class_name = self.class_loader.borrow().class_name_of(class_id)
+                .ok_or_else(|| JvmError::IllegalStateError(format!("对象头中的类ID无效: {}", class_id)))?;
+            self.load_uninitialized(&class_name)?;
+        }
+        let vm: &Vm = self;
+        Ok(vm.class_loader.borrow().with_klass(class_id, |klass| f(vm, klass)))
+    }
+
+    /// 判断类型 sub 能否赋值给类型 sup（JVMS 的 assignability 规则）
+    /// 类名使用内部形式（如 `java/lang/String`），数组使用描述符形式（如 `[Ljava/lang/String;`）
+    pub fn is_subtype_of(&mut self, sub: &str, sup: &str) -> bool {
+        let sub = BootstrapClassLoader::normalize_class_name(sub).to_string();
+        let sup = BootstrapClassLoader::normalize_class_name(sup).to_string();
+        if sub == sup || sup == "java/lang/Object" {
+            return true;
+        }
+        if let Some(sub_component) = sub.strip_prefix('[') {
+            return match sup.strip_prefix('[') {
+                // 基本类型数组要求元素类型完全相同，引用类型数组按元素类型协变
+                Some(sup_component) => {
+                    let is_reference = |c: &str| c.starts_with('L') || c.starts_with('[');
+                    if is_reference(sub_component) && is_reference(sup_component) {
+                        self.is_subtype_of(sub_component, sup_component)
+                    } else {
+                        sub_component == sup_component
+                    }
+                }
+                None => sup == "java/lang/Cloneable" || sup == "java/io/Serializable",
+            };
+        }
+        if sup.starts_with('[') {
+            return false;
+        }
+        let instance = match self.load_uninitialized(&sub) {
+            Ok(Klass::Instance(instance)) => instance,
+            _ => return false,
+        };
+        for interface in instance.get_interfaces() {
+            if self.is_subtype_of(interface, &sup) {
+                return true;
+            }
+        }
+        let super_class = instance.get_super_class_name();
+        !super_class.is_empty() && self.is_subtype_of(super_class, &sup)
+    }
+
     /// 设置静态字段值
     pub fn set_static_field(&mut self, class_name: &str, field_name: &str, value: JvmValue) {
         self.static_fields.insert((class_name.to_string(), field_name.to_string()), value);
@@ -80,7 +469,7 @@ impl Vm {
         let full_name = format!("{}.{}", class_name, method_name);
         jvm_log!("[Native] call_native_method key: {}", full_name);
         // 先取出方法引用，避免self多重借用
-        let native_method = self.native_methods.get(&full_name).map(|m| &**m as *const dyn NativeMethod);
+        let native_method = self.native_methods.get(&full_name).map(|m| m as *const dyn NativeMethod);
         if let Some(native_method_ptr) = native_method {
             let native_method: &dyn NativeMethod = unsafe { &*native_method_ptr };
             jvm_log!("[Native] native method found for key: {}", full_name);
@@ -91,226 +480,451 @@ impl Vm {
         }
     }
     
+    /// 是否注册了 class_name.method_name 的native实现
+    pub fn has_native_method(&self, class_name: &str, method_name: &str) -> bool {
+        self.native_methods.get(&format!("{}.{}", class_name, method_name)).is_some()
+    }
+
+    /// 设置线程栈的最大深度
+    pub fn set_max_stack_depth(&mut self, depth: usize) {
+        self.max_stack_depth = depth;
+    }
+
+    /// 按符号引用解析方法：加载类并在类及其父类中查找
+    pub fn resolve_method(&mut self, class_name: &str, method_name: &str, descriptor: &str) -> Result<Method, JvmError> {
+        let klass = self
+            .load(class_name)
+            .map_err(|e| JvmError::ClassNotFoundError(format!("Failed to load class {}: {:?}", class_name, e)))?;
+        klass
+            .lookup_method(method_name, descriptor, self)
+            .ok_or_else(|| JvmError::IllegalStateError(format!("Method {}.{}{} not found", class_name, method_name, descriptor)))
+    }
+
     /// 通用的方法调用分发函数
     pub fn dispatch_method_call(&mut self, class_name: &str, method_name: &str, descriptor: &str, args: Vec<JvmValue>) -> Result<Option<JvmValue>, JvmError> {
-        // 1. 尝试加载类
-        let klass = match self.load(class_name) {
-            Ok(k) => k,
-            Err(e) => {
-                return Err(JvmError::ClassNotFoundError(format!("Failed to load class {}: {:?}", class_name, e)));
+        let method = self.resolve_method(class_name, method_name, descriptor)?;
+        self.invoke_method(&method, args)
+    }
+
+    /// 注册过native实现的方法，native实现同时作为内建实现，替代同名Java方法的字节码；
+    /// 继承来的native方法按声明它的类注册
+    fn has_native_impl(&self, method: &Method) -> bool {
+        method.is_native() || self.native_methods.get(&format!("{}.{}", method.class_name, method.name)).is_some()
+    }
+
+    /// 从Rust代码中调用已经选定的方法并等待它返回，args中实例方法的第一个参数是this。
+    /// 被调用的Java方法及其调用的方法都在一个新的帧栈上由解释循环执行
+    pub fn invoke_method(&mut self, method: &Method, args: Vec<JvmValue>) -> Result<Option<JvmValue>, JvmError> {
+        if self.has_native_impl(method) {
+            jvm_log!("[Dispatch] Calling native method: {}.{}", method.class_name, method.name);
+            return self.call_native_method(&method.class_name, &method.name, args);
+        }
+        let depth = self.call_stack.len();
+        let frame = self.enter_method(method, args)?;
+        let mut thread = JvmThread::with_frame(frame);
+        let result = thread.run(0, &mut Some(&mut *self));
+        // 入口帧执行到代码末尾或遇到内部错误时没有经过返回指令，去掉调用栈上剩余的登记
+        self.call_stack.truncate(depth);
+        result
+    }
+
+    /// 调用指令调用已经选定的方法：native方法立即执行，返回值压入调用者的操作数栈；
+    /// Java方法创建帧后交给解释循环压栈执行，返回时由返回指令把返回值交给调用者
+    pub fn call_method(&mut self, caller: &mut crate::jvm_thread::Frame, method: &Method, args: Vec<JvmValue>) -> Result<(), JvmError> {
+        if self.has_native_impl(method) {
+            jvm_log!("[Dispatch] Calling native method: {}.{}", method.class_name, method.name);
+            let return_value = self.call_native_method(&method.class_name, &method.name, args)?;
+            return crate::instructions::method_utils::push_return_value(caller, return_value);
+        }
+        self.pending_frame = Some(self.enter_method(method, args)?);
+        Ok(())
+    }
+
+    /// 取出调用指令创建的帧
+    pub fn take_pending_frame(&mut self) -> Option<crate::jvm_thread::Frame> {
+        self.pending_frame.take()
+    }
+
+    /// 进入Java方法：检查线程栈深度，创建帧并把参数存入局部变量表，在调用栈上登记
+    pub fn enter_method(&mut self, method: &Method, args: Vec<JvmValue>) -> Result<crate::jvm_thread::Frame, JvmError> {
+        if self.call_stack.len() >= self.max_stack_depth {
+            return Err(JvmError::StackOverflowError(format!("线程栈深度超过{}", self.max_stack_depth)));
+        }
+        jvm_log!("[Dispatch] Calling Java method: {}.{}", method.class_name, method.name);
+
+        let mut frame = crate::jvm_thread::Frame::new(method);
+        crate::instructions::method_utils::store_arguments(&mut frame.local_vars, &method.descriptor, method.is_static(), &args)?;
+        self.push_call_frame(method);
+        Ok(frame)
+    }
+
+    /// invokevirtual的方法选择：按接收者的运行时类查找重写的方法，数组使用java/lang/Object的方法
+    pub fn select_virtual_method(&self, receiver: &Klass, method_name: &str, descriptor: &str) -> Result<Method, JvmError> {
+        let receiver = match receiver {
+            Klass::Instance(receiver) => receiver,
+            // 解析数组上调用的方法时已经加载了java/lang/Object
+            Klass::Array(_) => {
+                return self.class_loader.borrow()
+                    .with_class("java/lang/Object", |object| self.select_virtual_method(object, method_name, descriptor))
+                    .unwrap_or_else(|| Err(JvmError::IllegalStateError("java/lang/Object 没有加载".to_string())));
             }
         };
+        Self::select_method(receiver, method_name, descriptor)?.ok_or_else(|| {
+            JvmError::NoSuchMethodError(format!("{}.{}{}", receiver.get_class_name().replace('/', "."), method_name, descriptor))
+        })
+    }
 
-        // 2. 在类中查找方法
-        let method = match klass.lookup_method(method_name, descriptor, self) {
-            Some(m) => m,
-            None => {
-                return Err(JvmError::IllegalStateError(format!("Method {}.{}{} not found", class_name, method_name, descriptor)));
+    /// invokeinterface的方法选择，接收者的类必须实现被调用的接口
+    pub fn select_interface_method(&self, receiver: &Klass, interface_name: &str, method_name: &str, descriptor: &str) -> Result<Method, JvmError> {
+        let receiver = match receiver {
+            Klass::Instance(receiver) if receiver.get_all_interfaces().iter().any(|name| name == interface_name) => receiver,
+            // 数组只实现Cloneable和Serializable，它们没有实例方法
+            _ => {
+                let receiver_class = match receiver {
+                    Klass::Instance(receiver) => receiver.get_class_name().to_string(),
+                    Klass::Array(array) => self.class_loader.borrow().class_name_of(array.class_id).unwrap_or_default(),
+                };
+                return Err(JvmError::IncompatibleClassChangeError(format!(
+                    "Class {} does not implement the requested interface {}",
+                    receiver_class.replace('/', "."),
+                    interface_name.replace('/', ".")
+                )));
             }
         };
+        Self::select_method(receiver, method_name, descriptor)?.ok_or_else(|| {
+            JvmError::AbstractMethodError(format!("{}.{}{}", receiver.get_class_name().replace('/', "."), method_name, descriptor))
+        })
+    }
 
-        // 3. 检查是否为native方法
-        if method.is_native() {
-            jvm_log!("[Dispatch] Calling native method: {}.{}", class_name, method_name);
-            return self.call_native_method(class_name, method_name, args);
+    /// 在接收者类上选择实例方法（JVMS 5.4.6）：先查虚方法表中类声明或继承的方法，
+    /// 没有时使用接口方法表中最具体的default方法；两张表中都没有时返回None
+    fn select_method(receiver: &crate::class::InstanceKlass, method_name: &str, descriptor: &str) -> Result<Option<Method>, JvmError> {
+        let abstract_method_error = || {
+            JvmError::AbstractMethodError(format!("{}.{}{}", receiver.get_class_name().replace('/', "."), method_name, descriptor))
+        };
+        if let Some(method) = receiver.get_vtable_method(method_name, descriptor) {
+            return if method.is_abstract() { Err(abstract_method_error()) } else { Ok(Some(method.clone())) };
+        }
+        match receiver.get_itable_entry(method_name, descriptor) {
+            Some(ItableEntry::Default(method)) => Ok(Some(method.clone())),
+            Some(ItableEntry::Conflict(interfaces)) => Err(JvmError::IncompatibleClassChangeError(format!(
+                "Conflicting default methods: {}",
+                interfaces.iter().map(|name| format!("{}.{}", name.replace('/', "."), method_name)).collect::<Vec<_>>().join(" ")
+            ))),
+            Some(ItableEntry::Abstract) => Err(abstract_method_error()),
+            None => Ok(None),
         }
+    }
 
-        // 4. 对于Java方法，创建新的执行帧并执行
-        jvm_log!("[Dispatch] Calling Java method: {}.{}", class_name, method_name);
+    /// 处理指令抛出的异常：在当前方法的异常表中按顺序查找覆盖opcode_pc且类型匹配的处理器，
+    /// 找到则清空操作数栈、压入异常对象并跳转；否则返回异常，交给调用者所在的帧继续展开
+    pub fn handle_exception(&mut self, frame: &mut crate::jvm_thread::Frame, opcode_pc: usize, err: JvmError) -> Result<(), JvmError> {
+        // 内部错误在抛出它的帧记录栈轨迹；从invoke返回的错误来自更深的帧时已经记录过，
+        // 没有记录则是invoke指令自身产生的错误（如方法选择失败）
+        let is_invoke = frame.method.code.index_of(opcode_pc)
+            .and_then(|index| frame.method.code.get(index))
+            .is_some_and(|instruction| matches!(instruction.opcode(), 0xb6..=0xba));
+        if err.java_exception_class().is_some() && (!is_invoke || self.pending_trace.is_none()) {
+            self.pending_trace = Some(self.capture_stack_trace());
+        }
+        // 没有受保护区间时不必创建异常对象，保留原始错误向上传播
+        if !frame.method.exception_table.iter().any(|entry| entry.covers(opcode_pc)) {
+            return Err(err);
+        }
+        let exception = self.create_exception_object(err)?;
+        let class_name = self.get_object_class_name(exception)
+            .ok_or_else(|| JvmError::IllegalStateError(format!("无法确定异常对象的类型: {:?}", exception)))?;
+        // 匹配处理器期间异常对象作为根，查找结束后从这里取出
+        self.pending_exception = Some(exception);
+        let entries = frame.method.exception_table.clone();
+        for entry in entries.iter().filter(|entry| entry.covers(opcode_pc)) {
+            let matched = entry.catch_type == 0 || {
+                let catch_class = frame.method.constant_pool.get_class_name(entry.catch_type);
+                self.is_subtype_of(&class_name, &catch_class)
+            };
+            if matched {
+                jvm_log!("[Exception] {} 在pc={}被捕获，跳转到{}", class_name, opcode_pc, entry.handler_pc);
+                let exception = self.pending_exception.take().unwrap_or(exception);
+                frame.stack.clear();
+                frame.stack.push_obj_ref(exception)?;
+                frame.pc = frame.method.code.index_of(entry.handler_pc as usize).ok_or_else(|| {
+                    JvmError::IllegalStateError(format!("异常处理器地址{}不是指令边界", entry.handler_pc))
+                })?;
+                return Ok(());
+            }
+        }
+        let exception = self.pending_exception.take().unwrap_or(exception);
+        Err(self.throw(exception))
+    }
 
-        // 解析参数类型
-        let param_types = crate::instructions::method_utils::parse_method_descriptor(descriptor);
-        // 判断是否静态方法（ACC_STATIC = 0x0008）
-        let is_static = (method.access_flags & 0x0008) != 0;
-        
-        // 使用更大的栈大小防止栈溢出
-        let mut thread = crate::jvm_thread::JvmThread::new(65536, method.max_locals);
-        thread.frames.clear();
-        
-        // 构造Frame
-        let mut frame = crate::jvm_thread::Frame {
-            local_vars: crate::local_vars::LocalVars::new(method.max_locals),
-            stack: crate::operand_stack::OperandStack::new(65536), // 使用更大的栈大小
-            method: method.clone(),
-            pc: 0,
+    /// 将内部错误转换为Java异常对象，已经是Java异常的直接返回；
+    /// 没有对应Java类或类无法加载时返回原始错误
+    pub fn create_exception_object(&mut self, err: JvmError) -> Result<RawPtr, JvmError> {
+        if let JvmError::JavaException(exception) = err {
+            return Ok(exception.get());
+        }
+        let class_name = match err.java_exception_class() {
+            Some(class_name) => class_name,
+            None => return Err(err),
         };
-        
-        let mut arg_index = 0;
-        // 实例方法第一个参数是this
-        if !is_static {
-            if let Some(JvmValue::ObjRef(this_ref)) = args.get(0) {
-                frame.local_vars.set_obj_ref(0, *this_ref);
-                arg_index = 1;
+        // 堆耗尽时不再分配，抛出预先分配的OutOfMemoryError
+        if let (JvmError::OutOfMemoryError(_), Some(error)) = (&err, self.out_of_memory_error) {
+            let trace = self.pending_trace.take().unwrap_or_else(|| self.capture_stack_trace());
+            self.stack_traces.borrow_mut().insert(error, trace);
+            return Ok(error);
+        }
+        let klass = match self.load(class_name) {
+            Ok(klass) => klass,
+            Err(e) => {
+                jvm_log!("[Exception] 无法加载异常类 {}: {:?}", class_name, e);
+                return Err(err);
+            }
+        };
+        let exception = self.alloc_object(&klass)?;
+        let exception = match err.message() {
+            Some(message) => {
+                // 创建消息字符串可能触发垃圾收集
+                let handle = self.push_handle(exception);
+                let result = self.set_detail_message(handle, message);
+                let exception = self.handle(handle);
+                self.pop_handles(handle);
+                result?;
+                exception
             }
+            None => exception,
+        };
+        let trace = self.pending_trace.take().unwrap_or_else(|| self.capture_stack_trace());
+        self.stack_traces.borrow_mut().insert(exception, trace);
+        jvm_log!("[Exception] {} 转换为 {}", err, class_name);
+        Ok(exception)
+    }
+
+    /// 设置 Throwable.detailMessage 字段，异常对象通过句柄传入
+    fn set_detail_message(&mut self, exception: usize, message: &str) -> Result<(), JvmError> {
+        if let Some(offset) = self.throwable_field_offset("detailMessage") {
+            let string = self.create_string_object(message)?;
+            self.heap.borrow_mut().put_field(self.handle(exception), offset, JvmValue::ObjRef(string));
         }
-        // 其余参数
-        for (i, param) in param_types.iter().enumerate() {
-            let arg = args.get(arg_index + i).cloned().unwrap_or(JvmValue::Null);
-            match param.as_str() {
-                "I" | "Z" | "B" | "C" | "S" => {
-                    if let JvmValue::Int(v) = arg {
-                        frame.local_vars.set_int(arg_index + i, v as i32);
-                    }
-                }
-                "J" => {
-                    if let JvmValue::Long(v) = arg {
-                        // long 拆成两个i32存储，低位在前
-                        frame.local_vars.set_int(arg_index + i, (v & 0xFFFF_FFFF) as i32);
-                        frame.local_vars.set_int(arg_index + i + 1, (v >> 32) as i32);
-                    }
-                }
-                "F" => {
-                    if let JvmValue::Float(v) = arg {
-                        frame.local_vars.set_int(arg_index + i, v as i32);
-                    }
-                }
-                "D" => {
-                    if let JvmValue::Double(v) = arg {
-                        frame.local_vars.set_int(arg_index + i, (v & 0xFFFF_FFFF) as i32);
-                        frame.local_vars.set_int(arg_index + i + 1, (v >> 32) as i32);
-                    }
-                }
-                s if s.starts_with("L") || s.starts_with("[") => {
-                    if let JvmValue::ObjRef(ptr) = arg {
-                        frame.local_vars.set_obj_ref(arg_index + i, ptr);
-                    }
-                }
-                _ => {}
+        Ok(())
+    }
+
+    /// 按字段名设置对象的实例字段，字段在class_name（含继承的字段）中查找
+    pub fn put_field_by_name(&mut self, obj: RawPtr, class_name: &str, field_name: &str, value: JvmValue) -> Result<(), JvmError> {
+        let offset = match self.load(class_name)? {
+            Klass::Instance(klass) => klass.get_instance_fields().iter()
+                .find(|f| f.get_name() == field_name)
+                .map(|f| f.get_offset()),
+            _ => None,
+        };
+        match offset {
+            Some(offset) => {
+                self.heap.borrow_mut().put_field(obj, offset, value);
+                Ok(())
             }
+            None => Err(JvmError::IllegalStateError(format!("找不到字段 {}.{}", class_name, field_name))),
         }
-        thread.frames.push(frame);
-        
-        // 执行方法
-        let code = method.get_code();
-        let mut ret: Option<JvmValue> = None;
-        
-        // 添加执行步数限制，防止无限循环
-        let mut step_count = 0;
-        let max_steps = 10000; // 最大执行步数
-        
-        while !thread.frames.is_empty() && thread.frames[0].pc < code.len() && step_count < max_steps {
-            step_count += 1;
-            let opcode = code[thread.frames[0].pc];
-            thread.frames[0].pc += 1;
-            let frame = &mut thread.frames[0];
-            
-            match opcode {
-                0xac => { // ireturn
-                    if !frame.stack.is_values_empty() {
-                        let v = frame.stack.pop_int();
-                        ret = Some(JvmValue::Int(v as u32));
-                    }
-                    thread.frames.pop();
-                    break;
-                }
-                0xad => { // lreturn
-                    // TODO: long
-                    thread.frames.pop();
-                    break;
-                }
-                0xae => { // freturn
-                    // TODO: float
-                    thread.frames.pop();
-                    break;
-                }
-                0xaf => { // dreturn
-                    // TODO: double
-                    thread.frames.pop();
-                    break;
-                }
-                0xb0 => { // areturn
-                    if !frame.stack.is_obj_refs_empty() {
-                        let v = frame.stack.pop_obj_ref();
-                        ret = Some(JvmValue::ObjRef(v));
-                    }
-                    thread.frames.pop();
-                    break;
-                }
-                0xb1 => { // return
-                    thread.frames.pop();
-                    break;
+    }
+
+    /// java/lang/Throwable 中实例字段的偏移，所有异常子类共用同一布局
+    fn throwable_field_offset(&mut self, field_name: &str) -> Option<usize> {
+        match self.load("java/lang/Throwable") {
+            Ok(Klass::Instance(throwable)) => throwable.get_instance_fields().iter()
+                .find(|f| f.get_name() == field_name)
+                .map(|f| f.get_offset()),
+            _ => None,
+        }
+    }
+
+    /// 读取异常对象的引用类型字段（detailMessage、cause），null或不存在时返回None
+    fn throwable_ref_field(&mut self, exception: RawPtr, field_name: &str) -> Option<RawPtr> {
+        let offset = self.throwable_field_offset(field_name)?;
+        match self.heap.borrow().get_field(exception, offset, "Ljava/lang/Object;") {
+            JvmValue::ObjRef(ptr) if !ptr.is_null() => Some(ptr),
+            _ => None,
+        }
+    }
+
+    /// 读取Java字符串对象的内容
+    pub fn get_string_content(&self, string: RawPtr) -> Option<String> {
+        if let Some(content) = self.string_map.borrow().get(&string) {
+            return Some(content.clone());
+        }
+        crate::native_method::extract_string_content(string).ok()
+    }
+
+    /// 进入Java方法时登记调用帧
+    pub fn push_call_frame(&mut self, method: &Method) {
+        self.call_stack.push(ActiveFrame::new(method.clone()));
+    }
+
+    pub fn pop_call_frame(&mut self) {
+        self.call_stack.pop();
+    }
+
+    /// 最内层帧正在执行的指令地址
+    pub fn current_pc(&self) -> usize {
+        self.call_stack.last().map(|frame| frame.pc).unwrap_or(0)
+    }
+
+    /// 记录最内层帧当前执行的指令地址
+    pub fn set_current_pc(&mut self, pc: usize) {
+        if let Some(frame) = self.call_stack.last_mut() {
+            frame.pc = pc;
+        }
+    }
+
+    /// 遍历调用栈生成栈轨迹，最内层帧在前
+    pub fn capture_stack_trace(&self) -> Vec<StackTraceElement> {
+        self.call_stack.iter().rev().map(StackTraceElement::of).collect()
+    }
+
+    /// Throwable.fillInStackTrace：记录当前调用栈，
+    /// 与HotSpot一样略去异常对象自身（及其父类）构造函数的帧
+    pub fn fill_in_stack_trace(&mut self, exception: RawPtr) {
+        let exception_class = self.get_object_class_name(exception).unwrap_or_default();
+        let mut trace = self.capture_stack_trace();
+        let mut skip = 0;
+        while let Some(element) = trace.get(skip) {
+            let skippable = element.method_name == "fillInStackTrace"
+                || (element.method_name == "<init>" && self.is_subtype_of(&exception_class, &element.class_name));
+            if !skippable {
+                break;
+            }
+            skip += 1;
+        }
+        trace.drain(..skip);
+        self.stack_traces.borrow_mut().insert(exception, trace);
+    }
+
+    /// 异常对象的栈轨迹，未记录时为空
+    pub fn get_stack_trace(&self, exception: RawPtr) -> Vec<StackTraceElement> {
+        self.stack_traces.borrow().get(&exception).cloned().unwrap_or_default()
+    }
+
+    /// Throwable.toString：`java.lang.X: msg`，没有消息时只有类名
+    pub fn throwable_to_string(&mut self, exception: RawPtr) -> String {
+        let class_name = self.get_object_class_name(exception)
+            .unwrap_or_else(|| "java/lang/Throwable".to_string())
+            .replace('/', ".");
+        let message = self.throwable_ref_field(exception, "detailMessage")
+            .and_then(|message| self.get_string_content(message));
+        match message {
+            Some(message) => format!("{}: {}", class_name, message),
+            None => class_name,
+        }
+    }
+
+    /// 按 Throwable.printStackTrace 的格式输出异常、栈轨迹以及 `Caused by:` 链
+    pub fn format_stack_trace(&mut self, exception: RawPtr) -> String {
+        // 第一次读取Throwable的字段会加载并初始化Throwable，期间可能发生垃圾收集，异常对象通过句柄跟随移动；
+        // 类加载之后读取异常链不再分配
+        let handle = self.push_handle(exception);
+        self.throwable_field_offset("cause");
+        let exception = self.handle(handle);
+        self.pop_handles(handle);
+        let mut out = format!("{}\n", self.throwable_to_string(exception));
+        let mut trace = self.get_stack_trace(exception);
+        stack_trace::write_trace(&mut out, &trace, None);
+        let mut seen = vec![exception];
+        let mut current = exception;
+        while let Some(cause) = self.throwable_ref_field(current, "cause") {
+            // 防止cause形成环
+            if seen.contains(&cause) {
+                break;
+            }
+            seen.push(cause);
+            out.push_str(&format!("Caused by: {}\n", self.throwable_to_string(cause)));
+            let cause_trace = self.get_stack_trace(cause);
+            stack_trace::write_trace(&mut out, &cause_trace, Some(&trace));
+            trace = cause_trace;
+            current = cause;
+        }
+        out
+    }
+
+    /// 线程因未捕获的异常终止时，按HotSpot的格式输出到标准错误
+    pub fn report_uncaught_exception(&mut self, thread_name: &str, err: JvmError) {
+        match self.create_exception_object(err) {
+            Ok(exception) => {
+                eprint!("Exception in thread \"{}\" {}", thread_name, self.format_stack_trace(exception));
+            }
+            Err(err) => {
+                eprintln!("Exception in thread \"{}\" java.lang.InternalError: {}", thread_name, err);
+            }
+        }
+    }
+
+    /// Runtime.getRuntime返回的单例对象，第一次调用时创建
+    pub fn runtime_object(&mut self) -> Result<RawPtr, JvmError> {
+        if let Some(runtime) = self.runtime {
+            return Ok(runtime);
+        }
+        let klass = self.load("java/lang/Runtime")?;
+        let runtime = self.alloc_object(&klass)?;
+        self.runtime = Some(runtime);
+        Ok(runtime)
+    }
+
+    /// 注册关闭钩子，同一个线程对象只登记一次
+    pub fn add_shutdown_hook(&mut self, hook: RawPtr) {
+        if !self.shutdown_hooks.contains(&hook) {
+            self.shutdown_hooks.push(hook);
+        }
+    }
+
+    /// 注销关闭钩子，返回钩子是否注册过
+    pub fn remove_shutdown_hook(&mut self, hook: RawPtr) -> bool {
+        let registered = self.shutdown_hooks.len();
+        self.shutdown_hooks.retain(|&h| h != hook);
+        self.shutdown_hooks.len() != registered
+    }
+
+    /// main线程结束后关闭虚拟机，返回进程的退出状态码：
+    /// 正常返回为0，未捕获的异常按HotSpot的格式输出并返回1，System.exit返回请求的状态码，
+    /// 这三种情况都会运行关闭钩子；Runtime.halt直接返回它的状态码
+    pub fn shutdown(&mut self, result: Result<(), JvmError>) -> i32 {
+        let status = match result {
+            Ok(()) => 0,
+            Err(JvmError::Exit(status)) => status,
+            Err(JvmError::Halt(status)) => return status,
+            Err(err) => {
+                self.report_uncaught_exception("main", err);
+                1
+            }
+        };
+        self.run_shutdown_hooks().unwrap_or(status)
+    }
+
+    /// 依次运行关闭钩子的run方法。钩子中未捕获的异常只输出不影响其他钩子；
+    /// 钩子调用Runtime.halt时立即停止并返回它的状态码
+    fn run_shutdown_hooks(&mut self) -> Option<i32> {
+        // 钩子留在注册表中作为垃圾收集的根，全部运行后再清空
+        for index in 0..self.shutdown_hooks.len() {
+            let hook = self.shutdown_hooks[index];
+            let thread_name = format!("Thread-{}", index);
+            let run = self.with_object_klass(hook, |vm, klass| vm.select_virtual_method(klass, "run", "()V"));
+            let result = match run {
+                Ok(Some(run)) => run.and_then(|run| self.invoke_method(&run, vec![JvmValue::ObjRef(hook)])),
+                Ok(None) => Err(JvmError::IllegalStateError(format!("关闭钩子不是有效的线程对象: {:?}", hook))),
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(_) => {}
+                Err(JvmError::Halt(status)) => {
+                    self.shutdown_hooks.clear();
+                    return Some(status);
                 }
-                _ => {
-                    // 复用主线程的VM和heap，直接调用execute方法单步执行
-                    // 这里直接复用frame和VM即可
-                    // 由于execute_one不存在，直接复用frame和VM的指令分发
-                    // 这里直接调用主线程的execute方法更安全
-                    // 但此处为单步，直接match分发
-                    match opcode {
-                        0x00 => (), // nop
-                        0x01 => crate::instructions::constants::exec_aconst_null(frame, code, Some(self))?,
-                        0x02 => crate::instructions::constants::exec_iconst_m1(frame, code, Some(self))?,
-                        0x03 => crate::instructions::constants::exec_iconst_0(frame, code, Some(self))?,
-                        0x04 => crate::instructions::constants::exec_iconst_1(frame, code, Some(self))?,
-                        0x05 => crate::instructions::constants::exec_iconst_2(frame, code, Some(self))?,
-                        0x06 => crate::instructions::constants::exec_iconst_3(frame, code, Some(self))?,
-                        0x07 => crate::instructions::constants::exec_iconst_4(frame, code, Some(self))?,
-                        0x08 => crate::instructions::constants::exec_iconst_5(frame, code, Some(self))?,
-                        0x10 => crate::instructions::constants::exec_bipush(frame, code, Some(self))?,
-                        0x11 => crate::instructions::ldc_ops::exec_sipush(frame, code, Some(self))?,
-                        0x12 => crate::instructions::ldc_ops::exec_ldc(frame, code, Some(self))?,
-                        0x13 => crate::instructions::ldc_ops::exec_ldc_w(frame, code, Some(self))?,
-                        0x14 => crate::instructions::ldc_ops::exec_ldc2_w(frame, code, Some(self))?,
-                        0x15 => crate::instructions::load_store::exec_iload(frame, code, Some(self))?,
-                        0x1a => crate::instructions::load_store::exec_iload_0(frame, code, Some(self))?,
-                        0x1b => crate::instructions::load_store::exec_iload_1(frame, code, Some(self))?,
-                        0x1c => crate::instructions::load_store::exec_iload_2(frame, code, Some(self))?,
-                        0x1d => crate::instructions::load_store::exec_iload_3(frame, code, Some(self))?,
-                        0x2a => crate::instructions::aload_0::exec_aload_0(frame, code, Some(self))?,
-                        0x2b => crate::instructions::load_store::exec_aload_1(frame, code, Some(self))?,
-                        0x2c => crate::instructions::load_store::exec_aload_2(frame, code, Some(self))?,
-                        0x2d => crate::instructions::load_store::exec_aload_3(frame, code, Some(self))?,
-                        0x36 => crate::instructions::load_store::exec_istore(frame, code, Some(self))?,
-                        0x3b => crate::instructions::load_store::exec_istore_0(frame, code, Some(self))?,
-                        0x3c => crate::instructions::load_store::exec_istore_1(frame, code, Some(self))?,
-                        0x3d => crate::instructions::load_store::exec_istore_2(frame, code, Some(self))?,
-                        0x3e => crate::instructions::load_store::exec_istore_3(frame, code, Some(self))?,
-                        0x4b => crate::instructions::load_store::exec_astore_0(frame, code, Some(self))?,
-                        0x4c => crate::instructions::load_store::exec_astore_1(frame, code, Some(self))?,
-                        0x4d => crate::instructions::load_store::exec_astore_2(frame, code, Some(self))?,
-                        0x4e => crate::instructions::load_store::exec_astore_3(frame, code, Some(self))?,
-                        0x59 => crate::instructions::stack::exec_dup(frame, code, Some(self))?,
-                        0xb7 => crate::instructions::invokespecial::exec_invokespecial(frame, code, Some(self))?,
-                        0x60 => crate::instructions::arithmetic::exec_iadd(frame, code, Some(self))?,
-                        0x64 => crate::instructions::arithmetic::exec_isub(frame, code, Some(self))?,
-                        0x68 => crate::instructions::arithmetic::exec_imul(frame, code, Some(self))?,
-                        0x6c => crate::instructions::arithmetic::exec_idiv(frame, code, Some(self))?,
-                        0x84 => crate::instructions::iinc::exec_iinc(frame, code, Some(self))?,
-                        0x99 => crate::instructions::control::exec_ifeq(frame, code, Some(self))?,
-                        0x9a => crate::instructions::control::exec_ifne(frame, code, Some(self))?,
-                        0x9f => crate::instructions::control::exec_ifge(frame, code, Some(self))?,
-                        0xa7 => crate::instructions::control::exec_goto(frame, code, Some(self))?,
-                        0xb2 => crate::instructions::field_ops::exec_getstatic(frame, code, Some(self), &method)?,
-                        0xb3 => crate::instructions::field_ops::exec_putstatic(frame, code, Some(self), &method)?,
-                        0xb6 => crate::instructions::invokevirtual::exec_invokevirtual(frame, code, Some(self))?,
-                        0xb5 => crate::instructions::object_ops::exec_putfield(frame, code, Some(self))?,
-                        0xbc => crate::instructions::array_ops::exec_newarray(frame, code, Some(self))?,
-                        0xbe => crate::instructions::array_ops::exec_arraylength(frame, code, Some(self))?,
-                        0x4f => crate::instructions::array_ops::exec_iastore(frame, code, Some(self))?,
-                        0x2e => crate::instructions::array_ops::exec_iaload(frame, code, Some(self))?,
-                        0xa2 => crate::instructions::control_extended::exec_if_icmpge(frame, code, Some(self))?,
-                        0xb0 => crate::instructions::control_extended::exec_areturn(frame, code, Some(self))?,
-                        0xbb => crate::instructions::object_ops::exec_new(frame, code, Some(self))?,
-                        0xb8 => crate::instructions::invokestatic::exec_invokestatic(frame, code, Some(self))?,
-                        0x3f => crate::instructions::load_store::exec_istore_0(frame, code, Some(self))?,
-                        0x40 => crate::instructions::load_store::exec_istore_1(frame, code, Some(self))?,
-                        0x41 => crate::instructions::load_store::exec_istore_2(frame, code, Some(self))?,
-                        0x42 => crate::instructions::load_store::exec_istore_3(frame, code, Some(self))?,
-                        0xb4 => crate::instructions::object_ops::exec_getfield(frame, code, Some(self))?,
-                        _ => return Err(JvmError::IllegalStateError(format!("Unknown opcode: 0x{:x}", opcode))),
-                    }
+                // 关闭过程中再次调用System.exit在HotSpot中会一直阻塞，这里只结束这个钩子
+                Err(JvmError::Exit(status)) => {
+                    jvm_log!("[Shutdown] {} 在关闭过程中调用exit({})，忽略", thread_name, status);
                 }
+                Err(err) => self.report_uncaught_exception(&thread_name, err),
             }
         }
-        
-        // 检查是否因为步数限制而退出
-        if step_count >= max_steps {
-            return Err(JvmError::IllegalStateError(format!("Method execution exceeded maximum steps: {}", max_steps)));
-        }
-        
-        Ok(ret)
+        self.shutdown_hooks.clear();
+        None
     }
-    
+
     /// 创建字符串对象
     pub fn create_string_object(&mut self, string_content: &str) -> Result<RawPtr, AllocError> {
         // 简化实现：直接创建字符串对象，不依赖加载完整的String类
@@ -343,34 +957,18 @@ impl Vm {
         
         jvm_log!("[String] Allocating string object: header_size={}, total_size={}", header_size, total_size);
         
-        // 分配内存
-        let layout = std::alloc::Layout::from_size_align(total_size, 8).unwrap();
-        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
-        
-        if ptr.is_null() {
-            jvm_log!("[String] Memory allocation failed");
-            return Err(AllocError::OOM);
-        }
-        
-        let string_ptr = RawPtr(ptr);
+        // 分配String对象时可能发生垃圾收集，字符数组通过句柄跟随移动
+        let class_id = self.class_loader.borrow().class_id_of("java/lang/String");
+        let handle = self.push_handle(char_array_ptr);
+        let result = self.alloc_with_gc(|heap| heap.alloc_with_header(class_id, total_size));
+        let char_array_ptr = self.handle(handle);
+        self.pop_handles(handle);
+        let string_ptr = result?;
         jvm_log!("[String] Allocated string object: {:?}", string_ptr);
         
-        // 初始化头部（简化版本）
-        unsafe {
-            let header_ptr = ptr as *mut crate::heap::Header;
-            *header_ptr = crate::heap::Header::new()
-                .with_class_id(0) // 使用0表示String类
-                .with_state(crate::heap::GcState::Unmarked)
-                .with_size(total_size);
-            jvm_log!("[String] Initialized header");
-        }
-        
-        // 设置value字段指向字符数组
-        unsafe {
-            let value_field_ptr = ptr.add(header_size) as *mut RawPtr;
-            *value_field_ptr = char_array_ptr;
-            jvm_log!("[String] Set value field to char array: {:?}", char_array_ptr);
-        }
+        // 设置value字段指向字符数组，经过写屏障
+        self.heap.borrow_mut().put_field(string_ptr, 0, JvmValue::ObjRef(char_array_ptr));
+        jvm_log!("[String] Set value field to char array: {:?}", char_array_ptr);
         
         jvm_log!("[String] Successfully created string object: {:?}", string_ptr);
         
@@ -380,95 +978,29 @@ impl Vm {
         Ok(string_ptr)
     }
     
+    /// 创建String数组，如main方法的命令行参数；每个元素是一个新的String对象，按aastore的语义存入
+    pub fn create_string_array(&mut self, strings: &[String]) -> Result<RawPtr, JvmError> {
+        let klass = self.load("[Ljava/lang/String;")?;
+        let array = self.alloc_array(&klass, strings.len())?;
+        // 创建元素时可能发生垃圾收集，数组通过句柄跟随移动
+        let handle = self.push_handle(array);
+        let result = strings.iter().enumerate().try_for_each(|(index, string)| {
+            let string = self.create_string_object(string)?;
+            crate::instructions::array_ops::reference_array_store(self, self.handle(handle), index as i32, string)
+        });
+        let array = self.handle(handle);
+        self.pop_handles(handle);
+        result.map(|_| array)
+    }
+
     /// 创建字符数组
     fn create_char_array(&mut self, chars: &[u16]) -> Result<RawPtr, AllocError> {
-        let header_size = std::mem::size_of::<crate::heap::Header>();
-        let total_size = header_size + 8 + chars.len() * 2; // 8字节存储length，每个char 2字节
-        
-        // 分配内存
-        let layout = std::alloc::Layout::from_size_align(total_size, 8).unwrap();
-        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
-        
-        if ptr.is_null() {
-            return Err(AllocError::OOM);
-        }
-        
-        let array_ptr = RawPtr(ptr);
-        
-        // 初始化头部
-        unsafe {
-            let header_ptr = ptr as *mut crate::heap::Header;
-            *header_ptr = crate::heap::Header::new()
-                .with_class_id(1) // 使用1表示char[]类
-                .with_state(crate::heap::GcState::Unmarked)
-                .with_size(total_size);
-        }
-        
-        // 设置数组长度
-        unsafe {
-            let length_ptr = ptr.add(header_size) as *mut usize;
-            *length_ptr = chars.len();
-        }
-        
-        // 写入字符数据
+        let class_id = self.class_loader.borrow().class_id_of("[C");
+        let ptr = self.alloc_with_gc(|heap| heap.alloc_array_of(class_id, 2, chars.len()))?;
+        let mut heap = self.heap.borrow_mut();
         for (i, &ch) in chars.iter().enumerate() {
-            unsafe {
-                let char_ptr = ptr.add(header_size + 8 + i * 2) as *mut u16;
-                *char_ptr = ch;
-            }
-        }
-        
-        Ok(array_ptr)
-    }
-    
-    /// 创建简单的数组对象（用于newarray指令）
-    pub fn create_simple_array(&mut self, length: usize, array_type: u8) -> Result<RawPtr, AllocError> {
-        let header_size = std::mem::size_of::<crate::heap::Header>();
-        
-        // 根据数组类型确定元素大小
-        let element_size = match array_type {
-            4 => 1,  // T_BOOLEAN
-            5 => 2,  // T_CHAR
-            6 => 4,  // T_FLOAT
-            7 => 8,  // T_DOUBLE
-            8 => 1,  // T_BYTE
-            9 => 2,  // T_SHORT
-            10 => 4, // T_INT
-            11 => 8, // T_LONG
-            _ => return Err(AllocError::BadRequest),
-        };
-        
-        let total_size = header_size + 8 + length * element_size; // 8字节存储length
-        
-        // 分配内存
-        let layout = std::alloc::Layout::from_size_align(total_size, 8).unwrap();
-        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
-        
-        if ptr.is_null() {
-            return Err(AllocError::OOM);
-        }
-        
-        let array_ptr = RawPtr(ptr);
-        
-        // 初始化头部
-        unsafe {
-            let header_ptr = ptr as *mut crate::heap::Header;
-            *header_ptr = crate::heap::Header::new()
-                .with_class_id(array_type as usize) // 使用数组类型作为类ID
-                .with_state(crate::heap::GcState::Unmarked)
-                .with_size(total_size);
-        }
-        
-        // 设置数组长度
-        unsafe {
-            let length_ptr = ptr.add(header_size) as *mut usize;
-            *length_ptr = length;
+            heap.put_array_element(ptr, i, JvmValue::Char(ch));
         }
-        
-        Ok(array_ptr)
+        Ok(ptr)
     }
 }
-
-
-
-